@@ -136,6 +136,7 @@ fn create_tool_results(count: usize) -> Vec<ToolResult> {
             tool_use_id: format!("call_{}", i),
             content: format!("Result from tool {}: success with some output data", i),
             is_error: if i % 10 == 0 { Some(true) } else { None },
+            image: None,
         })
         .collect()
 }
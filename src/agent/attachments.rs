@@ -0,0 +1,126 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Image attachments in user prompts.
+//!
+//! A prompt can reference images as `@path/to/screenshot.png` (png, jpg,
+//! gif or webp). When the model supports vision, each referenced image is
+//! loaded with [`load_image`] and attached to the user message after the
+//! prompt text. Relative paths are resolved against the working directory
+//! and `~/` against the home directory.
+//!
+//! Images that can't be loaded, or references made to a model without
+//! vision, are noted in the message so the model can tell the user.
+
+use std::path::PathBuf;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::tools::image::load_image;
+use crate::types::{ContentBlock, Message, Role};
+
+static IMAGE_REFERENCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|\s)@(\S+\.(?i:png|jpe?g|gif|webp))\b").unwrap()
+});
+
+/// Image paths referenced as `@path` in a prompt, in order and without duplicates.
+pub fn image_references(text: &str) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    for captures in IMAGE_REFERENCE.captures_iter(text) {
+        let path = captures[1].to_string();
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Build the user message for a prompt, attaching referenced images if
+/// the model supports `vision`.
+pub async fn user_message(text: &str, vision: bool) -> Message {
+    let references = image_references(text);
+    if references.is_empty() {
+        return Message::user(text);
+    }
+    if !vision {
+        return Message::user(format!(
+            "{}\n\n[Referenced images were not attached: the current model cannot view images.]",
+            text
+        ));
+    }
+
+    let mut notes = Vec::new();
+    let mut images = Vec::new();
+    for reference in references {
+        let path = resolve(&reference);
+        match load_image(&path).await {
+            Ok(image) => {
+                images.push(ContentBlock::text(image.describe(&path)));
+                images.push(ContentBlock::image(image.source));
+            }
+            Err(e) => {
+                tracing::warn!("Failed to attach {}: {}", reference, e);
+                notes.push(format!("[Could not attach @{}: {}]", reference, e));
+            }
+        }
+    }
+
+    let mut prompt = text.to_string();
+    for note in notes {
+        prompt.push_str("\n\n");
+        prompt.push_str(&note);
+    }
+    if images.is_empty() {
+        return Message::user(prompt);
+    }
+
+    let mut blocks = vec![ContentBlock::text(prompt)];
+    blocks.extend(images);
+    Message::with_blocks(Role::User, blocks)
+}
+
+fn resolve(reference: &str) -> PathBuf {
+    match (reference.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(reference),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ContentBlockType;
+
+    #[test]
+    fn test_image_references() {
+        let text = "Compare @shots/before.png and @shots/After.JPG, see @shots/before.png. \
+                    Not an image: @src/main.rs, not a reference: me@host.png";
+        assert_eq!(image_references(text), vec!["shots/before.png", "shots/After.JPG"]);
+        assert!(image_references("no references").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_user_message_attaches_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.png");
+        image::RgbImage::new(8, 4).save(&path).unwrap();
+        let missing = dir.path().join("missing.png");
+        let text = format!("What is in @{} and @{}?", path.display(), missing.display());
+
+        let msg = user_message(&text, true).await;
+        let blocks = msg.as_blocks().unwrap();
+        assert_eq!(blocks.len(), 3);
+        let prompt = blocks[0].text.as_deref().unwrap();
+        assert!(prompt.starts_with(&text));
+        assert!(prompt.contains("[Could not attach @"));
+        assert!(blocks[1].text.as_deref().unwrap().ends_with("shot.png (8x4)"));
+        assert_eq!(blocks[2].block_type, ContentBlockType::Image);
+
+        let msg = user_message(&text, false).await;
+        assert!(msg.as_text().unwrap().contains("the current model cannot view images"));
+
+        let msg = user_message("plain prompt", true).await;
+        assert_eq!(msg.as_text(), Some("plain prompt"));
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Spending limits for agent turns, sessions and days.
+//!
+//! Costs are computed from each response's [`TokenUsage`](crate::types::TokenUsage)
+//! and the provider's [`ModelPricing`](crate::types::ModelPricing). When a
+//! configured cap is reached the agent stops with
+//! [`AgentError::BudgetExceeded`] before sending the next request. With caps
+//! set, models without known pricing are refused with
+//! [`AgentError::UnpricedModel`] rather than run unmetered.
+//!
+//! When a daily cap is set, daily spend is shared across processes through a
+//! small ledger file (`~/.codi/spend.json`) that is reset on the first write
+//! of a new day.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{get_global_config_dir, ResolvedConfig};
+use crate::error::AgentError;
+
+pub use crate::error::BudgetScope;
+
+/// File name of the daily spend ledger inside the global config directory.
+pub const SPEND_LEDGER_FILE: &str = "spend.json";
+
+/// Spending caps in USD. `None` means unlimited.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BudgetConfig {
+    /// Maximum cost of a single turn.
+    pub max_turn_cost: Option<f64>,
+    /// Maximum cost of the session.
+    pub max_session_cost: Option<f64>,
+    /// Maximum cost across all sessions in a calendar day.
+    pub max_daily_cost: Option<f64>,
+    /// Ledger file used to track spend per day (`None` disables daily tracking).
+    pub spend_ledger: Option<PathBuf>,
+}
+
+impl BudgetConfig {
+    /// Build budget caps from `maxTurnCost` / `maxSessionCost` / `maxDailyCost`.
+    ///
+    /// The spend ledger is only kept when a daily cap is set.
+    pub fn from_config(config: &ResolvedConfig) -> Self {
+        Self {
+            max_turn_cost: config.max_turn_cost,
+            max_session_cost: config.max_session_cost,
+            max_daily_cost: config.max_daily_cost,
+            spend_ledger: config
+                .max_daily_cost
+                .and_then(|_| default_spend_ledger_path()),
+        }
+    }
+
+    /// Caps on further spend once `turn` and `session` have been spent, for sub-agents.
+    pub fn remaining(&self, turn: f64, session: f64) -> Self {
+        Self {
+            max_turn_cost: self.max_turn_cost.map(|limit| limit - turn),
+            max_session_cost: self.max_session_cost.map(|limit| limit - session),
+            ..self.clone()
+        }
+    }
+
+    /// Whether any cap is set.
+    pub fn has_caps(&self) -> bool {
+        self.max_turn_cost.is_some() || self.max_session_cost.is_some() || self.max_daily_cost.is_some()
+    }
+
+    /// Check spend so far against the caps.
+    ///
+    /// `daily` is only consulted when a daily cap is set, since reading it
+    /// touches the ledger file.
+    pub fn check(
+        &self,
+        turn: f64,
+        session: f64,
+        daily: impl FnOnce() -> f64,
+    ) -> Result<(), AgentError> {
+        let exceeded = |scope, spent: f64, limit: Option<f64>| match limit {
+            Some(limit) if spent >= limit => Err(AgentError::BudgetExceeded { scope, spent, limit }),
+            _ => Ok(()),
+        };
+
+        exceeded(BudgetScope::Turn, turn, self.max_turn_cost)?;
+        exceeded(BudgetScope::Session, session, self.max_session_cost)?;
+        if self.max_daily_cost.is_some() {
+            exceeded(BudgetScope::Day, daily(), self.max_daily_cost)?;
+        }
+        Ok(())
+    }
+}
+
+/// Default location of the daily spend ledger.
+pub fn default_spend_ledger_path() -> Option<PathBuf> {
+    get_global_config_dir().map(|dir| dir.join(SPEND_LEDGER_FILE))
+}
+
+/// Contents of the daily spend ledger.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DailySpend {
+    /// Local date the total applies to (YYYY-MM-DD).
+    date: String,
+    /// Total cost in USD on that date.
+    cost: f64,
+}
+
+fn today() -> String {
+    chrono::Local::now().date_naive().to_string()
+}
+
+fn read_ledger(path: &Path) -> Option<DailySpend> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Get today's total spend from the ledger (0 if missing or from another day).
+pub fn load_daily_spend(path: &Path) -> f64 {
+    match read_ledger(path) {
+        Some(spend) if spend.date == today() => spend.cost,
+        _ => 0.0,
+    }
+}
+
+/// Add `cost` to today's total in the ledger and return the new total.
+///
+/// The update holds an exclusive lock on a sibling `.lock` file and replaces
+/// the ledger by renaming a temporary file, so concurrent sessions neither
+/// lose each other's spend nor read a half-written ledger.
+pub fn record_daily_spend(path: &Path, cost: f64) -> std::io::Result<f64> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let lock_file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_extension("lock"))?;
+    let mut lock = fd_lock::RwLock::new(lock_file);
+    let _guard = lock.write()?;
+
+    let date = today();
+    let previous = match read_ledger(path) {
+        Some(spend) if spend.date == date => spend.cost,
+        _ => 0.0,
+    };
+    let spend = DailySpend {
+        date,
+        cost: previous + cost,
+    };
+
+    let json = serde_json::to_string(&spend).map_err(std::io::Error::other)?;
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, json)?;
+    std::fs::rename(&temp, path)?;
+    Ok(spend.cost)
+}
+
+/// Format a USD amount for display, with more precision for small amounts.
+pub fn format_cost(cost: f64) -> String {
+    if cost < 1.0 {
+        format!("${:.4}", cost)
+    } else {
+        format!("${:.2}", cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_caps() {
+        let budget = BudgetConfig {
+            max_turn_cost: Some(1.0),
+            max_session_cost: Some(5.0),
+            max_daily_cost: None,
+            spend_ledger: None,
+        };
+        assert!(budget.check(0.5, 4.0, || unreachable!()).is_ok());
+
+        let err = budget.check(1.2, 4.0, || 0.0).unwrap_err();
+        assert!(matches!(err, AgentError::BudgetExceeded { scope: BudgetScope::Turn, .. }));
+
+        let err = budget.check(0.1, 5.0, || 0.0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Session budget exceeded: spent $5.0000 of $5.00 limit"
+        );
+
+        let daily = BudgetConfig {
+            max_daily_cost: Some(20.0),
+            ..budget
+        };
+        let err = daily.check(0.1, 1.0, || 25.0).unwrap_err();
+        assert!(matches!(err, AgentError::BudgetExceeded { scope: BudgetScope::Day, .. }));
+    }
+
+    #[test]
+    fn test_ledger_only_with_daily_cap() {
+        assert_eq!(BudgetConfig::default().spend_ledger, None);
+
+        let mut config = ResolvedConfig {
+            max_session_cost: Some(5.0),
+            ..Default::default()
+        };
+        assert_eq!(BudgetConfig::from_config(&config).spend_ledger, None);
+
+        config.max_daily_cost = Some(20.0);
+        assert_eq!(
+            BudgetConfig::from_config(&config).spend_ledger,
+            default_spend_ledger_path()
+        );
+    }
+
+    #[test]
+    fn test_daily_ledger_accumulates_and_resets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(SPEND_LEDGER_FILE);
+        assert_eq!(load_daily_spend(&path), 0.0);
+
+        record_daily_spend(&path, 0.25).unwrap();
+        let total = record_daily_spend(&path, 0.5).unwrap();
+        assert!((total - 0.75).abs() < 1e-9);
+        assert!((load_daily_spend(&path) - 0.75).abs() < 1e-9);
+
+        // A ledger from another day does not count
+        std::fs::write(&path, r#"{"date":"2000-01-01","cost":99.0}"#).unwrap();
+        assert_eq!(load_daily_spend(&path), 0.0);
+        let total = record_daily_spend(&path, 0.1).unwrap();
+        assert!((total - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_concurrent_spend_is_not_lost() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SPEND_LEDGER_FILE);
+
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        record_daily_spend(&path, 0.01).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert!((load_daily_spend(&path) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_format_cost() {
+        assert_eq!(format_cost(0.01234), "$0.0123");
+        assert_eq!(format_cost(12.345), "$12.35");
+    }
+}
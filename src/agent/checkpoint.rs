@@ -0,0 +1,499 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Turn-level file checkpoints for `/undo` and `/rewind`.
+//!
+//! Before a turn first changes a file, the file's current content is copied
+//! into a content-addressed object store. Every turn that touched files
+//! records a [`Checkpoint`] listing those files and their prior contents, so
+//! the turn's edits can be reverted later.
+//!
+//! Paths for `write_file`/`edit_file` come straight from the tool input. For
+//! `bash`, affected files are found through `git status` in the command's
+//! repository: dirty files are snapshotted before the command runs, and files
+//! it made dirty are restored from `HEAD` (or removed if they are new).
+//! Commands run outside a git repository are not checkpointed.
+//!
+//! Checkpoints of a saved session are kept in
+//! `~/.codi/checkpoints/<session-id>/` so they survive restarts; unsaved
+//! conversations keep them in memory.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::get_global_config_dir;
+use crate::tools::FILE_EDIT_TOOLS;
+use crate::types::ToolCall;
+
+/// Directory inside the global config directory holding session checkpoints.
+pub const CHECKPOINTS_DIR: &str = "checkpoints";
+
+/// Index file listing a session's checkpoints.
+const INDEX_FILE: &str = "index.json";
+
+/// Directory holding snapshotted file contents, named by their SHA-256.
+const OBJECTS_DIR: &str = "objects";
+
+/// Checkpoint store shared between the app and its agents.
+pub type SharedCheckpointStore = Arc<Mutex<CheckpointStore>>;
+
+/// A file's content before a turn changed it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    /// Absolute path of the file.
+    pub path: PathBuf,
+    /// Object holding the previous content, `None` if the file did not exist.
+    pub blob: Option<String>,
+}
+
+/// Files changed by one conversation turn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Zero-based index of the user turn in the conversation.
+    pub turn: usize,
+    /// Files in the order they were first changed.
+    pub files: Vec<FileSnapshot>,
+}
+
+/// Persisted list of checkpoints.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointIndex {
+    /// Turn index assigned to the next turn.
+    next_turn: usize,
+    /// Recorded checkpoints, oldest first.
+    checkpoints: Vec<Checkpoint>,
+}
+
+/// Git state captured before a shell command runs.
+#[derive(Debug)]
+pub struct CommandBaseline {
+    /// Root of the repository the command runs in.
+    repo: PathBuf,
+    /// Files that were already dirty.
+    dirty: HashSet<PathBuf>,
+}
+
+/// Store of per-turn file checkpoints.
+#[derive(Debug, Default)]
+pub struct CheckpointStore {
+    /// Directory for the index and objects (`None` keeps everything in memory).
+    dir: Option<PathBuf>,
+    index: CheckpointIndex,
+    /// Object contents when running in memory.
+    objects: HashMap<String, Vec<u8>>,
+    /// Checkpoint of the turn in progress.
+    pending: Option<Checkpoint>,
+}
+
+impl CheckpointStore {
+    /// Create a store that keeps checkpoints in memory only.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open (or create) a store persisted in `dir`.
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        let index = match std::fs::read_to_string(dir.join(INDEX_FILE)) {
+            Ok(content) => serde_json::from_str(&content).map_err(io::Error::other)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => CheckpointIndex::default(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            dir: Some(dir),
+            index,
+            ..Default::default()
+        })
+    }
+
+    /// Open the store of a saved session, falling back to memory if it can't be read.
+    pub fn for_session(session_id: &str) -> Self {
+        let Some(dir) = session_checkpoints_dir(session_id) else {
+            return Self::in_memory();
+        };
+        Self::open(&dir).unwrap_or_else(|e| {
+            tracing::warn!("Failed to open checkpoints in {}: {}", dir.display(), e);
+            Self::in_memory()
+        })
+    }
+
+    /// Recorded checkpoints, oldest first.
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.index.checkpoints
+    }
+
+    /// Turn index that the next turn will be recorded under.
+    pub fn next_turn(&self) -> usize {
+        self.index.next_turn
+    }
+
+    /// Set the turn index of the next turn, e.g. to match the conversation.
+    pub fn set_next_turn(&mut self, turn: usize) {
+        self.index.next_turn = turn;
+    }
+
+    /// Start recording a new turn.
+    pub fn begin_turn(&mut self) {
+        self.pending = Some(Checkpoint {
+            turn: self.index.next_turn,
+            files: Vec::new(),
+        });
+        self.index.next_turn += 1;
+    }
+
+    /// Finish the current turn, saving its checkpoint if any files changed.
+    ///
+    /// Returns the recorded checkpoint.
+    pub fn finish_turn(&mut self) -> io::Result<Option<&Checkpoint>> {
+        let recorded = match self.pending.take() {
+            Some(checkpoint) if !checkpoint.files.is_empty() => {
+                self.index.checkpoints.push(checkpoint);
+                true
+            }
+            _ => false,
+        };
+        self.save_index()?;
+        Ok(recorded.then(|| self.index.checkpoints.last()).flatten())
+    }
+
+    /// Snapshot a file before it is changed in the current turn.
+    ///
+    /// Does nothing outside a turn or if the file was already captured.
+    pub fn capture(&mut self, path: &Path) -> io::Result<()> {
+        if !self.is_capturing(path) || path.is_dir() {
+            return Ok(());
+        }
+        let content = match std::fs::read(path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        self.record(path, content)
+    }
+
+    /// Snapshot the files a tool call is about to change.
+    ///
+    /// Returns a baseline to pass to [`Self::finish_tool_call`] for shell
+    /// commands run inside a git repository. Failures are logged rather than
+    /// returned so checkpointing never blocks a tool.
+    pub fn begin_tool_call(&mut self, tool_call: &ToolCall) -> Option<CommandBaseline> {
+        self.pending.as_ref()?;
+
+        if FILE_EDIT_TOOLS.contains(&tool_call.name.as_str()) {
+            let path = tool_call.input.get("file_path").and_then(|p| p.as_str())?;
+            if let Err(e) = self.capture(Path::new(path)) {
+                tracing::warn!("Failed to checkpoint {}: {}", path, e);
+            }
+            return None;
+        }
+
+        if tool_call.name != "bash" {
+            return None;
+        }
+        let cwd = match tool_call.input.get("cwd").and_then(|c| c.as_str()) {
+            Some(cwd) => PathBuf::from(cwd),
+            None => std::env::current_dir().ok()?,
+        };
+        let repo = repo_root(&cwd)?;
+        let dirty = dirty_files(&repo)?;
+        for path in &dirty {
+            if let Err(e) = self.capture(path) {
+                tracing::warn!("Failed to checkpoint {}: {}", path.display(), e);
+            }
+        }
+        Some(CommandBaseline { repo, dirty })
+    }
+
+    /// Record files a shell command changed, using their content at `HEAD`.
+    pub fn finish_tool_call(&mut self, baseline: CommandBaseline) {
+        let Some(dirty) = dirty_files(&baseline.repo) else {
+            return;
+        };
+        for path in dirty.difference(&baseline.dirty) {
+            if !self.is_capturing(path) {
+                continue;
+            }
+            // Files not in HEAD are new and get removed on restore
+            let content = path
+                .strip_prefix(&baseline.repo)
+                .ok()
+                .and_then(|rel| git(&baseline.repo, &["show", &format!("HEAD:{}", rel.display())]));
+            if let Err(e) = self.record(path, content) {
+                tracing::warn!("Failed to checkpoint {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Revert the most recent checkpoint and drop it.
+    pub fn undo(&mut self) -> io::Result<Option<Checkpoint>> {
+        let Some(checkpoint) = self.index.checkpoints.pop() else {
+            return Ok(None);
+        };
+        self.restore(&checkpoint)?;
+        self.save_index()?;
+        Ok(Some(checkpoint))
+    }
+
+    /// Revert all checkpoints from `turn` onward, newest first, and continue
+    /// numbering from `turn`.
+    ///
+    /// Returns the reverted checkpoints in the order they were restored.
+    pub fn rewind_to(&mut self, turn: usize) -> io::Result<Vec<Checkpoint>> {
+        let mut reverted = Vec::new();
+        while self.index.checkpoints.last().is_some_and(|c| c.turn >= turn) {
+            let checkpoint = self.index.checkpoints.pop().expect("checked above");
+            self.restore(&checkpoint)?;
+            reverted.push(checkpoint);
+        }
+        self.index.next_turn = turn;
+        self.save_index()?;
+        Ok(reverted)
+    }
+
+    /// Write back the previous contents of every file in a checkpoint.
+    fn restore(&self, checkpoint: &Checkpoint) -> io::Result<()> {
+        for file in checkpoint.files.iter().rev() {
+            match &file.blob {
+                Some(id) => {
+                    let content = self.read_object(id)?;
+                    if let Some(parent) = file.path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&file.path, content)?;
+                }
+                None => match std::fs::remove_file(&file.path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a turn is in progress and `path` hasn't been captured yet.
+    fn is_capturing(&self, path: &Path) -> bool {
+        self.pending
+            .as_ref()
+            .is_some_and(|pending| pending.files.iter().all(|f| f.path != path))
+    }
+
+    /// Add a snapshot of `path` to the current turn.
+    fn record(&mut self, path: &Path, content: Option<Vec<u8>>) -> io::Result<()> {
+        let blob = content.map(|c| self.write_object(c)).transpose()?;
+        if let Some(ref mut pending) = self.pending {
+            pending.files.push(FileSnapshot {
+                path: path.to_path_buf(),
+                blob,
+            });
+        }
+        Ok(())
+    }
+
+    fn write_object(&mut self, content: Vec<u8>) -> io::Result<String> {
+        let id = format!("{:x}", Sha256::digest(&content));
+        match self.dir {
+            Some(ref dir) => {
+                let path = dir.join(OBJECTS_DIR).join(&id);
+                if !path.exists() {
+                    std::fs::create_dir_all(dir.join(OBJECTS_DIR))?;
+                    std::fs::write(path, content)?;
+                }
+            }
+            None => {
+                self.objects.insert(id.clone(), content);
+            }
+        }
+        Ok(id)
+    }
+
+    fn read_object(&self, id: &str) -> io::Result<Vec<u8>> {
+        match self.dir {
+            Some(ref dir) => std::fs::read(dir.join(OBJECTS_DIR).join(id)),
+            None => self.objects.get(id).cloned().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("missing checkpoint object {}", id))
+            }),
+        }
+    }
+
+    fn save_index(&self) -> io::Result<()> {
+        let Some(ref dir) = self.dir else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(dir)?;
+        let json = serde_json::to_string_pretty(&self.index).map_err(io::Error::other)?;
+        std::fs::write(dir.join(INDEX_FILE), json)
+    }
+}
+
+/// Directory holding the checkpoints of a saved session.
+pub fn session_checkpoints_dir(session_id: &str) -> Option<PathBuf> {
+    get_global_config_dir().map(|dir| dir.join(CHECKPOINTS_DIR).join(session_id))
+}
+
+/// Delete the checkpoints of a saved session.
+pub fn remove_session_checkpoints(session_id: &str) -> io::Result<()> {
+    match session_checkpoints_dir(session_id) {
+        Some(dir) if dir.exists() => std::fs::remove_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+/// Run a git command, returning stdout on success.
+fn git(cwd: &Path, args: &[&str]) -> Option<Vec<u8>> {
+    let output = Command::new("git").args(args).current_dir(cwd).output().ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+/// Root of the git repository containing `cwd`.
+fn repo_root(cwd: &Path) -> Option<PathBuf> {
+    let stdout = git(cwd, &["rev-parse", "--show-toplevel"])?;
+    Some(PathBuf::from(String::from_utf8_lossy(&stdout).trim()))
+}
+
+/// Absolute paths of modified, deleted and untracked files in a repository.
+fn dirty_files(repo: &Path) -> Option<HashSet<PathBuf>> {
+    let stdout = git(repo, &["status", "--porcelain", "-z", "--untracked-files=all"])?;
+    let mut files = HashSet::new();
+    let mut entries = stdout.split(|b| *b == 0).filter(|e| e.len() > 3);
+    while let Some(entry) = entries.next() {
+        let entry = String::from_utf8_lossy(entry);
+        files.insert(repo.join(&entry[3..]));
+        // Renames and copies are followed by their source path
+        if matches!(entry.as_bytes()[0], b'R' | b'C') {
+            if let Some(source) = entries.next() {
+                files.insert(repo.join(String::from_utf8_lossy(source).as_ref()));
+            }
+        }
+    }
+    Some(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_call(name: &str, input: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: "call-1".to_string(),
+            name: name.to_string(),
+            input,
+        }
+    }
+
+    fn write_file_call(path: &Path) -> ToolCall {
+        tool_call("write_file", serde_json::json!({ "file_path": path }))
+    }
+
+    #[test]
+    fn test_undo_restores_and_removes_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let existing = temp.path().join("existing.txt");
+        let created = temp.path().join("created.txt");
+        std::fs::write(&existing, "original").unwrap();
+
+        let mut store = CheckpointStore::in_memory();
+        store.begin_turn();
+        store.begin_tool_call(&write_file_call(&existing));
+        std::fs::write(&existing, "first edit").unwrap();
+        // A second edit in the same turn keeps the original snapshot
+        store.begin_tool_call(&write_file_call(&existing));
+        std::fs::write(&existing, "second edit").unwrap();
+        store.begin_tool_call(&write_file_call(&created));
+        std::fs::write(&created, "new").unwrap();
+
+        let checkpoint = store.finish_turn().unwrap().unwrap();
+        assert_eq!(checkpoint.turn, 0);
+        assert_eq!(checkpoint.files.len(), 2);
+
+        let undone = store.undo().unwrap().unwrap();
+        assert_eq!(undone.turn, 0);
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "original");
+        assert!(!created.exists());
+        assert!(store.undo().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_turns_without_changes_are_not_recorded() {
+        let mut store = CheckpointStore::in_memory();
+        store.begin_turn();
+        store.begin_tool_call(&tool_call("read_file", serde_json::json!({ "file_path": "/tmp/x" })));
+        assert!(store.finish_turn().unwrap().is_none());
+        assert!(store.checkpoints().is_empty());
+        assert_eq!(store.next_turn(), 1);
+    }
+
+    #[test]
+    fn test_rewind_reverts_later_turns_and_persists() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("file.txt");
+        let dir = temp.path().join("checkpoints");
+        std::fs::write(&file, "v0").unwrap();
+
+        let mut store = CheckpointStore::open(&dir).unwrap();
+        for version in ["v1", "v2", "v3"] {
+            store.begin_turn();
+            store.begin_tool_call(&write_file_call(&file));
+            std::fs::write(&file, version).unwrap();
+            store.finish_turn().unwrap();
+        }
+
+        // Checkpoints survive reopening the store
+        let mut store = CheckpointStore::open(&dir).unwrap();
+        assert_eq!(store.checkpoints().len(), 3);
+        assert_eq!(store.next_turn(), 3);
+
+        let reverted = store.rewind_to(1).unwrap();
+        assert_eq!(reverted.iter().map(|c| c.turn).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+        assert_eq!(store.next_turn(), 1);
+
+        let store = CheckpointStore::open(&dir).unwrap();
+        assert_eq!(store.checkpoints().len(), 1);
+    }
+
+    #[test]
+    fn test_bash_changes_are_captured_from_git() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().canonicalize().unwrap();
+        let run = |args: &[&str]| {
+            let ok = Command::new("git")
+                .args(args)
+                .current_dir(&repo)
+                .output()
+                .map(|o| o.status.success())
+                .unwrap_or(false);
+            assert!(ok, "git {:?} failed", args);
+        };
+        run(&["init", "-q"]);
+        run(&["config", "user.email", "test@example.com"]);
+        run(&["config", "user.name", "Test"]);
+        std::fs::write(repo.join("tracked.txt"), "committed").unwrap();
+        std::fs::write(repo.join("dirty.txt"), "committed").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "-q", "-m", "init"]);
+        std::fs::write(repo.join("dirty.txt"), "uncommitted").unwrap();
+
+        let mut store = CheckpointStore::in_memory();
+        store.begin_turn();
+        let call = tool_call("bash", serde_json::json!({ "command": "…", "cwd": repo }));
+        let baseline = store.begin_tool_call(&call).expect("inside a git repo");
+        std::fs::write(repo.join("tracked.txt"), "changed").unwrap();
+        std::fs::write(repo.join("dirty.txt"), "changed").unwrap();
+        std::fs::write(repo.join("new.txt"), "new").unwrap();
+        store.finish_tool_call(baseline);
+        assert_eq!(store.finish_turn().unwrap().unwrap().files.len(), 3);
+
+        store.undo().unwrap();
+        assert_eq!(std::fs::read_to_string(repo.join("tracked.txt")).unwrap(), "committed");
+        assert_eq!(std::fs::read_to_string(repo.join("dirty.txt")).unwrap(), "uncommitted");
+        assert!(!repo.join("new.txt").exists());
+    }
+}
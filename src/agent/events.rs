@@ -0,0 +1,419 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Machine-readable run events for non-interactive output.
+//!
+//! [`RunReporter`] builds [`AgentCallbacks`] that record the tool calls and
+//! usage of a run for the final JSON report and, for `--output-format
+//! stream-json`, write each [`RunEvent`] as a line of JSON as it happens:
+//!
+//! ```text
+//! {"type":"text_delta","text":"Let me look"}
+//! {"type":"tool_call","id":"toolu_1","name":"read_file","input":{"file_path":"src/lib.rs"}}
+//! {"type":"tool_result","id":"toolu_1","name":"read_file","content":"...","isError":false}
+//! {"type":"turn_complete","stats":{"toolCallCount":1,...}}
+//! {"type":"result","success":true,"response":"...","toolCalls":[...],"usage":{...},"cost":0.0021}
+//! ```
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use super::plan::Plan;
+use super::{AgentCallbacks, ConfirmationResult, ToolConfirmation, TurnStats};
+use crate::types::{StopReason, StreamEvent, TokenUsage};
+
+/// An event of a non-interactive run, serialized with a `type` tag.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum RunEvent {
+    /// A chunk of response text.
+    TextDelta { text: String },
+    /// A chunk of reasoning/thinking text.
+    ReasoningDelta { text: String },
+    /// The model started a tool use block.
+    ToolUseStart { id: String, name: String },
+    /// A chunk of tool input JSON.
+    ToolInputDelta { json: String },
+    /// The model finished a tool use block.
+    ToolUseEnd,
+    /// Token usage reported for a model request.
+    Usage { usage: TokenUsage },
+    /// A model request finished.
+    Done { stop_reason: StopReason },
+    /// The provider reported an error.
+    Error { message: String },
+    /// A provider request failed and will be retried.
+    Retrying {
+        attempt: u32,
+        max_attempts: u32,
+        delay_ms: u64,
+        reason: String,
+    },
+    /// A provider request failed and the next model in the fallback chain answers instead.
+    Fallback { from: String, to: String, reason: String },
+    /// A tool is about to run.
+    ToolCall {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    /// A tool finished.
+    ToolResult {
+        id: String,
+        name: String,
+        content: String,
+        is_error: bool,
+    },
+    /// A tool call needed confirmation (always approved in non-interactive mode).
+    Confirmation {
+        tool_name: String,
+        input: serde_json::Value,
+        is_dangerous: bool,
+        danger_reason: Option<String>,
+        approved: bool,
+    },
+    /// A hook blocked an action or reported feedback.
+    Hook { event: String, message: String },
+    /// The agent turn finished.
+    TurnComplete { stats: TurnStats },
+    /// Final outcome of the run.
+    Result(RunResult),
+}
+
+impl From<&StreamEvent> for RunEvent {
+    fn from(event: &StreamEvent) -> Self {
+        match event {
+            StreamEvent::TextDelta(text) => Self::TextDelta { text: text.clone() },
+            StreamEvent::ReasoningDelta(text) => Self::ReasoningDelta { text: text.clone() },
+            StreamEvent::ToolUseStart { id, name } => Self::ToolUseStart {
+                id: id.clone(),
+                name: name.clone(),
+            },
+            StreamEvent::ToolInputDelta(json) => Self::ToolInputDelta { json: json.clone() },
+            StreamEvent::ToolUseEnd => Self::ToolUseEnd,
+            StreamEvent::Usage(usage) => Self::Usage { usage: usage.clone() },
+            StreamEvent::Done(stop_reason) => Self::Done { stop_reason: *stop_reason },
+            StreamEvent::Error(message) => Self::Error { message: message.clone() },
+            StreamEvent::Retrying { attempt, max_attempts, delay_ms, reason } => Self::Retrying {
+                attempt: *attempt,
+                max_attempts: *max_attempts,
+                delay_ms: *delay_ms,
+                reason: reason.clone(),
+            },
+            StreamEvent::Fallback { from, to, reason } => Self::Fallback {
+                from: from.clone(),
+                to: to.clone(),
+                reason: reason.clone(),
+            },
+        }
+    }
+}
+
+/// A tool call made during a run, with its result.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallRecord {
+    /// Tool use ID.
+    pub id: String,
+    /// Tool name.
+    pub name: String,
+    /// Tool input arguments.
+    pub input: serde_json::Value,
+    /// Tool output (`None` if the call never finished).
+    pub result: Option<String>,
+    /// Whether the call failed.
+    pub is_error: bool,
+}
+
+/// Final report of a non-interactive run.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunResult {
+    /// Whether the run completed without error.
+    pub success: bool,
+    /// Final response text.
+    pub response: String,
+    /// Tool calls made, in order.
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// Turn statistics including token usage.
+    pub usage: Option<TurnStats>,
+    /// Session cost in USD.
+    pub cost: f64,
+    /// Error message if the run failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Plan produced in plan mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<Plan>,
+    /// Final answer parsed as JSON when an output schema was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<serde_json::Value>,
+}
+
+impl RunResult {
+    /// Report of a run that failed before the agent started.
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            response: String::new(),
+            tool_calls: Vec::new(),
+            usage: None,
+            cost: 0.0,
+            error: Some(error.into()),
+            plan: None,
+            output: None,
+        }
+    }
+}
+
+/// Shared writer that receives newline-delimited events.
+pub type EventWriter = Arc<Mutex<dyn Write + Send>>;
+
+#[derive(Debug, Default)]
+struct RunState {
+    tool_calls: Vec<ToolCallRecord>,
+    stats: Option<TurnStats>,
+}
+
+/// Records a run through agent callbacks and optionally streams its events.
+#[derive(Clone, Default)]
+pub struct RunReporter {
+    writer: Option<EventWriter>,
+    state: Arc<Mutex<RunState>>,
+}
+
+impl RunReporter {
+    /// Create a reporter that only records the run.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a reporter that also writes every event to `writer` as NDJSON.
+    pub fn streaming(writer: EventWriter) -> Self {
+        Self {
+            writer: Some(writer),
+            ..Default::default()
+        }
+    }
+
+    /// Write an event line, if streaming.
+    pub fn emit(&self, event: &RunEvent) {
+        let Some(ref writer) = self.writer else {
+            return;
+        };
+        match serde_json::to_string(event) {
+            Ok(line) => {
+                let mut writer = writer.lock().unwrap();
+                let _ = writeln!(writer, "{}", line);
+                let _ = writer.flush();
+            }
+            Err(e) => tracing::warn!("Failed to serialize run event: {}", e),
+        }
+    }
+
+    /// Tool calls recorded so far.
+    pub fn tool_calls(&self) -> Vec<ToolCallRecord> {
+        self.state.lock().unwrap().tool_calls.clone()
+    }
+
+    /// Stats of the last completed turn.
+    pub fn stats(&self) -> Option<TurnStats> {
+        self.state.lock().unwrap().stats.clone()
+    }
+
+    /// Build the final report from the agent's outcome.
+    pub fn result(&self, outcome: Result<String, String>, cost: f64) -> RunResult {
+        let (success, response, error) = match outcome {
+            Ok(response) => (true, response, None),
+            Err(error) => (false, String::new(), Some(error)),
+        };
+        RunResult {
+            success,
+            response,
+            tool_calls: self.tool_calls(),
+            usage: self.stats(),
+            cost,
+            error,
+            plan: None,
+            output: None,
+        }
+    }
+
+    /// Agent callbacks that feed this reporter.
+    ///
+    /// Confirmation and hook callbacks are only installed when streaming, so a
+    /// recording-only reporter doesn't change how tools are approved.
+    pub fn callbacks(&self) -> AgentCallbacks {
+        let on_tool_call = {
+            let reporter = self.clone();
+            Arc::new(move |id: &str, name: &str, input: &serde_json::Value| {
+                reporter.state.lock().unwrap().tool_calls.push(ToolCallRecord {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    input: input.clone(),
+                    result: None,
+                    is_error: false,
+                });
+                reporter.emit(&RunEvent::ToolCall {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    input: input.clone(),
+                });
+            })
+        };
+
+        let on_tool_result = {
+            let reporter = self.clone();
+            Arc::new(move |id: &str, name: &str, result: &str, is_error: bool| {
+                if let Some(record) = reporter
+                    .state
+                    .lock()
+                    .unwrap()
+                    .tool_calls
+                    .iter_mut()
+                    .rev()
+                    .find(|r| r.id == id)
+                {
+                    record.result = Some(result.to_string());
+                    record.is_error = is_error;
+                }
+                reporter.emit(&RunEvent::ToolResult {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    content: result.to_string(),
+                    is_error,
+                });
+            })
+        };
+
+        let on_turn_complete = {
+            let reporter = self.clone();
+            Arc::new(move |stats: &TurnStats| {
+                reporter.state.lock().unwrap().stats = Some(stats.clone());
+                reporter.emit(&RunEvent::TurnComplete { stats: stats.clone() });
+            })
+        };
+
+        let mut callbacks = AgentCallbacks {
+            on_tool_call: Some(on_tool_call),
+            on_tool_result: Some(on_tool_result),
+            on_turn_complete: Some(on_turn_complete),
+            ..Default::default()
+        };
+
+        if self.writer.is_some() {
+            let reporter = self.clone();
+            callbacks.on_stream_event = Some(Arc::new(move |event: &StreamEvent| {
+                reporter.emit(&RunEvent::from(event));
+            }));
+
+            let reporter = self.clone();
+            callbacks.on_confirm = Some(Arc::new(move |confirmation: ToolConfirmation| {
+                reporter.emit(&RunEvent::Confirmation {
+                    tool_name: confirmation.tool_name,
+                    input: confirmation.input,
+                    is_dangerous: confirmation.is_dangerous,
+                    danger_reason: confirmation.danger_reason,
+                    approved: true,
+                });
+                ConfirmationResult::Approve
+            }));
+
+            let reporter = self.clone();
+            callbacks.on_hook = Some(Arc::new(move |event: &str, message: &str| {
+                reporter.emit(&RunEvent::Hook {
+                    event: event.to_string(),
+                    message: message.to_string(),
+                });
+            }));
+        }
+
+        callbacks
+    }
+}
+
+impl std::fmt::Debug for RunReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunReporter")
+            .field("streaming", &self.writer.is_some())
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captured(buffer: &Arc<Mutex<Vec<u8>>>) -> Vec<serde_json::Value> {
+        String::from_utf8(buffer.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_stream_events_serialize_with_type_tag() {
+        let event = RunEvent::from(&StreamEvent::ToolUseStart {
+            id: "toolu_1".to_string(),
+            name: "grep".to_string(),
+        });
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({"type": "tool_use_start", "id": "toolu_1", "name": "grep"})
+        );
+
+        let event = RunEvent::from(&StreamEvent::Done(StopReason::ToolUse));
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({"type": "done", "stopReason": "tool_use"})
+        );
+    }
+
+    #[test]
+    fn test_reporter_records_and_streams_tool_calls() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let reporter = RunReporter::streaming(buffer.clone());
+        let callbacks = reporter.callbacks();
+
+        let input = serde_json::json!({"pattern": "TODO"});
+        (callbacks.on_tool_call.as_ref().unwrap())("toolu_1", "grep", &input);
+        (callbacks.on_tool_result.as_ref().unwrap())("toolu_1", "grep", "src/lib.rs:3", false);
+        let approval = (callbacks.on_confirm.as_ref().unwrap())(ToolConfirmation {
+            tool_name: "bash".to_string(),
+            input: serde_json::json!({"command": "rm -rf target"}),
+            is_dangerous: true,
+            danger_reason: Some("rm -rf".to_string()),
+        });
+        assert_eq!(approval, ConfirmationResult::Approve);
+        let stats = TurnStats {
+            tool_call_count: 1,
+            input_tokens: 120,
+            ..Default::default()
+        };
+        (callbacks.on_turn_complete.as_ref().unwrap())(&stats);
+
+        let events = captured(&buffer);
+        let types: Vec<&str> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+        assert_eq!(types, vec!["tool_call", "tool_result", "confirmation", "turn_complete"]);
+        assert_eq!(events[1]["isError"], false);
+        assert_eq!(events[3]["stats"]["inputTokens"], 120);
+
+        let result = serde_json::to_value(reporter.result(Ok("Found it".to_string()), 0.5)).unwrap();
+        assert_eq!(result["toolCalls"][0]["name"], "grep");
+        assert_eq!(result["toolCalls"][0]["result"], "src/lib.rs:3");
+        assert_eq!(result["usage"]["toolCallCount"], 1);
+        assert!(result.get("error").is_none());
+    }
+
+    #[test]
+    fn test_recording_reporter_leaves_confirmation_alone() {
+        let callbacks = RunReporter::new().callbacks();
+        assert!(callbacks.on_confirm.is_none());
+        assert!(callbacks.on_stream_event.is_none());
+        assert!(callbacks.on_tool_call.is_some());
+    }
+}
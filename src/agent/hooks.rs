@@ -0,0 +1,355 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! User-configured shell hooks around tool calls, prompts and turns.
+//!
+//! Hooks are defined in the `hooks` section of the config:
+//!
+//! ```json
+//! {
+//!   "hooks": {
+//!     "preToolUse": [{ "command": "./scripts/guard.sh", "tools": ["bash"] }],
+//!     "postToolUse": [{ "command": "cargo fmt", "tools": ["write_file", "edit_file"] }],
+//!     "userPromptSubmit": [{ "command": "tee -a .codi/prompts.log" }],
+//!     "turnComplete": [{ "command": "notify-send codi done" }]
+//!   }
+//! }
+//! ```
+//!
+//! Each hook runs through the shell with a JSON payload on stdin containing
+//! the event name and, for tool hooks, `toolName` and `input`. The event and
+//! tool name are also set in `CODI_HOOK_EVENT` and `CODI_TOOL_NAME`.
+//!
+//! A nonzero exit from a `preToolUse` hook blocks the tool call and its
+//! stderr is returned to the model as the tool result. A nonzero exit from a
+//! `userPromptSubmit` hook rejects the prompt. Failing `postToolUse` hooks
+//! append their stderr to the tool result; `turnComplete` failures are only
+//! logged.
+
+use std::fmt;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use crate::config::{HookConfig, HooksConfig};
+use crate::types::{ToolCall, ToolResult};
+
+use super::TurnStats;
+
+/// Default hook timeout.
+pub const DEFAULT_HOOK_TIMEOUT_MS: u64 = 60_000;
+
+/// Event that triggers a hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// Before a tool call runs.
+    PreToolUse,
+    /// After a tool call ran.
+    PostToolUse,
+    /// When the user submits a prompt.
+    UserPromptSubmit,
+    /// After an agent turn finished.
+    TurnComplete,
+}
+
+impl HookEvent {
+    /// Config key of the event.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PreToolUse => "preToolUse",
+            Self::PostToolUse => "postToolUse",
+            Self::UserPromptSubmit => "userPromptSubmit",
+            Self::TurnComplete => "turnComplete",
+        }
+    }
+}
+
+impl fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Result of running one hook command.
+#[derive(Debug, Clone)]
+struct HookOutput {
+    success: bool,
+    stderr: String,
+}
+
+/// Runs the configured hooks for agent events.
+#[derive(Debug, Clone, Default)]
+pub struct HookRunner {
+    config: HooksConfig,
+}
+
+impl HookRunner {
+    /// Create a runner for the given hooks.
+    pub fn new(config: HooksConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether no hooks are configured.
+    pub fn is_empty(&self) -> bool {
+        self.config.is_empty()
+    }
+
+    /// Run `preToolUse` hooks. Returns the reason if a hook blocked the call.
+    pub async fn pre_tool_use(&self, tool_call: &ToolCall) -> Result<(), String> {
+        let payload = tool_payload(HookEvent::PreToolUse, tool_call);
+        for hook in matching(&self.config.pre_tool_use, &tool_call.name) {
+            let output = run_hook(hook, HookEvent::PreToolUse, Some(&tool_call.name), &payload).await;
+            if !output.success {
+                return Err(failure_message(hook, &output));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `postToolUse` hooks. Returns feedback from hooks that failed.
+    pub async fn post_tool_use(&self, tool_call: &ToolCall, result: &ToolResult) -> Option<String> {
+        let mut payload = tool_payload(HookEvent::PostToolUse, tool_call);
+        payload["result"] = serde_json::json!(result.content);
+        payload["isError"] = serde_json::json!(result.is_error.unwrap_or(false));
+
+        let mut feedback = Vec::new();
+        for hook in matching(&self.config.post_tool_use, &tool_call.name) {
+            let output = run_hook(hook, HookEvent::PostToolUse, Some(&tool_call.name), &payload).await;
+            if !output.success {
+                feedback.push(failure_message(hook, &output));
+            }
+        }
+        (!feedback.is_empty()).then(|| feedback.join("\n"))
+    }
+
+    /// Run `userPromptSubmit` hooks. Returns the reason if a hook rejected the prompt.
+    pub async fn user_prompt_submit(&self, prompt: &str) -> Result<(), String> {
+        let payload = serde_json::json!({
+            "event": HookEvent::UserPromptSubmit.as_str(),
+            "prompt": prompt,
+        });
+        for hook in &self.config.user_prompt_submit {
+            let output = run_hook(hook, HookEvent::UserPromptSubmit, None, &payload).await;
+            if !output.success {
+                return Err(failure_message(hook, &output));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `turnComplete` hooks, logging failures.
+    pub async fn turn_complete(&self, stats: &TurnStats, response: &str) {
+        let payload = serde_json::json!({
+            "event": HookEvent::TurnComplete.as_str(),
+            "response": response,
+            "stats": {
+                "toolCallCount": stats.tool_call_count,
+                "inputTokens": stats.input_tokens,
+                "outputTokens": stats.output_tokens,
+                "totalTokens": stats.total_tokens,
+                "cost": stats.cost,
+                "durationMs": stats.duration_ms,
+            },
+        });
+        for hook in &self.config.turn_complete {
+            let output = run_hook(hook, HookEvent::TurnComplete, None, &payload).await;
+            if !output.success {
+                tracing::warn!("{}", failure_message(hook, &output));
+            }
+        }
+    }
+}
+
+/// Hooks that apply to a tool.
+fn matching<'a>(hooks: &'a [HookConfig], tool_name: &'a str) -> impl Iterator<Item = &'a HookConfig> {
+    hooks.iter().filter(move |hook| {
+        hook.tools
+            .as_ref()
+            .is_none_or(|tools| tools.iter().any(|t| t == tool_name))
+    })
+}
+
+fn tool_payload(event: HookEvent, tool_call: &ToolCall) -> serde_json::Value {
+    serde_json::json!({
+        "event": event.as_str(),
+        "toolName": tool_call.name,
+        "toolUseId": tool_call.id,
+        "input": tool_call.input,
+    })
+}
+
+fn failure_message(hook: &HookConfig, output: &HookOutput) -> String {
+    let stderr = output.stderr.trim();
+    if stderr.is_empty() {
+        format!("Hook `{}` failed", hook.command)
+    } else {
+        format!("Hook `{}` failed: {}", hook.command, stderr)
+    }
+}
+
+/// Run a hook command with the payload on stdin.
+///
+/// Failing to start the command or timing out counts as a failure.
+async fn run_hook(
+    hook: &HookConfig,
+    event: HookEvent,
+    tool_name: Option<&str>,
+    payload: &serde_json::Value,
+) -> HookOutput {
+    let (shell, shell_flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut cmd = Command::new(shell);
+    cmd.arg(shell_flag)
+        .arg(&hook.command)
+        .env("CODI_HOOK_EVENT", event.as_str())
+        .env("CODI_TOOL_NAME", tool_name.unwrap_or_default())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let failed = |stderr: String| HookOutput { success: false, stderr };
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return failed(format!("failed to start: {}", e)),
+    };
+
+    // Feed stdin, drain stderr and wait together so a hook that never reads
+    // a large payload can't block past the timeout
+    let stdin = child.stdin.take();
+    let mut stderr = child.stderr.take();
+    let input = payload.to_string();
+    let write = async move {
+        if let Some(mut stdin) = stdin {
+            // Hooks that don't read stdin may exit first; that's fine
+            let _ = stdin.write_all(input.as_bytes()).await;
+        }
+    };
+    let read = async {
+        let mut buf = Vec::new();
+        if let Some(stderr) = stderr.as_mut() {
+            let _ = stderr.read_to_end(&mut buf).await;
+        }
+        buf
+    };
+    let run = async {
+        let ((), stderr, status) = tokio::join!(write, read, child.wait());
+        status.map(|status| (status, stderr))
+    };
+
+    let timeout = Duration::from_millis(hook.timeout_ms.unwrap_or(DEFAULT_HOOK_TIMEOUT_MS));
+    match tokio::time::timeout(timeout, run).await {
+        Ok(Ok((status, stderr))) => HookOutput {
+            success: status.success(),
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        },
+        Ok(Err(e)) => failed(e.to_string()),
+        Err(_) => {
+            let _ = child.kill().await;
+            failed(format!("timed out after {}ms", timeout.as_millis()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(command: &str, tools: Option<&[&str]>) -> HookConfig {
+        HookConfig {
+            command: command.to_string(),
+            tools: tools.map(|t| t.iter().map(|s| s.to_string()).collect()),
+            timeout_ms: None,
+        }
+    }
+
+    fn bash_call(command: &str) -> ToolCall {
+        ToolCall {
+            id: "call-1".to_string(),
+            name: "bash".to_string(),
+            input: serde_json::json!({ "command": command }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pre_tool_use_blocks_on_nonzero_exit() {
+        let runner = HookRunner::new(HooksConfig {
+            pre_tool_use: vec![hook(
+                "grep -q deploy/ && echo 'deploy/ is off limits' >&2 && exit 1; exit 0",
+                Some(&["bash"]),
+            )],
+            ..Default::default()
+        });
+
+        assert!(runner.pre_tool_use(&bash_call("ls src/")).await.is_ok());
+        let err = runner.pre_tool_use(&bash_call("rm -rf deploy/")).await.unwrap_err();
+        assert!(err.contains("deploy/ is off limits"), "{}", err);
+
+        // Hooks scoped to other tools don't run
+        let write = ToolCall {
+            name: "write_file".to_string(),
+            ..bash_call("deploy/")
+        };
+        assert!(runner.pre_tool_use(&write).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_hooks_receive_payload_on_stdin() {
+        let temp = tempfile::tempdir().unwrap();
+        let payload_file = temp.path().join("payload.json");
+        let event_file = temp.path().join("event.txt");
+        let runner = HookRunner::new(HooksConfig {
+            post_tool_use: vec![hook(
+                &format!(
+                    "cat > {}; echo \"$CODI_HOOK_EVENT $CODI_TOOL_NAME\" > {}",
+                    payload_file.display(),
+                    event_file.display()
+                ),
+                None,
+            )],
+            ..Default::default()
+        });
+
+        let result = ToolResult::success("call-1", "ok");
+        assert!(runner.post_tool_use(&bash_call("ls"), &result).await.is_none());
+
+        let payload: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&payload_file).unwrap()).unwrap();
+        assert_eq!(payload["toolName"], "bash");
+        assert_eq!(payload["input"]["command"], "ls");
+        assert_eq!(payload["result"], "ok");
+        assert_eq!(std::fs::read_to_string(&event_file).unwrap().trim(), "postToolUse bash");
+    }
+
+    #[tokio::test]
+    async fn test_prompt_hook_rejects_and_timeout_fails() {
+        let runner = HookRunner::new(HooksConfig {
+            user_prompt_submit: vec![HookConfig {
+                timeout_ms: Some(50),
+                ..hook("sleep 5", None)
+            }],
+            ..Default::default()
+        });
+        let err = runner.user_prompt_submit("hello").await.unwrap_err();
+        assert!(err.contains("timed out"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_covers_unread_payload() {
+        let runner = HookRunner::new(HooksConfig {
+            pre_tool_use: vec![HookConfig {
+                timeout_ms: Some(200),
+                ..hook("sleep 5", None)
+            }],
+            ..Default::default()
+        });
+
+        // Larger than a pipe buffer, so writing it blocks until the hook exits
+        let call = bash_call(&"x".repeat(1024 * 1024));
+        let started = std::time::Instant::now();
+        let err = runner.pre_tool_use(&call).await.unwrap_err();
+        assert!(err.contains("timed out"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+}
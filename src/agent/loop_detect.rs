@@ -0,0 +1,218 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Detection of repetitive tool use within a turn.
+//!
+//! Weaker models sometimes get stuck calling the same tool with the same
+//! input over and over, or flipping a file between two versions. The
+//! [`LoopDetector`] fingerprints every tool call and result of a turn and
+//! reports two patterns:
+//!
+//! - **Repeats**: the same call returned the same result
+//!   [`AgentConfig::loop_repeat_threshold`](super::AgentConfig::loop_repeat_threshold) times.
+//! - **Oscillation**: an edit to a file repeats the edit made two edits
+//!   earlier (A→B→A→B), [`AgentConfig::loop_oscillation_threshold`](super::AgentConfig::loop_oscillation_threshold)
+//!   times.
+//!
+//! The agent answers the first detections with a corrective note and stops
+//! the turn with [`AgentError::LoopDetected`](crate::error::AgentError::LoopDetected)
+//! once the pattern continues past
+//! [`AgentConfig::max_loop_warnings`](super::AgentConfig::max_loop_warnings).
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::tools::FILE_EDIT_TOOLS;
+use crate::types::{ToolCall, ToolResult};
+
+/// A repetitive pattern found in a turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoopPattern {
+    /// The same call produced the same result `count` times.
+    Repeat { tool: String, count: usize },
+    /// Edits to `path` keep returning to an earlier version.
+    Oscillation { path: String, count: usize },
+}
+
+impl fmt::Display for LoopPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Repeat { tool, count } => write!(
+                f,
+                "`{}` was called {} times with the same input and returned the same result",
+                tool, count
+            ),
+            Self::Oscillation { path, count } => write!(
+                f,
+                "edits to {} keep undoing each other ({} times)",
+                path, count
+            ),
+        }
+    }
+}
+
+impl LoopPattern {
+    /// Corrective note sent to the model when the pattern is first seen.
+    pub fn correction_message(&self) -> String {
+        let advice = match self {
+            Self::Repeat { .. } => "Repeating the call will not give a different result. Use what \
+                 you already have, try a different approach, or finish with what you know.",
+            Self::Oscillation { .. } => "Stop switching the file between versions. Decide on one \
+                 version, explain why, and move on.",
+        };
+        format!("System note: {}. {}", self, advice)
+    }
+}
+
+/// Thresholds for [`LoopDetector`]. A threshold of 0 disables that check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopThresholds {
+    /// Identical call-and-result count that counts as a loop.
+    pub repeat: usize,
+    /// A→B→A edit returns on one file that count as oscillation.
+    pub oscillation: usize,
+}
+
+/// Fingerprints tool calls within a turn to find loops.
+#[derive(Debug)]
+pub struct LoopDetector {
+    thresholds: LoopThresholds,
+    /// Occurrences of each (call, result) fingerprint.
+    repeats: HashMap<u64, usize>,
+    /// Fingerprints of successful edits per file, oldest first.
+    edits: HashMap<String, Vec<u64>>,
+    /// Returns to an earlier version per file.
+    oscillations: HashMap<String, usize>,
+}
+
+impl LoopDetector {
+    /// Create a detector with the given thresholds.
+    pub fn new(thresholds: LoopThresholds) -> Self {
+        Self {
+            thresholds,
+            repeats: HashMap::new(),
+            edits: HashMap::new(),
+            oscillations: HashMap::new(),
+        }
+    }
+
+    /// Record a tool call and its result. Returns the pattern it completes, if any.
+    pub fn record(&mut self, call: &ToolCall, result: &ToolResult) -> Option<LoopPattern> {
+        let call_print = fingerprint(&(&call.name, call.input.to_string()));
+
+        let count = self
+            .repeats
+            .entry(fingerprint(&(call_print, &result.content)))
+            .or_insert(0);
+        *count += 1;
+        if self.thresholds.repeat > 0 && *count >= self.thresholds.repeat {
+            return Some(LoopPattern::Repeat {
+                tool: call.name.clone(),
+                count: *count,
+            });
+        }
+
+        // Failed edits don't change the file, so they can't oscillate
+        if result.is_error.unwrap_or(false) || !FILE_EDIT_TOOLS.contains(&call.name.as_str()) {
+            return None;
+        }
+        let path = ["file_path", "path"]
+            .iter()
+            .find_map(|key| call.input.get(*key).and_then(|v| v.as_str()))?;
+
+        let history = self.edits.entry(path.to_string()).or_default();
+        let returns = history.len() >= 2
+            && history[history.len() - 2] == call_print
+            && history[history.len() - 1] != call_print;
+        history.push(call_print);
+        if !returns {
+            return None;
+        }
+
+        let count = self.oscillations.entry(path.to_string()).or_insert(0);
+        *count += 1;
+        (self.thresholds.oscillation > 0 && *count >= self.thresholds.oscillation).then(|| {
+            LoopPattern::Oscillation {
+                path: path.to_string(),
+                count: *count,
+            }
+        })
+    }
+}
+
+fn fingerprint(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, input: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: "id".to_string(),
+            name: name.to_string(),
+            input,
+        }
+    }
+
+    fn detector() -> LoopDetector {
+        LoopDetector::new(LoopThresholds {
+            repeat: 3,
+            oscillation: 2,
+        })
+    }
+
+    #[test]
+    fn test_repeated_call_with_same_result() {
+        let mut detector = detector();
+        let grep = call("grep", serde_json::json!({ "pattern": "foo" }));
+        let same = ToolResult::success("id", "no matches");
+
+        assert_eq!(detector.record(&grep, &same), None);
+        // A different result means the call made progress
+        assert_eq!(detector.record(&grep, &ToolResult::success("id", "a.rs:1")), None);
+        assert_eq!(detector.record(&grep, &same), None);
+        assert_eq!(
+            detector.record(&grep, &same),
+            Some(LoopPattern::Repeat { tool: "grep".to_string(), count: 3 })
+        );
+    }
+
+    #[test]
+    fn test_edit_oscillation() {
+        let mut detector = detector();
+        let edit = |old: &str, new: &str| {
+            call(
+                "edit_file",
+                serde_json::json!({ "file_path": "src/lib.rs", "old_string": old, "new_string": new }),
+            )
+        };
+        let ok = ToolResult::success("id", "Edited src/lib.rs");
+
+        assert_eq!(detector.record(&edit("a", "b"), &ok), None);
+        assert_eq!(detector.record(&edit("b", "a"), &ok), None);
+        // A→B→A→B: first return to an earlier edit
+        assert_eq!(detector.record(&edit("a", "b"), &ok), None);
+        let pattern = detector.record(&edit("b", "a"), &ok).unwrap();
+        assert_eq!(
+            pattern,
+            LoopPattern::Oscillation { path: "src/lib.rs".to_string(), count: 2 }
+        );
+        assert!(pattern.correction_message().starts_with("System note: edits to src/lib.rs"));
+    }
+
+    #[test]
+    fn test_zero_thresholds_disable_detection() {
+        let mut detector = LoopDetector::new(LoopThresholds { repeat: 0, oscillation: 0 });
+        let grep = call("grep", serde_json::json!({ "pattern": "foo" }));
+        let result = ToolResult::success("id", "none");
+        for _ in 0..10 {
+            assert_eq!(detector.record(&grep, &result), None);
+        }
+    }
+}
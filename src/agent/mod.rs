@@ -25,6 +25,8 @@
 //!     system_prompt: Some("You are a helpful assistant.".to_string()),
 //!     config: AgentConfig::default(),
 //!     callbacks: AgentCallbacks::default(),
+//!     summarize_provider: None,
+//!     output_schema: None,
 //! });
 //!
 //! // Chat
@@ -32,24 +34,43 @@
 //! println!("{}", response);
 //! ```
 
+pub mod attachments;
+pub mod budget;
+pub mod checkpoint;
+pub mod events;
+pub mod hooks;
+pub mod loop_detect;
+pub mod plan;
+pub mod schema;
+pub mod summarize;
+pub mod task;
+pub mod text_tools;
 mod types;
 
 pub use types::{
     AgentCallbacks, AgentConfig, AgentOptions, AgentState,
     ConfirmationResult, ToolConfirmation,
-    TurnStats, TurnToolCall,
+    TurnStats, TurnToolCall, UsageAnchor,
     DESTRUCTIVE_TOOLS,
 };
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use tokio::sync::watch;
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
 
+use self::checkpoint::SharedCheckpointStore;
+use self::hooks::{HookEvent, HookRunner};
+use self::loop_detect::{LoopDetector, LoopThresholds};
+use self::plan::Plan;
+use self::task::ParentTurn;
 use crate::error::{AgentError, Result};
+use crate::providers::RetryingProvider;
+use crate::session::context::{estimate_tokens_for_chars, ContextConfig};
 use crate::types::{
-    BoxedProvider, ContentBlock, Message, Role, StreamEvent,
-    ToolCall, ToolDefinition, ToolResult,
+    BoxedProvider, ChatOptions, ContentBlock, Message, ProviderResponse, Role, SharedProvider, StreamEvent,
+    TokenUsage, ToolCall, ToolDefinition, ToolResult,
 };
 use crate::tools::ToolRegistry;
 
@@ -68,6 +89,17 @@ pub struct Agent {
     config: AgentConfig,
     /// Event callbacks.
     callbacks: AgentCallbacks,
+    /// Provider for model-generated summaries during compaction.
+    summarize_provider: Option<SharedProvider>,
+    /// Store recording the files each turn changes.
+    checkpoints: Option<SharedCheckpointStore>,
+    /// Configured shell hooks.
+    hooks: Arc<HookRunner>,
+    /// JSON Schema the final answer must match.
+    output_schema: Option<serde_json::Value>,
+    /// Spend shared with sibling sub-agents, checked against the budget in
+    /// place of this agent's own.
+    shared_spend: Option<Arc<Mutex<f64>>>,
     /// Internal state.
     state: AgentState,
 }
@@ -79,12 +111,26 @@ impl Agent {
             "You are a helpful AI assistant.".to_string()
         });
 
+        // Retry rate limits, overloads and network failures transparently
+        let provider: BoxedProvider = if options.config.retry.is_enabled() {
+            Box::new(RetryingProvider::new(options.provider, options.config.retry.clone()))
+        } else {
+            options.provider
+        };
+
+        let hooks = Arc::new(HookRunner::new(options.config.hooks.clone()));
+
         Self {
-            provider: options.provider,
+            provider,
             tool_registry: options.tool_registry,
             system_prompt,
+            hooks,
             config: options.config,
             callbacks: options.callbacks,
+            summarize_provider: options.summarize_provider,
+            checkpoints: None,
+            output_schema: options.output_schema,
+            shared_spend: None,
             state: AgentState::default(),
         }
     }
@@ -96,9 +142,44 @@ impl Agent {
 
     /// Get a mutable reference to the messages (for loading sessions).
     pub fn messages_mut(&mut self) -> &mut Vec<Message> {
+        // Reported usage no longer describes the conversation once it is edited
+        self.state.usage_anchor = None;
         &mut self.state.messages
     }
 
+    /// Position the next message will take in the history.
+    ///
+    /// Unlike an index into [`messages`](Self::messages), positions stay valid
+    /// when compaction removes older messages.
+    pub fn history_position(&self) -> usize {
+        self.state.compacted_messages + self.state.messages.len()
+    }
+
+    /// Drop all messages from `position` (see [`history_position`](Self::history_position))
+    /// onward. If that message was compacted into the summary, the whole history goes.
+    pub fn truncate_to_position(&mut self, position: usize) {
+        self.truncate_messages(position.saturating_sub(self.state.compacted_messages));
+    }
+
+    /// Drop all messages from index `len` onward (for rewinding turns).
+    pub fn truncate_messages(&mut self, len: usize) {
+        self.state.messages.truncate(len);
+        self.state.running_char_count = self.state.messages.iter()
+            .map(|m| self.message_char_count(m))
+            .sum();
+        self.state.usage_anchor = None;
+    }
+
+    /// Whether plan mode is on.
+    pub fn plan_mode(&self) -> bool {
+        self.config.plan_mode
+    }
+
+    /// Turn plan mode on or off for the following turns.
+    pub fn set_plan_mode(&mut self, plan_mode: bool) {
+        self.config.plan_mode = plan_mode;
+    }
+
     /// Clear the conversation history.
     pub fn clear(&mut self) {
         self.state = AgentState::default();
@@ -114,6 +195,17 @@ impl Agent {
         self.system_prompt = prompt.into();
     }
 
+    /// Get the total cost in USD of this conversation so far.
+    pub fn session_cost(&self) -> f64 {
+        self.state.session_cost
+    }
+
+    /// Set the session cost, e.g. when continuing a saved session, so the
+    /// session budget accounts for earlier spend.
+    pub fn set_session_cost(&mut self, cost: f64) {
+        self.state.session_cost = cost;
+    }
+
     /// Get the conversation summary if available.
     pub fn conversation_summary(&self) -> Option<&str> {
         self.state.conversation_summary.as_deref()
@@ -124,16 +216,37 @@ impl Agent {
         self.state.messages.len()
     }
 
+    /// Set the provider used to summarize older messages during compaction.
+    pub fn set_summarize_provider(&mut self, provider: Option<SharedProvider>) {
+        self.summarize_provider = provider;
+    }
+
+    /// Set the store that records a checkpoint of the files each turn changes.
+    pub fn set_checkpoints(&mut self, checkpoints: Option<SharedCheckpointStore>) {
+        self.checkpoints = checkpoints;
+    }
+
+    /// Add spend to `total` as it happens, and hold the budget against it.
+    ///
+    /// Sub-agents started in the same batch share one total, so together they
+    /// stay within the parent's remaining budget.
+    pub(crate) fn set_shared_spend(&mut self, total: Arc<Mutex<f64>>) {
+        self.shared_spend = Some(total);
+    }
+
     /// Force context compaction to reduce token usage.
     /// Returns the number of messages that were summarized.
-    pub fn compact_context(&mut self) -> usize {
+    pub async fn compact_context(&mut self) -> usize {
         let msg_count_before = self.state.messages.len();
-        self.compact_context_internal();
+        self.compact_context_internal().await;
         msg_count_before.saturating_sub(self.state.messages.len())
     }
 
     /// Internal implementation of context compaction.
-    fn compact_context_internal(&mut self) {
+    ///
+    /// Older messages are summarized by the summarize provider when one is
+    /// configured; if that fails (or none is set) a truncation summary is used.
+    async fn compact_context_internal(&mut self) {
         // Notify that compaction is starting
         if let Some(ref on_compaction) = self.callbacks.on_compaction {
             on_compaction(true);
@@ -153,10 +266,77 @@ impl Agent {
         // Split messages: older ones to summarize, recent ones to keep
         let split_at = msg_count - keep_recent;
         let older_messages: Vec<Message> = self.state.messages.drain(..split_at).collect();
+        self.state.compacted_messages += split_at;
+
+        let summary = match self.generate_summary(&older_messages).await {
+            Some(summary) => summary,
+            None => self.truncation_summary(&older_messages),
+        };
+        self.state.conversation_summary = Some(summary);
+
+        // Recalculate running_char_count from remaining messages
+        self.state.running_char_count = self.state.messages.iter()
+            .map(|m| self.message_char_count(m))
+            .sum();
+        self.state.usage_anchor = None;
+
+        tracing::info!(
+            "Context compacted: removed {} messages, {} remaining",
+            split_at,
+            self.state.messages.len()
+        );
+
+        // Notify that compaction is complete
+        if let Some(ref on_compaction) = self.callbacks.on_compaction {
+            on_compaction(false);
+        }
+    }
+
+    /// Ask the summarize provider for a structured summary of `older_messages`,
+    /// folding in any existing summary. Returns `None` if no provider is set or
+    /// the request fails.
+    async fn generate_summary(&self, older_messages: &[Message]) -> Option<String> {
+        let provider = self.summarize_provider.as_ref()?;
+        let request = summarize::build_summary_request(
+            self.state.conversation_summary.as_deref(),
+            older_messages,
+        );
+
+        let result = provider
+            .chat(
+                &[Message::user(request)],
+                None,
+                Some(summarize::SUMMARY_SYSTEM_PROMPT),
+                &ChatOptions::default(),
+            )
+            .await;
+
+        match result {
+            Ok(response) => {
+                let text = response.content.trim();
+                if text.is_empty() {
+                    tracing::warn!("Summarize model {} returned an empty summary", provider.model());
+                    None
+                } else {
+                    Some(Self::truncate_str(text, summarize::MAX_SUMMARY_CHARS))
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Summarize model {} failed, falling back to truncation: {}",
+                    provider.model(),
+                    e
+                );
+                None
+            }
+        }
+    }
 
-        // Build a simple summary from older messages by extracting text content
+    /// Build a summary by truncating the text of each older message.
+    /// Used when no summarize provider is available.
+    fn truncation_summary(&self, older_messages: &[Message]) -> String {
         let mut summary_parts: Vec<String> = Vec::new();
-        for msg in &older_messages {
+        for msg in older_messages {
             let role = match msg.role {
                 Role::User => "User",
                 Role::Assistant => "Assistant",
@@ -183,41 +363,81 @@ impl Agent {
         // Prepend existing summary if there is one
         if let Some(ref existing) = self.state.conversation_summary {
             let combined = format!("{}\n\n{}", existing, new_summary);
-            self.state.conversation_summary = Some(Self::truncate_str(&combined, 4000));
+            Self::truncate_str(&combined, 4000)
         } else {
-            self.state.conversation_summary = Some(new_summary);
+            new_summary
         }
+    }
 
-        // Recalculate running_char_count from remaining messages
-        self.state.running_char_count = self.state.messages.iter()
-            .map(|m| self.message_char_count(m))
-            .sum();
+    /// Price a response's token usage and add it to the turn, session and daily totals.
+    fn record_cost(&mut self, usage: &TokenUsage, turn_stats: &mut TurnStats) {
+        let Some(pricing) = self.provider.pricing() else {
+            return;
+        };
+        let cost = pricing.cost(usage);
+        turn_stats.cost += cost;
+        self.state.session_cost += cost;
+        if let Some(ref shared) = self.shared_spend {
+            *shared.lock().unwrap() += cost;
+        }
 
-        tracing::info!(
-            "Context compacted: removed {} messages, {} remaining",
-            split_at,
-            self.state.messages.len()
-        );
+        if cost > 0.0 {
+            if let Some(ref ledger) = self.config.budget.spend_ledger {
+                if let Err(e) = budget::record_daily_spend(ledger, cost) {
+                    tracing::warn!("Failed to record daily spend: {}", e);
+                }
+            }
+        }
+    }
 
-        // Notify that compaction is complete
-        if let Some(ref on_compaction) = self.callbacks.on_compaction {
-            on_compaction(false);
+    /// Definitions of the tools offered to the model (reader tools only in plan mode).
+    fn tool_definitions(&self) -> Vec<ToolDefinition> {
+        let mut definitions = self.tool_registry.definitions();
+        if self.config.plan_mode {
+            definitions.retain(|d| plan::is_plan_tool(&d.name));
         }
+        definitions
     }
 
     /// Get tool definitions if tools are enabled and supported.
     fn get_tool_definitions(&self) -> Option<Vec<ToolDefinition>> {
         if self.config.use_tools && self.provider.supports_tool_use() {
-            Some(self.tool_registry.definitions())
+            Some(self.tool_definitions())
         } else {
             None
         }
     }
 
+    /// Whether tool calls should be parsed out of plain-text responses.
+    ///
+    /// Only for models without native tool calling, so markup quoted by a
+    /// model that has it is never run.
+    fn extracts_text_tools(&self) -> bool {
+        self.config.use_tools
+            && self.config.extract_tools_from_text
+            && !self.provider.supports_tool_use()
+    }
+
     /// Build the system context including any conversation summary.
     fn build_system_context(&self) -> String {
         let mut context = self.system_prompt.clone();
 
+        // Models without native tool calling need the tools described in the prompt
+        if self.extracts_text_tools() {
+            context.push_str("\n\n");
+            context.push_str(&text_tools::tool_use_instructions(&self.tool_definitions()));
+        }
+
+        if self.config.plan_mode {
+            context.push_str("\n\n");
+            context.push_str(plan::PLAN_MODE_INSTRUCTIONS);
+        }
+
+        if let Some(ref output_schema) = self.output_schema {
+            context.push_str("\n\n");
+            context.push_str(&schema::schema_instructions(output_schema));
+        }
+
         if let Some(ref summary) = self.state.conversation_summary {
             context.push_str("\n\n## Previous Conversation Summary\n");
             context.push_str(summary);
@@ -226,10 +446,19 @@ impl Agent {
         context
     }
 
-    /// Estimate the current token count of all messages.
-    /// Uses the standard approximation of ~4 characters per token.
-    /// Leverages `running_char_count` to avoid re-serializing every message each iteration.
+    /// Estimate the current token count of the conversation.
+    ///
+    /// Anchors on the prompt size the provider reported for the latest request
+    /// and only estimates messages added since then. Before the first reported
+    /// usage (or after compaction) everything is estimated at ~4 characters per
+    /// token, using `running_char_count` to avoid re-serializing every message.
     fn estimate_tokens(&self) -> usize {
+        if let Some(anchor) = self.state.usage_anchor {
+            if let Some(new_chars) = self.state.running_char_count.checked_sub(anchor.char_count) {
+                return (anchor.tokens + estimate_tokens_for_chars(new_chars)) as usize;
+            }
+        }
+
         let mut total_chars: usize = self.state.running_char_count;
 
         // Add system prompt + summary (not cached — these are cheap to measure)
@@ -238,7 +467,17 @@ impl Agent {
             total_chars += summary.len();
         }
 
-        total_chars / 4
+        estimate_tokens_for_chars(total_chars) as usize
+    }
+
+    /// Context limits for the current model, optionally capped by `max_context_tokens`.
+    fn context_config(&self) -> ContextConfig {
+        let window = self.provider.context_window() as u64;
+        let window = match self.config.max_context_tokens {
+            Some(cap) => window.min(cap as u64),
+            None => window,
+        };
+        ContextConfig::for_model(window)
     }
 
     /// Count the characters in a message's content.
@@ -271,14 +510,11 @@ impl Agent {
         }
     }
 
-    /// Check whether a tool call needs confirmation and, if so, ask the user.
+    /// Build the confirmation request for a tool call, if one is needed.
     ///
-    /// Returns `None` when no confirmation is needed (tool is auto-approved and
-    /// no dangerous pattern matches). Otherwise returns the user's decision.
-    /// Serializes the input only once to avoid redundant work.
-    fn maybe_confirm(&self, tool_call: &ToolCall) -> Option<ConfirmationResult> {
-        let on_confirm = self.callbacks.on_confirm.as_ref()?;
-
+    /// Returns `None` when the tool is auto-approved and no dangerous pattern
+    /// matches. Serializes the input only once to avoid redundant work.
+    fn confirmation_request(&self, tool_call: &ToolCall) -> Option<ToolConfirmation> {
         let is_builtin_dangerous = DESTRUCTIVE_TOOLS.contains(&tool_call.name.as_str());
         let needs_builtin_confirm = is_builtin_dangerous
             && !self.config.should_auto_approve(&tool_call.name);
@@ -299,64 +535,151 @@ impl Agent {
         let is_dangerous = is_builtin_dangerous || pattern_match.is_some();
         let danger_reason = pattern_match.map(|p| format!("Matches dangerous pattern: {}", p));
 
-        let confirmation = ToolConfirmation {
+        Some(ToolConfirmation {
             tool_name: tool_call.name.clone(),
             input: tool_call.input.clone(),
             is_dangerous,
             danger_reason,
-        };
+        })
+    }
+
+    /// Check whether a tool call needs confirmation and, if so, ask the user.
+    ///
+    /// Returns `None` when no confirmation is needed (tool is auto-approved and
+    /// no dangerous pattern matches). Otherwise returns the user's decision.
+    fn maybe_confirm(&self, tool_call: &ToolCall) -> Option<ConfirmationResult> {
+        let on_confirm = self.callbacks.on_confirm.as_ref()?;
+        let confirmation = self.confirmation_request(tool_call)?;
         Some(on_confirm(confirmation))
     }
 
+    /// Whether a tool call can run concurrently with its neighbours.
+    ///
+    /// Only calls to registered, non-mutating tools that don't need
+    /// confirmation qualify; everything else is an ordering barrier.
+    fn is_concurrent_safe(&self, tool_call: &ToolCall) -> bool {
+        let read_only = self
+            .tool_registry
+            .get(&tool_call.name)
+            .is_some_and(|handler| !handler.is_mutating());
+
+        read_only
+            && (self.callbacks.on_confirm.is_none()
+                || self.confirmation_request(tool_call).is_none())
+    }
+
+    /// Build a tool executor that can be moved into spawned tasks.
+    fn tool_executor(&self) -> ToolExecutor {
+        ToolExecutor {
+            tool_registry: self.tool_registry.clone(),
+            callbacks: self.callbacks.clone(),
+            hooks: self.hooks.clone(),
+            vision: self.provider.supports_vision(),
+        }
+    }
+
     /// Execute a single tool call.
     async fn execute_tool(&self, tool_call: &ToolCall) -> ToolResult {
-        // Notify callback
-        if let Some(ref on_tool_call) = self.callbacks.on_tool_call {
-            on_tool_call(&tool_call.id, &tool_call.name, &tool_call.input);
-        }
+        self.tool_executor().execute(tool_call).await
+    }
 
-        // Execute the tool
-        let dispatch_result = self.tool_registry
-            .dispatch(&tool_call.name, tool_call.input.clone())
-            .await;
+    /// Execute a batch of concurrent-safe tool calls.
+    ///
+    /// Runs up to `max_parallel_tools` calls at a time and returns each result
+    /// with its duration in the same order as `batch`.
+    async fn execute_batch(&self, batch: &[ToolCall]) -> Vec<(ToolResult, u64)> {
+        let limit = self.config.max_parallel_tools.max(1);
+
+        if batch.len() <= 1 || limit == 1 {
+            let mut results = Vec::with_capacity(batch.len());
+            for tool_call in batch {
+                let start = Instant::now();
+                let result = self.execute_tool(tool_call).await;
+                results.push((result, start.elapsed().as_millis() as u64));
+            }
+            return results;
+        }
 
-        // Convert to ToolResult
-        let result = match dispatch_result {
-            Ok(dr) => {
-                #[cfg(feature = "telemetry")]
-                {
-                    GLOBAL_METRICS.record_tool(&tool_call.name, dr.duration, dr.is_error);
-                }
+        let semaphore = Arc::new(Semaphore::new(limit));
+        let mut tasks = JoinSet::new();
+
+        for (idx, tool_call) in batch.iter().cloned().enumerate() {
+            let executor = self.tool_executor();
+            let semaphore = semaphore.clone();
+            let parent_turn = ParentTurn::current();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let start = Instant::now();
+                let result = match parent_turn {
+                    // Spawned tasks don't see the caller's task-local turn
+                    Some(turn) => turn.scope(executor.execute(&tool_call)).await,
+                    None => executor.execute(&tool_call).await,
+                };
+                (idx, result, start.elapsed().as_millis() as u64)
+            });
+        }
 
-                ToolResult {
-                    tool_use_id: tool_call.id.clone(),
-                    content: dr.output.content().to_string(),
-                    is_error: if dr.is_error { Some(true) } else { None },
-                }
-            }
-            Err(e) => {
-                ToolResult {
-                    tool_use_id: tool_call.id.clone(),
-                    content: format!("Error: {}", e),
-                    is_error: Some(true),
-                }
+        let mut slots: Vec<Option<(ToolResult, u64)>> = vec![None; batch.len()];
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((idx, result, duration_ms)) => slots[idx] = Some((result, duration_ms)),
+                Err(e) => tracing::warn!("Tool task failed: {}", e),
             }
-        };
+        }
 
-        // Notify callback
-        if let Some(ref on_tool_result) = self.callbacks.on_tool_result {
-            on_tool_result(
-                &tool_call.id,
-                &tool_call.name,
-                &result.content,
-                result.is_error.unwrap_or(false),
-            );
+        slots
+            .into_iter()
+            .zip(batch)
+            .map(|(slot, tool_call)| {
+                slot.unwrap_or_else(|| {
+                    (
+                        ToolResult::error(&tool_call.id, "Error: tool execution task failed"),
+                        0,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Run and drain the pending batch, appending results in order.
+    /// Returns whether any call in the batch failed.
+    async fn flush_batch(
+        &self,
+        batch: &mut Vec<ToolCall>,
+        results: &mut Vec<ToolResult>,
+        turn_stats: &mut TurnStats,
+    ) -> bool {
+        let mut has_error = false;
+        let executed = self.execute_batch(batch).await;
+        for (tool_call, (result, duration_ms)) in batch.drain(..).zip(executed) {
+            has_error |= Self::record_tool_call(turn_stats, &tool_call, &result, duration_ms);
+            results.push(result);
         }
+        has_error
+    }
 
-        result
+    /// Record stats for an executed tool call and return whether it failed.
+    fn record_tool_call(
+        turn_stats: &mut TurnStats,
+        tool_call: &ToolCall,
+        result: &ToolResult,
+        duration_ms: u64,
+    ) -> bool {
+        let is_err = result.is_error.unwrap_or(false);
+        turn_stats.tool_call_count += 1;
+        turn_stats.tool_calls.push(TurnToolCall {
+            name: tool_call.name.clone(),
+            duration_ms,
+            is_error: is_err,
+        });
+        is_err
     }
 
     /// Process tool calls from a response.
+    ///
+    /// Consecutive concurrent-safe calls are batched and run in parallel;
+    /// mutating calls and calls that need confirmation run one at a time in
+    /// order. Results are returned in the original `tool_use` order.
     async fn process_tool_calls(
         &self,
         tool_calls: &[ToolCall],
@@ -365,8 +688,30 @@ impl Agent {
         let mut results = Vec::with_capacity(tool_calls.len());
         let mut aborted = false;
         let mut has_error = false;
+        let mut batch: Vec<ToolCall> = Vec::new();
 
         for tool_call in tool_calls {
+            let blocked = self.config.plan_mode && !plan::is_plan_tool(&tool_call.name);
+            if !blocked && self.is_concurrent_safe(tool_call) {
+                batch.push(tool_call.clone());
+                continue;
+            }
+
+            // Run pending read-only calls before this barrier
+            has_error |= self.flush_batch(&mut batch, &mut results, turn_stats).await;
+
+            if blocked {
+                results.push(ToolResult::error(
+                    &tool_call.id,
+                    format!(
+                        "Tool '{}' is not available in plan mode. Use read-only tools and finish with a plan.",
+                        tool_call.name
+                    ),
+                ));
+                has_error = true;
+                continue;
+            }
+
             // Check if confirmation is needed, and if so, get the user's decision
             if let Some(decision) = self.maybe_confirm(tool_call) {
                 match decision {
@@ -374,47 +719,39 @@ impl Agent {
                         // Continue to execute
                     }
                     ConfirmationResult::Deny => {
-                        results.push(ToolResult {
-                            tool_use_id: tool_call.id.clone(),
-                            content: "User denied this operation. Please try a different approach.".to_string(),
-                            is_error: Some(true),
-                        });
+                        results.push(ToolResult::error(
+                            &tool_call.id,
+                            "User denied this operation. Please try a different approach.",
+                        ));
                         has_error = true;
                         continue;
                     }
                     ConfirmationResult::Abort => {
-                        results.push(ToolResult {
-                            tool_use_id: tool_call.id.clone(),
-                            content: "User aborted the operation.".to_string(),
-                            is_error: Some(true),
-                        });
+                        results.push(ToolResult::error(&tool_call.id, "User aborted the operation."));
                         aborted = true;
                         break;
                     }
                 }
             }
 
-            // Execute the tool
+            // Execute the tool, snapshotting the files it may change
+            let baseline = self
+                .checkpoints
+                .as_ref()
+                .and_then(|store| store.lock().unwrap().begin_tool_call(tool_call));
             let start = Instant::now();
             let result = self.execute_tool(tool_call).await;
             let duration_ms = start.elapsed().as_millis() as u64;
-
-            // Track stats
-            let is_err = result.is_error.unwrap_or(false);
-            turn_stats.tool_call_count += 1;
-            turn_stats.tool_calls.push(TurnToolCall {
-                name: tool_call.name.clone(),
-                duration_ms,
-                is_error: is_err,
-            });
-
-            if is_err {
-                has_error = true;
+            if let (Some(store), Some(baseline)) = (&self.checkpoints, baseline) {
+                store.lock().unwrap().finish_tool_call(baseline);
             }
 
+            has_error |= Self::record_tool_call(turn_stats, tool_call, &result, duration_ms);
             results.push(result);
         }
 
+        has_error |= self.flush_batch(&mut batch, &mut results, turn_stats).await;
+
         if aborted {
             Err(AgentError::UserCancelled)
         } else {
@@ -422,11 +759,89 @@ impl Agent {
         }
     }
 
+    /// Check a final answer against the output schema.
+    ///
+    /// If the text answer doesn't conform and the provider supports
+    /// structured output, the answer is requested again through it. Returns
+    /// the conforming JSON, or the validation errors to send back.
+    async fn conform_final_answer(
+        &mut self,
+        response: &str,
+        turn_stats: &mut TurnStats,
+    ) -> std::result::Result<std::result::Result<String, Vec<String>>, AgentError> {
+        let Some(output_schema) = self.output_schema.clone() else {
+            return Ok(Ok(response.to_string()));
+        };
+
+        let errors = match schema::check_response(response, &output_schema) {
+            Ok(value) => return Ok(Ok(value.to_string())),
+            Err(errors) => errors,
+        };
+        if !self.provider.supports_structured_output() {
+            return Ok(Err(errors));
+        }
+
+        self.add_user_text(schema::STRUCTURED_ANSWER_REQUEST.to_string());
+        let system_context = self.build_system_context();
+        let structured = self
+            .provider
+            .chat_structured(&self.state.messages, Some(&system_context), &output_schema)
+            .await?;
+        if let Some(ref usage) = structured.usage {
+            turn_stats.add_usage(usage);
+            self.record_cost(usage, turn_stats);
+        }
+
+        let msg = Message::assistant(&structured.content);
+        self.state.running_char_count += self.message_char_count(&msg);
+        self.state.messages.push(msg);
+
+        Ok(schema::check_response(&structured.content, &output_schema).map(|value| value.to_string()))
+    }
+
+    /// Add a plain user message to the history (e.g. text-mode tool results).
+    fn add_user_text(&mut self, text: String) {
+        let msg = Message::user(text);
+        self.state.running_char_count += self.message_char_count(&msg);
+        self.state.messages.push(msg);
+    }
+
+    /// Format results of text-extracted tool calls as a user message.
+    ///
+    /// Models that call tools through text don't understand native
+    /// `tool_result` blocks, so results are sent back as tagged text.
+    fn format_text_tool_results(
+        tool_calls: &[ToolCall],
+        results: &[ToolResult],
+        errors: &[String],
+    ) -> String {
+        let mut parts: Vec<String> = tool_calls
+            .iter()
+            .zip(results)
+            .map(|(call, result)| {
+                let status = if result.is_error.unwrap_or(false) { " error=\"true\"" } else { "" };
+                format!(
+                    "<tool_result name=\"{}\"{}>\n{}\n</tool_result>",
+                    call.name, status, result.content
+                )
+            })
+            .collect();
+
+        if !errors.is_empty() {
+            parts.push(text_tools::correction_message(errors));
+        }
+
+        parts.join("\n\n")
+    }
+
     /// Add tool results to the message history.
     fn add_tool_results(&mut self, results: Vec<ToolResult>) {
         let content: Vec<ContentBlock> = results
             .into_iter()
-            .map(|r| ContentBlock::tool_result(&r.tool_use_id, &r.content, r.is_error.unwrap_or(false)))
+            .map(|r| match r.image {
+                Some(image) => ContentBlock::tool_result_with_image(&r.tool_use_id, &r.content, image),
+                None => ContentBlock::tool_result(&r.tool_use_id, &r.content, r.is_error.unwrap_or(false)),
+            })
             .collect();
 
         let msg = Message {
@@ -461,6 +876,26 @@ impl Agent {
         &mut self,
         user_message: &str,
         cancel_rx: Option<watch::Receiver<bool>>,
+    ) -> Result<String> {
+        if let Some(ref store) = self.checkpoints {
+            store.lock().unwrap().begin_turn();
+        }
+
+        let result = self.run_turn(user_message, cancel_rx).await;
+
+        if let Some(ref store) = self.checkpoints {
+            if let Err(e) = store.lock().unwrap().finish_turn() {
+                tracing::warn!("Failed to save checkpoint: {}", e);
+            }
+        }
+        result
+    }
+
+    /// Run one turn of the agentic loop.
+    async fn run_turn(
+        &mut self,
+        user_message: &str,
+        cancel_rx: Option<watch::Receiver<bool>>,
     ) -> Result<String> {
         let mut cancel_rx = cancel_rx;
         let start_time = Instant::now();
@@ -469,8 +904,18 @@ impl Agent {
         // Initialize turn stats
         let mut turn_stats = TurnStats::default();
 
-        // Add user message to history
-        let user_msg = Message::user(user_message);
+        // Let prompt hooks reject the message before it enters the history
+        if let Err(reason) = self.hooks.user_prompt_submit(user_message).await {
+            if let Some(ref on_hook) = self.callbacks.on_hook {
+                on_hook(HookEvent::UserPromptSubmit.as_str(), &reason);
+            }
+            return Err(AgentError::HookBlocked(reason).into());
+        }
+
+        self.provider.discover_capabilities().await;
+
+        // Add user message to history, with any `@path` images attached
+        let user_msg = attachments::user_message(user_message, self.provider.supports_vision()).await;
         self.state.running_char_count += self.message_char_count(&user_msg);
         self.state.messages.push(user_msg);
 
@@ -479,6 +924,12 @@ impl Agent {
         self.state.consecutive_errors = 0;
 
         let mut final_response = String::new();
+        let mut schema_retries = 0;
+        let mut loop_detector = LoopDetector::new(LoopThresholds {
+            repeat: self.config.loop_repeat_threshold,
+            oscillation: self.config.loop_oscillation_threshold,
+        });
+        let mut loop_warnings = 0;
 
         // Main loop
         loop {
@@ -502,14 +953,36 @@ impl Agent {
                 break;
             }
 
+            // Stop before spending more once a budget cap is reached
+            let budget = &self.config.budget;
+            let (turn_cost, session_cost) = match self.shared_spend {
+                Some(ref shared) => {
+                    let spent = *shared.lock().unwrap();
+                    (spent, spent)
+                }
+                None => (turn_stats.cost, self.state.session_cost),
+            };
+            budget.check(turn_cost, session_cost, || {
+                budget
+                    .spend_ledger
+                    .as_deref()
+                    .map(budget::load_daily_spend)
+                    .unwrap_or(0.0)
+            })?;
+            // Caps can't be enforced without prices, so don't run unmetered
+            if budget.has_caps() && self.provider.pricing().is_none() {
+                return Err(AgentError::UnpricedModel(self.provider.model().to_string()).into());
+            }
+
             // Check if context needs compaction
-            if self.estimate_tokens() > self.config.max_context_tokens {
-                self.compact_context();
+            if self.estimate_tokens() as u64 > self.context_config().summarization_threshold() {
+                self.compact_context().await;
             }
 
             // Build request parameters
             let tools = self.get_tool_definitions();
             let system_context = self.build_system_context();
+            let options = ChatOptions::default();
 
             // Clone callbacks for the streaming closure (Arc clones are cheap)
             let on_text = self.callbacks.on_text.clone();
@@ -525,6 +998,7 @@ impl Agent {
                         &self.state.messages,
                         tools.as_deref(),
                         Some(&system_context),
+                        &options,
                         Box::new(move |event| {
                             // Forward raw stream events
                             if let Some(ref cb) = on_stream_event {
@@ -551,6 +1025,7 @@ impl Agent {
                         &self.state.messages,
                         tools.as_deref(),
                         Some(&system_context),
+                        &options,
                         Box::new(move |event| {
                             // Forward raw stream events
                             if let Some(ref cb) = on_stream_event {
@@ -569,27 +1044,30 @@ impl Agent {
 
             // Update token stats
             if let Some(ref usage) = response.usage {
-                turn_stats.input_tokens += usage.input_tokens as u64;
-                turn_stats.output_tokens += usage.output_tokens as u64;
-                turn_stats.total_tokens = turn_stats.input_tokens + turn_stats.output_tokens;
+                turn_stats.add_usage(usage);
+                self.record_cost(usage, &mut turn_stats);
             }
 
+            // Models without native tool calling may emit tool calls as text
+            let text_calls = if response.tool_calls.is_empty() && self.extracts_text_tools() {
+                let extracted = text_tools::extract_tool_calls(
+                    &response.content,
+                    &self.tool_definitions(),
+                );
+                (!extracted.is_empty()).then_some(extracted)
+            } else {
+                None
+            };
+
             // Store final response text
-            if !response.content.is_empty() {
+            if let Some(ref extracted) = text_calls {
+                final_response = extracted.text.clone();
+            } else if !response.content.is_empty() {
                 final_response = response.content.clone();
             }
 
             // Build assistant message
-            let mut assistant_blocks: Vec<ContentBlock> = Vec::new();
-
-            if !response.content.is_empty() {
-                assistant_blocks.push(ContentBlock::text(&response.content));
-            }
-
-            for tc in &response.tool_calls {
-                assistant_blocks.push(ContentBlock::tool_use(&tc.id, &tc.name, tc.input.clone()));
-            }
-
+            let assistant_blocks = assistant_blocks(&response);
             if !assistant_blocks.is_empty() {
                 let assistant_msg = Message {
                     role: Role::Assistant,
@@ -599,33 +1077,129 @@ impl Agent {
                 self.state.messages.push(assistant_msg);
             }
 
+            // Anchor future estimates on what the provider actually counted
+            if let Some(ref usage) = response.usage {
+                self.state.usage_anchor = Some(UsageAnchor {
+                    tokens: usage.context_tokens() as u64 + usage.output_tokens as u64,
+                    char_count: self.state.running_char_count,
+                });
+            }
+
+            let (tool_calls, extraction_errors) = match text_calls {
+                Some(extracted) => (extracted.calls, Some(extracted.errors)),
+                None => (response.tool_calls, None),
+            };
+
             // If no tool calls, we're done
-            if response.tool_calls.is_empty() {
-                break;
+            if tool_calls.is_empty() {
+                let correction = match extraction_errors {
+                    // Only malformed text tool calls: ask the model to fix them
+                    Some(errors) => text_tools::correction_message(&errors),
+                    // Plan mode must end with a plan the user can approve
+                    None if self.config.plan_mode && Plan::parse(&final_response).is_none() => {
+                        plan::PLAN_CORRECTION_MESSAGE.to_string()
+                    }
+                    // Scripted callers need an answer matching their schema
+                    None if self.output_schema.is_some() => {
+                        match self.conform_final_answer(&final_response, &mut turn_stats).await? {
+                            Ok(answer) => {
+                                final_response = answer;
+                                break;
+                            }
+                            Err(errors) => {
+                                schema_retries += 1;
+                                if schema_retries > self.config.max_schema_retries {
+                                    return Err(AgentError::SchemaValidation(errors.join("; ")).into());
+                                }
+                                self.add_user_text(schema::correction_message(&errors));
+                                continue;
+                            }
+                        }
+                    }
+                    None => break,
+                };
+
+                self.add_user_text(correction);
+                self.state.consecutive_errors += 1;
+                if self.state.consecutive_errors >= self.config.max_consecutive_errors {
+                    final_response.push_str("\n\n(Stopping due to repeated errors)");
+                    break;
+                }
+                continue;
             }
 
-            // Process tool calls
+            // Process tool calls, letting sub-agents inherit this turn's limits
+            let parent_turn = ParentTurn {
+                budget: self
+                    .config
+                    .budget
+                    .remaining(turn_stats.cost, self.state.session_cost),
+                retry: self.config.retry.clone(),
+                cancel: cancel_rx.clone(),
+                spent: Arc::default(),
+            };
+            let spent = parent_turn.spent.clone();
             let tool_result = if let Some(rx) = cancel_rx.as_mut() {
                 if *rx.borrow() {
                     return Err(AgentError::UserCancelled.into());
                 }
                 tokio::select! {
-                    res = self.process_tool_calls(&response.tool_calls, &mut turn_stats) => res,
-                    _ = rx.changed() => {
-                        if *rx.borrow() {
-                            return Err(AgentError::UserCancelled.into());
-                        }
-                        continue;
-                    }
+                    res = parent_turn.scope(self.process_tool_calls(&tool_calls, &mut turn_stats)) => Some(res),
+                    _ = rx.changed() => None,
                 }
             } else {
-                self.process_tool_calls(&response.tool_calls, &mut turn_stats).await
+                Some(
+                    parent_turn
+                        .scope(self.process_tool_calls(&tool_calls, &mut turn_stats))
+                        .await,
+                )
+            };
+
+            // Sub-agent spend counts against this turn even if it was cut short
+            let sub_agent_cost = *spent.lock().unwrap();
+            turn_stats.cost += sub_agent_cost;
+            self.state.session_cost += sub_agent_cost;
+
+            let Some(tool_result) = tool_result else {
+                if cancel_rx.as_ref().is_some_and(|rx| *rx.borrow()) {
+                    return Err(AgentError::UserCancelled.into());
+                }
+                continue;
             };
 
             match tool_result {
-                Ok((results, has_error)) => {
+                Ok((mut results, has_error)) => {
+                    // Warn about repetitive tool use, then stop if it continues
+                    let pattern = tool_calls
+                        .iter()
+                        .zip(&results)
+                        .filter_map(|(call, result)| loop_detector.record(call, result))
+                        .last();
+                    if let Some(ref pattern) = pattern {
+                        loop_warnings += 1;
+                        if let Some(last) = results.last_mut() {
+                            last.content.push_str("\n\n");
+                            last.content.push_str(&pattern.correction_message());
+                        }
+                    }
+
                     // Add tool results to history
-                    self.add_tool_results(results);
+                    let has_error = match extraction_errors {
+                        Some(errors) => {
+                            let text = Self::format_text_tool_results(&tool_calls, &results, &errors);
+                            self.add_user_text(text);
+                            has_error || !errors.is_empty()
+                        }
+                        None => {
+                            self.add_tool_results(results);
+                            has_error
+                        }
+                    };
+
+                    // Stop only once the results are in history, so every tool call stays answered
+                    if let Some(pattern) = pattern.filter(|_| loop_warnings > self.config.max_loop_warnings) {
+                        return Err(AgentError::LoopDetected(pattern.to_string()).into());
+                    }
 
                     // Track consecutive errors
                     if has_error {
@@ -662,6 +1236,7 @@ impl Agent {
         if let Some(ref on_turn_complete) = self.callbacks.on_turn_complete {
             on_turn_complete(&turn_stats);
         }
+        self.hooks.turn_complete(&turn_stats, &final_response).await;
 
         Ok(final_response)
     }
@@ -675,6 +1250,122 @@ impl Agent {
     }
 }
 
+/// Executes tool calls against the registry and reports them via callbacks.
+///
+/// Holds only shared handles so it can be moved into spawned tasks when
+/// read-only tool calls run concurrently.
+#[derive(Clone)]
+struct ToolExecutor {
+    tool_registry: Arc<ToolRegistry>,
+    callbacks: AgentCallbacks,
+    hooks: Arc<HookRunner>,
+    /// Whether image tool output can be sent to the model.
+    vision: bool,
+}
+
+impl ToolExecutor {
+    /// Execute a single tool call.
+    async fn execute(&self, tool_call: &ToolCall) -> ToolResult {
+        // Notify callback
+        if let Some(ref on_tool_call) = self.callbacks.on_tool_call {
+            on_tool_call(&tool_call.id, &tool_call.name, &tool_call.input);
+        }
+
+        // A failing pre-hook blocks the call and explains why to the model
+        let result = match self.hooks.pre_tool_use(tool_call).await {
+            Ok(()) => self.dispatch(tool_call).await,
+            Err(reason) => {
+                self.notify_hook(HookEvent::PreToolUse, &reason);
+                ToolResult::error(&tool_call.id, reason)
+            }
+        };
+
+        // Notify callback
+        if let Some(ref on_tool_result) = self.callbacks.on_tool_result {
+            on_tool_result(
+                &tool_call.id,
+                &tool_call.name,
+                &result.content,
+                result.is_error.unwrap_or(false),
+            );
+        }
+
+        result
+    }
+
+    /// Dispatch a tool call to its handler and run post-hooks on the result.
+    async fn dispatch(&self, tool_call: &ToolCall) -> ToolResult {
+        let dispatch_result = self.tool_registry
+            .dispatch(&tool_call.name, tool_call.input.clone())
+            .await;
+
+        // Convert to ToolResult
+        let mut result = match dispatch_result {
+            Ok(dr) => {
+                #[cfg(feature = "telemetry")]
+                {
+                    GLOBAL_METRICS.record_tool(&tool_call.name, dr.duration, dr.is_error);
+                }
+
+                let mut content = dr.output.content().to_string();
+                let image = dr.output.attached_image().cloned();
+                if image.is_some() && !self.vision {
+                    content.push_str(
+                        "\n\nThe image was not attached because the current model cannot view images.",
+                    );
+                }
+
+                ToolResult {
+                    tool_use_id: tool_call.id.clone(),
+                    content,
+                    is_error: if dr.is_error { Some(true) } else { None },
+                    image: image.filter(|_| self.vision),
+                }
+            }
+            Err(e) => ToolResult::error(&tool_call.id, format!("Error: {}", e)),
+        };
+
+        if let Some(feedback) = self.hooks.post_tool_use(tool_call, &result).await {
+            self.notify_hook(HookEvent::PostToolUse, &feedback);
+            result.content.push_str("\n\n");
+            result.content.push_str(&feedback);
+        }
+        result
+    }
+
+    fn notify_hook(&self, event: HookEvent, message: &str) {
+        if let Some(ref on_hook) = self.callbacks.on_hook {
+            on_hook(event.as_str(), message);
+        }
+    }
+}
+
+/// Content blocks of the assistant message stored for a response.
+///
+/// Reasoning stays out of the history, except signed thinking that the
+/// provider needs back alongside the results of the tool calls it led to.
+fn assistant_blocks(response: &ProviderResponse) -> Vec<ContentBlock> {
+    let mut blocks = Vec::new();
+
+    if let (Some(reasoning), Some(signature), true) = (
+        &response.reasoning_content,
+        &response.reasoning_signature,
+        response.has_tool_calls(),
+    ) {
+        blocks.push(ContentBlock::thinking_with_signature(reasoning, signature));
+    }
+
+    if !response.content.is_empty() {
+        blocks.push(ContentBlock::text(&response.content));
+    }
+
+    for tc in &response.tool_calls {
+        blocks.push(ContentBlock::tool_use(&tc.id, &tc.name, tc.input.clone()));
+    }
+
+    blocks
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -683,7 +1374,8 @@ mod tests {
     use tokio::time::Duration;
 
     use crate::error::ProviderError;
-    use crate::types::{Provider, ProviderResponse};
+    use crate::tools::{ToolHandler, ToolOutput, ToolRegistryBuilder};
+    use crate::types::{ContentBlockType, ModelPricing, Provider};
 
     #[test]
     fn test_agent_config_default() {
@@ -832,8 +1524,9 @@ mod tests {
             _messages: &[Message],
             _tools: Option<&[ToolDefinition]>,
             _system_prompt: Option<&str>,
+            _options: &ChatOptions,
         ) -> std::result::Result<ProviderResponse, ProviderError> {
-            self.stream_chat(_messages, _tools, _system_prompt, Box::new(|_| {}))
+            self.stream_chat(_messages, _tools, _system_prompt, _options, Box::new(|_| {}))
                 .await
         }
 
@@ -842,6 +1535,7 @@ mod tests {
             _messages: &[Message],
             _tools: Option<&[ToolDefinition]>,
             _system_prompt: Option<&str>,
+            _options: &ChatOptions,
             on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
         ) -> std::result::Result<ProviderResponse, ProviderError> {
             on_event(StreamEvent::TextDelta("partial".to_string()));
@@ -862,6 +1556,968 @@ mod tests {
         }
     }
 
+    /// Provider that answers summarization requests with a fixed reply.
+    struct SummaryProvider {
+        reply: std::result::Result<String, String>,
+    }
+
+    #[async_trait]
+    impl Provider for SummaryProvider {
+        async fn chat(
+            &self,
+            messages: &[Message],
+            _tools: Option<&[ToolDefinition]>,
+            system_prompt: Option<&str>,
+            _options: &ChatOptions,
+        ) -> std::result::Result<ProviderResponse, ProviderError> {
+            assert_eq!(system_prompt, Some(summarize::SUMMARY_SYSTEM_PROMPT));
+            assert!(messages[0].as_text().unwrap_or_default().contains("<transcript>"));
+            match &self.reply {
+                Ok(text) => Ok(ProviderResponse::text(text.clone())),
+                Err(e) => Err(ProviderError::NetworkError(e.clone())),
+            }
+        }
+
+        async fn stream_chat(
+            &self,
+            messages: &[Message],
+            tools: Option<&[ToolDefinition]>,
+            system_prompt: Option<&str>,
+            options: &ChatOptions,
+            _on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+        ) -> std::result::Result<ProviderResponse, ProviderError> {
+            self.chat(messages, tools, system_prompt, options).await
+        }
+
+        fn supports_tool_use(&self) -> bool {
+            false
+        }
+
+        fn name(&self) -> &str {
+            "summary"
+        }
+
+        fn model(&self) -> &str {
+            "summary-model"
+        }
+    }
+
+    fn agent_with_history(summarize_provider: Option<SharedProvider>, count: usize) -> Agent {
+        let mut agent = Agent::new(AgentOptions {
+            provider: Box::new(SlowProvider { delay: Duration::from_millis(0) }),
+            tool_registry: Arc::new(ToolRegistry::new()),
+            system_prompt: None,
+            config: AgentConfig::default(),
+            callbacks: AgentCallbacks::default(),
+            summarize_provider,
+            output_schema: None,
+        });
+        for i in 0..count {
+            agent.messages_mut().push(Message::user(format!("message {}", i)));
+        }
+        agent
+    }
+
+    #[tokio::test]
+    async fn test_compact_context_uses_summarize_provider() {
+        let provider: SharedProvider = Arc::new(SummaryProvider {
+            reply: Ok("## Goals\nFix the parser\n\n## Files Touched\nsrc/parser.rs".to_string()),
+        });
+        let mut agent = agent_with_history(Some(provider), 14);
+
+        let removed = agent.compact_context().await;
+        assert_eq!(removed, 4);
+        assert_eq!(agent.message_count(), 10);
+        let summary = agent.conversation_summary().unwrap();
+        assert!(summary.starts_with("## Goals"));
+        assert!(summary.contains("src/parser.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_compact_context_falls_back_to_truncation() {
+        let provider: SharedProvider = Arc::new(SummaryProvider {
+            reply: Err("offline".to_string()),
+        });
+        let mut agent = agent_with_history(Some(provider), 12);
+
+        let removed = agent.compact_context().await;
+        assert_eq!(removed, 2);
+        let summary = agent.conversation_summary().unwrap();
+        assert_eq!(summary, "User: message 0\nUser: message 1");
+    }
+
+    #[tokio::test]
+    async fn test_compact_context_without_summarize_provider() {
+        let mut agent = agent_with_history(None, 11);
+        assert_eq!(agent.compact_context().await, 1);
+        assert_eq!(agent.conversation_summary(), Some("User: message 0"));
+    }
+
+    #[tokio::test]
+    async fn test_history_positions_survive_compaction() {
+        let mut agent = agent_with_history(None, 14);
+        assert_eq!(agent.history_position(), 14);
+        agent.compact_context().await;
+        assert_eq!(agent.history_position(), 14);
+
+        agent.truncate_to_position(12);
+        assert_eq!(agent.messages().last().unwrap().as_text(), Some("message 11"));
+        // Positions inside the summary drop the whole history
+        agent.truncate_to_position(2);
+        assert!(agent.messages().is_empty());
+    }
+
+    /// Tool that records start/end events and tracks peak concurrency.
+    struct TrackingTool {
+        name: &'static str,
+        mutating: bool,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+        active: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ToolHandler for TrackingTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition::new(self.name, "test tool")
+        }
+
+        fn is_mutating(&self) -> bool {
+            self.mutating
+        }
+
+        async fn execute(
+            &self,
+            input: serde_json::Value,
+        ) -> std::result::Result<ToolOutput, crate::error::ToolError> {
+            use std::sync::atomic::Ordering;
+
+            let label = input["label"].as_str().unwrap_or_default().to_string();
+            self.log.lock().unwrap().push(format!("start:{}", label));
+            let now = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+
+            tokio::time::sleep(Duration::from_millis(30)).await;
+
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            self.log.lock().unwrap().push(format!("end:{}", label));
+            Ok(ToolOutput::success(label))
+        }
+    }
+
+    struct ToolHarness {
+        agent: Agent,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    fn tool_harness(max_parallel_tools: usize) -> ToolHarness {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let active = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let mut builder = ToolRegistryBuilder::new();
+        for (name, mutating) in [("read", false), ("write", true)] {
+            builder.register(TrackingTool {
+                name,
+                mutating,
+                log: log.clone(),
+                active: active.clone(),
+                peak: peak.clone(),
+            });
+        }
+
+        let agent = Agent::new(AgentOptions {
+            provider: Box::new(SlowProvider { delay: Duration::from_millis(0) }),
+            tool_registry: Arc::new(builder.build()),
+            system_prompt: None,
+            config: AgentConfig {
+                max_parallel_tools,
+                ..AgentConfig::default()
+            },
+            callbacks: AgentCallbacks::default(),
+            summarize_provider: None,
+            output_schema: None,
+        });
+
+        ToolHarness { agent, log, peak }
+    }
+
+    fn call(name: &str, label: &str) -> ToolCall {
+        ToolCall {
+            id: format!("id-{}", label),
+            name: name.to_string(),
+            input: serde_json::json!({ "label": label }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_only_tools_run_concurrently_in_order() {
+        let harness = tool_harness(8);
+        let calls: Vec<ToolCall> = ["a", "b", "c", "d"].iter().map(|l| call("read", l)).collect();
+        let mut stats = TurnStats::default();
+
+        let (results, has_error) = harness
+            .agent
+            .process_tool_calls(&calls, &mut stats)
+            .await
+            .unwrap();
+
+        assert!(!has_error);
+        let ids: Vec<&str> = results.iter().map(|r| r.tool_use_id.as_str()).collect();
+        assert_eq!(ids, vec!["id-a", "id-b", "id-c", "id-d"]);
+        assert_eq!(stats.tool_call_count, 4);
+        assert!(harness.peak.load(std::sync::atomic::Ordering::SeqCst) > 1);
+    }
+
+    #[tokio::test]
+    async fn test_mutating_tool_is_a_barrier() {
+        let harness = tool_harness(8);
+        let calls = vec![
+            call("read", "a"),
+            call("read", "b"),
+            call("write", "w"),
+            call("read", "c"),
+        ];
+        let mut stats = TurnStats::default();
+
+        let (results, _) = harness
+            .agent
+            .process_tool_calls(&calls, &mut stats)
+            .await
+            .unwrap();
+
+        let contents: Vec<&str> = results.iter().map(|r| r.content.as_str()).collect();
+        assert_eq!(contents, vec!["a", "b", "w", "c"]);
+
+        let log = harness.log.lock().unwrap().clone();
+        let pos = |e: &str| log.iter().position(|l| l == e).unwrap();
+        assert!(pos("end:a") < pos("start:w"));
+        assert!(pos("end:b") < pos("start:w"));
+        assert!(pos("end:w") < pos("start:c"));
+    }
+
+    #[tokio::test]
+    async fn test_max_parallel_tools_limit() {
+        let harness = tool_harness(1);
+        let calls: Vec<ToolCall> = ["a", "b", "c"].iter().map(|l| call("read", l)).collect();
+        let mut stats = TurnStats::default();
+
+        harness
+            .agent
+            .process_tool_calls(&calls, &mut stats)
+            .await
+            .unwrap();
+
+        assert_eq!(harness.peak.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    /// Provider that replays a fixed sequence of text responses.
+    struct ScriptedProvider {
+        responses: std::sync::Mutex<std::collections::VecDeque<String>>,
+        system_prompts: Arc<std::sync::Mutex<Vec<String>>>,
+        context_window: u32,
+        usage: Option<TokenUsage>,
+        pricing: Option<ModelPricing>,
+        /// Answer returned by `chat_structured`; `None` means unsupported.
+        structured_answer: Option<String>,
+        native_tools: bool,
+    }
+
+    impl ScriptedProvider {
+        fn new(responses: &[&str]) -> Self {
+            Self {
+                responses: std::sync::Mutex::new(responses.iter().map(|r| r.to_string()).collect()),
+                system_prompts: Arc::new(std::sync::Mutex::new(Vec::new())),
+                context_window: 128_000,
+                usage: None,
+                pricing: None,
+                structured_answer: None,
+                native_tools: false,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Provider for ScriptedProvider {
+        async fn chat(
+            &self,
+            messages: &[Message],
+            tools: Option<&[ToolDefinition]>,
+            system_prompt: Option<&str>,
+            options: &ChatOptions,
+        ) -> std::result::Result<ProviderResponse, ProviderError> {
+            self.stream_chat(messages, tools, system_prompt, options, Box::new(|_| {})).await
+        }
+
+        async fn stream_chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolDefinition]>,
+            system_prompt: Option<&str>,
+            _options: &ChatOptions,
+            _on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+        ) -> std::result::Result<ProviderResponse, ProviderError> {
+            self.system_prompts
+                .lock()
+                .unwrap()
+                .push(system_prompt.unwrap_or_default().to_string());
+            let text = self.responses.lock().unwrap().pop_front().unwrap_or_default();
+            let mut response = ProviderResponse::text(text);
+            response.usage = self.usage.clone();
+            Ok(response)
+        }
+
+        fn supports_tool_use(&self) -> bool {
+            self.native_tools
+        }
+
+        fn supports_structured_output(&self) -> bool {
+            self.structured_answer.is_some()
+        }
+
+        async fn chat_structured(
+            &self,
+            _messages: &[Message],
+            _system_prompt: Option<&str>,
+            _schema: &serde_json::Value,
+        ) -> std::result::Result<ProviderResponse, ProviderError> {
+            Ok(ProviderResponse::text(self.structured_answer.clone().unwrap_or_default()))
+        }
+
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn model(&self) -> &str {
+            "scripted-model"
+        }
+
+        fn context_window(&self) -> u32 {
+            self.context_window
+        }
+
+        fn pricing(&self) -> Option<ModelPricing> {
+            self.pricing.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_executes_text_tool_calls() {
+        let mut harness = tool_harness(8);
+        let provider = ScriptedProvider::new(&[
+            "Reading.\n<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"x\"}}</tool_call>",
+            "The file says x.",
+        ]);
+        let system_prompts = provider.system_prompts.clone();
+        harness.agent.provider = Box::new(provider);
+
+        let response = harness.agent.chat("read x").await.unwrap();
+        assert_eq!(response, "The file says x.");
+        assert_eq!(harness.log.lock().unwrap().as_slice(), ["start:x", "end:x"]);
+
+        let results = harness.agent.messages()[2].as_text().unwrap();
+        assert!(results.contains("<tool_result name=\"read\">\nx\n</tool_result>"));
+        assert!(system_prompts.lock().unwrap()[0].contains("## Tools"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_correction_for_malformed_text_tool_call() {
+        let mut harness = tool_harness(8);
+        harness.agent.provider = Box::new(ScriptedProvider::new(&[
+            "<tool_call>{\"name\": \"nope\", \"arguments\": {}}</tool_call>",
+            "Sorry, done.",
+        ]));
+
+        let response = harness.agent.chat("go").await.unwrap();
+        assert_eq!(response, "Sorry, done.");
+        assert!(harness.log.lock().unwrap().is_empty());
+
+        let correction = harness.agent.messages()[2].as_text().unwrap();
+        assert!(correction.contains("Unknown tool \"nope\""));
+    }
+
+    #[tokio::test]
+    async fn test_text_tool_calls_ignored_when_disabled() {
+        let mut harness = tool_harness(8);
+        harness.agent.config.extract_tools_from_text = false;
+        harness.agent.provider = Box::new(ScriptedProvider::new(&[
+            "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"x\"}}</tool_call>",
+        ]));
+
+        harness.agent.chat("go").await.unwrap();
+        assert!(harness.log.lock().unwrap().is_empty());
+        assert_eq!(harness.agent.message_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_text_tool_calls_ignored_with_native_tool_use() {
+        let mut harness = tool_harness(8);
+        let mut provider = ScriptedProvider::new(&[
+            "Use it like <tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"x\"}}</tool_call>",
+        ]);
+        provider.native_tools = true;
+        harness.agent.provider = Box::new(provider);
+
+        let response = harness.agent.chat("how do I read x?").await.unwrap();
+        assert!(response.contains("<tool_call>"));
+        assert!(harness.log.lock().unwrap().is_empty());
+        assert_eq!(harness.agent.message_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_chat_records_checkpoint_of_written_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("notes.txt");
+        std::fs::write(&file, "before").unwrap();
+
+        let mut harness = tool_harness(8);
+        harness.agent.tool_registry = Arc::new(ToolRegistry::with_defaults());
+        harness.agent.provider = Box::new(ScriptedProvider::new(&[
+            &format!(
+                "<tool_call>{{\"name\": \"write_file\", \"arguments\": {{\"file_path\": {:?}, \"content\": \"after\"}}}}</tool_call>",
+                file.display().to_string()
+            ),
+            "Done.",
+        ]));
+        let store = Arc::new(std::sync::Mutex::new(checkpoint::CheckpointStore::in_memory()));
+        harness.agent.set_checkpoints(Some(store.clone()));
+
+        harness.agent.chat("update notes").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "after");
+        assert_eq!(store.lock().unwrap().checkpoints().len(), 1);
+
+        store.lock().unwrap().undo().unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "before");
+    }
+
+    #[tokio::test]
+    async fn test_pre_tool_hook_blocks_call_and_reports_stderr() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("notes.txt");
+        std::fs::write(&file, "before").unwrap();
+
+        let mut harness = tool_harness(8);
+        harness.agent.tool_registry = Arc::new(ToolRegistry::with_defaults());
+        harness.agent.provider = Box::new(ScriptedProvider::new(&[
+            &format!(
+                "<tool_call>{{\"name\": \"write_file\", \"arguments\": {{\"file_path\": {:?}, \"content\": \"after\"}}}}</tool_call>",
+                file.display().to_string()
+            ),
+            "Understood.",
+        ]));
+        harness.agent.hooks = Arc::new(HookRunner::new(crate::config::HooksConfig {
+            pre_tool_use: vec![crate::config::HookConfig {
+                command: "echo 'notes are read-only' >&2; exit 2".to_string(),
+                tools: Some(vec!["write_file".to_string()]),
+                timeout_ms: None,
+            }],
+            ..Default::default()
+        }));
+
+        let results = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let captured = results.clone();
+        harness.agent.callbacks.on_tool_result = Some(Arc::new(
+            move |_id: &str, _name: &str, result: &str, is_error: bool| {
+                captured.lock().unwrap().push((result.to_string(), is_error));
+            },
+        ));
+
+        harness.agent.chat("update notes").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "before");
+
+        let results = results.lock().unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].0.contains("notes are read-only"), "{}", results[0].0);
+        assert!(results[0].1);
+    }
+
+    fn count_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "required": ["count"],
+            "properties": { "count": { "type": "integer" } }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_output_schema_reprompts_until_answer_conforms() {
+        let mut harness = tool_harness(8);
+        harness.agent.output_schema = Some(count_schema());
+        let provider = ScriptedProvider::new(&[
+            "There are three files.",
+            "```json\n{\"count\": \"3\"}\n```",
+            "```json\n{\"count\": 3}\n```",
+        ]);
+        let system_prompts = provider.system_prompts.clone();
+        harness.agent.provider = Box::new(provider);
+
+        let response = harness.agent.chat("count files").await.unwrap();
+        assert_eq!(response, "{\"count\":3}");
+        assert!(system_prompts.lock().unwrap()[0].contains("## Output Format"));
+
+        let first = harness.agent.messages()[2].as_text().unwrap();
+        assert!(first.contains("$: response is not valid JSON"), "{}", first);
+        let second = harness.agent.messages()[4].as_text().unwrap();
+        assert!(second.contains("$.count: \"3\" is not of type \"integer\""), "{}", second);
+
+        // Give up once the retry limit is reached
+        harness.agent.config.max_schema_retries = 1;
+        harness.agent.provider = Box::new(ScriptedProvider::new(&["no", "still no"]));
+        let err = harness.agent.chat("again").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<AgentError>(),
+            Some(AgentError::SchemaValidation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_output_schema_uses_structured_output_when_supported() {
+        let mut harness = tool_harness(8);
+        harness.agent.output_schema = Some(count_schema());
+        let mut provider = ScriptedProvider::new(&["There are three files."]);
+        provider.structured_answer = Some("{\"count\": 3}".to_string());
+        harness.agent.provider = Box::new(provider);
+
+        let response = harness.agent.chat("count files").await.unwrap();
+        assert_eq!(response, "{\"count\":3}");
+        let messages = harness.agent.messages();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[2].as_text().unwrap(), schema::STRUCTURED_ANSWER_REQUEST);
+        assert_eq!(messages[3].as_text().unwrap(), "{\"count\": 3}");
+    }
+
+    #[tokio::test]
+    async fn test_repeated_tool_calls_warn_then_stop_turn() {
+        let mut harness = tool_harness(8);
+        let read = "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"x\"}}</tool_call>";
+        harness.agent.provider = Box::new(ScriptedProvider::new(&[read; 6]));
+
+        let err = harness.agent.chat("read x").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<AgentError>(),
+            Some(AgentError::LoopDetected(_))
+        ));
+        // Warned on the third identical call, stopped on the fourth
+        assert_eq!(harness.log.lock().unwrap().len(), 8);
+        let note = harness.agent.messages()[6].as_text().unwrap();
+        assert!(note.contains("System note: `read` was called 3 times"), "{}", note);
+        // The last call is still answered, so the session can continue
+        let last = harness.agent.messages().last().unwrap();
+        assert_eq!(last.role, Role::User);
+        assert!(last.as_text().unwrap().contains("System note: `read` was called 4 times"));
+    }
+
+    #[tokio::test]
+    async fn test_task_tool_runs_sub_agent_and_returns_summary() {
+        let mut harness = tool_harness(8);
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let callbacks = AgentCallbacks {
+            on_tool_call: Some(Arc::new({
+                let seen = seen.clone();
+                move |_id: &str, name: &str, _input: &serde_json::Value| {
+                    seen.lock().unwrap().push(name.to_string());
+                }
+            })),
+            ..Default::default()
+        };
+
+        let factory: task::ProviderFactory = Arc::new(|| {
+            Ok(Box::new(ScriptedProvider::new(&[
+                "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"cfg\"}}</tool_call>",
+                "<tool_call>{\"name\": \"write\", \"arguments\": {\"label\": \"w\"}}</tool_call>",
+                "Configured in cfg.",
+            ])) as BoxedProvider)
+        });
+        let registry = task::with_task_tool(
+            (*harness.agent.tool_registry).clone(),
+            factory,
+            &callbacks,
+            task::TaskConfig {
+                tools: vec!["read".to_string()],
+                ..Default::default()
+            },
+        );
+        harness.agent.tool_registry = Arc::new(registry);
+        harness.agent.callbacks = callbacks;
+        harness.agent.provider = Box::new(ScriptedProvider::new(&[
+            "<tool_call>{\"name\": \"task\", \"arguments\": {\"prompt\": \"Where is cfg?\"}}</tool_call>",
+            "It is in cfg.",
+        ]));
+
+        let response = harness.agent.chat("find cfg").await.unwrap();
+        assert_eq!(response, "It is in cfg.");
+
+        // Only the summary reaches the parent; the write tool wasn't available
+        let result = harness.agent.messages()[2].as_text().unwrap();
+        assert!(result.contains("Configured in cfg."), "{}", result);
+        assert_eq!(harness.agent.messages().len(), 4);
+        assert_eq!(harness.log.lock().unwrap().as_slice(), ["start:cfg", "end:cfg"]);
+        assert_eq!(seen.lock().unwrap().as_slice(), ["task", "read"]);
+    }
+
+    #[tokio::test]
+    async fn test_sub_agents_share_the_parent_budget() {
+        let mut harness = tool_harness(8);
+        let priced = |responses: &[&str]| {
+            let mut provider = ScriptedProvider::new(responses);
+            provider.usage = Some(TokenUsage {
+                input_tokens: 100_000,
+                output_tokens: 10_000,
+                ..Default::default()
+            });
+            provider.pricing = Some(ModelPricing::new(3.0, 15.0));
+            provider
+        };
+        let factory: task::ProviderFactory = Arc::new(move || {
+            Ok(Box::new(priced(&[
+                "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"a\"}}</tool_call>",
+                "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"b\"}}</tool_call>",
+                "Never reached.",
+            ])) as BoxedProvider)
+        });
+        let registry = task::with_task_tool(
+            (*harness.agent.tool_registry).clone(),
+            factory,
+            &AgentCallbacks::default(),
+            task::TaskConfig {
+                tools: vec!["read".to_string()],
+                ..Default::default()
+            },
+        );
+        harness.agent.tool_registry = Arc::new(registry);
+        harness.agent.provider = Box::new(priced(&[
+            "<tool_call>{\"name\": \"task\", \"arguments\": {\"prompt\": \"Look around\"}}</tool_call>",
+            "Done.",
+        ]));
+        harness.agent.config.budget = budget::BudgetConfig {
+            max_turn_cost: Some(1.0),
+            spend_ledger: None,
+            ..Default::default()
+        };
+
+        let err = harness.agent.chat("go").await.unwrap_err();
+        let exceeded = err.downcast_ref::<AgentError>().is_some_and(|e| {
+            matches!(e, AgentError::BudgetExceeded { scope: budget::BudgetScope::Turn, .. })
+        });
+        assert!(exceeded, "unexpected error: {}", err);
+
+        // The sub-agent stopped once it spent the 0.55 left after the parent's
+        // first response, and its two responses count against the parent
+        assert_eq!(
+            harness.log.lock().unwrap().as_slice(),
+            ["start:a", "end:a", "start:b", "end:b"]
+        );
+        assert!((harness.agent.session_cost() - 1.35).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_sub_agents_split_the_remaining_budget() {
+        let mut harness = tool_harness(8);
+        let priced = |responses: &[&str]| {
+            let mut provider = ScriptedProvider::new(responses);
+            provider.usage = Some(TokenUsage {
+                input_tokens: 100_000,
+                output_tokens: 10_000,
+                ..Default::default()
+            });
+            provider.pricing = Some(ModelPricing::new(3.0, 15.0));
+            provider
+        };
+        let factory: task::ProviderFactory = Arc::new(move || {
+            Ok(Box::new(priced(&[
+                "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"a\"}}</tool_call>",
+                "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"b\"}}</tool_call>",
+                "Never reached.",
+            ])) as BoxedProvider)
+        });
+        let registry = task::with_task_tool(
+            (*harness.agent.tool_registry).clone(),
+            factory,
+            &AgentCallbacks::default(),
+            task::TaskConfig {
+                tools: vec!["read".to_string()],
+                ..Default::default()
+            },
+        );
+        harness.agent.tool_registry = Arc::new(registry);
+        harness.agent.provider = Box::new(priced(&[
+            "<tool_call>{\"name\": \"task\", \"arguments\": {\"prompt\": \"Look left\"}}</tool_call>\n\
+             <tool_call>{\"name\": \"task\", \"arguments\": {\"prompt\": \"Look right\"}}</tool_call>",
+            "Done.",
+        ]));
+        harness.agent.config.budget = budget::BudgetConfig {
+            max_turn_cost: Some(1.0),
+            spend_ledger: None,
+            ..Default::default()
+        };
+
+        harness.agent.chat("go").await.unwrap_err();
+
+        // The 0.55 left is shared: two responses between both sub-agents, not two each
+        assert_eq!(harness.log.lock().unwrap().len(), 4);
+        assert!((harness.agent.session_cost() - 1.35).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_plan_mode_offers_reader_tools_and_requires_plan() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("notes.txt");
+        std::fs::write(&file, "before").unwrap();
+
+        let plan_block = "```plan\n{\"summary\": \"Update notes\", \"steps\": [{\"description\": \"Rewrite notes\", \"files\": [\"notes.txt\"]}]}\n```";
+        let provider = ScriptedProvider::new(&[
+            &format!(
+                "<tool_call>{{\"name\": \"write_file\", \"arguments\": {{\"file_path\": {:?}, \"content\": \"after\"}}}}</tool_call>",
+                file.display().to_string()
+            ),
+            "I would rewrite the notes.",
+            plan_block,
+        ]);
+        let system_prompts = provider.system_prompts.clone();
+
+        let mut harness = tool_harness(8);
+        harness.agent.tool_registry = Arc::new(ToolRegistry::with_defaults());
+        harness.agent.provider = Box::new(provider);
+        harness.agent.set_plan_mode(true);
+
+        let response = harness.agent.chat("update notes").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "before");
+        assert_eq!(Plan::parse(&response).unwrap().files(), vec!["notes.txt"]);
+
+        let prompts = system_prompts.lock().unwrap();
+        assert_eq!(prompts.len(), 3);
+        assert!(prompts[0].contains("## Plan Mode"));
+        assert!(prompts[0].contains("read_file"));
+        assert!(!prompts[0].contains("write_file"));
+    }
+
+    #[tokio::test]
+    async fn test_plan_mode_blocks_bash() {
+        let temp = tempfile::tempdir().unwrap();
+        let marker = temp.path().join("ran");
+        let provider = ScriptedProvider::new(&[
+            &format!(
+                "<tool_call>{{\"name\": \"bash\", \"arguments\": {{\"command\": \"touch {}\"}}}}</tool_call>",
+                marker.display()
+            ),
+            "```plan\n{\"summary\": \"Nothing\", \"steps\": []}\n```",
+        ]);
+        let system_prompts = provider.system_prompts.clone();
+
+        let mut harness = tool_harness(8);
+        harness.agent.tool_registry = Arc::new(ToolRegistry::with_defaults());
+        harness.agent.config.auto_approve_all = true;
+        harness.agent.provider = Box::new(provider);
+        harness.agent.set_plan_mode(true);
+
+        harness.agent.chat("look around").await.unwrap();
+        assert!(!marker.exists());
+        assert!(!system_prompts.lock().unwrap()[0].contains("\n- bash:"));
+
+        // A native call that names bash anyway is refused
+        let call = ToolCall {
+            id: "call-1".to_string(),
+            name: "bash".to_string(),
+            input: serde_json::json!({ "command": format!("touch {}", marker.display()) }),
+        };
+        let (results, has_error) = harness
+            .agent
+            .process_tool_calls(&[call], &mut TurnStats::default())
+            .await
+            .unwrap();
+        assert!(has_error);
+        assert!(results[0].content.contains("not available in plan mode"));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn test_estimate_tokens_anchors_on_reported_usage() {
+        let mut agent = agent_with_history(None, 0);
+        assert_eq!(agent.estimate_tokens(), "You are a helpful AI assistant.".len() / 4);
+
+        agent.state.running_char_count = 4_000;
+        agent.state.usage_anchor = Some(UsageAnchor { tokens: 50_000, char_count: 4_000 });
+        agent.add_user_text("x".repeat(400));
+        assert_eq!(agent.estimate_tokens(), 50_100);
+
+        // Editing the history drops the anchor
+        agent.messages_mut().clear();
+        assert!(agent.state.usage_anchor.is_none());
+    }
+
+    #[test]
+    fn test_compaction_threshold_follows_model_window() {
+        let mut harness = tool_harness(8);
+        let mut provider = ScriptedProvider::new(&[]);
+        provider.context_window = 8_000;
+        harness.agent.provider = Box::new(provider);
+        assert_eq!(harness.agent.context_config().summarization_threshold(), 6_400);
+
+        harness.agent.config.max_context_tokens = Some(4_000);
+        assert_eq!(harness.agent.context_config().summarization_threshold(), 3_200);
+
+        let mut provider = ScriptedProvider::new(&[]);
+        provider.context_window = 200_000;
+        harness.agent.provider = Box::new(provider);
+        harness.agent.config.max_context_tokens = None;
+        assert_eq!(harness.agent.context_config().summarization_threshold(), 160_000);
+    }
+
+    #[tokio::test]
+    async fn test_reported_usage_triggers_compaction() {
+        let mut harness = tool_harness(8);
+        let mut provider = ScriptedProvider::new(&[
+            "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"x\"}}</tool_call>",
+            "Done.",
+        ]);
+        provider.context_window = 8_000;
+        provider.usage = Some(TokenUsage {
+            input_tokens: 1_000,
+            output_tokens: 100,
+            cache_read_input_tokens: Some(6_000),
+            ..Default::default()
+        });
+        harness.agent.provider = Box::new(provider);
+
+        let compactions = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = compactions.clone();
+        harness.agent.callbacks.on_compaction = Some(Arc::new(move |starting| {
+            sink.lock().unwrap().push(starting);
+        }));
+
+        harness.agent.chat("go").await.unwrap();
+        // The short transcript alone is far below the threshold; only the
+        // reported 7,100 tokens push it over 6,400 before the second request.
+        assert_eq!(compactions.lock().unwrap().as_slice(), [true, false]);
+    }
+
+    #[tokio::test]
+    async fn test_turn_stats_include_cache_tokens() {
+        let mut harness = tool_harness(8);
+        let mut provider = ScriptedProvider::new(&[
+            "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"x\"}}</tool_call>",
+            "Done.",
+        ]);
+        provider.usage = Some(TokenUsage {
+            input_tokens: 50,
+            output_tokens: 10,
+            cache_creation_input_tokens: Some(2_000),
+            cache_read_input_tokens: Some(3_000),
+            ..Default::default()
+        });
+        harness.agent.provider = Box::new(provider);
+
+        let stats = Arc::new(std::sync::Mutex::new(None));
+        let sink = stats.clone();
+        harness.agent.callbacks.on_turn_complete = Some(Arc::new(move |s: &TurnStats| {
+            *sink.lock().unwrap() = Some(s.clone());
+        }));
+
+        harness.agent.chat("go").await.unwrap();
+        let stats = stats.lock().unwrap().clone().unwrap();
+        assert_eq!(stats.input_tokens, 100);
+        assert_eq!(stats.cache_write_tokens, 4_000);
+        assert_eq!(stats.cache_read_tokens, 6_000);
+    }
+
+    #[test]
+    fn test_assistant_blocks_keep_signed_thinking_for_tool_calls() {
+        let mut response = ProviderResponse::text("Reading it.");
+        response.reasoning_content = Some("Need the file first".to_string());
+        response.reasoning_signature = Some("sig".to_string());
+        response.tool_calls = vec![call("read", "x")];
+
+        let blocks = assistant_blocks(&response);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].block_type, ContentBlockType::Thinking);
+        assert_eq!(blocks[0].signature.as_deref(), Some("sig"));
+        assert_eq!(blocks[1].text.as_deref(), Some("Reading it."));
+
+        // Without tool calls, or without a signature, reasoning isn't kept
+        response.tool_calls.clear();
+        assert_eq!(assistant_blocks(&response).len(), 1);
+        response.tool_calls = vec![call("read", "x")];
+        response.reasoning_signature = None;
+        assert!(assistant_blocks(&response)
+            .iter()
+            .all(|b| b.block_type != ContentBlockType::Thinking));
+    }
+
+    #[tokio::test]
+    async fn test_turn_budget_stops_agent_loop() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = dir.path().join("spend.json");
+
+        let mut harness = tool_harness(8);
+        let mut provider = ScriptedProvider::new(&[
+            "<tool_call>{\"name\": \"read\", \"arguments\": {\"label\": \"x\"}}</tool_call>",
+            "Done.",
+        ]);
+        provider.usage = Some(TokenUsage {
+            input_tokens: 100_000,
+            output_tokens: 10_000,
+            ..Default::default()
+        });
+        provider.pricing = Some(ModelPricing::new(3.0, 15.0));
+        harness.agent.provider = Box::new(provider);
+        harness.agent.config.budget = budget::BudgetConfig {
+            max_turn_cost: Some(0.40),
+            spend_ledger: Some(ledger.clone()),
+            ..Default::default()
+        };
+
+        let err = harness.agent.chat("go").await.unwrap_err();
+        let exceeded = err.downcast_ref::<AgentError>().is_some_and(|e| {
+            matches!(e, AgentError::BudgetExceeded { scope: budget::BudgetScope::Turn, .. })
+        });
+        assert!(exceeded, "unexpected error: {}", err);
+
+        // The first response (0.30 input + 0.15 output) was paid for and its tool ran
+        assert_eq!(harness.log.lock().unwrap().as_slice(), ["start:x", "end:x"]);
+        assert!((harness.agent.session_cost() - 0.45).abs() < 1e-9);
+        assert!((budget::load_daily_spend(&ledger) - 0.45).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_budget_caps_require_pricing() {
+        let mut harness = tool_harness(8);
+        let provider = ScriptedProvider::new(&["Hi."]);
+        let system_prompts = provider.system_prompts.clone();
+        harness.agent.provider = Box::new(provider);
+        harness.agent.config.budget = budget::BudgetConfig {
+            max_daily_cost: Some(5.0),
+            spend_ledger: None,
+            ..Default::default()
+        };
+
+        let err = harness.agent.chat("hello").await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<AgentError>(), Some(AgentError::UnpricedModel(_))));
+        assert!(system_prompts.lock().unwrap().is_empty());
+
+        // Without caps, unpriced models run as before
+        harness.agent.config.budget.max_daily_cost = None;
+        assert_eq!(harness.agent.chat("hello").await.unwrap(), "Hi.");
+    }
+
+    #[tokio::test]
+    async fn test_session_budget_blocks_new_turns() {
+        let mut harness = tool_harness(8);
+        let provider = ScriptedProvider::new(&["Hi."]);
+        let system_prompts = provider.system_prompts.clone();
+        harness.agent.provider = Box::new(provider);
+        harness.agent.config.budget = budget::BudgetConfig {
+            max_session_cost: Some(5.0),
+            spend_ledger: None,
+            ..Default::default()
+        };
+        harness.agent.set_session_cost(5.0);
+
+        let err = harness.agent.chat("hello").await.unwrap_err();
+        assert!(err.to_string().contains("Session budget exceeded"));
+        assert!(system_prompts.lock().unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_chat_with_cancel_returns_user_cancelled() {
         let provider: BoxedProvider = Box::new(SlowProvider {
@@ -874,6 +2530,8 @@ mod tests {
             system_prompt: None,
             config: AgentConfig::default(),
             callbacks: AgentCallbacks::default(),
+            summarize_provider: None,
+            output_schema: None,
         });
 
         let (tx, rx) = watch::channel(false);
@@ -0,0 +1,159 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Plan mode: read-only investigation that ends in an approvable plan.
+//!
+//! While plan mode is on, the agent only offers reader tools without shell
+//! access ([`is_plan_tool`]) to the model and asks it to finish with a fenced
+//! `plan` block:
+//!
+//! ````text
+//! ```plan
+//! {"summary": "Extract config loading", "steps": [
+//!   {"description": "Move parsing into config/loader.rs", "files": ["src/config/loader.rs"]}
+//! ]}
+//! ```
+//! ````
+//!
+//! The UI shows the parsed [`Plan`] and, once approved, turns plan mode off
+//! and sends [`Plan::execution_prompt`] to carry it out.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::orchestrate::is_reader_tool;
+
+static PLAN_BLOCK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)```[ \t]*plan[ \t]*\r?\n(.*?)```").unwrap()
+});
+
+/// Whether a tool may run in plan mode: reader tools, minus unfiltered shell access.
+pub fn is_plan_tool(tool_name: &str) -> bool {
+    tool_name != "bash" && is_reader_tool(tool_name)
+}
+
+/// System prompt instructions used while plan mode is on.
+pub const PLAN_MODE_INSTRUCTIONS: &str = "\
+## Plan Mode
+
+You are in plan mode. Investigate the codebase with the read-only tools \
+available, but do not modify anything. When you understand the change, \
+finish your response with the plan in a fenced `plan` block:
+
+```plan
+{\"summary\": \"One-line description of the change\", \"steps\": [
+  {\"description\": \"What to do in this step\", \"files\": [\"path/to/file.rs\"]}
+]}
+```
+
+List every file each step will create or modify. The user reviews the plan \
+before anything is changed.";
+
+/// Message sent back when a plan-mode response has no valid plan.
+pub const PLAN_CORRECTION_MESSAGE: &str = "\
+Your response did not end with a valid plan. Finish with a fenced `plan` block \
+containing JSON with a `summary` and a non-empty list of `steps`, each with a \
+`description` and the `files` it changes.";
+
+/// A structured plan produced in plan mode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    /// Short description of the overall change.
+    #[serde(default)]
+    pub summary: String,
+    /// Ordered steps to carry out.
+    pub steps: Vec<PlanStep>,
+}
+
+/// A single step of a [`Plan`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanStep {
+    /// What to do.
+    pub description: String,
+    /// Files created or modified by this step.
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+impl Plan {
+    /// Parse the last `plan` block in a response. Returns `None` if there is
+    /// no block, it isn't valid JSON, or it has no steps.
+    pub fn parse(response: &str) -> Option<Self> {
+        let block = PLAN_BLOCK.captures_iter(response).last()?;
+        let plan: Self = serde_json::from_str(block[1].trim()).ok()?;
+        (!plan.steps.is_empty()).then_some(plan)
+    }
+
+    /// All files the plan touches, in first-mention order without duplicates.
+    pub fn files(&self) -> Vec<&str> {
+        let mut files: Vec<&str> = Vec::new();
+        for file in self.steps.iter().flat_map(|s| &s.files) {
+            if !files.contains(&file.as_str()) {
+                files.push(file);
+            }
+        }
+        files
+    }
+
+    /// Format the plan for display.
+    pub fn format(&self) -> String {
+        let mut out = String::new();
+        if !self.summary.is_empty() {
+            out.push_str(&self.summary);
+            out.push_str("\n\n");
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            out.push_str(&format!("{}. {}\n", i + 1, step.description));
+            if !step.files.is_empty() {
+                out.push_str(&format!("   files: {}\n", step.files.join(", ")));
+            }
+        }
+        out.trim_end().to_string()
+    }
+
+    /// Prompt asking the model to carry out the approved plan.
+    pub fn execution_prompt(&self) -> String {
+        format!(
+            "The plan below has been approved. Carry it out now, step by step.\n\n{}",
+            self.format()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_last_plan_block() {
+        let response = "I looked at the loader.\n\n\
+            ```plan\n{\"summary\": \"draft\", \"steps\": [{\"description\": \"old\"}]}\n```\n\
+            Revised:\n\
+            ```plan\n{\"summary\": \"Split loader\", \"steps\": [\
+              {\"description\": \"Move parsing\", \"files\": [\"src/config/loader.rs\", \"src/config/mod.rs\"]},\
+              {\"description\": \"Update callers\", \"files\": [\"src/main.rs\", \"src/config/mod.rs\"]}]}\n```";
+
+        let plan = Plan::parse(response).unwrap();
+        assert_eq!(plan.summary, "Split loader");
+        assert_eq!(plan.steps.len(), 2);
+        assert_eq!(
+            plan.files(),
+            vec!["src/config/loader.rs", "src/config/mod.rs", "src/main.rs"]
+        );
+        assert_eq!(
+            plan.format(),
+            "Split loader\n\n1. Move parsing\n   files: src/config/loader.rs, src/config/mod.rs\n\
+             2. Update callers\n   files: src/main.rs, src/config/mod.rs"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_missing_or_empty_plans() {
+        assert!(Plan::parse("No plan here.").is_none());
+        assert!(Plan::parse("```plan\nnot json\n```").is_none());
+        assert!(Plan::parse("```plan\n{\"summary\": \"x\", \"steps\": []}\n```").is_none());
+        // Regular JSON blocks are not plans
+        assert!(Plan::parse("```json\n{\"steps\": [{\"description\": \"x\"}]}\n```").is_none());
+    }
+}
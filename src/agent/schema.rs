@@ -0,0 +1,258 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Schema-constrained final answers.
+//!
+//! When [`AgentOptions::output_schema`](super::AgentOptions::output_schema)
+//! is set, the agent asks the model to finish with JSON matching the schema
+//! and checks the final message with [`validate`]. Validation errors are sent
+//! back to the model until the answer conforms or
+//! [`AgentConfig::max_schema_retries`](super::AgentConfig::max_schema_retries)
+//! is reached.
+//!
+//! Validation is done by the `jsonschema` crate, so every keyword of the
+//! schema's draft is enforced, including `$ref`, `pattern` and `format`.
+//! Only local references resolve; remote ones are rejected when compiling.
+
+use jsonschema::paths::{Location, LocationSegment};
+use jsonschema::Validator;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+static JSON_BLOCK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)```[ \t]*json[ \t]*\r?\n(.*?)```").unwrap()
+});
+
+/// System prompt instructions for a required output schema.
+pub fn schema_instructions(schema: &Value) -> String {
+    format!(
+        "## Output Format\n\n\
+         When you have finished, your final response must be only a JSON value \
+         matching this JSON Schema, with no other text:\n\n```json\n{}\n```",
+        serde_json::to_string_pretty(schema).unwrap_or_default()
+    )
+}
+
+/// Message asking the model to fix a final answer that failed validation.
+pub fn correction_message(errors: &[String]) -> String {
+    format!(
+        "Your final response does not match the required JSON Schema:\n{}\n\n\
+         Respond again with only the corrected JSON.",
+        errors
+            .iter()
+            .map(|e| format!("- {}", e))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
+/// Message asking for the final answer through a provider's structured output.
+pub const STRUCTURED_ANSWER_REQUEST: &str =
+    "Provide your final answer now as JSON matching the required schema.";
+
+/// Extract a JSON value from a response.
+///
+/// Tries the whole response, then the last fenced `json` block, then the
+/// span from the first `{` or `[` to the last matching closer.
+pub fn extract_json(response: &str) -> Option<Value> {
+    let trimmed = response.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Some(value);
+    }
+
+    if let Some(block) = JSON_BLOCK.captures_iter(response).last() {
+        if let Ok(value) = serde_json::from_str(block[1].trim()) {
+            return Some(value);
+        }
+    }
+
+    [('{', '}'), ('[', ']')].iter().find_map(|&(open, close)| {
+        let start = trimmed.find(open)?;
+        let end = trimmed.rfind(close)?;
+        (end > start)
+            .then(|| serde_json::from_str(&trimmed[start..=end]).ok())
+            .flatten()
+    })
+}
+
+/// Validate a response against a schema, returning the parsed value or the
+/// list of errors.
+pub fn check_response(response: &str, schema: &Value) -> Result<Value, Vec<String>> {
+    let Some(value) = extract_json(response) else {
+        return Err(vec!["$: response is not valid JSON".to_string()]);
+    };
+    let errors = validate(&value, schema);
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Compile `schema`, describing the problem if it is not a valid JSON Schema.
+///
+/// `format` is asserted rather than treated as an annotation, and unknown
+/// formats are rejected so a schema never constrains less than it says.
+pub fn compile(schema: &Value) -> Result<Validator, String> {
+    jsonschema::options()
+        .should_validate_formats(true)
+        .should_ignore_unknown_formats(false)
+        .build(schema)
+        .map_err(|e| e.to_string())
+}
+
+/// Validate `value` against `schema`. Returns one message per violation,
+/// prefixed with the JSON path (`$.items[0].name`).
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let validator = match compile(schema) {
+        Ok(validator) => validator,
+        Err(e) => return vec![format!("$: invalid schema: {}", e)],
+    };
+    validator
+        .iter_errors(value)
+        .map(|error| format!("{}: {}", json_path(&error.instance_path), error))
+        .collect()
+}
+
+fn json_path(location: &Location) -> String {
+    let mut path = String::from("$");
+    for segment in location {
+        match segment {
+            LocationSegment::Property(name) => {
+                path.push('.');
+                path.push_str(name);
+            }
+            LocationSegment::Index(i) => path.push_str(&format!("[{}]", i)),
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn review_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["verdict", "issues"],
+            "additionalProperties": false,
+            "properties": {
+                "verdict": { "enum": ["approve", "reject"] },
+                "score": { "type": "integer", "minimum": 0, "maximum": 10 },
+                "issues": {
+                    "type": "array",
+                    "maxItems": 2,
+                    "items": {
+                        "type": "object",
+                        "required": ["file"],
+                        "properties": { "file": { "type": "string", "minLength": 1 } }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_validate_reports_paths() {
+        let schema = review_schema();
+        let ok = json!({ "verdict": "approve", "score": 7, "issues": [{ "file": "a.rs" }] });
+        assert!(validate(&ok, &schema).is_empty());
+
+        let bad = json!({
+            "verdict": "maybe",
+            "score": 11.5,
+            "issues": [{ "file": "" }, {}, { "file": "c.rs" }],
+            "extra": true
+        });
+        let errors = validate(&bad, &schema);
+        assert_eq!(
+            errors,
+            vec![
+                "$.issues[0].file: \"\" is shorter than 1 character",
+                "$.issues[1]: \"file\" is a required property",
+                "$.issues: [{\"file\":\"\"},{},{\"file\":\"c.rs\"}] has more than 2 items",
+                "$.score: 11.5 is greater than the maximum of 10",
+                "$.score: 11.5 is not of type \"integer\"",
+                "$.verdict: \"maybe\" is not one of [\"approve\",\"reject\"]",
+                "$: Additional properties are not allowed ('extra' was unexpected)",
+            ]
+        );
+        assert_eq!(
+            validate(&json!({ "verdict": "approve" }), &schema),
+            vec!["$: \"issues\" is a required property"]
+        );
+    }
+
+    #[test]
+    fn test_validate_combinators() {
+        let schema = json!({ "anyOf": [{ "type": "string" }, { "type": "null" }] });
+        assert!(validate(&json!(null), &schema).is_empty());
+        assert_eq!(validate(&json!(3), &schema).len(), 1);
+
+        let schema = json!({ "oneOf": [{ "type": "number" }, { "type": "integer" }] });
+        assert!(validate(&json!(1.5), &schema).is_empty());
+        assert_eq!(validate(&json!(2), &schema).len(), 1);
+
+        let schema = json!({ "type": ["string", "array"] });
+        assert!(validate(&json!([]), &schema).is_empty());
+        assert_eq!(validate(&json!({}), &schema), vec!["$: {} is not of types \"array\", \"string\""]);
+    }
+
+    #[test]
+    fn test_validate_full_keyword_set() {
+        let schema = json!({
+            "$defs": { "id": { "type": "string", "pattern": "^[a-z]+-[0-9]+$" } },
+            "type": "object",
+            "minProperties": 1,
+            "properties": {
+                "id": { "$ref": "#/$defs/id" },
+                "email": { "type": "string", "format": "email" },
+                "ratio": { "type": "number", "exclusiveMinimum": 0, "multipleOf": 0.5 },
+                "tags": { "type": "array", "uniqueItems": true }
+            }
+        });
+        let ok = json!({ "id": "abc-1", "email": "a@b.io", "ratio": 1.5, "tags": ["x", "y"] });
+        assert!(validate(&ok, &schema).is_empty());
+
+        let bad = json!({ "id": "ABC", "email": "nope", "ratio": 0, "tags": ["x", "x"] });
+        let mut paths: Vec<String> = validate(&bad, &schema)
+            .iter()
+            .map(|e| e.split(':').next().unwrap().to_string())
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["$.email", "$.id", "$.ratio", "$.tags"]);
+        assert_eq!(validate(&json!({}), &schema).len(), 1);
+    }
+
+    #[test]
+    fn test_compile_rejects_invalid_schemas() {
+        assert!(compile(&review_schema()).is_ok());
+        assert!(compile(&json!({ "type": "strnig" })).is_err());
+        assert!(compile(&json!({ "format": "postcode" })).is_err());
+        assert!(compile(&json!({ "$ref": "https://example.com/schema.json" })).is_err());
+
+        let errors = validate(&json!(1), &json!({ "minimum": "zero" }));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("$: invalid schema: "), "{}", errors[0]);
+    }
+
+    #[test]
+    fn test_extract_json() {
+        assert_eq!(extract_json(" {\"a\": 1} "), Some(json!({ "a": 1 })));
+        assert_eq!(
+            extract_json("Done.\n```json\n{\"a\": 1}\n```\nFinal:\n```json\n[2]\n```"),
+            Some(json!([2]))
+        );
+        assert_eq!(
+            extract_json("The result is {\"a\": {\"b\": 2}} as requested."),
+            Some(json!({ "a": { "b": 2 } }))
+        );
+        assert_eq!(extract_json("no json here"), None);
+
+        let err = check_response("nothing", &review_schema()).unwrap_err();
+        assert_eq!(err, vec!["$: response is not valid JSON"]);
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Model-generated conversation summaries for context compaction.
+//!
+//! When the agent compacts its context, older messages are rendered into a
+//! plain-text transcript and sent to a summarization model, which returns a
+//! structured summary (goals, decisions, files touched, open todos). If no
+//! summarization model is configured or the request fails, the agent falls
+//! back to its built-in truncation summary.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::ResolvedConfig;
+use crate::model_map::{load_model_map, ModelRegistry, TaskRouter};
+use crate::providers::create_summarize_provider_from_config;
+use crate::types::{Message, MessageContent, Role, SharedProvider};
+
+use super::Agent;
+
+/// System prompt for the summarization model.
+pub const SUMMARY_SYSTEM_PROMPT: &str = "You summarize coding-assistant conversations so that \
+the work can continue after older messages are discarded. Be precise and terse. Preserve exact \
+file paths, function names, commands, error messages and decisions. Never invent details that \
+are not in the transcript. Respond with the summary only.";
+
+/// Maximum characters of a single text block included in the transcript.
+const MAX_TEXT_CHARS: usize = 4_000;
+
+/// Maximum characters of a tool input or tool result included in the transcript.
+const MAX_TOOL_CHARS: usize = 1_000;
+
+/// Maximum characters of the whole transcript sent to the summarization model.
+const MAX_TRANSCRIPT_CHARS: usize = 120_000;
+
+/// Maximum characters kept from a model-generated summary.
+pub const MAX_SUMMARY_CHARS: usize = 8_000;
+
+/// Build the user prompt asking the model to summarize `messages`.
+///
+/// An existing summary from an earlier compaction is included so the model
+/// can fold it into the new one instead of losing it.
+pub fn build_summary_request(previous_summary: Option<&str>, messages: &[Message]) -> String {
+    let mut request = String::from(
+        "Summarize the conversation below. Use exactly these markdown sections:\n\n\
+         ## Goals\nWhat the user is trying to achieve.\n\n\
+         ## Decisions\nApproaches chosen or rejected, and why.\n\n\
+         ## Files Touched\nFile paths read, created or modified, with a few words on each.\n\n\
+         ## Open TODOs\nWork that is still pending or was left unfinished.\n\n\
+         Write \"None\" under a section that has nothing to report.\n",
+    );
+
+    if let Some(previous) = previous_summary {
+        request.push_str("\n<previous_summary>\n");
+        request.push_str(previous);
+        request.push_str("\n</previous_summary>\n");
+    }
+
+    request.push_str("\n<transcript>\n");
+    request.push_str(&build_transcript(messages));
+    request.push_str("\n</transcript>\n");
+    request
+}
+
+/// Render messages as a plain-text transcript, including tool calls and results.
+///
+/// Long blocks are truncated and, if the transcript is still too long, the
+/// oldest part is dropped so the most recent context survives.
+pub fn build_transcript(messages: &[Message]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    for msg in messages {
+        let role = match msg.role {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::System => "System",
+        };
+
+        match &msg.content {
+            MessageContent::Text(text) => {
+                if !text.is_empty() {
+                    lines.push(format!("{}: {}", role, Agent::truncate_str(text, MAX_TEXT_CHARS)));
+                }
+            }
+            MessageContent::Blocks(blocks) => {
+                for block in blocks {
+                    if let Some(ref name) = block.name {
+                        let input = block
+                            .input
+                            .as_ref()
+                            .map(|v| v.to_string())
+                            .unwrap_or_default();
+                        lines.push(format!(
+                            "{} called tool {}: {}",
+                            role,
+                            name,
+                            Agent::truncate_str(&input, MAX_TOOL_CHARS)
+                        ));
+                    } else if let Some(ref content) = block.content {
+                        let label = if block.is_error == Some(true) {
+                            "Tool error"
+                        } else {
+                            "Tool result"
+                        };
+                        lines.push(format!("{}: {}", label, Agent::truncate_str(content, MAX_TOOL_CHARS)));
+                    } else if let Some(ref text) = block.text {
+                        if !text.is_empty() {
+                            lines.push(format!("{}: {}", role, Agent::truncate_str(text, MAX_TEXT_CHARS)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let transcript = lines.join("\n");
+    let char_count = transcript.chars().count();
+    if char_count <= MAX_TRANSCRIPT_CHARS {
+        return transcript;
+    }
+
+    let tail: String = transcript
+        .chars()
+        .skip(char_count - MAX_TRANSCRIPT_CHARS)
+        .collect();
+    format!("[earlier messages omitted]\n{}", tail)
+}
+
+/// Resolve the provider used for conversation summaries.
+///
+/// Explicit settings (`models.summarize` in the workspace config, or
+/// `--summarize-provider` / `--summarize-model`) take precedence. Otherwise the
+/// model map is consulted via [`TaskRouter::get_summarize_model`]. Returns
+/// `None` when nothing is configured, in which case compaction uses truncation.
+pub async fn resolve_summarize_provider(
+    config: &ResolvedConfig,
+    project_path: &Path,
+) -> Option<SharedProvider> {
+    match create_summarize_provider_from_config(config) {
+        Ok(Some(provider)) => return Some(Arc::from(provider)),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::warn!("Failed to create summarize provider from config: {}", e);
+        }
+    }
+
+    let model_map = load_model_map(project_path).config?;
+    let registry = Arc::new(ModelRegistry::new(model_map.clone()));
+    let router = TaskRouter::new(model_map, registry.clone());
+
+    let resolved = match router.get_summarize_model().await {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            tracing::warn!("No summarize model in model map: {}", e);
+            return None;
+        }
+    };
+
+    match registry.get_provider(&resolved.name).await {
+        Ok(provider) => Some(provider),
+        Err(e) => {
+            tracing::warn!("Failed to create summarize provider '{}': {}", resolved.name, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ContentBlock;
+
+    #[test]
+    fn test_transcript_includes_tool_calls_and_results() {
+        let messages = vec![
+            Message::user("Fix the login bug"),
+            Message::with_blocks(
+                Role::Assistant,
+                vec![
+                    ContentBlock::text("Let me look at the handler."),
+                    ContentBlock::tool_use(
+                        "t1",
+                        "read_file",
+                        serde_json::json!({"path": "src/auth/login.rs"}),
+                    ),
+                ],
+            ),
+            Message::with_blocks(
+                Role::User,
+                vec![ContentBlock::tool_result("t1", "fn login() {}", false)],
+            ),
+        ];
+
+        let transcript = build_transcript(&messages);
+        assert!(transcript.contains("User: Fix the login bug"));
+        assert!(transcript.contains("Assistant: Let me look at the handler."));
+        assert!(transcript.contains("Assistant called tool read_file"));
+        assert!(transcript.contains("src/auth/login.rs"));
+        assert!(transcript.contains("Tool result: fn login() {}"));
+    }
+
+    #[test]
+    fn test_transcript_keeps_most_recent_when_too_long() {
+        let messages: Vec<Message> = (0..100)
+            .map(|i| Message::user(format!("message-{} {}", i, "x".repeat(3_000))))
+            .collect();
+
+        let transcript = build_transcript(&messages);
+        assert!(transcript.starts_with("[earlier messages omitted]"));
+        assert!(transcript.contains("message-99"));
+        assert!(!transcript.contains("message-0 "));
+    }
+
+    #[test]
+    fn test_summary_request_sections_and_previous_summary() {
+        let request = build_summary_request(
+            Some("## Goals\nShip v1"),
+            &[Message::user("continue")],
+        );
+        for section in ["## Goals", "## Decisions", "## Files Touched", "## Open TODOs"] {
+            assert!(request.contains(section));
+        }
+        assert!(request.contains("<previous_summary>\n## Goals\nShip v1"));
+        assert!(request.contains("User: continue"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_summarize_provider_explicit_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ResolvedConfig {
+            summarize_provider: Some("ollama".to_string()),
+            summarize_model: Some("qwen2.5:3b".to_string()),
+            ..Default::default()
+        };
+        let provider = resolve_summarize_provider(&config, dir.path()).await.unwrap();
+        assert_eq!(provider.model(), "qwen2.5:3b");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_summarize_provider_from_model_map() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("codi-models.yaml"),
+            "version: \"1\"\nmodels:\n  local:\n    provider: ollama\n    model: summarizer-test\ntasks:\n  summarize:\n    model: local\n",
+        )
+        .unwrap();
+
+        let provider = resolve_summarize_provider(&ResolvedConfig::default(), dir.path())
+            .await
+            .unwrap();
+        assert_eq!(provider.model(), "summarizer-test");
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! In-process sub-agents for delegated research.
+//!
+//! The `task` tool starts a nested [`Agent`] with a fresh context, a
+//! restricted tool set and its own iteration and time budget. Only the
+//! sub-agent's final summary is returned as the tool result, which keeps the
+//! parent's context small.
+//!
+//! This is the lightweight alternative to the worker processes spawned by
+//! [`Commander`](crate::orchestrate::Commander): no worktree, no IPC, just
+//! another agent loop sharing the parent's process.
+//!
+//! Sub-agent tool calls, results, confirmations and hook notices are
+//! forwarded to the parent's [`AgentCallbacks`], so they show up in the UI.
+//! Text and stream events are not, since only the summary is meant for the
+//! parent.
+//!
+//! While the parent runs its tools it publishes a [`ParentTurn`]: sub-agents
+//! inherit its remaining budget, retry settings and cancellation signal, and
+//! their spend is added to the parent's turn and session cost.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::sync::watch;
+
+use crate::config::{HooksConfig, ResolvedConfig};
+use crate::error::{ProviderError, ToolError};
+use crate::orchestrate::READER_ALLOWED_TOOLS;
+use crate::providers::{create_provider_from_config, RetryConfig};
+use crate::tools::{parse_arguments, ToolHandler, ToolOutput, ToolRegistry};
+use crate::types::{BoxedProvider, InputSchema, ToolDefinition};
+
+use super::budget::BudgetConfig;
+use super::{Agent, AgentCallbacks, AgentConfig, AgentOptions};
+
+/// Name of the sub-agent tool.
+pub const TASK_TOOL_NAME: &str = "task";
+
+/// System prompt for sub-agents.
+const SUB_AGENT_PROMPT: &str = "\
+You are a sub-agent doing focused research for another coding agent. Use \
+your tools to investigate the task, then reply with a concise summary of \
+what you found, including the relevant file paths and line numbers. Your \
+final message is the only thing the other agent will see.";
+
+tokio::task_local! {
+    /// Turn of the agent whose tool calls are running.
+    static PARENT_TURN: ParentTurn;
+}
+
+/// What sub-agents inherit from the turn of the agent that starts them.
+#[derive(Clone)]
+pub(crate) struct ParentTurn {
+    /// Caps on what the turn's sub-agents may still spend between them.
+    pub budget: BudgetConfig,
+    /// Retry settings of the parent.
+    pub retry: RetryConfig,
+    /// Cancellation signal of the parent's turn.
+    pub cancel: Option<watch::Receiver<bool>>,
+    /// Spend of sub-agents started during the turn, added as it happens.
+    pub spent: Arc<Mutex<f64>>,
+}
+
+impl ParentTurn {
+    /// Run `future` with this turn visible to sub-agents started inside it.
+    pub(crate) async fn scope<F: Future>(self, future: F) -> F::Output {
+        PARENT_TURN.scope(self, future).await
+    }
+
+    /// The turn of the enclosing [`scope`](Self::scope), if any.
+    pub(crate) fn current() -> Option<Self> {
+        PARENT_TURN.try_with(Clone::clone).ok()
+    }
+}
+
+/// Creates a fresh provider for each sub-agent.
+pub type ProviderFactory = Arc<dyn Fn() -> Result<BoxedProvider, ProviderError> + Send + Sync>;
+
+/// Provider factory that builds the configured provider.
+pub fn provider_factory(config: &ResolvedConfig) -> ProviderFactory {
+    let config = config.clone();
+    Arc::new(move || create_provider_from_config(&config))
+}
+
+/// Limits and tools for sub-agents started by the `task` tool.
+#[derive(Debug, Clone)]
+pub struct TaskConfig {
+    /// Tools the sub-agent may use.
+    pub tools: Vec<String>,
+    /// Maximum iterations per sub-agent.
+    pub max_iterations: usize,
+    /// Maximum sub-agent run time in milliseconds.
+    pub max_duration_ms: u64,
+    /// Shell hooks applied to sub-agent tool calls.
+    pub hooks: HooksConfig,
+}
+
+impl Default for TaskConfig {
+    fn default() -> Self {
+        Self {
+            // Reader tools, minus unfiltered shell access
+            tools: READER_ALLOWED_TOOLS
+                .iter()
+                .filter(|t| **t != "bash")
+                .map(|t| t.to_string())
+                .collect(),
+            max_iterations: 20,
+            max_duration_ms: 5 * 60 * 1000, // 5 minutes
+            hooks: HooksConfig::default(),
+        }
+    }
+}
+
+impl TaskConfig {
+    /// Default limits with the configured hooks.
+    pub fn from_config(config: &ResolvedConfig) -> Self {
+        Self {
+            hooks: config.hooks.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Handler for the `task` tool.
+pub struct TaskTool {
+    provider_factory: ProviderFactory,
+    tool_registry: Arc<ToolRegistry>,
+    callbacks: AgentCallbacks,
+    config: TaskConfig,
+}
+
+/// Arguments for the task tool.
+#[derive(Debug, Deserialize)]
+struct TaskArgs {
+    /// Short label for the task.
+    #[serde(default)]
+    description: Option<String>,
+    /// Full instructions for the sub-agent.
+    prompt: String,
+}
+
+impl TaskTool {
+    /// Create the tool. Sub-agents may use the tools of `tools` listed in
+    /// `config.tools`; the `task` tool itself is never passed down.
+    pub fn new(
+        provider_factory: ProviderFactory,
+        tools: &ToolRegistry,
+        callbacks: &AgentCallbacks,
+        config: TaskConfig,
+    ) -> Self {
+        let tool_registry = tools.filtered(|name| {
+            name != TASK_TOOL_NAME && config.tools.iter().any(|t| t == name)
+        });
+
+        // Sub-agent output stays out of the parent's transcript
+        let callbacks = AgentCallbacks {
+            on_tool_call: callbacks.on_tool_call.clone(),
+            on_tool_result: callbacks.on_tool_result.clone(),
+            on_confirm: callbacks.on_confirm.clone(),
+            on_hook: callbacks.on_hook.clone(),
+            ..Default::default()
+        };
+
+        Self {
+            provider_factory,
+            tool_registry: Arc::new(tool_registry),
+            callbacks,
+            config,
+        }
+    }
+
+    fn agent_config(&self, parent: Option<&ParentTurn>) -> AgentConfig {
+        let defaults = AgentConfig::default();
+        AgentConfig {
+            max_iterations: self.config.max_iterations,
+            max_turn_duration_ms: self.config.max_duration_ms,
+            retry: parent.map_or(defaults.retry.clone(), |p| p.retry.clone()),
+            budget: parent.map_or(defaults.budget.clone(), |p| p.budget.clone()),
+            hooks: self.config.hooks.clone(),
+            ..defaults
+        }
+    }
+}
+
+/// Register a [`TaskTool`] alongside the tools of `registry`.
+pub fn with_task_tool(
+    registry: ToolRegistry,
+    provider_factory: ProviderFactory,
+    callbacks: &AgentCallbacks,
+    config: TaskConfig,
+) -> ToolRegistry {
+    let task = TaskTool::new(provider_factory, &registry, callbacks, config);
+    let mut builder = registry.into_builder();
+    builder.register(task);
+    builder.build()
+}
+
+#[async_trait]
+impl ToolHandler for TaskTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            TASK_TOOL_NAME,
+            "Delegate a research task to a sub-agent with read-only tools and a fresh \
+             context, such as finding where something is defined or configured. \
+             Returns only the sub-agent's summary. Give it a complete, self-contained prompt.",
+        )
+        .with_schema(
+            InputSchema::new()
+                .with_property("description", serde_json::json!({
+                    "type": "string",
+                    "description": "Short (3-5 word) label for the task"
+                }))
+                .with_property("prompt", serde_json::json!({
+                    "type": "string",
+                    "description": "Instructions for the sub-agent, including what to report back"
+                }))
+                .with_required(vec!["prompt".to_string()]),
+        )
+    }
+
+    fn is_mutating(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> Result<ToolOutput, ToolError> {
+        let args: TaskArgs = parse_arguments(&input)?;
+        if args.prompt.trim().is_empty() {
+            return Err(ToolError::InvalidInput("prompt must not be empty".to_string()));
+        }
+
+        let provider = (self.provider_factory)()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to create provider: {}", e)))?;
+
+        let parent = ParentTurn::current();
+        let mut agent = Agent::new(AgentOptions {
+            provider,
+            tool_registry: self.tool_registry.clone(),
+            system_prompt: Some(SUB_AGENT_PROMPT.to_string()),
+            config: self.agent_config(parent.as_ref()),
+            callbacks: self.callbacks.clone(),
+            summarize_provider: None,
+            output_schema: None,
+        });
+        if let Some(ref parent) = parent {
+            agent.set_shared_spend(parent.spent.clone());
+        }
+
+        let result = match parent.as_ref().and_then(|p| p.cancel.clone()) {
+            Some(cancel) => agent.chat_with_cancel(&args.prompt, cancel).await,
+            None => agent.chat(&args.prompt).await,
+        };
+        let summary = result.map_err(|e| ToolError::ExecutionFailed(format!("Sub-agent failed: {}", e)))?;
+
+        let summary = summary.trim();
+        let content = if summary.is_empty() {
+            "Sub-agent finished without a summary.".to_string()
+        } else {
+            summary.to_string()
+        };
+
+        Ok(ToolOutput::structured(
+            content,
+            true,
+            serde_json::json!({
+                "description": args.description,
+                "cost": agent.session_cost(),
+            }),
+        ))
+    }
+}
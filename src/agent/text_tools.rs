@@ -0,0 +1,471 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Tool-call extraction from plain-text model output.
+//!
+//! Models without native function calling (many local Ollama models) emit
+//! tool calls as text. This module recognizes the common formats:
+//!
+//! - Hermes/Qwen tags: `<tool_call>{"name": ..., "arguments": {...}}</tool_call>`
+//! - `<tool_call>` XML: `<tool_call><name>..</name><arguments>{..}</arguments></tool_call>`
+//! - Qwen-Coder function tags: `<function=name><parameter=key>value</parameter></function>`
+//! - Fenced JSON blocks: ```` ```json {"name": ..., "arguments": {...}} ``` ````
+//!
+//! Extracted calls are validated against the registry's [`ToolDefinition`]
+//! schemas. Problems are reported as messages suitable for sending back to
+//! the model so it can correct itself.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::types::{ToolCall, ToolDefinition};
+
+static FENCED_BLOCK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)```[ \t]*(?:json|tool_call|tool)?[ \t]*\r?\n(.*?)```").unwrap()
+});
+
+static FUNCTION_TAG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<function=([^>\s]+)>(.*?)</function>").unwrap()
+});
+
+static PARAMETER_TAG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<parameter=([^>\s]+)>(.*?)</parameter>").unwrap()
+});
+
+static NAME_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<name>(.*?)</name>").unwrap());
+
+static ARGUMENTS_TAG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<(arguments|parameters)>(.*?)</(?:arguments|parameters)>").unwrap()
+});
+
+/// A parsed `(tool name, arguments)` pair, or a message describing the problem.
+type ParsedCall = Result<(String, serde_json::Value), String>;
+
+/// Keys that may hold the tool name in a JSON tool call.
+const NAME_KEYS: &[&str] = &["name", "tool", "tool_name"];
+
+/// Keys that may hold the tool arguments in a JSON tool call.
+const ARGUMENT_KEYS: &[&str] = &["arguments", "input", "parameters", "args"];
+
+/// Result of scanning model text for tool calls.
+#[derive(Debug, Clone, Default)]
+pub struct TextToolCalls {
+    /// Valid tool calls, in the order they appear in the text.
+    pub calls: Vec<ToolCall>,
+    /// Problems with malformed or invalid calls, for feedback to the model.
+    pub errors: Vec<String>,
+    /// The text with tool-call markup removed.
+    pub text: String,
+}
+
+impl TextToolCalls {
+    /// Whether anything tool-like was found (valid or not).
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// Extract tool calls from model text and validate them against `tools`.
+pub fn extract_tool_calls(text: &str, tools: &[ToolDefinition]) -> TextToolCalls {
+    // (position, parsed call or error)
+    let mut found: Vec<(usize, ParsedCall)> = Vec::new();
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+
+    // <tool_call> ... </tool_call> (an unterminated tag runs to the end of the text)
+    let mut search_from = 0;
+    while let Some(rel) = text[search_from..].find("<tool_call>") {
+        let start = search_from + rel;
+        let body_start = start + "<tool_call>".len();
+        let (body_end, end) = match text[body_start..].find("</tool_call>") {
+            Some(rel_end) => (body_start + rel_end, body_start + rel_end + "</tool_call>".len()),
+            None => (text.len(), text.len()),
+        };
+        found.push((start, parse_tagged_body(&text[body_start..body_end])));
+        spans.push((start, end));
+        search_from = end;
+    }
+
+    // Bare <function=name> tags outside <tool_call>
+    for caps in FUNCTION_TAG.captures_iter(text) {
+        let m = caps.get(0).unwrap();
+        if overlaps(&spans, m.start(), m.end()) {
+            continue;
+        }
+        found.push((m.start(), Ok((caps[1].to_string(), parse_parameter_tags(&caps[2])))));
+        spans.push((m.start(), m.end()));
+    }
+
+    // Fenced JSON blocks that look like tool calls
+    for caps in FENCED_BLOCK.captures_iter(text) {
+        let m = caps.get(0).unwrap();
+        if overlaps(&spans, m.start(), m.end()) {
+            continue;
+        }
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(caps[1].trim()) else {
+            continue;
+        };
+        let candidates = match value {
+            serde_json::Value::Array(items) => items,
+            other => vec![other],
+        };
+        if !candidates.iter().all(looks_like_tool_call) {
+            continue;
+        }
+        for candidate in candidates {
+            found.push((m.start(), normalize_json_call(candidate)));
+        }
+        spans.push((m.start(), m.end()));
+    }
+
+    found.sort_by_key(|(pos, _)| *pos);
+
+    let mut result = TextToolCalls {
+        text: strip_spans(text, &mut spans),
+        ..Default::default()
+    };
+
+    for (_, parsed) in found {
+        match parsed.and_then(|(name, input)| validate_call(&name, input, tools)) {
+            Ok(call) => result.calls.push(call),
+            Err(e) => result.errors.push(e),
+        }
+    }
+
+    result
+}
+
+/// Build the corrective message sent back when tool calls are malformed.
+pub fn correction_message(errors: &[String]) -> String {
+    let mut message = String::from("Some of your tool calls could not be executed:\n");
+    for error in errors {
+        message.push_str("- ");
+        message.push_str(error);
+        message.push('\n');
+    }
+    message.push_str(
+        "\nFix the call and try again, using exactly this format:\n\
+         <tool_call>\n{\"name\": \"tool_name\", \"arguments\": {\"param\": \"value\"}}\n</tool_call>",
+    );
+    message
+}
+
+/// Build system prompt instructions describing the tools and call format,
+/// for models that don't support native tool calling.
+pub fn tool_use_instructions(tools: &[ToolDefinition]) -> String {
+    let mut tools: Vec<&ToolDefinition> = tools.iter().collect();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut prompt = String::from(
+        "## Tools\n\
+         You can call tools. To call a tool, reply with one block per call in exactly this format:\n\n\
+         <tool_call>\n{\"name\": \"tool_name\", \"arguments\": {\"param\": \"value\"}}\n</tool_call>\n\n\
+         Tool results are returned in the next message. Available tools:\n",
+    );
+
+    for tool in tools {
+        let schema = serde_json::to_string(&tool.input_schema).unwrap_or_default();
+        prompt.push_str(&format!("\n- {}: {}\n  Parameters: {}\n", tool.name, tool.description, schema));
+    }
+
+    prompt
+}
+
+/// Parse the body of a `<tool_call>` tag (JSON or XML).
+fn parse_tagged_body(body: &str) -> ParsedCall {
+    let body = body.trim();
+
+    if body.starts_with('{') || body.starts_with('[') {
+        let value: serde_json::Value = serde_json::from_str(body)
+            .map_err(|e| format!("Invalid JSON in <tool_call>: {}", e))?;
+        return normalize_json_call(value);
+    }
+
+    if let Some(caps) = FUNCTION_TAG.captures(body) {
+        return Ok((caps[1].to_string(), parse_parameter_tags(&caps[2])));
+    }
+
+    if let Some(name) = NAME_TAG.captures(body) {
+        let input = match ARGUMENTS_TAG.captures(body) {
+            Some(args) => {
+                let raw = args[2].trim();
+                if raw.is_empty() {
+                    serde_json::json!({})
+                } else {
+                    serde_json::from_str(raw)
+                        .map_err(|e| format!("Invalid JSON in <{}>: {}", &args[1], e))?
+                }
+            }
+            None => serde_json::json!({}),
+        };
+        return Ok((name[1].trim().to_string(), input));
+    }
+
+    Err("Could not parse <tool_call>: expected a JSON object with \"name\" and \"arguments\"".to_string())
+}
+
+/// Collect `<parameter=key>value</parameter>` tags into a JSON object.
+///
+/// Values stay strings here; [`validate_call`] coerces them to the types the
+/// schema declares.
+fn parse_parameter_tags(body: &str) -> serde_json::Value {
+    let mut params = serde_json::Map::new();
+    for caps in PARAMETER_TAG.captures_iter(body) {
+        let value = caps[2].trim_matches('\n').to_string();
+        params.insert(caps[1].to_string(), serde_json::Value::String(value));
+    }
+    serde_json::Value::Object(params)
+}
+
+/// Whether a JSON value has the shape of a tool call.
+fn looks_like_tool_call(value: &serde_json::Value) -> bool {
+    let Some(obj) = value.as_object() else {
+        return false;
+    };
+    let has_name = NAME_KEYS.iter().any(|k| obj.get(*k).is_some_and(|v| v.is_string()))
+        || obj.get("function").is_some_and(|f| f.get("name").is_some());
+    let has_args = ARGUMENT_KEYS.iter().any(|k| obj.contains_key(*k))
+        || obj.get("function").is_some_and(|f| f.get("arguments").is_some());
+    has_name && has_args
+}
+
+/// Normalize the many JSON tool-call shapes into `(name, arguments)`.
+fn normalize_json_call(value: serde_json::Value) -> ParsedCall {
+    let obj = match value {
+        serde_json::Value::Object(obj) => obj,
+        _ => return Err("Tool call must be a JSON object".to_string()),
+    };
+
+    // OpenAI-style {"function": {"name": ..., "arguments": ...}}
+    if let Some(serde_json::Value::Object(function)) = obj.get("function") {
+        return normalize_json_call(serde_json::Value::Object(function.clone()));
+    }
+
+    let name = NAME_KEYS
+        .iter()
+        .find_map(|k| obj.get(*k).and_then(|v| v.as_str()))
+        .ok_or_else(|| "Tool call is missing \"name\"".to_string())?
+        .to_string();
+
+    let input = match ARGUMENT_KEYS.iter().find_map(|k| obj.get(*k)) {
+        // Arguments encoded as a JSON string
+        Some(serde_json::Value::String(s)) => serde_json::from_str(s)
+            .map_err(|e| format!("Invalid JSON arguments for {}: {}", name, e))?,
+        Some(v) => v.clone(),
+        None => serde_json::json!({}),
+    };
+
+    Ok((name, input))
+}
+
+/// Validate a parsed call against the tool definitions and build a [`ToolCall`].
+fn validate_call(
+    name: &str,
+    mut input: serde_json::Value,
+    tools: &[ToolDefinition],
+) -> Result<ToolCall, String> {
+    let tool = tools.iter().find(|t| t.name == name).ok_or_else(|| {
+        let mut names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        names.sort_unstable();
+        format!("Unknown tool \"{}\". Available tools: {}", name, names.join(", "))
+    })?;
+
+    let args = input
+        .as_object_mut()
+        .ok_or_else(|| format!("Arguments for {} must be a JSON object", name))?;
+
+    for required in tool.input_schema.required.iter().flatten() {
+        if !args.contains_key(required) {
+            return Err(format!("Missing required parameter \"{}\" for {}", required, name));
+        }
+    }
+
+    for (key, value) in args.iter_mut() {
+        let Some(expected) = tool
+            .input_schema
+            .properties
+            .get(key)
+            .and_then(|schema| schema.get("type"))
+            .and_then(|t| t.as_str())
+        else {
+            continue;
+        };
+
+        coerce_string(value, expected);
+        if !matches_type(value, expected) {
+            return Err(format!(
+                "Parameter \"{}\" for {} must be of type {}",
+                key, name, expected
+            ));
+        }
+    }
+
+    Ok(ToolCall {
+        id: format!("text_call_{}", uuid::Uuid::new_v4().simple()),
+        name: name.to_string(),
+        input,
+    })
+}
+
+/// Convert a string value to the schema's type when possible
+/// (parameter tags and some models send everything as strings).
+fn coerce_string(value: &mut serde_json::Value, expected: &str) {
+    let serde_json::Value::String(s) = value else {
+        return;
+    };
+    if expected == "string" {
+        return;
+    }
+    if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(s.trim()) {
+        if matches_type(&parsed, expected) {
+            *value = parsed;
+        }
+    }
+}
+
+/// Check a JSON value against a JSON schema primitive type name.
+fn matches_type(value: &serde_json::Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn overlaps(spans: &[(usize, usize)], start: usize, end: usize) -> bool {
+    spans.iter().any(|&(s, e)| start < e && s < end)
+}
+
+/// Remove the given byte spans from `text` and tidy the leftover whitespace.
+fn strip_spans(text: &str, spans: &mut [(usize, usize)]) -> String {
+    spans.sort_unstable();
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for &(start, end) in spans.iter() {
+        if start >= cursor {
+            out.push_str(&text[cursor..start]);
+        }
+        cursor = cursor.max(end);
+    }
+    out.push_str(&text[cursor..]);
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::InputSchema;
+
+    fn tools() -> Vec<ToolDefinition> {
+        vec![
+            ToolDefinition::new("read_file", "Read a file").with_schema(
+                InputSchema::new()
+                    .with_property("path", serde_json::json!({"type": "string"}))
+                    .with_property("offset", serde_json::json!({"type": "integer"}))
+                    .with_required(vec!["path".to_string()]),
+            ),
+            ToolDefinition::new("glob", "Find files").with_schema(
+                InputSchema::new()
+                    .with_property("pattern", serde_json::json!({"type": "string"}))
+                    .with_required(vec!["pattern".to_string()]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_hermes_tool_call() {
+        let text = "Let me read it.\n<tool_call>\n{\"name\": \"read_file\", \"arguments\": {\"path\": \"src/main.rs\"}}\n</tool_call>";
+        let result = extract_tool_calls(text, &tools());
+
+        assert!(result.errors.is_empty());
+        assert_eq!(result.calls.len(), 1);
+        assert_eq!(result.calls[0].name, "read_file");
+        assert_eq!(result.calls[0].input["path"], "src/main.rs");
+        assert_eq!(result.text, "Let me read it.");
+    }
+
+    #[test]
+    fn test_xml_tool_call() {
+        let text = "<tool_call><name>glob</name><arguments>{\"pattern\": \"**/*.rs\"}</arguments></tool_call>";
+        let result = extract_tool_calls(text, &tools());
+
+        assert_eq!(result.calls.len(), 1);
+        assert_eq!(result.calls[0].name, "glob");
+        assert_eq!(result.calls[0].input["pattern"], "**/*.rs");
+    }
+
+    #[test]
+    fn test_function_parameter_tags_with_coercion() {
+        let text = "<tool_call>\n<function=read_file>\n<parameter=path>\nsrc/lib.rs\n</parameter>\n<parameter=offset>\n10\n</parameter>\n</function>\n</tool_call>";
+        let result = extract_tool_calls(text, &tools());
+
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.calls[0].input["path"], "src/lib.rs");
+        assert_eq!(result.calls[0].input["offset"], 10);
+    }
+
+    #[test]
+    fn test_fenced_json_tool_calls_in_order() {
+        let text = "First:\n```json\n{\"name\": \"glob\", \"arguments\": {\"pattern\": \"*.toml\"}}\n```\nthen\n<tool_call>{\"name\": \"read_file\", \"arguments\": \"{\\\"path\\\": \\\"Cargo.toml\\\"}\"}</tool_call>";
+        let result = extract_tool_calls(text, &tools());
+
+        let names: Vec<&str> = result.calls.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["glob", "read_file"]);
+        assert_eq!(result.calls[1].input["path"], "Cargo.toml");
+        assert_eq!(result.text, "First:\n\nthen");
+    }
+
+    #[test]
+    fn test_fenced_json_that_is_not_a_tool_call_is_ignored() {
+        let text = "Your config:\n```json\n{\"name\": \"my-app\", \"version\": \"1.0\"}\n```";
+        let result = extract_tool_calls(text, &tools());
+
+        assert!(result.is_empty());
+        assert_eq!(result.text, text);
+    }
+
+    #[test]
+    fn test_malformed_calls_report_errors() {
+        let text = "<tool_call>{\"name\": \"read_file\", \"arguments\": {</tool_call>\
+                    <tool_call>{\"name\": \"delete_everything\", \"arguments\": {}}</tool_call>\
+                    <tool_call>{\"name\": \"read_file\", \"arguments\": {\"offset\": 3}}</tool_call>\
+                    <tool_call>{\"name\": \"glob\", \"arguments\": {\"pattern\": 42}}</tool_call>";
+        let result = extract_tool_calls(text, &tools());
+
+        assert!(result.calls.is_empty());
+        assert_eq!(result.errors.len(), 4);
+        assert!(result.errors[0].contains("Invalid JSON"));
+        assert!(result.errors[1].contains("Unknown tool \"delete_everything\""));
+        assert!(result.errors[2].contains("Missing required parameter \"path\""));
+        assert!(result.errors[3].contains("must be of type string"));
+
+        let message = correction_message(&result.errors);
+        assert!(message.contains("<tool_call>"));
+        assert!(message.contains("delete_everything"));
+    }
+
+    #[test]
+    fn test_unterminated_tool_call() {
+        let text = "<tool_call>\n{\"name\": \"glob\", \"arguments\": {\"pattern\": \"*\"}}";
+        let result = extract_tool_calls(text, &tools());
+        assert_eq!(result.calls.len(), 1);
+    }
+
+    #[test]
+    fn test_plain_text_has_no_calls() {
+        let result = extract_tool_calls("All done, the tests pass.", &tools());
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_tool_use_instructions_lists_tools() {
+        let prompt = tool_use_instructions(&tools());
+        assert!(prompt.contains("<tool_call>"));
+        assert!(prompt.contains("- glob: Find files"));
+        assert!(prompt.contains("- read_file: Read a file"));
+    }
+}
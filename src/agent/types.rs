@@ -5,20 +5,30 @@
 
 use std::sync::Arc;
 
+use serde::Serialize;
+
+use crate::config::HooksConfig;
+use crate::providers::RetryConfig;
+use super::budget::BudgetConfig;
 use crate::tools::ToolRegistry;
-use crate::types::{BoxedProvider, Message, StreamEvent};
+use crate::types::{BoxedProvider, Message, SharedProvider, StreamEvent, TokenUsage};
 
 /// Statistics for a single turn (user message -> final response).
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TurnStats {
     /// Number of tool calls executed.
     pub tool_call_count: usize,
-    /// Input tokens used.
+    /// Input tokens used, excluding prompt cache reads and writes.
     pub input_tokens: u64,
     /// Output tokens generated.
     pub output_tokens: u64,
     /// Total tokens (input + output).
     pub total_tokens: u64,
+    /// Input tokens read from the provider's prompt cache.
+    pub cache_read_tokens: u64,
+    /// Input tokens written to the provider's prompt cache.
+    pub cache_write_tokens: u64,
     /// Estimated cost in USD.
     pub cost: f64,
     /// Duration of the turn in milliseconds.
@@ -27,8 +37,26 @@ pub struct TurnStats {
     pub tool_calls: Vec<TurnToolCall>,
 }
 
+impl TurnStats {
+    /// Add the token usage of one provider response.
+    ///
+    /// OpenAI's cached tokens are part of its `input_tokens`, so they are
+    /// moved to `cache_read_tokens`; Anthropic reports cache reads and writes
+    /// separately already.
+    pub fn add_usage(&mut self, usage: &TokenUsage) {
+        let openai_cached = usage.cached_input_tokens.unwrap_or(0).min(usage.input_tokens);
+        self.input_tokens += (usage.input_tokens - openai_cached) as u64;
+        self.output_tokens += usage.output_tokens as u64;
+        self.total_tokens = self.input_tokens + self.output_tokens;
+        self.cache_read_tokens +=
+            (usage.cache_read_input_tokens.unwrap_or(0) + openai_cached) as u64;
+        self.cache_write_tokens += usage.cache_creation_input_tokens.unwrap_or(0) as u64;
+    }
+}
+
 /// Statistics for a single tool call.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TurnToolCall {
     /// Tool name.
     pub name: String,
@@ -66,6 +94,7 @@ pub enum ConfirmationResult {
 ///
 /// Uses `Arc` instead of `Box` so callbacks can be cloned into streaming
 /// closures and background tasks without lifetime issues.
+#[derive(Clone)]
 pub struct AgentCallbacks {
     /// Called when the model outputs text (streaming deltas).
     pub on_text: Option<Arc<dyn Fn(&str) + Send + Sync>>,
@@ -81,6 +110,8 @@ pub struct AgentCallbacks {
     pub on_turn_complete: Option<Arc<dyn Fn(&TurnStats) + Send + Sync>>,
     /// Called for each raw stream event from the provider.
     pub on_stream_event: Option<Arc<dyn Fn(&StreamEvent) + Send + Sync>>,
+    /// Called when a hook blocks an action or reports feedback (event, message).
+    pub on_hook: Option<Arc<dyn Fn(&str, &str) + Send + Sync>>,
 }
 
 impl Default for AgentCallbacks {
@@ -93,6 +124,7 @@ impl Default for AgentCallbacks {
             on_compaction: None,
             on_turn_complete: None,
             on_stream_event: None,
+            on_hook: None,
         }
     }
 }
@@ -107,6 +139,7 @@ impl std::fmt::Debug for AgentCallbacks {
             .field("on_compaction", &self.on_compaction.is_some())
             .field("on_turn_complete", &self.on_turn_complete.is_some())
             .field("on_stream_event", &self.on_stream_event.is_some())
+            .field("on_hook", &self.on_hook.is_some())
             .finish()
     }
 }
@@ -120,8 +153,8 @@ pub struct AgentConfig {
     pub max_consecutive_errors: usize,
     /// Maximum turn duration in milliseconds.
     pub max_turn_duration_ms: u64,
-    /// Maximum context tokens before compaction.
-    pub max_context_tokens: usize,
+    /// Cap on the context window used for compaction; `None` uses the model's window.
+    pub max_context_tokens: Option<usize>,
     /// Whether to use tools (if provider supports them).
     pub use_tools: bool,
     /// Whether to extract tool calls from text (for models without native support).
@@ -132,6 +165,24 @@ pub struct AgentConfig {
     pub auto_approve_tools: Vec<String>,
     /// Regex patterns that flag tool inputs as dangerous (from config `dangerousPatterns`).
     pub dangerous_patterns: Vec<String>,
+    /// Maximum read-only tool calls to run concurrently within a turn (1 disables concurrency).
+    pub max_parallel_tools: usize,
+    /// Retry policy for transient provider errors.
+    pub retry: RetryConfig,
+    /// Spending caps for turns, sessions and days.
+    pub budget: BudgetConfig,
+    /// Shell hooks run around tool calls, prompts and turns.
+    pub hooks: HooksConfig,
+    /// Plan mode: only offer reader tools and finish with a structured plan.
+    pub plan_mode: bool,
+    /// Re-prompts allowed when the final answer doesn't match the output schema.
+    pub max_schema_retries: usize,
+    /// Identical tool calls with identical results in a turn that count as a loop (0 disables).
+    pub loop_repeat_threshold: usize,
+    /// A→B→A returns of edits to one file in a turn that count as oscillation (0 disables).
+    pub loop_oscillation_threshold: usize,
+    /// Corrective notes sent for detected loops before the turn is stopped.
+    pub max_loop_warnings: usize,
 }
 
 impl Default for AgentConfig {
@@ -140,12 +191,21 @@ impl Default for AgentConfig {
             max_iterations: 50,
             max_consecutive_errors: 3,
             max_turn_duration_ms: 30 * 60 * 1000, // 30 minutes
-            max_context_tokens: 100_000,
+            max_context_tokens: None,
             use_tools: true,
             extract_tools_from_text: true,
             auto_approve_all: false,
             auto_approve_tools: Vec::new(),
             dangerous_patterns: Vec::new(),
+            max_parallel_tools: 8,
+            retry: RetryConfig::default(),
+            budget: BudgetConfig::default(),
+            hooks: HooksConfig::default(),
+            plan_mode: false,
+            max_schema_retries: 3,
+            loop_repeat_threshold: 3,
+            loop_oscillation_threshold: 2,
+            max_loop_warnings: 1,
         }
     }
 }
@@ -202,6 +262,11 @@ pub struct AgentOptions {
     pub config: AgentConfig,
     /// Event callbacks.
     pub callbacks: AgentCallbacks,
+    /// Provider used to summarize older messages during context compaction.
+    /// When `None`, compaction falls back to truncating old messages.
+    pub summarize_provider: Option<SharedProvider>,
+    /// JSON Schema the final answer of each turn must match.
+    pub output_schema: Option<serde_json::Value>,
 }
 
 /// Internal state of the agent.
@@ -211,12 +276,30 @@ pub struct AgentState {
     pub messages: Vec<Message>,
     /// Conversation summary (from compaction).
     pub conversation_summary: Option<String>,
+    /// Messages removed from the front of the history by compaction.
+    pub compacted_messages: usize,
     /// Current iteration in the turn.
     pub current_iteration: usize,
     /// Consecutive error count.
     pub consecutive_errors: usize,
     /// Running character count across all messages (avoids re-serializing JSON each iteration).
     pub running_char_count: usize,
+    /// Context size reported by the provider for the latest request.
+    pub usage_anchor: Option<UsageAnchor>,
+    /// Total cost in USD of all turns in this conversation.
+    pub session_cost: f64,
+}
+
+/// Provider-reported context size, used as the base for token estimates.
+///
+/// Only messages added after the anchor are estimated; everything up to it is
+/// counted exactly as the provider reported it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageAnchor {
+    /// Prompt tokens plus output tokens of the latest response.
+    pub tokens: u64,
+    /// `running_char_count` right after the response was recorded.
+    pub char_count: usize,
 }
 
 impl Default for AgentState {
@@ -224,9 +307,12 @@ impl Default for AgentState {
         Self {
             messages: Vec::new(),
             conversation_summary: None,
+            compacted_messages: 0,
             current_iteration: 0,
             consecutive_errors: 0,
             running_char_count: 0,
+            usage_anchor: None,
+            session_cost: 0.0,
         }
     }
 }
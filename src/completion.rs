@@ -26,6 +26,7 @@ pub fn get_command_names() -> Vec<String> {
         "settings".to_string(),
         "models".to_string(),
         "sessions".to_string(),
+        "fork".to_string(),
         "save".to_string(),
         "load".to_string(),
         "label".to_string(),
@@ -35,6 +36,10 @@ pub fn get_command_names() -> Vec<String> {
         "delegate".to_string(),
         "workers".to_string(),
         "worktrees".to_string(),
+        // Checkpoint commands
+        "undo".to_string(),
+        "rewind".to_string(),
+        "plan".to_string(),
         // Consolidated git commands (short aliases)
         "commit".to_string(),
         "branch".to_string(),
@@ -43,7 +48,6 @@ pub fn get_command_names() -> Vec<String> {
         "stash".to_string(),
         "log".to_string(),
         "status".to_string(),
-        "undo".to_string(),
         "merge".to_string(),
         "rebase".to_string(),
         // Programming commands
@@ -68,15 +72,14 @@ const COMMAND_SUBCOMMANDS: &[(&str, &[&str])] = &[
     (
         "git",
         &[
-            "commit", "branch", "diff", "pr", "stash", "log", "status", "undo", "merge", "rebase",
+            "commit", "branch", "diff", "pr", "stash", "log", "status", "merge", "rebase",
         ],
     ),
     ("code", &["refactor", "fix", "test", "doc", "optimize"]),
     // Individual commands with subcommands
     ("branch", &["list", "create", "switch", "delete", "rename"]),
     ("stash", &["save", "list", "pop", "apply", "drop", "clear"]),
-    ("undo", &["commits", "staged", "file"]),
-    ("sessions", &["info", "delete", "clear"]),
+    ("sessions", &["info", "tree", "delete", "clear"]),
     ("workers", &["list", "cancel"]),
     ("worktrees", &["list", "cleanup"]),
     ("compact", &["status", "summarize"]),
@@ -85,7 +88,7 @@ const COMMAND_SUBCOMMANDS: &[(&str, &[&str])] = &[
 /// Static arguments for commands in Rust implementation.
 #[allow(dead_code)]
 const COMMAND_STATIC_ARGS: &[(&str, &[&str])] = &[
-    ("models", &["anthropic", "openai", "ollama", "runpod"]),
+    ("models", &["anthropic", "openai", "gemini", "ollama", "runpod"]),
     (
         "commit",
         &[
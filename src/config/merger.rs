@@ -82,6 +82,14 @@ fn apply_workspace_config(result: &mut ResolvedConfig, config: &WorkspaceConfig)
         result.endpoint_id = config.endpoint_id.clone();
     }
 
+    if let Some(api) = config.runpod_api {
+        result.runpod_api = Some(api);
+    }
+
+    if let Some(api) = config.openai_api {
+        result.openai_api = Some(api);
+    }
+
     if let Some(ref auto_approve) = config.auto_approve {
         // Merge auto-approve lists
         for tool in auto_approve {
@@ -148,13 +156,53 @@ fn apply_workspace_config(result: &mut ResolvedConfig, config: &WorkspaceConfig)
     }
 
     if let Some(max_tokens) = config.max_context_tokens {
-        result.max_context_tokens = max_tokens;
+        result.max_context_tokens = Some(max_tokens);
     }
 
     if let Some(clean) = config.clean_hallucinated_traces {
         result.clean_hallucinated_traces = clean;
     }
 
+    if let Some(max_parallel) = config.max_parallel_tools {
+        result.max_parallel_tools = max_parallel.max(1);
+    }
+
+    if let Some(attempts) = config.max_retry_attempts {
+        result.max_retry_attempts = attempts.max(1);
+    }
+
+    if let Some(elapsed) = config.max_retry_elapsed_ms {
+        result.max_retry_elapsed_ms = elapsed;
+    }
+
+    if let Some(ref models) = config.fallback_models {
+        result.fallback_models = models.clone();
+    }
+
+    if let Some(caching) = config.prompt_caching {
+        result.prompt_caching = caching;
+    }
+
+    if let Some(budget) = config.thinking_budget {
+        result.thinking_budget = Some(budget);
+    }
+
+    if let Some(effort) = config.reasoning_effort {
+        result.reasoning_effort = Some(effort);
+    }
+
+    if let Some(cost) = config.max_turn_cost {
+        result.max_turn_cost = Some(cost);
+    }
+
+    if let Some(cost) = config.max_session_cost {
+        result.max_session_cost = Some(cost);
+    }
+
+    if let Some(cost) = config.max_daily_cost {
+        result.max_daily_cost = Some(cost);
+    }
+
     if let Some(ref models) = config.models {
         if let Some(ref summarize) = models.summarize {
             if summarize.provider.is_some() {
@@ -200,6 +248,10 @@ fn apply_workspace_config(result: &mut ResolvedConfig, config: &WorkspaceConfig)
             timeout: security.timeout.unwrap_or(10000),
         });
     }
+
+    if let Some(ref hooks) = config.hooks {
+        result.hooks.extend(hooks);
+    }
 }
 
 fn apply_cli_options(result: &mut ResolvedConfig, cli: &CliOptions) {
@@ -291,6 +343,7 @@ mod tests {
     use super::*;
     use super::super::types::ToolsConfig;
     use std::collections::HashMap;
+    use crate::types::{OpenAIApi, ReasoningEffort, RunPodApi};
 
     #[test]
     fn test_default_config() {
@@ -330,6 +383,138 @@ mod tests {
         assert_eq!(result.model, Some("local-model".to_string()));
     }
 
+    #[test]
+    fn test_merge_max_parallel_tools() {
+        let result = merge_config(None, None, None, CliOptions::default());
+        assert_eq!(result.max_parallel_tools, 8);
+
+        let workspace = WorkspaceConfig {
+            max_parallel_tools: Some(0),
+            ..Default::default()
+        };
+        let result = merge_config(None, Some(workspace), None, CliOptions::default());
+        // Zero is clamped so tool calls still run
+        assert_eq!(result.max_parallel_tools, 1);
+    }
+
+    #[test]
+    fn test_merge_retry_settings() {
+        let result = merge_config(None, None, None, CliOptions::default());
+        assert_eq!(result.max_retry_attempts, 5);
+        assert_eq!(result.max_retry_elapsed_ms, 120_000);
+
+        let workspace = WorkspaceConfig {
+            max_retry_attempts: Some(0),
+            max_retry_elapsed_ms: Some(30_000),
+            ..Default::default()
+        };
+        let result = merge_config(None, Some(workspace), None, CliOptions::default());
+        // Zero is clamped so the request is still sent once
+        assert_eq!(result.max_retry_attempts, 1);
+        assert_eq!(result.max_retry_elapsed_ms, 30_000);
+    }
+
+    #[test]
+    fn test_merge_prompt_caching() {
+        let result = merge_config(None, None, None, CliOptions::default());
+        assert!(result.prompt_caching);
+
+        let workspace = WorkspaceConfig {
+            prompt_caching: Some(false),
+            ..Default::default()
+        };
+        let result = merge_config(None, Some(workspace), None, CliOptions::default());
+        assert!(!result.prompt_caching);
+    }
+
+    #[test]
+    fn test_merge_reasoning_settings() {
+        let result = merge_config(None, None, None, CliOptions::default());
+        assert_eq!(result.thinking_budget, None);
+        assert_eq!(result.reasoning_effort, None);
+
+        let workspace: WorkspaceConfig =
+            serde_json::from_str(r#"{"thinkingBudget": 8000, "reasoningEffort": "high"}"#).unwrap();
+        let result = merge_config(None, Some(workspace), None, CliOptions::default());
+        assert_eq!(result.thinking_budget, Some(8000));
+        assert_eq!(result.reasoning_effort, Some(ReasoningEffort::High));
+    }
+
+    #[test]
+    fn test_merge_runpod_settings() {
+        let workspace: WorkspaceConfig =
+            serde_json::from_str(r#"{"provider": "runpod", "endpointId": "ep-1", "runpodApi": "queue"}"#).unwrap();
+        let cli = CliOptions {
+            endpoint_id: Some("ep-2".to_string()),
+            ..Default::default()
+        };
+        let result = merge_config(None, Some(workspace), None, cli);
+        assert_eq!(result.endpoint_id.as_deref(), Some("ep-2"));
+        assert_eq!(result.runpod_api, Some(RunPodApi::Queue));
+    }
+
+    #[test]
+    fn test_merge_openai_api() {
+        let global: WorkspaceConfig = serde_json::from_str(r#"{"openaiApi": "responses"}"#).unwrap();
+        let result = merge_config(Some(global), None, None, CliOptions::default());
+        assert_eq!(result.openai_api, Some(OpenAIApi::Responses));
+
+        let workspace: WorkspaceConfig = serde_json::from_str(r#"{"openaiApi": "chat"}"#).unwrap();
+        let result = merge_config(None, Some(workspace), None, CliOptions::default());
+        assert_eq!(result.openai_api, Some(OpenAIApi::Chat));
+    }
+
+    #[test]
+    fn test_merge_fallback_models() {
+        let global: WorkspaceConfig =
+            serde_json::from_str(r#"{"fallbackModels": ["openai/gpt-4o", "local"]}"#).unwrap();
+        let workspace: WorkspaceConfig = serde_json::from_str(r#"{"fallbackModels": ["gemini/gemini-2.5-flash"]}"#).unwrap();
+        let result = merge_config(Some(global), Some(workspace), None, CliOptions::default());
+        assert_eq!(result.fallback_models, vec!["gemini/gemini-2.5-flash"]);
+    }
+
+    #[test]
+    fn test_merge_budget_caps() {
+        let result = merge_config(None, None, None, CliOptions::default());
+        assert_eq!(result.max_session_cost, None);
+
+        let global = WorkspaceConfig {
+            max_daily_cost: Some(50.0),
+            max_session_cost: Some(10.0),
+            ..Default::default()
+        };
+        let workspace = WorkspaceConfig {
+            max_session_cost: Some(2.5),
+            max_turn_cost: Some(0.5),
+            ..Default::default()
+        };
+        let result = merge_config(Some(global), Some(workspace), None, CliOptions::default());
+        assert_eq!(result.max_turn_cost, Some(0.5));
+        assert_eq!(result.max_session_cost, Some(2.5));
+        assert_eq!(result.max_daily_cost, Some(50.0));
+    }
+
+    #[test]
+    fn test_merge_hooks() {
+        let global: WorkspaceConfig = serde_json::from_str(
+            r#"{"hooks": {"preToolUse": [{"command": "audit.sh"}]}}"#,
+        )
+        .unwrap();
+        let workspace: WorkspaceConfig = serde_json::from_str(
+            r#"{"hooks": {
+                "preToolUse": [{"command": "guard.sh", "tools": ["bash"]}],
+                "postToolUse": [{"command": "cargo fmt", "tools": ["write_file", "edit_file"], "timeoutMs": 30000}]
+            }}"#,
+        )
+        .unwrap();
+
+        let result = merge_config(Some(global), Some(workspace), None, CliOptions::default());
+        let commands: Vec<&str> = result.hooks.pre_tool_use.iter().map(|h| h.command.as_str()).collect();
+        assert_eq!(commands, ["audit.sh", "guard.sh"]);
+        assert_eq!(result.hooks.post_tool_use[0].timeout_ms, Some(30000));
+        assert!(result.hooks.turn_complete.is_empty());
+    }
+
     #[test]
     fn test_merge_auto_approve() {
         let global = WorkspaceConfig {
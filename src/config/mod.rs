@@ -28,8 +28,8 @@ pub use merger::{
 };
 
 pub use types::{
-    ApprovedPathPatternConfig, ApprovedPatternConfig, ContextOptimizationConfig,
-    ImportanceWeightsConfig, McpServerConfig, ModelRef, ModelsConfig, RagConfig, ResolvedConfig,
+    ApprovedPathPatternConfig, ApprovedPatternConfig, ContextOptimizationConfig, HookConfig,
+    HooksConfig, ImportanceWeightsConfig, McpServerConfig, ModelRef, ModelsConfig, RagConfig, ResolvedConfig,
     ResolvedSecurityModelConfig, ResolvedWebSearchConfig, SecurityModelConfig, ToolFallbackConfig,
     ToolsConfig, ToolsConfigPartial, WebSearchConfig, WorkspaceConfig,
 };
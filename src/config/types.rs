@@ -9,6 +9,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::types::{OpenAIApi, ReasoningEffort, RunPodApi};
+
 /// Workspace configuration for Codi.
 /// Can be defined in .codi.json or .codi/config.json in the project root.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -30,6 +32,14 @@ pub struct WorkspaceConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub endpoint_id: Option<String>,
 
+    /// RunPod endpoint API: "openai" (default) or "queue" for /run and /status jobs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runpod_api: Option<RunPodApi>,
+
+    /// OpenAI API: "chat" (default) or "responses" for the Responses API
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openai_api: Option<OpenAIApi>,
+
     /// Tools that don't require confirmation
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auto_approve: Option<Vec<String>>,
@@ -82,7 +92,7 @@ pub struct WorkspaceConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub enable_compression: Option<bool>,
 
-    /// Maximum context tokens before compaction
+    /// Cap on the context window used for compaction (defaults to the model's window)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_context_tokens: Option<u32>,
 
@@ -90,6 +100,46 @@ pub struct WorkspaceConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub clean_hallucinated_traces: Option<bool>,
 
+    /// Maximum read-only tool calls to run concurrently within a turn
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parallel_tools: Option<u32>,
+
+    /// Maximum provider request attempts for transient errors (1 disables retries)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retry_attempts: Option<u32>,
+
+    /// Stop retrying provider requests after this many milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retry_elapsed_ms: Option<u64>,
+
+    /// Models to try in order when the primary model fails: model-map names or provider/model pairs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_models: Option<Vec<String>>,
+
+    /// Mark the system prompt, tools and recent history for provider prompt caching
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_caching: Option<bool>,
+
+    /// Extended thinking token budget for Claude models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<u32>,
+
+    /// Reasoning effort for OpenAI o-series and gpt-5 models (minimal, low, medium, high)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+
+    /// Maximum cost of a single agent turn in USD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_turn_cost: Option<f64>,
+
+    /// Maximum cost of a session in USD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_session_cost: Option<f64>,
+
+    /// Maximum cost across all sessions per day in USD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_daily_cost: Option<f64>,
+
     /// Context optimization settings
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context_optimization: Option<ContextOptimizationConfig>,
@@ -121,6 +171,10 @@ pub struct WorkspaceConfig {
     /// Security model validation settings
     #[serde(skip_serializing_if = "Option::is_none")]
     pub security_model: Option<SecurityModelConfig>,
+
+    /// Shell commands run around tool calls, prompts and turns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HooksConfig>,
 }
 
 /// Approved pattern stored in config.
@@ -364,6 +418,63 @@ pub struct ToolFallbackConfig {
     pub parameter_aliasing: Option<bool>,
 }
 
+/// Hook commands grouped by the event that triggers them.
+///
+/// Hooks from every config layer run, global ones first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HooksConfig {
+    /// Run before a tool call; a nonzero exit blocks the call
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_tool_use: Vec<HookConfig>,
+
+    /// Run after a tool call; stderr of a nonzero exit is shown to the model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_tool_use: Vec<HookConfig>,
+
+    /// Run when a user prompt is submitted; a nonzero exit rejects the prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub user_prompt_submit: Vec<HookConfig>,
+
+    /// Run after each agent turn
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub turn_complete: Vec<HookConfig>,
+}
+
+impl HooksConfig {
+    /// Whether no hooks are configured.
+    pub fn is_empty(&self) -> bool {
+        self.pre_tool_use.is_empty()
+            && self.post_tool_use.is_empty()
+            && self.user_prompt_submit.is_empty()
+            && self.turn_complete.is_empty()
+    }
+
+    /// Append the hooks of another config layer.
+    pub fn extend(&mut self, other: &HooksConfig) {
+        self.pre_tool_use.extend(other.pre_tool_use.iter().cloned());
+        self.post_tool_use.extend(other.post_tool_use.iter().cloned());
+        self.user_prompt_submit.extend(other.user_prompt_submit.iter().cloned());
+        self.turn_complete.extend(other.turn_complete.iter().cloned());
+    }
+}
+
+/// A single hook command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookConfig {
+    /// Shell command to run; the event payload is passed as JSON on stdin
+    pub command: String,
+
+    /// Tool names the hook applies to (tool hooks only; all tools if unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+
+    /// Timeout in milliseconds (default: 60000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
 /// Security model validation settings.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -406,6 +517,8 @@ pub struct ResolvedConfig {
     pub model: Option<String>,
     pub base_url: Option<String>,
     pub endpoint_id: Option<String>,
+    pub runpod_api: Option<RunPodApi>,
+    pub openai_api: Option<OpenAIApi>,
     pub auto_approve: Vec<String>,
     pub approved_patterns: Vec<ApprovedPatternConfig>,
     pub approved_categories: Vec<String>,
@@ -419,14 +532,25 @@ pub struct ResolvedConfig {
     pub command_aliases: HashMap<String, String>,
     pub project_context: Option<String>,
     pub enable_compression: bool,
-    pub max_context_tokens: u32,
+    pub max_context_tokens: Option<u32>,
     pub clean_hallucinated_traces: bool,
+    pub max_parallel_tools: u32,
+    pub max_retry_attempts: u32,
+    pub max_retry_elapsed_ms: u64,
+    pub fallback_models: Vec<String>,
+    pub prompt_caching: bool,
+    pub thinking_budget: Option<u32>,
+    pub reasoning_effort: Option<ReasoningEffort>,
+    pub max_turn_cost: Option<f64>,
+    pub max_session_cost: Option<f64>,
+    pub max_daily_cost: Option<f64>,
     pub summarize_provider: Option<String>,
     pub summarize_model: Option<String>,
     pub tools_config: ToolsConfig,
     pub context_optimization: Option<ContextOptimizationConfig>,
     pub web_search: Option<ResolvedWebSearchConfig>,
     pub security_model: Option<ResolvedSecurityModelConfig>,
+    pub hooks: HooksConfig,
 }
 
 /// Resolved web search configuration.
@@ -460,6 +584,8 @@ impl Default for ResolvedConfig {
             model: None,
             base_url: None,
             endpoint_id: None,
+            runpod_api: None,
+            openai_api: None,
             auto_approve: Vec::new(),
             approved_patterns: Vec::new(),
             approved_categories: Vec::new(),
@@ -473,14 +599,25 @@ impl Default for ResolvedConfig {
             command_aliases: HashMap::new(),
             project_context: None,
             enable_compression: false,
-            max_context_tokens: 128000,
+            max_context_tokens: None,
             clean_hallucinated_traces: false,
+            max_parallel_tools: 8,
+            max_retry_attempts: 5,
+            max_retry_elapsed_ms: 120_000,
+            fallback_models: Vec::new(),
+            prompt_caching: true,
+            thinking_budget: None,
+            reasoning_effort: None,
+            max_turn_cost: None,
+            max_session_cost: None,
+            max_daily_cost: None,
             summarize_provider: None,
             summarize_model: None,
             tools_config: ToolsConfig::default(),
             context_optimization: None,
             web_search: None,
             security_model: None,
+            hooks: HooksConfig::default(),
         }
     }
 }
@@ -531,7 +668,8 @@ mod tests {
         let config = ResolvedConfig::default();
         assert_eq!(config.provider, "anthropic");
         assert!(!config.no_tools);
-        assert_eq!(config.max_context_tokens, 128000);
+        // Unset means the model's own context window is used
+        assert_eq!(config.max_context_tokens, None);
     }
 
     #[test]
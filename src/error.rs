@@ -6,6 +6,8 @@
 //! This module provides strongly-typed errors for different parts of the application,
 //! using `thiserror` for ergonomic error definitions and `anyhow` for error propagation.
 
+use std::fmt;
+
 use thiserror::Error;
 
 /// Errors that can occur during provider operations.
@@ -46,8 +48,18 @@ pub enum ProviderError {
 
     #[error("Timeout after {0}ms")]
     Timeout(u64),
+
+    /// A failed request where the server said how long to wait (`Retry-After`).
+    #[error("{error}")]
+    RetryAfter {
+        error: Box<ProviderError>,
+        retry_after_ms: u64,
+    },
 }
 
+/// HTTP status codes that indicate a transient failure worth retrying.
+const RETRYABLE_STATUS_CODES: &[u16] = &[408, 429, 500, 502, 503, 504, 529];
+
 impl ProviderError {
     /// Create an API error with status code.
     pub fn api(message: impl Into<String>, status_code: u16) -> Self {
@@ -65,17 +77,44 @@ impl ProviderError {
         }
     }
 
+    /// Attach a server-requested retry delay to this error.
+    pub fn with_retry_after(self, delay: std::time::Duration) -> Self {
+        Self::RetryAfter {
+            error: Box::new(self),
+            retry_after_ms: delay.as_millis() as u64,
+        }
+    }
+
+    /// Get the server-requested retry delay, if any.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::RetryAfter { retry_after_ms, .. } => {
+                Some(std::time::Duration::from_millis(*retry_after_ms))
+            }
+            _ => None,
+        }
+    }
+
     /// Check if this error is retryable.
     pub fn is_retryable(&self) -> bool {
-        matches!(
-            self,
-            Self::RateLimited(_) | Self::NetworkError(_) | Self::Timeout(_)
-        )
+        match self {
+            Self::RateLimited(_) | Self::NetworkError(_) | Self::Timeout(_) => true,
+            Self::ApiError {
+                status_code: Some(code),
+                ..
+            } => RETRYABLE_STATUS_CODES.contains(code),
+            Self::RetryAfter { error, .. } => error.is_retryable(),
+            _ => false,
+        }
     }
 
     /// Check if this is a rate limit error.
     pub fn is_rate_limited(&self) -> bool {
-        matches!(self, Self::RateLimited(_))
+        match self {
+            Self::RateLimited(_) => true,
+            Self::RetryAfter { error, .. } => error.is_rate_limited(),
+            _ => false,
+        }
     }
 }
 
@@ -202,6 +241,27 @@ impl From<std::io::Error> for SessionError {
     }
 }
 
+/// Which budget cap was exceeded (see [`crate::agent::budget`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetScope {
+    /// Cost of a single agent turn.
+    Turn,
+    /// Cost of the whole session.
+    Session,
+    /// Cost of all sessions today.
+    Day,
+}
+
+impl fmt::Display for BudgetScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Turn => write!(f, "Turn"),
+            Self::Session => write!(f, "Session"),
+            Self::Day => write!(f, "Daily"),
+        }
+    }
+}
+
 /// Errors that can occur during agent operations.
 #[derive(Error, Debug)]
 pub enum AgentError {
@@ -225,6 +285,25 @@ pub enum AgentError {
 
     #[error("Invalid state: {0}")]
     InvalidState(String),
+
+    #[error("{scope} budget exceeded: spent ${spent:.4} of ${limit:.2} limit")]
+    BudgetExceeded {
+        scope: BudgetScope,
+        spent: f64,
+        limit: f64,
+    },
+
+    #[error("Budget caps are set but {0} has no known pricing, so its spend can't be tracked")]
+    UnpricedModel(String),
+
+    #[error("Blocked by hook: {0}")]
+    HookBlocked(String),
+
+    #[error("Final answer does not match the output schema: {0}")]
+    SchemaValidation(String),
+
+    #[error("Stopped repetitive tool use: {0}")]
+    LoopDetected(String),
 }
 
 /// Result type alias using anyhow for flexible error handling.
@@ -246,6 +325,25 @@ mod tests {
         assert!(ProviderError::Timeout(30000).is_retryable());
         assert!(!ProviderError::AuthError("invalid key".to_string()).is_retryable());
         assert!(!ProviderError::ModelNotFound("gpt-5".to_string()).is_retryable());
+        assert!(ProviderError::api("overloaded", 529).is_retryable());
+        assert!(ProviderError::api("unavailable", 503).is_retryable());
+        assert!(!ProviderError::api("bad request", 400).is_retryable());
+        assert!(!ProviderError::api_message("unknown").is_retryable());
+    }
+
+    #[test]
+    fn test_provider_error_retry_after() {
+        let err = ProviderError::RateLimited("slow down".to_string())
+            .with_retry_after(std::time::Duration::from_secs(8));
+        assert_eq!(err.retry_after(), Some(std::time::Duration::from_secs(8)));
+        assert!(err.is_retryable());
+        assert!(err.is_rate_limited());
+        assert_eq!(err.to_string(), "Rate limited: slow down");
+
+        let err = ProviderError::AuthError("bad key".to_string())
+            .with_retry_after(std::time::Duration::from_secs(1));
+        assert!(!err.is_retryable());
+        assert_eq!(ProviderError::Timeout(10).retry_after(), None);
     }
 
     #[test]
@@ -76,8 +76,8 @@ pub mod completion;
 pub use error::{AgentError, ConfigError, ProviderError, Result, ToolError};
 pub use providers::{
     create_provider, create_provider_from_env,
-    anthropic, openai, ollama, ollama_at,
-    AnthropicProvider, OpenAIProvider, ProviderType,
+    anthropic, gemini, openai, ollama, ollama_at,
+    AnthropicProvider, GeminiProvider, OpenAIProvider, ProviderType,
 };
 pub use types::{
     // Message types
@@ -85,8 +85,8 @@ pub use types::{
     // Tool types
     ToolCall, ToolDefinition, ToolResult,
     // Provider types
-    BoxedProvider, ModelInfo, Provider, ProviderConfig, ProviderResponse, SharedProvider,
-    StopReason, StreamEvent, TokenUsage,
+    BoxedProvider, ChatOptions, ModelInfo, Provider, ProviderConfig, ProviderResponse,
+    SharedProvider, StopReason, StreamEvent, TokenUsage, ToolChoice,
 };
 
 /// Codi version.
@@ -3,14 +3,19 @@
 
 //! Codi main entry point - CLI, commands, and REPL.
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 
+use codi::agent::budget::BudgetConfig;
 use codi::agent::AgentConfig;
+use codi::agent::events::{RunEvent, RunReporter, RunResult};
+use codi::agent::plan::Plan;
 use codi::config::{self, CliOptions};
-use codi::providers::{create_provider_from_config, ProviderType};
+use codi::agent::summarize::resolve_summarize_provider;
+use codi::agent::task::{provider_factory, with_task_tool, TaskConfig};
+use codi::providers::{create_provider_from_config, ProviderType, RetryConfig};
 use codi::tools::ToolRegistry;
 use codi::tui::build_system_prompt_from_config;
 use codi::tui::terminal_ui::run_terminal_repl;
@@ -59,7 +64,7 @@ struct Cli {
     #[arg(short, long)]
     session: Option<String>,
 
-    /// Run a single prompt and exit
+    /// Run a single prompt and exit (reference images with @path.png)
     #[arg(short = 'P', long)]
     prompt: Option<String>,
 
@@ -75,6 +80,14 @@ struct Cli {
     #[arg(short = 'y', long)]
     yes: bool,
 
+    /// Start in plan mode (read-only tools, finish with a plan to approve)
+    #[arg(long)]
+    plan: bool,
+
+    /// JSON Schema file the final answer must match (with --prompt)
+    #[arg(long, value_name = "FILE", requires = "prompt")]
+    json_schema: Option<std::path::PathBuf>,
+
     /// Show verbose output (enables tool call visibility)
     #[arg(short = 'v', long)]
     verbose: bool,
@@ -98,10 +111,14 @@ enum Provider {
     Anthropic,
     /// OpenAI - GPT models
     Openai,
+    /// Gemini - Google Gemini models
+    Gemini,
     /// Ollama - Local models  
     Ollama,
     /// RunPod - Cloud inference
     Runpod,
+    /// Replay - Recorded responses from CODI_CASSETTE
+    Replay,
 }
 
 impl std::fmt::Display for Provider {
@@ -109,8 +126,10 @@ impl std::fmt::Display for Provider {
         match self {
             Provider::Anthropic => write!(f, "anthropic"),
             Provider::Openai => write!(f, "openai"),
+            Provider::Gemini => write!(f, "gemini"),
             Provider::Ollama => write!(f, "ollama"),
             Provider::Runpod => write!(f, "runpod"),
+            Provider::Replay => write!(f, "replay"),
         }
     }
 }
@@ -118,8 +137,12 @@ impl std::fmt::Display for Provider {
 /// Output format for non-interactive mode.
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum OutputFormat {
+    /// Plain response text
     Text,
+    /// One JSON object with the response, tool calls and usage
     Json,
+    /// Newline-delimited JSON events as they happen, ending with the result
+    StreamJson,
 }
 
 impl std::fmt::Display for OutputFormat {
@@ -127,6 +150,7 @@ impl std::fmt::Display for OutputFormat {
         match self {
             OutputFormat::Text => write!(f, "text"),
             OutputFormat::Json => write!(f, "json"),
+            OutputFormat::StreamJson => write!(f, "stream-json"),
         }
     }
 }
@@ -136,8 +160,10 @@ impl From<Provider> for ProviderType {
         match provider {
             Provider::Anthropic => ProviderType::Anthropic,
             Provider::Openai => ProviderType::OpenAI,
+            Provider::Gemini => ProviderType::Gemini,
             Provider::Ollama => ProviderType::Ollama,
-            Provider::Runpod => ProviderType::OpenAICompatible,
+            Provider::Runpod => ProviderType::RunPod,
+            Provider::Replay => ProviderType::Replay,
         }
     }
 }
@@ -176,7 +202,7 @@ enum ConfigAction {
 enum ModelsAction {
     /// List available models
     List {
-        /// Filter by provider (anthropic, openai, ollama, runpod)
+        /// Filter by provider (anthropic, openai, gemini, ollama, runpod)
         provider: Option<String>,
         /// Show only local Ollama models
         #[arg(long)]
@@ -230,11 +256,21 @@ async fn main() -> anyhow::Result<()> {
 
     // Handle non-interactive mode
     if let Some(prompt) = cli.prompt {
-        return handle_prompt(&config, &prompt, cli.output_format, cli.quiet, cli.yes).await;
+        let output_schema = cli.json_schema.as_deref().map(load_json_schema).transpose()?;
+        return handle_prompt(
+            &config,
+            &prompt,
+            cli.output_format,
+            cli.quiet,
+            cli.yes,
+            cli.plan,
+            output_schema,
+        )
+        .await;
     }
 
     // Start interactive REPL
-    run_repl(&config, cli.yes, cli.verbose).await
+    run_repl(&config, cli.yes, cli.plan, cli.verbose).await
 }
 
 async fn handle_command(command: Commands) -> anyhow::Result<()> {
@@ -269,12 +305,14 @@ async fn handle_models_command(action: Option<ModelsAction>) -> anyhow::Result<(
         Some(ModelsAction::List { provider, local, format }) => {
             // For now, show basic info and usage since we don't have full model listing implemented
             match format {
-                OutputFormat::Json => {
+                OutputFormat::Json | OutputFormat::StreamJson => {
                     let models = serde_json::json!([
                         {"provider": "anthropic", "id": "claude-3-5-sonnet-latest", "name": "Claude 3.5 Sonnet", "supports_tools": true, "supports_vision": false, "context_window": 200000},
                         {"provider": "anthropic", "id": "claude-3-5-haiku-latest", "name": "Claude 3.5 Haiku", "supports_tools": true, "supports_vision": false, "context_window": 200000},
                         {"provider": "openai", "id": "gpt-4o", "name": "GPT-4o", "supports_tools": true, "supports_vision": true, "context_window": 128000},
                         {"provider": "openai", "id": "gpt-4o-mini", "name": "GPT-4o Mini", "supports_tools": true, "supports_vision": true, "context_window": 128000},
+                        {"provider": "gemini", "id": "gemini-2.5-pro", "name": "Gemini 2.5 Pro", "supports_tools": true, "supports_vision": true, "context_window": 1048576},
+                        {"provider": "gemini", "id": "gemini-2.5-flash", "name": "Gemini 2.5 Flash", "supports_tools": true, "supports_vision": true, "context_window": 1048576},
                         {"provider": "ollama", "id": "llama3.2", "name": "Llama 3.2", "supports_tools": false, "supports_vision": false, "context_window": 8000},
                         {"provider": "ollama", "id": "llama3.1", "name": "Llama 3.1", "supports_tools": false, "supports_vision": false, "context_window": 8000}
                     ]);
@@ -289,6 +327,9 @@ async fn handle_models_command(action: Option<ModelsAction>) -> anyhow::Result<(
                         ("openai", "gpt-4o", "GPT-4o", "Multimodal reasoning"), 
                         ("openai", "gpt-4o-mini", "GPT-4o Mini", "Efficient GPT-4o"), 
                         ("openai", "gpt-4.1", "GPT-4.1", "Latest GPT-4"),
+                        // Gemini models
+                        ("gemini", "gemini-2.5-pro", "Gemini 2.5 Pro", "Most capable Gemini"),
+                        ("gemini", "gemini-2.5-flash", "Gemini 2.5 Flash", "Fast thinking model"),
                         // Ollama models 
                         ("ollama", "llama3.2", "Llama 3.2", "Latest Llama"),
                         ("ollama", "qwen3.2", "Qwen 3.2", "Powerful reasoning"),
@@ -324,13 +365,14 @@ async fn handle_models_command(action: Option<ModelsAction>) -> anyhow::Result<(
         Some(ModelsAction::Providers { format }) => {
             println!("{}", "📋 Available Providers".bright_blue().bold());
             match format {
-                OutputFormat::Json => {
-                    let providers = vec!["anthropic", "openai", "ollama", "runpod"];
+                OutputFormat::Json | OutputFormat::StreamJson => {
+                    let providers = vec!["anthropic", "openai", "gemini", "ollama", "runpod"];
                     println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "providers": providers }))?);
                 }
                 OutputFormat::Text => {
                     println!("✓ Anthropic - Claude models (claude-3-5-sonnet, claude-3-5-haiku)");
                     println!("✓ OpenAI - GPT models (gpt-4o, gpt-4o-mini, gpt-4.1)");
+                    println!("✓ Gemini - Google Gemini models (gemini-2.5-pro, gemini-2.5-flash)");
                     println!("✓ Ollama - Local models (llama3.2, qwen3.2, deepseek-coder)");
                     println!("✓ RunPod - Cloud GPU endpoints (custom endpoints)");
                     println!("\n{}", "Install Ollama from https://ollama.ai to use local models".dimmed());
@@ -345,6 +387,7 @@ async fn handle_models_command(action: Option<ModelsAction>) -> anyhow::Result<(
             let provider_str = match resolved.provider.as_str() {
                 "anthropic" => "ANTHROPIC",
                 "openai" => "OPENAI",
+                "gemini" => "GEMINI",
                 "ollama" => "OLLAMA",
                 "runpod" => "RUNPOD",
                 _ => &resolved.provider,
@@ -382,27 +425,55 @@ async fn handle_models_command(action: Option<ModelsAction>) -> anyhow::Result<(
 }
 
 fn init_tracing() {
-    // Only initialize if trace or debug is enabled
+    // Log to stderr so JSON output on stdout stays machine-readable
     if std::env::var("RUST_LOG").is_ok() {
         // Let env var control logging
-        tracing_subscriber::fmt::init();
+        tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .with_writer(std::io::stderr)
+            .init();
     } else {
         // Default to WARN level
-        tracing_subscriber::fmt().with_max_level(tracing::Level::WARN).init();
+        tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::WARN)
+            .with_writer(std::io::stderr)
+            .init();
     }
 }
 
+/// Read a JSON Schema file for `--json-schema`.
+fn load_json_schema(path: &std::path::Path) -> anyhow::Result<serde_json::Value> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read schema {}: {}", path.display(), e))?;
+    let schema: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Invalid JSON in schema {}: {}", path.display(), e))?;
+    if !schema.is_object() {
+        anyhow::bail!("Schema {} must be a JSON object", path.display());
+    }
+    codi::agent::schema::compile(&schema)
+        .map_err(|e| anyhow::anyhow!("Invalid JSON Schema {}: {}", path.display(), e))?;
+    Ok(schema)
+}
+
 async fn handle_prompt(
     config: &config::ResolvedConfig,
     prompt: &str,
     format: OutputFormat,
     quiet: bool,
     auto_approve: bool,
+    plan_mode: bool,
+    output_schema: Option<serde_json::Value>,
 ) -> anyhow::Result<()> {
-    if !quiet {
+    // Keep stdout to events only when streaming them
+    if !quiet && !matches!(format, OutputFormat::StreamJson) {
         println!("{} Processing prompt...", "→".cyan());
     }
 
+    let reporter = match format {
+        OutputFormat::StreamJson => RunReporter::streaming(Arc::new(Mutex::new(std::io::stdout()))),
+        _ => RunReporter::new(),
+    };
+
     // Create provider from configuration
     let provider = match create_provider_from_config(config) {
         Ok(provider) => provider,
@@ -414,90 +485,95 @@ async fn handle_prompt(
                     Ok(())
                 }
                 OutputFormat::Json => {
-                    let response = serde_json::json!({
-                        "success": false,
-                        "response": "",
-                        "toolCalls": [],
-                        "usage": null,
-                        "error": error_msg
-                    });
+                    let response = RunResult::failed(error_msg);
                     println!("{}", serde_json::to_string_pretty(&response)?);
                     Ok(())
                 }
+                OutputFormat::StreamJson => {
+                    reporter.emit(&RunEvent::Result(RunResult::failed(error_msg)));
+                    Ok(())
+                }
             };
         }
     };
 
-    // Create tool registry
-    let registry = Arc::new(ToolRegistry::with_defaults());
+    // Create tool registry, with sub-agents reporting through the same callbacks
+    let callbacks = reporter.callbacks();
+    let registry = Arc::new(with_task_tool(
+        ToolRegistry::with_defaults(),
+        provider_factory(config),
+        &callbacks,
+        TaskConfig::from_config(config),
+    ));
 
     // Create agent configuration from resolved config
     let agent_config = AgentConfig {
         max_iterations: 50,
         max_consecutive_errors: 3,
         max_turn_duration_ms: 120_000, // 2 minutes
-        max_context_tokens: config.max_context_tokens as usize,
+        max_context_tokens: config.max_context_tokens.map(|t| t as usize),
         use_tools: !config.no_tools,
         extract_tools_from_text: config.extract_tools_from_text,
         auto_approve_all: auto_approve,
         auto_approve_tools: config.auto_approve.clone(),
         dangerous_patterns: config.dangerous_patterns.clone(),
+        max_parallel_tools: config.max_parallel_tools as usize,
+        retry: RetryConfig::from_config(config),
+        budget: BudgetConfig::from_config(config),
+        hooks: config.hooks.clone(),
+        plan_mode,
+        ..AgentConfig::default()
     };
 
+    // Resolve the summarization model used for context compaction
+    let project_path = std::env::current_dir()?;
+    let summarize_provider = resolve_summarize_provider(config, &project_path).await;
+
     // Create and run agent
     let mut agent = codi::agent::Agent::new(codi::agent::AgentOptions {
         provider,
         tool_registry: registry,
         system_prompt: Some(build_system_prompt_from_config(Some(config))),
         config: agent_config,
-        callbacks: codi::agent::AgentCallbacks::default(),
+        callbacks,
+        summarize_provider,
+        output_schema: output_schema.clone(),
     });
 
     let result = agent.chat(prompt).await;
-    
-    match result {
-        Ok(response) => {
-            match format {
-                OutputFormat::Text => {
-                    println!("{}", response);
-                }
-                OutputFormat::Json => {
-                    let response_json = serde_json::json!({
-                        "success": true,
-                        "response": response,
-                        "toolCalls": [],
-                        "usage": null
-                    });
-                    println!("{}", serde_json::to_string_pretty(&response_json)?);
-                }
-            }
-        }
-        Err(e) => {
-            let error_msg = format!("Agent error: {}", e);
-            match format {
-                OutputFormat::Text => {
-                    eprintln!("{}", error_msg.red());
-                }
-                OutputFormat::Json => {
-                    let response = serde_json::json!({
-                        "success": false,
-                        "response": "",
-                        "toolCalls": [],
-                        "usage": null,
-                        "error": error_msg
-                    });
-                    println!("{}", serde_json::to_string_pretty(&response)?);
-                }
-            }
+    let cost = agent.session_cost();
+
+    if let OutputFormat::Text = format {
+        match result {
+            Ok(response) => println!("{}", response),
+            Err(e) => eprintln!("{}", format!("Agent error: {}", e).red()),
         }
+        return Ok(());
+    }
+
+    let mut report = reporter.result(result.map_err(|e| format!("Agent error: {}", e)), cost);
+    if plan_mode {
+        report.plan = Plan::parse(&report.response);
+    }
+    if output_schema.is_some() && report.success {
+        report.output = serde_json::from_str(&report.response).ok();
+    }
+    match format {
+        OutputFormat::StreamJson => reporter.emit(&RunEvent::Result(report)),
+        _ => println!("{}", serde_json::to_string_pretty(&report)?),
     }
 
     Ok(())
 }
 
-async fn run_repl(config: &config::ResolvedConfig, auto_approve: bool, verbose: bool) -> anyhow::Result<()> {
+async fn run_repl(
+    config: &config::ResolvedConfig,
+    auto_approve: bool,
+    plan_mode: bool,
+    verbose: bool,
+) -> anyhow::Result<()> {
     // Use new terminal-style REPL instead of full-screen TUI
     // Pass verbose flag to enable tool visibility
     let debug_mode = verbose || std::env::var("CODI_DEBUG").is_ok();
-    run_terminal_repl(config, auto_approve, debug_mode).await
+    run_terminal_repl(config, auto_approve, plan_mode, debug_mode).await
 }
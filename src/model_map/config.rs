@@ -751,6 +751,11 @@ mod tests {
                 max_tokens: None,
                 temperature: None,
                 base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
             },
         );
         config.tasks.insert(
@@ -790,6 +795,11 @@ mod tests {
                 max_tokens: None,
                 temperature: None,
                 base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
             },
         );
 
@@ -803,6 +813,11 @@ mod tests {
                 max_tokens: None,
                 temperature: None,
                 base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
             },
         );
 
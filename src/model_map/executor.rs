@@ -11,7 +11,7 @@ use regex::Regex;
 use std::sync::Arc;
 
 use crate::error::ProviderError;
-use crate::types::{Message, StreamEvent};
+use crate::types::{ChatOptions, Message, StreamEvent};
 
 /// Static regex for variable substitution (compiled once).
 static VAR_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{(\w+)\}").unwrap());
@@ -331,7 +331,7 @@ impl PipelineExecutor {
         });
 
         let response = provider
-            .stream_chat(&messages, None, None, on_event)
+            .stream_chat(&messages, None, None, &ChatOptions::default(), on_event)
             .await
             .map_err(ExecutorError::Provider)?;
 
@@ -412,6 +412,11 @@ mod tests {
                 max_tokens: None,
                 temperature: None,
                 base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
             },
         );
 
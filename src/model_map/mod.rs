@@ -174,7 +174,8 @@ pub use executor::{
 };
 
 pub use registry::{
-    create_shared_registry, create_shared_registry_with_options, ModelRegistry, RegistryOptions,
+    create_provider_for_model, create_shared_registry, create_shared_registry_with_options,
+    ModelRegistry, RegistryOptions,
 };
 
 pub use router::{create_task_router, TaskRouter};
@@ -231,6 +232,11 @@ mod tests {
                 max_tokens: None,
                 temperature: None,
                 base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
             },
         );
 
@@ -12,7 +12,7 @@ use std::time::{Duration, Instant};
 
 use tokio::sync::RwLock;
 
-use crate::providers::{create_provider, ProviderType};
+use crate::providers::{api_key_from_env, create_provider, ProviderType};
 use crate::types::{BoxedProvider, ProviderConfig, SharedProvider};
 
 use super::config::{ModelMapConfig, ModelMapError};
@@ -134,7 +134,7 @@ impl ModelRegistry {
         }
 
         // Not in pool - create provider
-        let boxed = self.create_provider_from_definition(model_name, &definition)?;
+        let boxed = create_provider_for_model(model_name, &definition)?;
         let provider: SharedProvider = Arc::from(boxed);
 
         // Evict oldest if at capacity (before inserting)
@@ -281,49 +281,6 @@ impl ModelRegistry {
 
     // --- Private methods ---
 
-    fn create_provider_from_definition(
-        &self,
-        model_name: &str,
-        definition: &ModelDefinition,
-    ) -> Result<BoxedProvider, ModelMapError> {
-        let provider_type = parse_provider_type(&definition.provider)?;
-
-        let mut config = ProviderConfig::default();
-        config.model = Some(definition.model.clone());
-
-        if let Some(base_url) = &definition.base_url {
-            config.base_url = Some(base_url.clone());
-        }
-
-        if let Some(temp) = definition.temperature {
-            config.temperature = Some(temp);
-        }
-
-        if let Some(max_tokens) = definition.max_tokens {
-            config.max_tokens = Some(max_tokens);
-        }
-
-        // Get API key from environment based on provider
-        match provider_type {
-            ProviderType::Anthropic => {
-                config.api_key = std::env::var("ANTHROPIC_API_KEY").ok();
-            }
-            ProviderType::OpenAI => {
-                config.api_key = std::env::var("OPENAI_API_KEY").ok();
-            }
-            ProviderType::Ollama | ProviderType::OpenAICompatible => {
-                // Ollama doesn't need an API key
-            }
-        }
-
-        create_provider(provider_type, config).map_err(|e| {
-            ModelMapError::ValidationError {
-                field: format!("models.{}", model_name),
-                message: format!("Failed to create provider: {}", e),
-            }
-        })
-    }
-
     fn evict_oldest_from_pool(&self, pool: &mut HashMap<String, PooledProvider>) {
         if let Some(oldest) = pool
             .iter()
@@ -336,6 +293,45 @@ impl ModelRegistry {
     }
 }
 
+/// Create an unpooled provider for a model definition.
+///
+/// The API key comes from the provider's environment variable.
+pub fn create_provider_for_model(
+    model_name: &str,
+    definition: &ModelDefinition,
+) -> Result<BoxedProvider, ModelMapError> {
+    let provider_type = parse_provider_type(&definition.provider)?;
+
+    let mut config = ProviderConfig::default();
+    config.model = Some(definition.model.clone());
+
+    if let Some(base_url) = &definition.base_url {
+        config.base_url = Some(base_url.clone());
+    }
+
+    if let Some(temp) = definition.temperature {
+        config.temperature = Some(temp);
+    }
+
+    if let Some(max_tokens) = definition.max_tokens {
+        config.max_tokens = Some(max_tokens);
+    }
+
+    config.endpoint_id = definition.endpoint_id.clone();
+    config.runpod_api = definition.runpod_api;
+    config.openai_api = definition.openai_api;
+    config.thinking_budget = definition.thinking_budget;
+    config.reasoning_effort = definition.reasoning_effort;
+    config.api_key = api_key_from_env(provider_type);
+
+    create_provider(provider_type, config).map_err(|e| {
+        ModelMapError::ValidationError {
+            field: format!("models.{}", model_name),
+            message: format!("Failed to create provider: {}", e),
+        }
+    })
+}
+
 /// Parse provider type from string.
 fn parse_provider_type(provider: &str) -> Result<ProviderType, ModelMapError> {
     match provider.to_lowercase().as_str() {
@@ -343,6 +339,9 @@ fn parse_provider_type(provider: &str) -> Result<ProviderType, ModelMapError> {
         "openai" | "gpt" => Ok(ProviderType::OpenAI),
         "ollama" => Ok(ProviderType::Ollama),
         "openai-compatible" | "openai_compatible" => Ok(ProviderType::OpenAICompatible),
+        "gemini" | "google" => Ok(ProviderType::Gemini),
+        "runpod" => Ok(ProviderType::RunPod),
+        "replay" => Ok(ProviderType::Replay),
         _ => Err(ModelMapError::ValidationError {
             field: "provider".to_string(),
             message: format!("Unknown provider type: {}", provider),
@@ -378,6 +377,11 @@ mod tests {
                 max_tokens: None,
                 temperature: None,
                 base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
             },
         );
         config.fallbacks.insert(
@@ -468,6 +472,10 @@ mod tests {
             parse_provider_type("ollama").unwrap(),
             ProviderType::Ollama
         );
+        assert_eq!(
+            parse_provider_type("gemini").unwrap(),
+            ProviderType::Gemini
+        );
         assert!(parse_provider_type("invalid").is_err());
     }
 }
@@ -304,6 +304,11 @@ mod tests {
                 max_tokens: None,
                 temperature: None,
                 base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
             },
         );
         config.models.insert(
@@ -315,6 +320,11 @@ mod tests {
                 max_tokens: None,
                 temperature: None,
                 base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
             },
         );
 
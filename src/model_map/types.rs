@@ -9,6 +9,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::types::{OpenAIApi, ReasoningEffort, RunPodApi};
+
 // ============================================================================
 // Provider Context and Role Mappings
 // ============================================================================
@@ -35,7 +37,7 @@ pub type ModelRoles = HashMap<String, RoleMapping>;
 /// Defines a model alias that can be referenced throughout the configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelDefinition {
-    /// Provider type (anthropic, openai, ollama, runpod)
+    /// Provider type (anthropic, openai, gemini, ollama, runpod)
     pub provider: String,
 
     /// Model name/ID (e.g., "claude-sonnet-4-20250514", "gpt-4o")
@@ -56,6 +58,26 @@ pub struct ModelDefinition {
     /// Custom API base URL
     #[serde(skip_serializing_if = "Option::is_none")]
     pub base_url: Option<String>,
+
+    /// RunPod serverless endpoint ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint_id: Option<String>,
+
+    /// RunPod endpoint API ("openai" or "queue")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runpod_api: Option<RunPodApi>,
+
+    /// OpenAI API ("chat" or "responses")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openai_api: Option<OpenAIApi>,
+
+    /// Extended thinking token budget (Claude)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<u32>,
+
+    /// Reasoning effort (OpenAI o-series and gpt-5)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
 }
 
 // ============================================================================
@@ -448,6 +470,11 @@ mod tests {
                 max_tokens: None,
                 temperature: None,
                 base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
             },
         };
 
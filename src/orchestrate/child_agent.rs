@@ -30,6 +30,7 @@ use crate::agent::{
     ToolConfirmation,
     TurnStats,
 };
+use crate::config::{self, CliOptions};
 use crate::tools::ToolRegistry;
 use crate::types::TokenUsage;
 use crate::providers::create_provider_from_env;
@@ -199,6 +200,10 @@ impl ChildAgent {
         // Create provider
         let provider = create_provider_from_env()?;
 
+        // Settings the commander doesn't send come from the workspace config
+        let resolved = config::load_config(self.workspace.path(), CliOptions::default())
+            .map_err(|e| ChildAgentError::Config(e.to_string()))?;
+
         // Create tool registry with defaults
         let registry = Arc::new(ToolRegistry::with_defaults());
 
@@ -262,18 +267,16 @@ impl ChildAgent {
                 }
             })),
             on_stream_event: None,
+            on_hook: None,
         };
 
         let agent_config = AgentConfig {
             max_iterations: self.config.max_iterations as usize,
-            max_consecutive_errors: 3,
             max_turn_duration_ms: self.timeout_ms,
-            max_context_tokens: 100_000,
-            use_tools: true,
-            extract_tools_from_text: true,
-            auto_approve_all: false,
             auto_approve_tools: self.auto_approve.clone(),
             dangerous_patterns: self.dangerous_patterns.clone(),
+            max_parallel_tools: resolved.max_parallel_tools.max(1) as usize,
+            ..AgentConfig::default()
         };
 
         let mut agent = Agent::new(AgentOptions {
@@ -282,6 +285,8 @@ impl ChildAgent {
             system_prompt: None,
             config: agent_config,
             callbacks,
+            summarize_provider: None,
+            output_schema: None,
         });
 
         // Send thinking status
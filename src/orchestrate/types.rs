@@ -515,6 +515,7 @@ pub static READER_ALLOWED_TOOLS: &[&str] = &[
     "get_dependency_graph",
     "search_codebase",
     "recall_result",
+    "read_output",
     "get_context_status",
     "bash", // Note: Still needs command filtering
 ];
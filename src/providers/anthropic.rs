@@ -31,11 +31,14 @@ use crate::telemetry::metrics::GLOBAL_METRICS;
 
 use crate::error::ProviderError;
 use crate::types::{
-    ContentBlock, ContentBlockType, ImageMediaType, Message, MessageContent,
+    ChatOptions, ContentBlock, ContentBlockType, ImageMediaType, Message, MessageContent,
     ModelCapabilities, ModelInfo, ModelPricing, Provider, ProviderConfig, ProviderResponse, Role,
-    StopReason, StreamEvent, TokenUsage, ToolCall, ToolDefinition,
+    StopReason, StreamEvent, TokenUsage, ToolCall, ToolChoice, ToolDefinition,
 };
 
+use super::retry::with_retry_after;
+use super::unsupported_option;
+
 /// Anthropic API version header value.
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 
@@ -45,6 +48,12 @@ const DEFAULT_MAX_TOKENS: u32 = 8192;
 /// Default request timeout in seconds.
 const DEFAULT_TIMEOUT_SECS: u64 = 300;
 
+/// Smallest thinking budget the API accepts.
+const MIN_THINKING_BUDGET: u32 = 1024;
+
+/// Tool forced by [`Provider::chat_structured`] to return schema-shaped output.
+const FINAL_ANSWER_TOOL: &str = "final_answer";
+
 /// Anthropic Claude provider.
 ///
 /// Implements the [`Provider`] trait for Anthropic's Claude models.
@@ -55,6 +64,10 @@ pub struct AnthropicProvider {
     base_url: String,
     max_tokens: u32,
     temperature: Option<f32>,
+    /// Add cache breakpoints to requests.
+    prompt_caching: bool,
+    /// Extended thinking budget, if enabled and supported by the model.
+    thinking_budget: Option<u32>,
 }
 
 impl AnthropicProvider {
@@ -82,13 +95,21 @@ impl AnthropicProvider {
             .build()
             .expect("Failed to build HTTP client");
 
+        let model = model.into();
+        let thinking_budget = config
+            .thinking_budget
+            .filter(|_| Self::model_supports_thinking(&model))
+            .map(|budget| budget.max(MIN_THINKING_BUDGET));
+
         Self {
             client,
             api_key: api_key.into(),
-            model: model.into(),
+            model,
             base_url: base_url.into(),
             max_tokens: config.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
             temperature: config.temperature,
+            prompt_caching: config.prompt_caching.unwrap_or(true),
+            thinking_budget,
         }
     }
 
@@ -98,20 +119,157 @@ impl AnthropicProvider {
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
     ) -> AnthropicRequest {
         let api_messages: Vec<ApiMessage> = messages.iter().map(|m| m.into()).collect();
 
         let api_tools: Option<Vec<ApiTool>> = tools.map(|t| t.iter().map(|t| t.into()).collect());
+        let tool_choice = api_tools.as_ref().and_then(|_| Self::tool_choice(options));
 
-        AnthropicRequest {
+        let mut request = AnthropicRequest {
             model: self.model.clone(),
-            max_tokens: self.max_tokens,
+            max_tokens: options.max_tokens.unwrap_or(self.max_tokens),
             messages: api_messages,
-            system: system_prompt.map(String::from),
+            system: system_prompt.map(|s| ApiContent::Text(s.to_string())),
             tools: api_tools,
             stream: Some(false),
-            temperature: self.temperature,
+            temperature: options.temperature.or(self.temperature),
+            tool_choice,
+            thinking: None,
+            stop_sequences: (!options.stop_sequences.is_empty()).then(|| options.stop_sequences.clone()),
+            metadata: options
+                .metadata
+                .get("user_id")
+                .map(|user_id| serde_json::json!({ "user_id": user_id })),
+        };
+        if let Some(budget) = self.thinking_budget {
+            // The budget counts towards max_tokens, and thinking can't be
+            // combined with a custom temperature
+            request.thinking = Some(ApiThinking::enabled(budget));
+            if request.max_tokens <= budget {
+                request.max_tokens = budget + DEFAULT_MAX_TOKENS;
+            }
+            request.temperature = None;
+        }
+        if self.prompt_caching {
+            request.add_cache_breakpoints();
+        }
+        request
+    }
+
+    /// The `tool_choice` object for the request options, if any.
+    ///
+    /// Parallel tool use is turned off inside `tool_choice`, so forbidding
+    /// it without a choice sends an explicit `auto`.
+    fn tool_choice(options: &ChatOptions) -> Option<serde_json::Value> {
+        let mut choice = match options.tool_choice {
+            Some(ToolChoice::Auto) => serde_json::json!({ "type": "auto" }),
+            Some(ToolChoice::None) => return Some(serde_json::json!({ "type": "none" })),
+            Some(ToolChoice::Required) => serde_json::json!({ "type": "any" }),
+            Some(ToolChoice::Tool(ref name)) => serde_json::json!({ "type": "tool", "name": name }),
+            None if options.parallel_tool_calls == Some(false) => serde_json::json!({ "type": "auto" }),
+            None => return None,
+        };
+        if options.parallel_tool_calls == Some(false) {
+            choice["disable_parallel_tool_use"] = serde_json::Value::Bool(true);
+        }
+        Some(choice)
+    }
+
+    /// Reject request options the Messages API can't honor.
+    fn check_options(
+        &self,
+        options: &ChatOptions,
+        tools: Option<&[ToolDefinition]>,
+    ) -> Result<(), ProviderError> {
+        options.check_tools(tools)?;
+        if let Some(key) = options.metadata.keys().find(|k| *k != "user_id") {
+            return Err(unsupported_option("Anthropic", &format!("request metadata '{}' (only user_id)", key)));
         }
+        if self.thinking_budget.is_some() {
+            // Extended thinking only allows automatic tool choice and the default temperature
+            if options.forces_tool_use() {
+                return Err(unsupported_option("Anthropic", "forced tool use with extended thinking"));
+            }
+            if options.temperature.is_some() {
+                return Err(unsupported_option("Anthropic", "temperature with extended thinking"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a non-streaming request and convert the response.
+    async fn send(&self, request: &AnthropicRequest) -> Result<ProviderResponse, ProviderError> {
+        let start = Instant::now();
+
+        #[cfg(feature = "telemetry")]
+        debug!(model = %self.model, messages = request.messages.len(), "Sending chat request");
+
+        let response = self
+            .client
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("content-type", "application/json")
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let error_text = response.text().await.unwrap_or_default();
+            #[cfg(feature = "telemetry")]
+            GLOBAL_METRICS.record_operation("anthropic.chat", start.elapsed());
+            return Err(with_retry_after(
+                self.handle_error_response(status.as_u16(), &error_text),
+                &headers,
+            ));
+        }
+
+        let api_response: ApiResponse = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+
+        let provider_response: ProviderResponse = api_response.into();
+
+        // Record metrics
+        #[cfg(feature = "telemetry")]
+        {
+            GLOBAL_METRICS.record_operation("anthropic.chat", start.elapsed());
+            if let Some(ref usage) = provider_response.usage {
+                GLOBAL_METRICS.record_tokens(usage.input_tokens as u64, usage.output_tokens as u64);
+            }
+        }
+
+        Ok(provider_response)
+    }
+
+    /// Build a request that forces a [`FINAL_ANSWER_TOOL`] call whose input
+    /// schema is the requested output schema.
+    fn build_structured_request(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        schema: &serde_json::Value,
+    ) -> AnthropicRequest {
+        let mut request = self.build_request(messages, None, system_prompt, &ChatOptions::default());
+        request.tools = Some(vec![ApiTool {
+            name: FINAL_ANSWER_TOOL.to_string(),
+            description: "Return the final answer. The input is the answer itself.".to_string(),
+            input_schema: schema.clone(),
+            cache_control: None,
+        }]);
+        request.tool_choice = Some(serde_json::json!({
+            "type": "tool",
+            "name": FINAL_ANSWER_TOOL,
+        }));
+        // Forcing a tool call isn't allowed with extended thinking
+        request.thinking = None;
+        request.temperature = self.temperature;
+        request
     }
 
     /// Build a streaming request body.
@@ -120,8 +278,9 @@ impl AnthropicProvider {
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
     ) -> AnthropicRequest {
-        let mut request = self.build_request(messages, tools, system_prompt);
+        let mut request = self.build_request(messages, tools, system_prompt, options);
         request.stream = Some(true);
         request
     }
@@ -147,6 +306,36 @@ impl AnthropicProvider {
         }
     }
 
+    /// Get per-million-token pricing (USD) for a model, including prompt cache rates.
+    fn get_pricing(model: &str) -> Option<ModelPricing> {
+        let (input, output) = if model.contains("opus-4-5") || model.contains("opus-4-6") {
+            (5.0, 25.0)
+        } else if model.contains("opus") {
+            (15.0, 75.0)
+        } else if model.contains("sonnet") {
+            (3.0, 15.0)
+        } else if model.contains("haiku-4") {
+            (1.0, 5.0)
+        } else if model.contains("3-5-haiku") {
+            (0.80, 4.0)
+        } else if model.contains("haiku") {
+            (0.25, 1.25)
+        } else {
+            return None;
+        };
+
+        // Cache reads cost 10% of input; 5-minute cache writes cost 125%
+        Some(ModelPricing::new(input, output).with_cache(input * 0.1, input * 1.25))
+    }
+
+    /// Check if a model supports extended thinking.
+    fn model_supports_thinking(model: &str) -> bool {
+        model.contains("claude-3-7")
+            || model.contains("claude-sonnet-4")
+            || model.contains("claude-opus-4")
+            || model.contains("claude-haiku-4")
+    }
+
     /// Check if a model supports vision.
     fn model_supports_vision(model: &str) -> bool {
         // All Claude 3+ models support vision
@@ -161,49 +350,11 @@ impl Provider for AnthropicProvider {
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
     ) -> Result<ProviderResponse, ProviderError> {
-        let request = self.build_request(messages, tools, system_prompt);
-        let start = Instant::now();
-
-        #[cfg(feature = "telemetry")]
-        debug!(model = %self.model, messages = messages.len(), "Sending chat request");
-
-        let response = self
-            .client
-            .post(format!("{}/v1/messages", self.base_url))
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            #[cfg(feature = "telemetry")]
-            GLOBAL_METRICS.record_operation("anthropic.chat", start.elapsed());
-            return Err(self.handle_error_response(status.as_u16(), &error_text));
-        }
-
-        let api_response: ApiResponse = response
-            .json()
-            .await
-            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
-
-        let provider_response: ProviderResponse = api_response.into();
-
-        // Record metrics
-        #[cfg(feature = "telemetry")]
-        {
-            GLOBAL_METRICS.record_operation("anthropic.chat", start.elapsed());
-            if let Some(ref usage) = provider_response.usage {
-                GLOBAL_METRICS.record_tokens(usage.input_tokens as u64, usage.output_tokens as u64);
-            }
-        }
-
-        Ok(provider_response)
+        self.check_options(options, tools)?;
+        let request = self.build_request(messages, tools, system_prompt, options);
+        self.send(&request).await
     }
 
     async fn stream_chat(
@@ -211,9 +362,11 @@ impl Provider for AnthropicProvider {
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
         on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
     ) -> Result<ProviderResponse, ProviderError> {
-        let request = self.build_streaming_request(messages, tools, system_prompt);
+        self.check_options(options, tools)?;
+        let request = self.build_streaming_request(messages, tools, system_prompt, options);
         let start = Instant::now();
 
         #[cfg(feature = "telemetry")]
@@ -232,10 +385,14 @@ impl Provider for AnthropicProvider {
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let error_text = response.text().await.unwrap_or_default();
             #[cfg(feature = "telemetry")]
             GLOBAL_METRICS.record_operation("anthropic.stream_chat", start.elapsed());
-            return Err(self.handle_error_response(status.as_u16(), &error_text));
+            return Err(with_retry_after(
+                self.handle_error_response(status.as_u16(), &error_text),
+                &headers,
+            ));
         }
 
         // Process SSE stream
@@ -291,6 +448,33 @@ impl Provider for AnthropicProvider {
         true
     }
 
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        schema: &serde_json::Value,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let request = self.build_structured_request(messages, system_prompt, schema);
+        let mut response = self.send(&request).await?;
+
+        let answer = response
+            .tool_calls
+            .iter()
+            .position(|call| call.name == FINAL_ANSWER_TOOL)
+            .map(|i| response.tool_calls.remove(i))
+            .ok_or_else(|| {
+                ProviderError::ParseError(format!("Response has no {} tool call", FINAL_ANSWER_TOOL))
+            })?;
+        response.content = answer.input.to_string();
+        response.tool_calls.clear();
+        response.stop_reason = StopReason::EndTurn;
+        Ok(response)
+    }
+
     fn supports_vision(&self) -> bool {
         Self::model_supports_vision(&self.model)
     }
@@ -307,6 +491,10 @@ impl Provider for AnthropicProvider {
         Self::get_context_window(&self.model)
     }
 
+    fn pricing(&self) -> Option<ModelPricing> {
+        Self::get_pricing(&self.model)
+    }
+
     async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
         // Anthropic doesn't have a models list API, so we return known models
         Ok(vec![
@@ -319,10 +507,7 @@ impl Provider for AnthropicProvider {
                     tool_use: true,
                 },
                 context_window: Some(200_000),
-                pricing: Some(ModelPricing {
-                    input: 15.0,
-                    output: 75.0,
-                }),
+                pricing: Self::get_pricing("claude-opus-4-20250514"),
                 deprecated: None,
             },
             ModelInfo {
@@ -334,10 +519,7 @@ impl Provider for AnthropicProvider {
                     tool_use: true,
                 },
                 context_window: Some(200_000),
-                pricing: Some(ModelPricing {
-                    input: 3.0,
-                    output: 15.0,
-                }),
+                pricing: Self::get_pricing("claude-sonnet-4-20250514"),
                 deprecated: None,
             },
             ModelInfo {
@@ -349,10 +531,7 @@ impl Provider for AnthropicProvider {
                     tool_use: true,
                 },
                 context_window: Some(200_000),
-                pricing: Some(ModelPricing {
-                    input: 3.0,
-                    output: 15.0,
-                }),
+                pricing: Self::get_pricing("claude-3-5-sonnet-latest"),
                 deprecated: None,
             },
             ModelInfo {
@@ -364,10 +543,7 @@ impl Provider for AnthropicProvider {
                     tool_use: true,
                 },
                 context_window: Some(200_000),
-                pricing: Some(ModelPricing {
-                    input: 0.80,
-                    output: 4.0,
-                }),
+                pricing: Self::get_pricing("claude-3-5-haiku-latest"),
                 deprecated: None,
             },
         ])
@@ -411,6 +587,8 @@ impl AnthropicProvider {
                     serde_json::from_str(data).map_err(|e| ProviderError::ParseError(e.to_string()))?;
                 if let Some(usage) = msg.message.usage {
                     state.input_tokens = usage.input_tokens;
+                    state.cache_creation_input_tokens = usage.cache_creation_input_tokens;
+                    state.cache_read_input_tokens = usage.cache_read_input_tokens;
                 }
             }
             "content_block_start" => {
@@ -462,6 +640,11 @@ impl AnthropicProvider {
                             on_event(StreamEvent::ReasoningDelta(thinking.clone()));
                         }
                     }
+                    "signature_delta" => {
+                        if let Some(signature) = &delta.delta.signature {
+                            state.reasoning_signature.push_str(signature);
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -500,12 +683,7 @@ impl AnthropicProvider {
                 }
             }
             "message_stop" => {
-                let usage = TokenUsage {
-                    input_tokens: state.input_tokens,
-                    output_tokens: state.output_tokens,
-                    ..Default::default()
-                };
-                on_event(StreamEvent::Usage(usage));
+                on_event(StreamEvent::Usage(state.usage()));
                 on_event(StreamEvent::Done(state.stop_reason.unwrap_or(StopReason::EndTurn)));
             }
             "error" => {
@@ -535,10 +713,13 @@ enum BlockType {
 struct StreamState {
     text_content: String,
     reasoning_content: String,
+    reasoning_signature: String,
     tool_calls: Vec<ToolCall>,
     stop_reason: Option<StopReason>,
     input_tokens: u32,
     output_tokens: u32,
+    cache_creation_input_tokens: Option<u32>,
+    cache_read_input_tokens: Option<u32>,
     current_block_type: Option<BlockType>,
     current_tool_id: Option<String>,
     current_tool_name: Option<String>,
@@ -550,10 +731,13 @@ impl StreamState {
         Self {
             text_content: String::new(),
             reasoning_content: String::new(),
+            reasoning_signature: String::new(),
             tool_calls: Vec::new(),
             stop_reason: None,
             input_tokens: 0,
             output_tokens: 0,
+            cache_creation_input_tokens: None,
+            cache_read_input_tokens: None,
             current_block_type: None,
             current_tool_id: None,
             current_tool_name: None,
@@ -562,6 +746,7 @@ impl StreamState {
     }
 
     fn into_response(self) -> ProviderResponse {
+        let usage = self.usage();
         ProviderResponse {
             content: self.text_content,
             tool_calls: self.tool_calls,
@@ -571,11 +756,22 @@ impl StreamState {
             } else {
                 Some(self.reasoning_content)
             },
-            usage: Some(TokenUsage {
-                input_tokens: self.input_tokens,
-                output_tokens: self.output_tokens,
-                ..Default::default()
-            }),
+            reasoning_signature: if self.reasoning_signature.is_empty() {
+                None
+            } else {
+                Some(self.reasoning_signature)
+            },
+            usage: Some(usage),
+        }
+    }
+
+    fn usage(&self) -> TokenUsage {
+        TokenUsage {
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            cache_creation_input_tokens: self.cache_creation_input_tokens,
+            cache_read_input_tokens: self.cache_read_input_tokens,
+            cached_input_tokens: None,
         }
     }
 }
@@ -591,13 +787,82 @@ struct AnthropicRequest {
     max_tokens: u32,
     messages: Vec<ApiMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<ApiContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<ApiTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<ApiThinking>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<serde_json::Value>,
+}
+
+/// Extended thinking settings.
+#[derive(Debug, Serialize)]
+struct ApiThinking {
+    #[serde(rename = "type")]
+    thinking_type: String,
+    budget_tokens: u32,
+}
+
+impl ApiThinking {
+    fn enabled(budget_tokens: u32) -> Self {
+        Self {
+            thinking_type: "enabled".to_string(),
+            budget_tokens,
+        }
+    }
+}
+
+impl AnthropicRequest {
+    /// Mark the system prompt, the tool list and the last message as cache
+    /// breakpoints.
+    ///
+    /// Anthropic caches the prompt prefix up to each breakpoint, so the next
+    /// request of an agent turn only pays full price for the messages added
+    /// since. The breakpoint on the last message moves forward with the
+    /// history; earlier ones are still found by the API's prefix lookback.
+    fn add_cache_breakpoints(&mut self) {
+        if let Some(ApiContent::Text(system)) = &self.system {
+            if !system.is_empty() {
+                let mut block = ApiContentBlock::from(&ContentBlock::text(system.clone()));
+                block.cache_control = Some(CacheControl::ephemeral());
+                self.system = Some(ApiContent::Blocks(vec![block]));
+            }
+        }
+
+        if let Some(tool) = self.tools.as_mut().and_then(|tools| tools.last_mut()) {
+            tool.cache_control = Some(CacheControl::ephemeral());
+        }
+
+        if let Some(message) = self.messages.last_mut() {
+            if let ApiContent::Text(text) = &message.content {
+                if text.is_empty() {
+                    return;
+                }
+                message.content =
+                    ApiContent::Blocks(vec![ApiContentBlock::from(&ContentBlock::text(text.clone()))]);
+            }
+            if let ApiContent::Blocks(blocks) = &mut message.content {
+                // Empty text and thinking blocks can't be cached
+                let cacheable = blocks.iter_mut().rev().find(|b| match b.block_type.as_str() {
+                    "text" => b.text.as_deref().is_some_and(|t| !t.is_empty()),
+                    "thinking" => false,
+                    _ => true,
+                });
+                if let Some(block) = cacheable {
+                    block.cache_control = Some(CacheControl::ephemeral());
+                }
+            }
+        }
+    }
 }
 
 /// API message format.
@@ -630,12 +895,34 @@ struct ApiContentBlock {
     input: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_use_id: Option<String>,
+    /// Tool result content: text, or text and image blocks
     #[serde(skip_serializing_if = "Option::is_none")]
-    content: Option<String>,
+    content: Option<ApiContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     is_error: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     source: Option<ApiImageSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+/// Marks the end of a prompt prefix to cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    cache_type: String,
+}
+
+impl CacheControl {
+    fn ephemeral() -> Self {
+        Self {
+            cache_type: "ephemeral".to_string(),
+        }
+    }
 }
 
 /// Image source in API format.
@@ -653,6 +940,8 @@ struct ApiTool {
     name: String,
     description: String,
     input_schema: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
 }
 
 /// API response format.
@@ -731,6 +1020,8 @@ struct ContentBlockDelta {
     partial_json: Option<String>,
     #[serde(default)]
     thinking: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -770,9 +1061,14 @@ impl From<&Message> for ApiMessage {
 
         let content = match &msg.content {
             MessageContent::Text(s) => ApiContent::Text(s.clone()),
-            MessageContent::Blocks(blocks) => {
-                ApiContent::Blocks(blocks.iter().map(|b| b.into()).collect())
-            }
+            // The API rejects thinking blocks it didn't sign
+            MessageContent::Blocks(blocks) => ApiContent::Blocks(
+                blocks
+                    .iter()
+                    .filter(|b| b.block_type != ContentBlockType::Thinking || b.signature.is_some())
+                    .map(|b| b.into())
+                    .collect(),
+            ),
         };
 
         Self {
@@ -795,6 +1091,9 @@ impl From<&ContentBlock> for ApiContentBlock {
                 content: None,
                 is_error: None,
                 source: None,
+                cache_control: None,
+                thinking: None,
+                signature: None,
             },
             ContentBlockType::ToolUse => Self {
                 block_type: "tool_use".to_string(),
@@ -806,6 +1105,9 @@ impl From<&ContentBlock> for ApiContentBlock {
                 content: None,
                 is_error: None,
                 source: None,
+                cache_control: None,
+                thinking: None,
+                signature: None,
             },
             ContentBlockType::ToolResult => Self {
                 block_type: "tool_result".to_string(),
@@ -814,9 +1116,18 @@ impl From<&ContentBlock> for ApiContentBlock {
                 name: None,
                 input: None,
                 tool_use_id: block.tool_use_id.clone(),
-                content: block.content.clone(),
+                content: match &block.image {
+                    Some(image) => Some(ApiContent::Blocks(vec![
+                        (&ContentBlock::text(block.content.clone().unwrap_or_default())).into(),
+                        (&ContentBlock::image(image.clone())).into(),
+                    ])),
+                    None => block.content.clone().map(ApiContent::Text),
+                },
                 is_error: block.is_error,
                 source: None,
+                cache_control: None,
+                thinking: None,
+                signature: None,
             },
             ContentBlockType::Image => {
                 let source = block.image.as_ref().map(|img| ApiImageSource {
@@ -839,11 +1150,14 @@ impl From<&ContentBlock> for ApiContentBlock {
                     content: None,
                     is_error: None,
                     source,
+                    cache_control: None,
+                    thinking: None,
+                    signature: None,
                 }
             }
             ContentBlockType::Thinking => Self {
                 block_type: "thinking".to_string(),
-                text: block.text.clone(),
+                text: None,
                 id: None,
                 name: None,
                 input: None,
@@ -851,6 +1165,9 @@ impl From<&ContentBlock> for ApiContentBlock {
                 content: None,
                 is_error: None,
                 source: None,
+                cache_control: None,
+                thinking: block.text.clone(),
+                signature: block.signature.clone(),
             },
         }
     }
@@ -862,6 +1179,7 @@ impl From<&ToolDefinition> for ApiTool {
             name: tool.name.clone(),
             description: tool.description.clone(),
             input_schema: serde_json::to_value(&tool.input_schema).unwrap_or_default(),
+            cache_control: None,
         }
     }
 }
@@ -871,6 +1189,7 @@ impl From<ApiResponse> for ProviderResponse {
         let mut content = String::new();
         let mut tool_calls = Vec::new();
         let mut reasoning_content = None;
+        let mut reasoning_signature = None;
 
         for block in response.content {
             match block.block_type.as_str() {
@@ -885,9 +1204,8 @@ impl From<ApiResponse> for ProviderResponse {
                     }
                 }
                 "thinking" => {
-                    if let Some(text) = block.text {
-                        reasoning_content = Some(text);
-                    }
+                    reasoning_content = block.thinking;
+                    reasoning_signature = block.signature;
                 }
                 _ => {}
             }
@@ -905,6 +1223,7 @@ impl From<ApiResponse> for ProviderResponse {
             tool_calls,
             stop_reason,
             reasoning_content,
+            reasoning_signature,
             usage: Some(TokenUsage {
                 input_tokens: response.usage.input_tokens,
                 output_tokens: response.usage.output_tokens,
@@ -919,6 +1238,7 @@ impl From<ApiResponse> for ProviderResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::ImageSource;
 
     #[test]
     fn test_provider_creation() {
@@ -943,6 +1263,18 @@ mod tests {
         assert_eq!(AnthropicProvider::get_context_window("claude-2.1"), 100_000);
     }
 
+    #[test]
+    fn test_pricing() {
+        let opus = AnthropicProvider::get_pricing("claude-opus-4-20250514").unwrap();
+        assert_eq!((opus.input, opus.output), (15.0, 75.0));
+        assert!((opus.cache_read.unwrap() - 1.5).abs() < 1e-9);
+        assert!((opus.cache_write.unwrap() - 18.75).abs() < 1e-9);
+
+        let haiku = AnthropicProvider::get_pricing("claude-3-5-haiku-latest").unwrap();
+        assert_eq!((haiku.input, haiku.output), (0.80, 4.0));
+        assert!(AnthropicProvider::get_pricing("claude-2.1").is_none());
+    }
+
     #[test]
     fn test_vision_support() {
         assert!(AnthropicProvider::model_supports_vision("claude-sonnet-4-20250514"));
@@ -962,6 +1294,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tool_result_image_conversion() {
+        let image = ImageSource::new_base64(ImageMediaType::Png, "iVBOR".to_string());
+        let msg = Message::with_blocks(
+            Role::User,
+            vec![
+                ContentBlock::tool_result("t1", "plain", false),
+                ContentBlock::tool_result_with_image("t2", "Image: a.png (8x4)", image),
+            ],
+        );
+        let body = serde_json::to_value(ApiMessage::from(&msg)).unwrap();
+
+        assert_eq!(body["content"][0]["content"], "plain");
+        let blocks = &body["content"][1]["content"];
+        assert_eq!(blocks[0], serde_json::json!({ "type": "text", "text": "Image: a.png (8x4)" }));
+        assert_eq!(blocks[1]["type"], "image");
+        assert_eq!(blocks[1]["source"]["media_type"], "image/png");
+        assert_eq!(blocks[1]["source"]["data"], "iVBOR");
+    }
+
     #[test]
     fn test_tool_conversion() {
         let tool = ToolDefinition::new("test_tool", "A test tool");
@@ -971,6 +1323,28 @@ mod tests {
         assert_eq!(api_tool.description, "A test tool");
     }
 
+    #[test]
+    fn test_structured_request_forces_final_answer_tool() {
+        let provider = AnthropicProvider::new(
+            "test-key",
+            "claude-sonnet-4-20250514",
+            "https://api.anthropic.com",
+            ProviderConfig::default(),
+        );
+        let schema = serde_json::json!({ "type": "object", "required": ["count"] });
+        let request = provider.build_structured_request(&[Message::user("count")], None, &schema);
+        let body = serde_json::to_value(&request).unwrap();
+
+        assert!(provider.supports_structured_output());
+        assert_eq!(body["tools"][0]["name"], FINAL_ANSWER_TOOL);
+        assert_eq!(body["tools"][0]["input_schema"], schema);
+        assert_eq!(body["tool_choice"], serde_json::json!({ "type": "tool", "name": "final_answer" }));
+        // Regular requests leave tool choice to the model
+        let request = provider.build_request(&[], None, None, &ChatOptions::default());
+        let plain = serde_json::to_value(request).unwrap();
+        assert!(plain.get("tool_choice").is_none());
+    }
+
     #[test]
     fn test_sse_parsing() {
         assert_eq!(
@@ -1001,6 +1375,178 @@ mod tests {
         assert_eq!(response.usage.unwrap().total(), 150);
     }
 
+    #[test]
+    fn test_stream_usage_includes_cache_tokens() {
+        let provider = AnthropicProvider::new("k", "claude-sonnet-4-20250514", "http://x", ProviderConfig::default());
+        let mut state = StreamState::new();
+        let data = r#"{"message": {"usage": {"input_tokens": 12, "output_tokens": 1,
+            "cache_creation_input_tokens": 300, "cache_read_input_tokens": 4000}}}"#;
+        provider
+            .process_stream_data("message_start", data, &mut state, &|_| {})
+            .unwrap();
+
+        let usage = state.into_response().usage.unwrap();
+        assert_eq!(usage.cache_creation_input_tokens, Some(300));
+        assert_eq!(usage.cache_read_input_tokens, Some(4000));
+        assert_eq!(usage.context_tokens(), 4312);
+    }
+
+    #[test]
+    fn test_cache_breakpoints() {
+        let provider = AnthropicProvider::new("k", "claude-sonnet-4-20250514", "http://x", ProviderConfig::default());
+        let tools = [ToolDefinition::new("read_file", "Read"), ToolDefinition::new("grep", "Search")];
+        let messages = [Message::user("first"), Message::assistant("ok"), Message::user("second")];
+        let options = ChatOptions::default();
+        let request = provider.build_request(&messages, Some(&tools), Some("You are Codi"), &options);
+        let body = serde_json::to_value(request).unwrap();
+
+        let ephemeral = serde_json::json!({ "type": "ephemeral" });
+        assert_eq!(body["system"][0]["text"], "You are Codi");
+        assert_eq!(body["system"][0]["cache_control"], ephemeral);
+        assert!(body["tools"][0].get("cache_control").is_none());
+        assert_eq!(body["tools"][1]["cache_control"], ephemeral);
+        assert_eq!(body["messages"][0]["content"], "first");
+        assert_eq!(body["messages"][2]["content"][0]["text"], "second");
+        assert_eq!(body["messages"][2]["content"][0]["cache_control"], ephemeral);
+
+        let config = ProviderConfig {
+            prompt_caching: Some(false),
+            ..Default::default()
+        };
+        let provider = AnthropicProvider::new("k", "claude-sonnet-4-20250514", "http://x", config);
+        let options = ChatOptions::default();
+        let request = provider.build_request(&messages, Some(&tools), Some("You are Codi"), &options);
+        let body = serde_json::to_value(request).unwrap();
+        assert_eq!(body["system"], "You are Codi");
+        assert!(!body.to_string().contains("cache_control"));
+    }
+
+    #[test]
+    fn test_thinking_request() {
+        let config = ProviderConfig {
+            thinking_budget: Some(10_000),
+            temperature: Some(0.2),
+            ..Default::default()
+        };
+        let provider = AnthropicProvider::new("k", "claude-sonnet-4-20250514", "http://x", config.clone());
+        let messages = [Message::user("hi")];
+        let request = provider.build_request(&messages, None, None, &ChatOptions::default());
+        let body = serde_json::to_value(request).unwrap();
+        assert_eq!(body["thinking"], serde_json::json!({ "type": "enabled", "budget_tokens": 10_000 }));
+        assert_eq!(body["max_tokens"], 10_000 + DEFAULT_MAX_TOKENS);
+        assert!(body.get("temperature").is_none());
+
+        // Forced tool use can't be combined with thinking
+        let schema = serde_json::json!({ "type": "object" });
+        let body = serde_json::to_value(provider.build_structured_request(&messages, None, &schema)).unwrap();
+        assert!(body.get("thinking").is_none());
+
+        // Models without extended thinking ignore the budget
+        let provider = AnthropicProvider::new("k", "claude-3-5-haiku-latest", "http://x", config);
+        let request = provider.build_request(&messages, None, None, &ChatOptions::default());
+        let body = serde_json::to_value(request).unwrap();
+        assert!(body.get("thinking").is_none());
+    }
+
+    #[test]
+    fn test_request_options() {
+        let provider = AnthropicProvider::new("k", "claude-sonnet-4-20250514", "http://x", ProviderConfig::default());
+        let tools = vec![ToolDefinition::new("read_file", "Read a file")];
+        let options = ChatOptions::new()
+            .with_tool_choice(ToolChoice::Tool("read_file".to_string()))
+            .with_parallel_tool_calls(false)
+            .with_stop_sequences(["</answer>"])
+            .with_max_tokens(256)
+            .with_temperature(0.0)
+            .with_metadata("user_id", "u-1");
+
+        let request = provider.build_request(&[Message::user("hi")], Some(&tools), None, &options);
+        let body = serde_json::to_value(request).unwrap();
+        assert_eq!(
+            body["tool_choice"],
+            serde_json::json!({ "type": "tool", "name": "read_file", "disable_parallel_tool_use": true })
+        );
+        assert_eq!(body["stop_sequences"], serde_json::json!(["</answer>"]));
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["temperature"], 0.0);
+        assert_eq!(body["metadata"], serde_json::json!({ "user_id": "u-1" }));
+
+        // Forbidding parallel calls alone still needs a tool_choice object
+        let options = ChatOptions::new().with_parallel_tool_calls(false);
+        assert_eq!(
+            AnthropicProvider::tool_choice(&options),
+            Some(serde_json::json!({ "type": "auto", "disable_parallel_tool_use": true }))
+        );
+        assert_eq!(
+            AnthropicProvider::tool_choice(&ChatOptions::new().with_tool_choice(ToolChoice::Required)),
+            Some(serde_json::json!({ "type": "any" }))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_options() {
+        let tools = vec![ToolDefinition::new("read_file", "Read a file")];
+        let provider = AnthropicProvider::new("k", "claude-sonnet-4-20250514", "http://x", ProviderConfig::default());
+        let err = provider
+            .check_options(&ChatOptions::new().with_metadata("trace_id", "t-1"), None)
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::UnsupportedOperation(_)));
+
+        let config = ProviderConfig {
+            thinking_budget: Some(4096),
+            ..Default::default()
+        };
+        let provider = AnthropicProvider::new("k", "claude-sonnet-4-20250514", "http://x", config);
+        let forced = ChatOptions::new().with_tool_choice(ToolChoice::Required);
+        assert!(provider.check_options(&forced, Some(&tools)).is_err());
+        assert!(provider.check_options(&ChatOptions::new().with_temperature(0.5), None).is_err());
+        assert!(provider.check_options(&ChatOptions::new().with_max_tokens(8192), None).is_ok());
+    }
+
+    #[test]
+    fn test_thinking_signature_round_trip() {
+        let provider = AnthropicProvider::new("k", "claude-sonnet-4-20250514", "http://x", ProviderConfig::default());
+        let mut state = StreamState::new();
+        let deltas = [
+            r#"{"delta": {"type": "thinking_delta", "thinking": "Check the file"}}"#,
+            r#"{"delta": {"type": "signature_delta", "signature": "sig-123"}}"#,
+        ];
+        for data in deltas {
+            provider
+                .process_stream_data("content_block_delta", data, &mut state, &|_| {})
+                .unwrap();
+        }
+        let response = state.into_response();
+        assert_eq!(response.reasoning_content.as_deref(), Some("Check the file"));
+        assert_eq!(response.reasoning_signature.as_deref(), Some("sig-123"));
+
+        let response: ApiResponse = serde_json::from_str(
+            r#"{"content": [{"type": "thinking", "thinking": "Plan", "signature": "sig-456"},
+                {"type": "text", "text": "Done"}],
+                "stop_reason": "end_turn", "usage": {"input_tokens": 1, "output_tokens": 2}}"#,
+        )
+        .unwrap();
+        let response = ProviderResponse::from(response);
+        assert_eq!(response.reasoning_content.as_deref(), Some("Plan"));
+        assert_eq!(response.reasoning_signature.as_deref(), Some("sig-456"));
+
+        // Only signed thinking is sent back
+        let msg = Message::with_blocks(
+            Role::Assistant,
+            vec![
+                ContentBlock::thinking("unsigned"),
+                ContentBlock::thinking_with_signature("Plan", "sig-456"),
+                ContentBlock::tool_use("t1", "read_file", serde_json::json!({})),
+            ],
+        );
+        let api_msg = serde_json::to_value(ApiMessage::from(&msg)).unwrap();
+        assert_eq!(api_msg["content"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            api_msg["content"][0],
+            serde_json::json!({ "type": "thinking", "thinking": "Plan", "signature": "sig-456" })
+        );
+    }
+
     #[test]
     fn test_provider_timeout_error() {
         // Test that provider correctly identifies timeout errors
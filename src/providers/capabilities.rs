@@ -0,0 +1,413 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Runtime model capability discovery.
+//!
+//! Local and self-hosted servers can run any model, so name-matching tables
+//! get their context windows and features wrong. Instead we ask the server:
+//!
+//! - **Ollama** - `POST /api/show` reports the Modelfile's `num_ctx`, the
+//!   model's capabilities, the chat template (tool support) and any vision
+//!   projector. Without a `num_ctx` the server runs at its own default and
+//!   silently truncates longer prompts, so the context window is left unknown
+//!   rather than taken from the trained context length.
+//! - **vLLM, LM Studio and other OpenAI-compatible servers** - `GET /models`
+//!   entries may carry `max_model_len` (vLLM), `max_context_length`, `type`
+//!   and `capabilities` (LM Studio) or `context_length`.
+//!
+//! Results are cached per base URL and model for the life of the process.
+//! Anything the server doesn't report stays `None`, and callers fall back to
+//! their built-in tables.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::error::ProviderError;
+
+/// Timeout for discovery requests; the answer isn't worth delaying a turn for.
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Discovered capabilities, keyed by (base URL, model).
+static CACHE: Lazy<Mutex<HashMap<(String, String), DiscoveredCapabilities>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Model capabilities reported by a server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiscoveredCapabilities {
+    /// Context window in tokens.
+    pub context_window: Option<u32>,
+    /// Whether the model supports native tool calling.
+    pub tool_use: Option<bool>,
+    /// Whether the model accepts images.
+    pub vision: Option<bool>,
+}
+
+/// How to ask a server about its models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoverySource {
+    /// Ollama's native `/api/show`.
+    Ollama,
+    /// Metadata in the OpenAI-compatible `/models` listing.
+    ModelList,
+}
+
+/// Cached capabilities for a model, if they have been discovered.
+pub fn cached(base_url: &str, model: &str) -> Option<DiscoveredCapabilities> {
+    CACHE
+        .lock()
+        .unwrap()
+        .get(&(base_url.to_string(), model.to_string()))
+        .copied()
+}
+
+/// Discover a model's capabilities, using the cache when possible.
+///
+/// A server that answers without the metadata is cached as reporting
+/// nothing. Network failures aren't cached, so a server that wasn't running
+/// yet is asked again next time.
+pub async fn discover(
+    client: &Client,
+    base_url: &str,
+    api_key: Option<&str>,
+    model: &str,
+    source: DiscoverySource,
+) -> Option<DiscoveredCapabilities> {
+    if let Some(capabilities) = cached(base_url, model) {
+        return Some(capabilities);
+    }
+
+    let result = match source {
+        DiscoverySource::Ollama => show_ollama_model(client, base_url, model).await,
+        DiscoverySource::ModelList => list_model(client, base_url, api_key, model).await,
+    };
+
+    let capabilities = match result {
+        Ok(capabilities) => capabilities,
+        Err(ProviderError::NetworkError(e)) => {
+            tracing::debug!("Capability discovery for {} at {} failed: {}", model, base_url, e);
+            return None;
+        }
+        Err(e) => {
+            tracing::debug!("{} at {} doesn't report capabilities: {}", model, base_url, e);
+            DiscoveredCapabilities::default()
+        }
+    };
+
+    CACHE
+        .lock()
+        .unwrap()
+        .insert((base_url.to_string(), model.to_string()), capabilities);
+    Some(capabilities)
+}
+
+/// Ask Ollama's native API about a model.
+async fn show_ollama_model(
+    client: &Client,
+    base_url: &str,
+    model: &str,
+) -> Result<DiscoveredCapabilities, ProviderError> {
+    // The OpenAI-compatible API lives under /v1; the native API is at the root
+    let root = base_url.trim_end_matches('/').trim_end_matches("/v1");
+    let response = client
+        .post(format!("{}/api/show", root))
+        .timeout(DISCOVERY_TIMEOUT)
+        .json(&serde_json::json!({ "model": model }))
+        .send()
+        .await
+        .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(ProviderError::api(body, status.as_u16()));
+    }
+
+    let show: OllamaShowResponse = response
+        .json()
+        .await
+        .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+    Ok(show.capabilities())
+}
+
+/// Find a model's entry in the `/models` listing.
+async fn list_model(
+    client: &Client,
+    base_url: &str,
+    api_key: Option<&str>,
+    model: &str,
+) -> Result<DiscoveredCapabilities, ProviderError> {
+    let mut request = client
+        .get(format!("{}/models", base_url))
+        .timeout(DISCOVERY_TIMEOUT);
+    if let Some(api_key) = api_key {
+        request = request.header("authorization", format!("Bearer {}", api_key));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(ProviderError::api(body, status.as_u16()));
+    }
+
+    let models: ModelsResponse = response
+        .json()
+        .await
+        .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+    models
+        .data
+        .iter()
+        .find(|entry| entry.id == model)
+        .map(ModelEntry::capabilities)
+        .ok_or_else(|| ProviderError::ModelNotFound(model.to_string()))
+}
+
+/// Response from Ollama's `/api/show`.
+#[derive(Debug, Default, Deserialize)]
+struct OllamaShowResponse {
+    /// Model parameters, one `name value` pair per line.
+    #[serde(default)]
+    parameters: String,
+    /// Chat template; tool-capable models reference `.Tools`.
+    #[serde(default)]
+    template: String,
+    /// Capabilities reported by newer Ollama versions ("completion", "tools", "vision", ...).
+    #[serde(default)]
+    capabilities: Vec<String>,
+    /// GGUF metadata, including `<architecture>.context_length`.
+    #[serde(default)]
+    model_info: HashMap<String, serde_json::Value>,
+    /// Present for models with a vision projector.
+    projector_info: Option<serde_json::Value>,
+}
+
+impl OllamaShowResponse {
+    fn capabilities(&self) -> DiscoveredCapabilities {
+        DiscoveredCapabilities {
+            context_window: self.num_ctx().map(|num_ctx| match self.context_length() {
+                Some(trained) => num_ctx.min(trained),
+                None => num_ctx,
+            }),
+            tool_use: Some(self.has_capability("tools") || self.template.contains(".Tools")),
+            vision: Some(self.has_capability("vision") || self.projector_info.is_some()),
+        }
+    }
+
+    fn has_capability(&self, name: &str) -> bool {
+        self.capabilities.iter().any(|c| c == name)
+    }
+
+    /// Context size configured in the Modelfile, which limits what the server accepts.
+    fn num_ctx(&self) -> Option<u32> {
+        self.parameters.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("num_ctx"), Some(value)) => value.parse().ok(),
+                _ => None,
+            }
+        })
+    }
+
+    /// Context length the model was trained with.
+    fn context_length(&self) -> Option<u32> {
+        let value = match self.model_info.get("general.architecture").and_then(|a| a.as_str()) {
+            Some(architecture) => self.model_info.get(&format!("{}.context_length", architecture)),
+            None => self
+                .model_info
+                .iter()
+                .find(|(key, _)| key.ends_with(".context_length"))
+                .map(|(_, value)| value),
+        };
+        value
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(u32::MAX as u64) as u32)
+    }
+}
+
+/// Response from an OpenAI-compatible `/models` endpoint.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ModelsResponse {
+    pub data: Vec<ModelEntry>,
+}
+
+/// A model in the listing, with the metadata some servers add.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ModelEntry {
+    pub id: String,
+    /// vLLM.
+    max_model_len: Option<u32>,
+    /// LM Studio.
+    max_context_length: Option<u32>,
+    /// Together, OpenRouter.
+    context_length: Option<u32>,
+    /// Groq.
+    context_window: Option<u32>,
+    /// LM Studio: "llm", "vlm" or "embeddings".
+    #[serde(rename = "type")]
+    model_type: Option<String>,
+    /// LM Studio: e.g. ["tool_use"].
+    capabilities: Option<Vec<String>>,
+}
+
+impl ModelEntry {
+    /// Capabilities described by the entry.
+    pub fn capabilities(&self) -> DiscoveredCapabilities {
+        let listed = |name: &str| {
+            self.capabilities
+                .as_ref()
+                .is_some_and(|c| c.iter().any(|c| c == name))
+        };
+
+        DiscoveredCapabilities {
+            context_window: self
+                .max_model_len
+                .or(self.max_context_length)
+                .or(self.context_length)
+                .or(self.context_window),
+            tool_use: self
+                .capabilities
+                .as_ref()
+                .map(|_| listed("tool_use") || listed("tools")),
+            vision: if listed("vision") {
+                Some(true)
+            } else {
+                self.model_type.as_deref().map(|t| t == "vlm")
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ollama_show_capabilities() {
+        let show: OllamaShowResponse = serde_json::from_value(serde_json::json!({
+            "parameters": "stop \"<|eot_id|>\"\nnum_ctx 16384",
+            "template": "{{ if .Tools }}...{{ end }}",
+            "model_info": {
+                "general.architecture": "llama",
+                "llama.context_length": 131072
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            show.capabilities(),
+            DiscoveredCapabilities {
+                context_window: Some(16_384),
+                tool_use: Some(true),
+                vision: Some(false),
+            }
+        );
+
+        // Without num_ctx the server default applies, not the trained length
+        let show: OllamaShowResponse = serde_json::from_value(serde_json::json!({
+            "capabilities": ["completion", "vision"],
+            "template": "{{ .Prompt }}",
+            "model_info": { "general.architecture": "gemma3", "gemma3.context_length": 131072 },
+            "projector_info": { "clip.has_vision_encoder": true }
+        }))
+        .unwrap();
+        assert_eq!(
+            show.capabilities(),
+            DiscoveredCapabilities {
+                context_window: None,
+                tool_use: Some(false),
+                vision: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn test_model_entry_capabilities() {
+        let entries: ModelsResponse = serde_json::from_value(serde_json::json!({
+            "data": [
+                { "id": "Qwen/Qwen2.5-7B-Instruct", "object": "model", "max_model_len": 32768 },
+                { "id": "qwen2-vl-7b", "type": "vlm", "max_context_length": 32768, "capabilities": ["tool_use"] },
+                { "id": "gpt-4o" }
+            ]
+        }))
+        .unwrap();
+
+        let caps: Vec<_> = entries.data.iter().map(ModelEntry::capabilities).collect();
+        assert_eq!(caps[0].context_window, Some(32_768));
+        assert_eq!((caps[0].tool_use, caps[0].vision), (None, None));
+        assert_eq!((caps[1].tool_use, caps[1].vision), (Some(true), Some(true)));
+        assert_eq!(caps[2], DiscoveredCapabilities::default());
+    }
+
+    #[tokio::test]
+    async fn test_discover_ollama_is_cached() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/show")
+            .match_body(mockito::Matcher::Json(serde_json::json!({ "model": "my-model" })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "capabilities": ["completion", "tools"],
+                    "parameters": "num_ctx 32768",
+                    "model_info": { "general.architecture": "qwen2", "qwen2.context_length": 32768 }
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Client::new();
+        let base_url = format!("{}/v1", server.url());
+        for _ in 0..2 {
+            let caps = discover(&client, &base_url, None, "my-model", DiscoverySource::Ollama)
+                .await
+                .unwrap();
+            assert_eq!(caps.context_window, Some(32_768));
+            assert_eq!(caps.tool_use, Some(true));
+        }
+        mock.assert_async().await;
+        assert_eq!(cached(&base_url, "my-model").unwrap().vision, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_discover_from_model_list() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/models")
+            .match_header("authorization", "Bearer key")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"object": "list", "data": [{"id": "served", "max_model_len": 65536}]}"#)
+            .create_async()
+            .await;
+
+        let client = Client::new();
+        let caps = discover(&client, &server.url(), Some("key"), "served", DiscoverySource::ModelList)
+            .await
+            .unwrap();
+        assert_eq!(caps.context_window, Some(65_536));
+
+        // A model missing from the listing is cached as reporting nothing
+        let caps = discover(&client, &server.url(), Some("key"), "other", DiscoverySource::ModelList)
+            .await
+            .unwrap();
+        assert_eq!(caps, DiscoveredCapabilities::default());
+    }
+
+    #[tokio::test]
+    async fn test_discover_unreachable_not_cached() {
+        let client = Client::new();
+        let base_url = "http://127.0.0.1:9/v1";
+        let caps = discover(&client, base_url, None, "llama3.2", DiscoverySource::Ollama).await;
+        assert!(caps.is_none());
+        assert!(cached(base_url, "llama3.2").is_none());
+    }
+}
@@ -0,0 +1,637 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Record-and-replay providers for deterministic tests.
+//!
+//! [`RecordingProvider`] wraps a real provider and appends every successful
+//! exchange, including the events of streamed responses, to a JSON
+//! [`Cassette`] file. [`ReplayProvider`] serves a cassette back without
+//! network access, so the agent, the TUI and pipelines can be tested against
+//! realistic transcripts.
+//!
+//! Requests are matched by their normalized messages (see
+//! [`normalize_messages`]): roles, text, tool calls and tool results with
+//! whitespace collapsed. Tool call ids, images and thinking are ignored, and
+//! so are the system prompt and tool list, which embed dates and paths.
+//! Identical requests are served in recorded order.
+//!
+//! # Environment Variables
+//!
+//! | Variable | Description |
+//! |----------|-------------|
+//! | `CODI_RECORD` | Record the exchanges of any provider to this cassette |
+//! | `CODI_PROVIDER=replay` | Serve responses from a cassette instead of an API |
+//! | `CODI_CASSETTE` | Cassette file read by the replay provider |
+//!
+//! ```bash
+//! CODI_RECORD=tests/cassettes/fix-bug.json codi -P "Fix the failing test"
+//! CODI_PROVIDER=replay CODI_CASSETTE=tests/cassettes/fix-bug.json codi -P "Fix the failing test"
+//! ```
+//!
+//! Failed requests aren't recorded; a retried request is recorded once it succeeds.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ProviderError;
+use crate::types::{
+    BoxedProvider, ChatOptions, ContentBlock, ContentBlockType, Message, MessageContent, ModelInfo,
+    ModelPricing, Provider, ProviderResponse, Role, StopReason, StreamEvent, TokenUsage, ToolDefinition,
+};
+
+/// Environment variable naming the cassette to record to.
+pub const RECORD_ENV: &str = "CODI_RECORD";
+
+/// Environment variable naming the cassette to replay.
+pub const CASSETTE_ENV: &str = "CODI_CASSETTE";
+
+/// Cassettes this process has started recording; later recorders append.
+static RECORDING: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Recorded exchanges with a provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cassette {
+    /// Name of the recorded provider.
+    pub provider: String,
+    /// Model of the recorded provider.
+    pub model: String,
+    /// Context window of the recorded model.
+    pub context_window: u32,
+    #[serde(default)]
+    pub supports_tool_use: bool,
+    #[serde(default)]
+    pub supports_vision: bool,
+    #[serde(default)]
+    pub supports_structured_output: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPricing>,
+    /// Exchanges in the order they happened.
+    #[serde(default)]
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Empty cassette describing `provider`.
+    pub fn for_provider(provider: &dyn Provider) -> Self {
+        Self {
+            provider: provider.name().to_string(),
+            model: provider.model().to_string(),
+            context_window: provider.context_window(),
+            supports_tool_use: provider.supports_tool_use(),
+            supports_vision: provider.supports_vision(),
+            supports_structured_output: provider.supports_structured_output(),
+            pricing: provider.pricing(),
+            interactions: Vec::new(),
+        }
+    }
+
+    /// Load a cassette file.
+    pub fn load(path: &Path) -> Result<Self, ProviderError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            ProviderError::NotConfigured(format!("Cannot read cassette {}: {}", path.display(), e))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            ProviderError::ParseError(format!("Invalid cassette {}: {}", path.display(), e))
+        })
+    }
+
+    /// Write the cassette, creating parent directories.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+/// Kind of provider request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestKind {
+    /// `chat` or `stream_chat`; either can replay the other.
+    Chat,
+    /// `chat_structured`.
+    Structured,
+}
+
+/// One recorded request and its response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Interaction {
+    pub kind: RequestKind,
+    /// Normalized request messages, used for matching.
+    pub messages: Vec<String>,
+    /// Events of a streamed response.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<RecordedEvent>,
+    pub response: ProviderResponse,
+}
+
+impl Interaction {
+    /// Events to replay for a streaming request. Responses recorded without
+    /// streaming are replayed as a single text delta.
+    fn stream_events(&self) -> Vec<StreamEvent> {
+        if !self.events.is_empty() {
+            return self.events.iter().map(RecordedEvent::to_stream_event).collect();
+        }
+
+        let mut events = Vec::new();
+        if !self.response.content.is_empty() {
+            events.push(StreamEvent::TextDelta(self.response.content.clone()));
+        }
+        if let Some(ref usage) = self.response.usage {
+            events.push(StreamEvent::Usage(usage.clone()));
+        }
+        events.push(StreamEvent::Done(self.response.stop_reason));
+        events
+    }
+}
+
+/// A recorded [`StreamEvent`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum RecordedEvent {
+    TextDelta { text: String },
+    ReasoningDelta { text: String },
+    ToolUseStart { id: String, name: String },
+    ToolInputDelta { json: String },
+    ToolUseEnd,
+    Usage { usage: TokenUsage },
+    Done { stop_reason: StopReason },
+    Error { message: String },
+}
+
+impl RecordedEvent {
+    /// Record a stream event. Retry and fallback notices aren't part of the exchange.
+    pub fn from_stream_event(event: &StreamEvent) -> Option<Self> {
+        Some(match event {
+            StreamEvent::TextDelta(text) => Self::TextDelta { text: text.clone() },
+            StreamEvent::ReasoningDelta(text) => Self::ReasoningDelta { text: text.clone() },
+            StreamEvent::ToolUseStart { id, name } => Self::ToolUseStart {
+                id: id.clone(),
+                name: name.clone(),
+            },
+            StreamEvent::ToolInputDelta(json) => Self::ToolInputDelta { json: json.clone() },
+            StreamEvent::ToolUseEnd => Self::ToolUseEnd,
+            StreamEvent::Usage(usage) => Self::Usage { usage: usage.clone() },
+            StreamEvent::Done(stop_reason) => Self::Done { stop_reason: *stop_reason },
+            StreamEvent::Error(message) => Self::Error { message: message.clone() },
+            StreamEvent::Retrying { .. } | StreamEvent::Fallback { .. } => return None,
+        })
+    }
+
+    /// The stream event to replay.
+    pub fn to_stream_event(&self) -> StreamEvent {
+        match self {
+            Self::TextDelta { text } => StreamEvent::TextDelta(text.clone()),
+            Self::ReasoningDelta { text } => StreamEvent::ReasoningDelta(text.clone()),
+            Self::ToolUseStart { id, name } => StreamEvent::ToolUseStart {
+                id: id.clone(),
+                name: name.clone(),
+            },
+            Self::ToolInputDelta { json } => StreamEvent::ToolInputDelta(json.clone()),
+            Self::ToolUseEnd => StreamEvent::ToolUseEnd,
+            Self::Usage { usage } => StreamEvent::Usage(usage.clone()),
+            Self::Done { stop_reason } => StreamEvent::Done(*stop_reason),
+            Self::Error { message } => StreamEvent::Error(message.clone()),
+        }
+    }
+}
+
+/// Normalize messages for matching: one `role: content` line per message.
+pub fn normalize_messages(messages: &[Message]) -> Vec<String> {
+    messages
+        .iter()
+        .map(|msg| {
+            let role = match msg.role {
+                Role::User => "user",
+                Role::Assistant => "assistant",
+                Role::System => "system",
+            };
+            let parts: Vec<String> = match &msg.content {
+                MessageContent::Text(text) => vec![collapse_whitespace(text)],
+                MessageContent::Blocks(blocks) => blocks.iter().filter_map(normalize_block).collect(),
+            };
+            let parts: Vec<String> = parts.into_iter().filter(|p| !p.is_empty()).collect();
+            format!("{}: {}", role, parts.join(" | "))
+        })
+        .collect()
+}
+
+fn normalize_block(block: &ContentBlock) -> Option<String> {
+    match block.block_type {
+        ContentBlockType::Text => block.text.as_deref().map(collapse_whitespace),
+        ContentBlockType::ToolUse => Some(format!(
+            "tool_use {} {}",
+            block.name.as_deref().unwrap_or_default(),
+            block.input.as_ref().map(|i| i.to_string()).unwrap_or_default()
+        )),
+        ContentBlockType::ToolResult => Some(format!(
+            "tool_result {}",
+            collapse_whitespace(block.content.as_deref().unwrap_or_default())
+        )),
+        ContentBlockType::Image => Some("[image]".to_string()),
+        ContentBlockType::Thinking => None,
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Provider that records the exchanges of another provider to a cassette.
+///
+/// The first recorder of a path in a process starts a new cassette; other
+/// recorders of the same path (summarization, sub-agents) append to it.
+pub struct RecordingProvider {
+    inner: BoxedProvider,
+    path: PathBuf,
+}
+
+impl RecordingProvider {
+    /// Record the exchanges of `inner` to the cassette at `path`.
+    pub fn new(inner: BoxedProvider, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+        }
+    }
+
+    /// Append an interaction to the cassette. Recording failures are logged,
+    /// not returned, so they never break the session being recorded.
+    fn record(&self, interaction: Interaction) {
+        let mut started = RECORDING.lock().unwrap_or_else(|e| e.into_inner());
+        let cassette = if started.contains(&self.path) {
+            Cassette::load(&self.path)
+        } else {
+            Ok(Cassette::for_provider(self.inner.as_ref()))
+        };
+
+        let result = cassette.map_err(|e| e.to_string()).and_then(|mut cassette| {
+            cassette.interactions.push(interaction);
+            cassette.save(&self.path).map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(()) => {
+                started.insert(self.path.clone());
+            }
+            Err(e) => tracing::warn!("Failed to record to cassette {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for RecordingProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let response = self.inner.chat(messages, tools, system_prompt, options).await?;
+        self.record(Interaction {
+            kind: RequestKind::Chat,
+            messages: normalize_messages(messages),
+            events: Vec::new(),
+            response: response.clone(),
+        });
+        Ok(response)
+    }
+
+    async fn stream_chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+        on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let forward = {
+            let events = events.clone();
+            Box::new(move |event: StreamEvent| {
+                if let Some(recorded) = RecordedEvent::from_stream_event(&event) {
+                    events.lock().unwrap_or_else(|e| e.into_inner()).push(recorded);
+                }
+                on_event(event);
+            })
+        };
+
+        let response = self
+            .inner
+            .stream_chat(messages, tools, system_prompt, options, forward)
+            .await?;
+        let events = std::mem::take(&mut *events.lock().unwrap_or_else(|e| e.into_inner()));
+        self.record(Interaction {
+            kind: RequestKind::Chat,
+            messages: normalize_messages(messages),
+            events,
+            response: response.clone(),
+        });
+        Ok(response)
+    }
+
+    fn supports_tool_use(&self) -> bool {
+        self.inner.supports_tool_use()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.inner.supports_structured_output()
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        schema: &serde_json::Value,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let response = self.inner.chat_structured(messages, system_prompt, schema).await?;
+        self.record(Interaction {
+            kind: RequestKind::Structured,
+            messages: normalize_messages(messages),
+            events: Vec::new(),
+            response: response.clone(),
+        });
+        Ok(response)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn context_window(&self) -> u32 {
+        self.inner.context_window()
+    }
+
+    fn pricing(&self) -> Option<ModelPricing> {
+        self.inner.pricing()
+    }
+
+    async fn discover_capabilities(&self) {
+        self.inner.discover_capabilities().await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        self.inner.list_models().await
+    }
+}
+
+/// Provider that serves recorded responses from a cassette.
+pub struct ReplayProvider {
+    cassette: Cassette,
+    /// Interactions already served, by index.
+    served: Mutex<Vec<bool>>,
+}
+
+impl ReplayProvider {
+    /// Replay `cassette`.
+    pub fn new(cassette: Cassette) -> Self {
+        let served = Mutex::new(vec![false; cassette.interactions.len()]);
+        Self { cassette, served }
+    }
+
+    /// Replay the cassette file at `path`.
+    pub fn load(path: &Path) -> Result<Self, ProviderError> {
+        Cassette::load(path).map(Self::new)
+    }
+
+    /// The first interaction not yet served that matches the request.
+    fn next(&self, kind: RequestKind, messages: &[Message]) -> Result<&Interaction, ProviderError> {
+        let key = normalize_messages(messages);
+        let mut served = self.served.lock().unwrap_or_else(|e| e.into_inner());
+
+        let index = self
+            .cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .position(|(i, interaction)| {
+                !served[i] && interaction.kind == kind && interaction.messages == key
+            })
+            .ok_or_else(|| {
+                ProviderError::api_message(format!(
+                    "No recorded response matches the request ending with {:?}",
+                    key.last().map(String::as_str).unwrap_or_default()
+                ))
+            })?;
+        served[index] = true;
+        Ok(&self.cassette.interactions[index])
+    }
+}
+
+#[async_trait]
+impl Provider for ReplayProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        _tools: Option<&[ToolDefinition]>,
+        _system_prompt: Option<&str>,
+        _options: &ChatOptions,
+    ) -> Result<ProviderResponse, ProviderError> {
+        Ok(self.next(RequestKind::Chat, messages)?.response.clone())
+    }
+
+    async fn stream_chat(
+        &self,
+        messages: &[Message],
+        _tools: Option<&[ToolDefinition]>,
+        _system_prompt: Option<&str>,
+        _options: &ChatOptions,
+        on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let interaction = self.next(RequestKind::Chat, messages)?;
+        for event in interaction.stream_events() {
+            on_event(event);
+        }
+        Ok(interaction.response.clone())
+    }
+
+    fn supports_tool_use(&self) -> bool {
+        self.cassette.supports_tool_use
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.cassette.supports_vision
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.cassette.supports_structured_output
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        _system_prompt: Option<&str>,
+        _schema: &serde_json::Value,
+    ) -> Result<ProviderResponse, ProviderError> {
+        Ok(self.next(RequestKind::Structured, messages)?.response.clone())
+    }
+
+    fn name(&self) -> &str {
+        &self.cassette.provider
+    }
+
+    fn model(&self) -> &str {
+        &self.cassette.model
+    }
+
+    fn context_window(&self) -> u32 {
+        self.cassette.context_window
+    }
+
+    fn pricing(&self) -> Option<ModelPricing> {
+        self.cassette.pricing.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ToolCall;
+
+    /// Streams a fixed reply and calls `read_file`.
+    struct FakeProvider;
+
+    #[async_trait]
+    impl Provider for FakeProvider {
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolDefinition]>,
+            _system_prompt: Option<&str>,
+            _options: &ChatOptions,
+        ) -> Result<ProviderResponse, ProviderError> {
+            Ok(ProviderResponse::text("plain reply"))
+        }
+
+        async fn stream_chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolDefinition]>,
+            _system_prompt: Option<&str>,
+            _options: &ChatOptions,
+            on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+        ) -> Result<ProviderResponse, ProviderError> {
+            on_event(StreamEvent::TextDelta("Let me ".to_string()));
+            on_event(StreamEvent::TextDelta("look.".to_string()));
+            on_event(StreamEvent::Done(StopReason::ToolUse));
+            let mut response = ProviderResponse::text("Let me look.");
+            response.stop_reason = StopReason::ToolUse;
+            response.tool_calls = vec![ToolCall {
+                id: "toolu_1".to_string(),
+                name: "read_file".to_string(),
+                input: serde_json::json!({ "file_path": "src/lib.rs" }),
+            }];
+            Ok(response)
+        }
+
+        fn supports_tool_use(&self) -> bool {
+            true
+        }
+
+        fn name(&self) -> &str {
+            "Fake"
+        }
+
+        fn model(&self) -> &str {
+            "fake-1"
+        }
+    }
+
+    type EventHandler = Box<dyn Fn(StreamEvent) + Send + Sync>;
+
+    fn collect_events() -> (Arc<Mutex<Vec<String>>>, EventHandler) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let on_event = Box::new(move |event: StreamEvent| {
+            if let StreamEvent::TextDelta(text) = event {
+                sink.lock().unwrap().push(text);
+            }
+        });
+        (seen, on_event)
+    }
+
+    #[test]
+    fn test_normalize_messages() {
+        let messages = [
+            Message::user("Fix   the\n bug"),
+            Message::with_blocks(
+                Role::Assistant,
+                vec![
+                    ContentBlock::thinking_with_signature("hmm", "sig"),
+                    ContentBlock::text("Reading."),
+                    ContentBlock::tool_use("toolu_9", "read_file", serde_json::json!({ "file_path": "a.rs" })),
+                ],
+            ),
+            Message::with_blocks(Role::User, vec![ContentBlock::tool_result("toolu_9", "fn  main() {}", false)]),
+        ];
+
+        assert_eq!(
+            normalize_messages(&messages),
+            vec![
+                "user: Fix the bug",
+                "assistant: Reading. | tool_use read_file {\"file_path\":\"a.rs\"}",
+                "user: tool_result fn main() {}",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassettes").join("session.json");
+        let messages = [Message::user("Where is main?")];
+
+        let recorder = RecordingProvider::new(Box::new(FakeProvider), &path);
+        let (_, on_event) = collect_events();
+        let recorded = recorder
+            .stream_chat(&messages, None, None, &ChatOptions::default(), on_event)
+            .await
+            .unwrap();
+        recorder.chat(&messages, None, None, &ChatOptions::default()).await.unwrap();
+
+        let replay = ReplayProvider::load(&path).unwrap();
+        assert_eq!(replay.name(), "Fake");
+        assert_eq!(replay.model(), "fake-1");
+        assert!(replay.supports_tool_use());
+
+        let (seen, on_event) = collect_events();
+        let replayed = replay
+            .stream_chat(&messages, None, None, &ChatOptions::default(), on_event)
+            .await
+            .unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec!["Let me ", "look."]);
+        assert_eq!(replayed.content, recorded.content);
+        assert_eq!(replayed.tool_calls[0].input, recorded.tool_calls[0].input);
+
+        // Identical requests are served in recorded order; the second one was
+        // recorded without streaming and replays as one delta
+        let (seen, on_event) = collect_events();
+        replay.stream_chat(&messages, None, None, &ChatOptions::default(), on_event).await.unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec!["plain reply"]);
+
+        let err = replay.chat(&messages, None, None, &ChatOptions::default()).await.unwrap_err();
+        assert!(err.to_string().contains("No recorded response matches"));
+        let err = replay
+            .chat(&[Message::user("Something else")], None, None, &ChatOptions::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("user: Something else"));
+    }
+}
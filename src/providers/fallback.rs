@@ -0,0 +1,793 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Provider fallback chains.
+//!
+//! [`FallbackProvider`] holds an ordered list of providers. A request goes to
+//! the first one; when it fails with a non-retryable error, or its retries
+//! are exhausted, the request moves to the next model in the chain. Each
+//! member is wrapped in a [`RetryingProvider`], so transient failures are
+//! retried on the same model before falling back.
+//!
+//! Chains are configured with `fallbackModels` in the workspace config, an
+//! ordered list of model-map names (from `codi-models.yaml`) or
+//! `provider/model` pairs tried after the configured provider and model:
+//!
+//! ```json
+//! { "provider": "anthropic", "fallbackModels": ["openai/gpt-4o", "local-llama"] }
+//! ```
+//!
+//! Conversation history is adapted for each model: thinking signatures are
+//! only sent back to the model that produced them, and images and tool
+//! exchanges become text for models that don't support them.
+//!
+//! The chain reports the context window of the member that answered last,
+//! so the agent compacts for the model it is using. When a request falls
+//! back to a model with a smaller window, the oldest messages that don't fit
+//! are left out of that request.
+//!
+//! Streaming requests only fall back while nothing has been streamed, and a
+//! [`StreamEvent::Fallback`] notice tells the UI which model answers instead.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::config::ResolvedConfig;
+use crate::error::ProviderError;
+use crate::model_map::{create_provider_for_model, load_model_map, ModelMapConfig};
+use crate::session::context::{
+    estimate_message_tokens, estimate_messages_tokens, find_safe_start_index, ContextConfig,
+};
+use crate::types::{
+    BoxedProvider, ChatOptions, ContentBlock, ContentBlockType, Message, MessageContent, ModelInfo,
+    ModelPricing, Provider, ProviderConfig, ProviderResponse, StreamEvent, ToolDefinition,
+};
+
+use super::retry::{RetryConfig, RetryingProvider};
+use super::{api_key_from_env, create_provider, ProviderType};
+
+/// Build a chain from `primary` and the configured `fallbackModels`.
+///
+/// Entries are looked up in the model map first, then parsed as
+/// `provider/model`. Entries that can't be created (unknown names, missing
+/// API keys) are skipped with a warning so the primary still works.
+pub fn create_fallback_chain(primary: BoxedProvider, config: &ResolvedConfig) -> FallbackProvider {
+    let model_map = std::env::current_dir()
+        .ok()
+        .and_then(|dir| load_model_map(&dir).config);
+
+    let mut providers = vec![primary];
+    for entry in &config.fallback_models {
+        match create_fallback_member(entry, model_map.as_ref()) {
+            Ok(provider) => providers.push(provider),
+            Err(e) => tracing::warn!("Skipping fallback model {}: {}", entry, e),
+        }
+    }
+
+    FallbackProvider::new(providers, RetryConfig::from_config(config))
+}
+
+/// Create the provider for one `fallbackModels` entry.
+fn create_fallback_member(
+    entry: &str,
+    model_map: Option<&ModelMapConfig>,
+) -> Result<BoxedProvider, ProviderError> {
+    if let Some(definition) = model_map.and_then(|m| m.models.get(entry)) {
+        return create_provider_for_model(entry, definition)
+            .map_err(|e| ProviderError::NotConfigured(e.to_string()));
+    }
+
+    let (provider, model) = entry.split_once('/').ok_or_else(|| {
+        ProviderError::NotConfigured(format!(
+            "Unknown fallback model '{}': use a model-map name or provider/model",
+            entry
+        ))
+    })?;
+    let provider_type: ProviderType = provider
+        .parse()
+        .map_err(|_| ProviderError::NotConfigured(format!("Unknown provider: {}", provider)))?;
+
+    let config = ProviderConfig {
+        api_key: api_key_from_env(provider_type),
+        model: Some(model.to_string()),
+        ..Default::default()
+    };
+    create_provider(provider_type, config)
+}
+
+/// Short status line for a fallback, e.g. "Anthropic/claude-sonnet-4 failed; answering with OpenAI/gpt-4o".
+pub fn fallback_status(from: &str, to: &str) -> String {
+    format!("{} failed; answering with {}", from, to)
+}
+
+/// Provider that falls back through an ordered chain of providers.
+pub struct FallbackProvider {
+    providers: Vec<BoxedProvider>,
+    /// Member that answered the last request; reported as name and model.
+    active: AtomicUsize,
+    /// Member that produced each thinking signature.
+    signatures: Mutex<HashMap<String, usize>>,
+}
+
+impl FallbackProvider {
+    /// Create a chain from providers in order of preference.
+    ///
+    /// Each provider is wrapped with `retry` when it allows retries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `providers` is empty.
+    pub fn new(providers: Vec<BoxedProvider>, retry: RetryConfig) -> Self {
+        assert!(!providers.is_empty(), "fallback chain needs at least one provider");
+        let providers = providers
+            .into_iter()
+            .map(|p| -> BoxedProvider {
+                if retry.is_enabled() {
+                    Box::new(RetryingProvider::new(p, retry.clone()))
+                } else {
+                    p
+                }
+            })
+            .collect();
+
+        Self {
+            providers,
+            active: AtomicUsize::new(0),
+            signatures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Number of providers in the chain.
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// Whether the chain has no providers (never true for a constructed chain).
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    fn label(&self, index: usize) -> String {
+        let provider = &self.providers[index];
+        format!("{}/{}", provider.name(), provider.model())
+    }
+
+    fn primary(&self) -> &BoxedProvider {
+        &self.providers[0]
+    }
+
+    fn current(&self) -> &BoxedProvider {
+        &self.providers[self.active.load(Ordering::SeqCst)]
+    }
+
+    /// Record which member answered, and the signature of its thinking.
+    fn answered(&self, index: usize, response: &ProviderResponse) {
+        self.active.store(index, Ordering::SeqCst);
+        if let Some(ref signature) = response.reasoning_signature {
+            self.signatures.lock().unwrap().insert(signature.clone(), index);
+        }
+    }
+
+    fn log_fallback(&self, index: usize, error: &ProviderError) {
+        tracing::warn!(
+            "{} failed: {}; falling back to {}",
+            self.label(index),
+            error,
+            self.label(index + 1)
+        );
+    }
+
+    /// Error returned when every member failed.
+    ///
+    /// It is never retryable, since each member already retried.
+    fn exhausted(&self, index: usize, error: ProviderError) -> ProviderError {
+        if self.providers.len() == 1 && !error.is_retryable() {
+            return error;
+        }
+        ProviderError::api_message(format!(
+            "All fallback models failed; last error from {}: {}",
+            self.label(index),
+            error
+        ))
+    }
+
+    /// Adapt the conversation for the member at `index`, and fit it to its window.
+    fn adapt_messages<'a>(&self, index: usize, messages: &'a [Message]) -> Cow<'a, [Message]> {
+        let adapted = self.adapt_blocks(index, messages);
+        self.fit_window(index, adapted)
+    }
+
+    /// Drop the oldest messages that don't fit the member's context window.
+    ///
+    /// The history was compacted for the active member, so this only trims
+    /// when falling back to a member with a smaller window.
+    fn fit_window<'a>(&self, index: usize, messages: Cow<'a, [Message]>) -> Cow<'a, [Message]> {
+        let window = self.providers[index].context_window() as u64;
+        let limit = ContextConfig::for_model(window).summarization_threshold();
+        if estimate_messages_tokens(&messages) <= limit {
+            return messages;
+        }
+
+        // Keep the newest messages that fit, and always the last one
+        let mut start = messages.len().saturating_sub(1);
+        let mut tokens = messages.last().map(estimate_message_tokens).unwrap_or_default();
+        while start > 0 {
+            tokens += estimate_message_tokens(&messages[start - 1]);
+            if tokens > limit {
+                break;
+            }
+            start -= 1;
+        }
+        // Don't start on tool results whose calls were dropped
+        start += find_safe_start_index(&messages[start..]);
+
+        tracing::warn!(
+            "Dropping {} older messages to fit the {}-token window of {}",
+            start,
+            window,
+            self.label(index)
+        );
+        match messages {
+            Cow::Borrowed(messages) => Cow::Borrowed(&messages[start..]),
+            Cow::Owned(mut messages) => {
+                messages.drain(..start);
+                Cow::Owned(messages)
+            }
+        }
+    }
+
+    /// Rewrite content blocks the member at `index` can't take.
+    fn adapt_blocks<'a>(&self, index: usize, messages: &'a [Message]) -> Cow<'a, [Message]> {
+        let provider = &self.providers[index];
+        let signatures = self.signatures.lock().unwrap();
+        let adapter = BlockAdapter {
+            // Signatures from before the chain existed belong to the primary
+            keep_signature: |signature: &str| signatures.get(signature).copied().unwrap_or(0) == index,
+            vision: provider.supports_vision(),
+            tool_use: provider.supports_tool_use(),
+        };
+
+        if !messages.iter().any(|m| adapter.needs_adaptation(m)) {
+            return Cow::Borrowed(messages);
+        }
+        Cow::Owned(messages.iter().map(|m| adapter.adapt(m)).collect())
+    }
+
+    fn member_tools<'a>(
+        &self,
+        index: usize,
+        tools: Option<&'a [ToolDefinition]>,
+    ) -> Option<&'a [ToolDefinition]> {
+        tools.filter(|_| self.providers[index].supports_tool_use())
+    }
+}
+
+/// Rewrites content blocks a model can't take.
+struct BlockAdapter<F: Fn(&str) -> bool> {
+    keep_signature: F,
+    vision: bool,
+    tool_use: bool,
+}
+
+impl<F: Fn(&str) -> bool> BlockAdapter<F> {
+    fn needs_adaptation(&self, message: &Message) -> bool {
+        message
+            .as_blocks()
+            .is_some_and(|blocks| blocks.iter().any(|b| !matches!(self.adapt_block(b), Adapted::Same)))
+    }
+
+    fn adapt(&self, message: &Message) -> Message {
+        let Some(blocks) = message.as_blocks() else {
+            return message.clone();
+        };
+        let blocks = blocks
+            .iter()
+            .filter_map(|b| match self.adapt_block(b) {
+                Adapted::Same => Some(b.clone()),
+                Adapted::Replaced(block) => Some(*block),
+                Adapted::Dropped => None,
+            })
+            .collect();
+        Message {
+            content: MessageContent::Blocks(blocks),
+            ..message.clone()
+        }
+    }
+
+    fn adapt_block(&self, block: &ContentBlock) -> Adapted {
+        match block.block_type {
+            ContentBlockType::Thinking => match block.signature.as_deref() {
+                Some(signature) if !(self.keep_signature)(signature) => Adapted::Dropped,
+                _ => Adapted::Same,
+            },
+            ContentBlockType::Image if !self.vision => Adapted::Replaced(Box::new(ContentBlock::text("[image omitted]"))),
+            ContentBlockType::ToolUse if !self.tool_use => Adapted::Replaced(Box::new(ContentBlock::text(format!(
+                "[Called tool {} with {}]",
+                block.name.as_deref().unwrap_or_default(),
+                block.input.as_ref().map(|i| i.to_string()).unwrap_or_default()
+            )))),
+            ContentBlockType::ToolResult if !self.tool_use => Adapted::Replaced(Box::new(ContentBlock::text(format!(
+                "[Tool result{}]\n{}",
+                if block.is_error == Some(true) { " (error)" } else { "" },
+                block.content.as_deref().unwrap_or_default()
+            )))),
+            ContentBlockType::ToolResult if !self.vision && block.image.is_some() => {
+                Adapted::Replaced(Box::new(ContentBlock {
+                    image: None,
+                    ..block.clone()
+                }))
+            }
+            _ => Adapted::Same,
+        }
+    }
+}
+
+enum Adapted {
+    Same,
+    Replaced(Box<ContentBlock>),
+    Dropped,
+}
+
+#[async_trait]
+impl Provider for FallbackProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let mut index = 0;
+        loop {
+            let adapted = self.adapt_messages(index, messages);
+            let tools = self.member_tools(index, tools);
+            let error = match self.providers[index].chat(&adapted, tools, system_prompt, options).await {
+                Ok(response) => {
+                    self.answered(index, &response);
+                    return Ok(response);
+                }
+                Err(e) => e,
+            };
+
+            if index + 1 == self.providers.len() {
+                return Err(self.exhausted(index, error));
+            }
+            self.log_fallback(index, &error);
+            index += 1;
+        }
+    }
+
+    async fn stream_chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+        on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let on_event: Arc<dyn Fn(StreamEvent) + Send + Sync> = Arc::from(on_event);
+        let mut index = 0;
+
+        loop {
+            let streamed = Arc::new(AtomicBool::new(false));
+            let forward = {
+                let on_event = on_event.clone();
+                let streamed = streamed.clone();
+                Box::new(move |event: StreamEvent| {
+                    if matches!(
+                        event,
+                        StreamEvent::TextDelta(_)
+                            | StreamEvent::ReasoningDelta(_)
+                            | StreamEvent::ToolUseStart { .. }
+                            | StreamEvent::ToolInputDelta(_)
+                    ) {
+                        streamed.store(true, Ordering::SeqCst);
+                    }
+                    on_event(event);
+                })
+            };
+
+            let adapted = self.adapt_messages(index, messages);
+            let tools = self.member_tools(index, tools);
+            let error = match self.providers[index]
+                .stream_chat(&adapted, tools, system_prompt, options, forward)
+                .await
+            {
+                Ok(response) => {
+                    self.answered(index, &response);
+                    return Ok(response);
+                }
+                Err(e) => e,
+            };
+
+            // Partial output has already been shown; another model would repeat it.
+            if streamed.load(Ordering::SeqCst) {
+                return Err(error);
+            }
+            if index + 1 == self.providers.len() {
+                return Err(self.exhausted(index, error));
+            }
+
+            self.log_fallback(index, &error);
+            on_event(StreamEvent::Fallback {
+                from: self.label(index),
+                to: self.label(index + 1),
+                reason: error.to_string(),
+            });
+            index += 1;
+        }
+    }
+
+    fn supports_tool_use(&self) -> bool {
+        self.primary().supports_tool_use()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.primary().supports_vision()
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.providers.iter().any(|p| p.supports_structured_output())
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        schema: &serde_json::Value,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let candidates: Vec<usize> = (0..self.providers.len())
+            .filter(|&i| self.providers[i].supports_structured_output())
+            .collect();
+
+        let mut last = None;
+        for (position, &index) in candidates.iter().enumerate() {
+            let adapted = self.adapt_messages(index, messages);
+            match self.providers[index].chat_structured(&adapted, system_prompt, schema).await {
+                Ok(response) => {
+                    self.answered(index, &response);
+                    return Ok(response);
+                }
+                Err(e) => {
+                    if let Some(&next) = candidates.get(position + 1) {
+                        tracing::warn!("{} failed: {}; falling back to {}", self.label(index), e, self.label(next));
+                    }
+                    last = Some((index, e));
+                }
+            }
+        }
+
+        match last {
+            Some((index, error)) => Err(self.exhausted(index, error)),
+            None => self.primary().chat_structured(messages, system_prompt, schema).await,
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.current().name()
+    }
+
+    fn model(&self) -> &str {
+        self.current().model()
+    }
+
+    fn context_window(&self) -> u32 {
+        // Smaller fallbacks trim their own requests, see `fit_window`
+        self.current().context_window()
+    }
+
+    fn pricing(&self) -> Option<ModelPricing> {
+        self.current().pricing()
+    }
+
+    async fn discover_capabilities(&self) {
+        for provider in &self.providers {
+            provider.discover_capabilities().await;
+        }
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        self.primary().list_models().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ImageMediaType, ImageSource, Role};
+    use std::sync::atomic::AtomicU32;
+
+    /// Provider that fails with a fixed error, or answers and records what it was sent.
+    struct StubProvider {
+        name: &'static str,
+        error: Option<fn() -> ProviderError>,
+        tool_use: bool,
+        signature: Option<&'static str>,
+        context_window: u32,
+        calls: Arc<AtomicU32>,
+        sent: Arc<Mutex<Vec<Message>>>,
+    }
+
+    impl StubProvider {
+        fn ok(name: &'static str) -> Self {
+            Self {
+                name,
+                error: None,
+                tool_use: true,
+                signature: None,
+                context_window: 200_000,
+                calls: Arc::new(AtomicU32::new(0)),
+                sent: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        fn failing(name: &'static str, error: fn() -> ProviderError) -> Self {
+            Self {
+                error: Some(error),
+                ..Self::ok(name)
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Provider for StubProvider {
+        async fn chat(
+            &self,
+            messages: &[Message],
+            tools: Option<&[ToolDefinition]>,
+            system_prompt: Option<&str>,
+            options: &ChatOptions,
+        ) -> Result<ProviderResponse, ProviderError> {
+            self.stream_chat(messages, tools, system_prompt, options, Box::new(|_| {}))
+                .await
+        }
+
+        async fn stream_chat(
+            &self,
+            messages: &[Message],
+            _tools: Option<&[ToolDefinition]>,
+            _system_prompt: Option<&str>,
+            _options: &ChatOptions,
+            _on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+        ) -> Result<ProviderResponse, ProviderError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            *self.sent.lock().unwrap() = messages.to_vec();
+            if let Some(error) = self.error {
+                return Err(error());
+            }
+            let mut response = ProviderResponse::text(format!("from {}", self.name));
+            response.reasoning_signature = self.signature.map(str::to_string);
+            Ok(response)
+        }
+
+        fn supports_tool_use(&self) -> bool {
+            self.tool_use
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn model(&self) -> &str {
+            "model"
+        }
+
+        fn context_window(&self) -> u32 {
+            self.context_window
+        }
+    }
+
+    fn fast_retry(max_attempts: u32) -> RetryConfig {
+        RetryConfig {
+            max_attempts,
+            initial_delay_ms: 1,
+            max_delay_ms: 2,
+            max_elapsed_ms: 10_000,
+        }
+    }
+
+    fn overloaded() -> ProviderError {
+        ProviderError::api("overloaded", 529)
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_after_exhausted_retries() {
+        let primary = StubProvider::failing("Anthropic", overloaded);
+        let primary_calls = primary.calls.clone();
+        let chain = FallbackProvider::new(
+            vec![Box::new(primary), Box::new(StubProvider::ok("OpenAI"))],
+            fast_retry(3),
+        );
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let response = chain
+            .stream_chat(
+                &[Message::user("Hi")],
+                None,
+                None,
+                &ChatOptions::default(),
+                Box::new(move |e| sink.lock().unwrap().push(e)),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "from OpenAI");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 3);
+        assert_eq!(chain.name(), "OpenAI");
+
+        let events = events.lock().unwrap();
+        assert!(matches!(
+            events.last(),
+            Some(StreamEvent::Fallback { from, to, .. }) if from == "Anthropic/model" && to == "OpenAI/model"
+        ));
+        assert_eq!(
+            events.iter().filter(|e| matches!(e, StreamEvent::Retrying { .. })).count(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_falls_back_immediately() {
+        let primary = StubProvider::failing("Anthropic", || ProviderError::AuthError("bad key".to_string()));
+        let primary_calls = primary.calls.clone();
+        let chain = FallbackProvider::new(
+            vec![Box::new(primary), Box::new(StubProvider::ok("Gemini"))],
+            fast_retry(3),
+        );
+
+        let response = chain.chat(&[Message::user("Hi")], None, None, &ChatOptions::default()).await.unwrap();
+        assert_eq!(response.content, "from Gemini");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_all_failed_is_not_retryable() {
+        let chain = FallbackProvider::new(
+            vec![
+                Box::new(StubProvider::failing("Anthropic", overloaded)),
+                Box::new(StubProvider::failing("OpenAI", overloaded)),
+            ],
+            RetryConfig::disabled(),
+        );
+
+        let err = chain.chat(&[Message::user("Hi")], None, None, &ChatOptions::default()).await.unwrap_err();
+        assert!(!err.is_retryable());
+        assert!(err.to_string().contains("OpenAI/model"));
+    }
+
+    #[tokio::test]
+    async fn test_messages_adapted_per_model() {
+        let mut primary = StubProvider::failing("Anthropic", overloaded);
+        primary.signature = Some("sig-anthropic");
+        let mut fallback = StubProvider::ok("Ollama");
+        fallback.tool_use = false;
+        let sent = fallback.sent.clone();
+        let chain = FallbackProvider::new(vec![Box::new(primary), Box::new(fallback)], RetryConfig::disabled());
+
+        let image = ImageSource::new_base64(ImageMediaType::Png, "iVBOR".to_string());
+        let messages = vec![
+            Message::with_blocks(Role::User, vec![ContentBlock::text("Look"), ContentBlock::image(image)]),
+            Message::with_blocks(
+                Role::Assistant,
+                vec![
+                    ContentBlock::thinking_with_signature("Reading", "sig-anthropic"),
+                    ContentBlock::tool_use("call_1", "read_file", serde_json::json!({ "path": "a.rs" })),
+                ],
+            ),
+            Message::with_blocks(Role::User, vec![ContentBlock::tool_result("call_1", "fn main() {}", false)]),
+        ];
+
+        chain.chat(&messages, None, None, &ChatOptions::default()).await.unwrap();
+
+        let sent = sent.lock().unwrap();
+        let blocks: Vec<&ContentBlock> = sent.iter().flat_map(|m| m.as_blocks().unwrap()).collect();
+        assert!(blocks.iter().all(|b| b.block_type == ContentBlockType::Text));
+        let texts: Vec<&str> = blocks.iter().filter_map(|b| b.text.as_deref()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Look",
+                "[image omitted]",
+                r#"[Called tool read_file with {"path":"a.rs"}]"#,
+                "[Tool result]\nfn main() {}"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_signatures_only_sent_to_their_model() {
+        let mut fallback = StubProvider::ok("Gemini");
+        fallback.signature = Some("sig-gemini");
+        let chain = FallbackProvider::new(
+            vec![
+                Box::new(StubProvider::failing("Anthropic", || ProviderError::AuthError("bad key".to_string()))),
+                Box::new(fallback),
+            ],
+            RetryConfig::disabled(),
+        );
+        chain.chat(&[Message::user("Hi")], None, None, &ChatOptions::default()).await.unwrap();
+
+        let messages = vec![Message::with_blocks(
+            Role::Assistant,
+            vec![
+                ContentBlock::thinking_with_signature("", "sig-gemini"),
+                ContentBlock::thinking_with_signature("Earlier", "sig-anthropic"),
+                ContentBlock::text("Done"),
+            ],
+        )];
+
+        let for_primary = chain.adapt_messages(0, &messages);
+        let kept: Vec<_> = for_primary[0].as_blocks().unwrap().iter().filter_map(|b| b.signature.as_deref()).collect();
+        assert_eq!(kept, vec!["sig-anthropic"]);
+
+        let for_gemini = chain.adapt_messages(1, &messages);
+        let kept: Vec<_> = for_gemini[0].as_blocks().unwrap().iter().filter_map(|b| b.signature.as_deref()).collect();
+        assert_eq!(kept, vec!["sig-gemini"]);
+    }
+
+    #[tokio::test]
+    async fn test_small_fallback_only_trims_its_own_requests() {
+        let primary = StubProvider::failing("Anthropic", overloaded);
+        let primary_sent = primary.sent.clone();
+        let mut fallback = StubProvider::ok("Ollama");
+        fallback.context_window = 1_000;
+        let fallback_sent = fallback.sent.clone();
+        let chain = FallbackProvider::new(vec![Box::new(primary), Box::new(fallback)], RetryConfig::disabled());
+
+        // The primary's window is reported until a fallback answers
+        assert_eq!(chain.context_window(), 200_000);
+
+        // ~250 tokens each, so only the last three fit the 800-token threshold
+        let messages: Vec<Message> = (0..6).map(|i| Message::user(format!("{}{}", i, "x".repeat(1_000)))).collect();
+        chain.chat(&messages, None, None, &ChatOptions::default()).await.unwrap();
+
+        assert_eq!(primary_sent.lock().unwrap().len(), 6);
+        let sent = fallback_sent.lock().unwrap();
+        assert_eq!(sent.len(), 3);
+        assert!(sent[0].as_text().unwrap().starts_with('3'));
+        assert_eq!(chain.context_window(), 1_000);
+    }
+
+    #[test]
+    fn test_create_fallback_member() {
+        let mut model_map = ModelMapConfig::default();
+        model_map.models.insert(
+            "local".to_string(),
+            crate::model_map::ModelDefinition {
+                provider: "ollama".to_string(),
+                model: "llama3.2".to_string(),
+                description: None,
+                max_tokens: None,
+                temperature: None,
+                base_url: None,
+                endpoint_id: None,
+                runpod_api: None,
+                openai_api: None,
+                thinking_budget: None,
+                reasoning_effort: None,
+            },
+        );
+
+        let provider = create_fallback_member("local", Some(&model_map)).unwrap();
+        assert_eq!((provider.name(), provider.model()), ("Ollama", "llama3.2"));
+
+        let provider = create_fallback_member("ollama/qwen2.5", None).unwrap();
+        assert_eq!(provider.model(), "qwen2.5");
+
+        assert!(create_fallback_member("nope", Some(&model_map)).is_err());
+        assert!(create_fallback_member("nope/model", None).is_err());
+    }
+
+    #[test]
+    fn test_fallback_status() {
+        assert_eq!(
+            fallback_status("Anthropic/claude-sonnet-4", "OpenAI/gpt-4o"),
+            "Anthropic/claude-sonnet-4 failed; answering with OpenAI/gpt-4o"
+        );
+    }
+}
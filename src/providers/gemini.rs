@@ -0,0 +1,1225 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Google Gemini provider implementation.
+//!
+//! This module provides a [`Provider`] implementation for Google's Gemini
+//! models using the native `generateContent` and `streamGenerateContent`
+//! endpoints of the Gemini API, rather than its OpenAI compatibility layer.
+//!
+//! # Features
+//!
+//! - Streaming chat completions with Server-Sent Events (SSE)
+//! - Function calling with thought signatures preserved across tool calls
+//! - Vision/image analysis support
+//! - Thinking (reasoning) summaries and thinking budgets
+//! - Structured output with JSON schemas
+//! - Token usage tracking with cached and thinking tokens
+//!
+//! # API Reference
+//!
+//! See [Gemini API](https://ai.google.dev/api/generate-content) for details.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "telemetry")]
+use tracing::debug;
+
+#[cfg(feature = "telemetry")]
+use crate::telemetry::metrics::GLOBAL_METRICS;
+
+use crate::error::ProviderError;
+use crate::types::{
+    ChatOptions, ContentBlock, ContentBlockType, ImageMediaType, ImageSource, Message, MessageContent,
+    ModelCapabilities, ModelInfo, ModelPricing, Provider, ProviderConfig, ProviderResponse, Role,
+    StopReason, StreamEvent, TokenUsage, ToolCall, ToolChoice, ToolDefinition,
+};
+
+use super::retry::with_retry_after;
+use super::unsupported_option;
+
+/// Default max output tokens if not specified.
+const DEFAULT_MAX_TOKENS: u32 = 8192;
+
+/// Default request timeout in seconds.
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Google Gemini provider.
+///
+/// Implements the [`Provider`] trait for Gemini models.
+pub struct GeminiProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    base_url: String,
+    max_tokens: u32,
+    temperature: Option<f32>,
+    /// Thinking token budget, if set and supported by the model.
+    thinking_budget: Option<u32>,
+}
+
+impl GeminiProvider {
+    /// Create a new Gemini provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - Gemini API key
+    /// * `model` - Model identifier (e.g., "gemini-2.5-flash")
+    /// * `base_url` - API base URL including the version (e.g., ".../v1beta")
+    /// * `config` - Additional configuration options
+    pub fn new(
+        api_key: impl Into<String>,
+        model: impl Into<String>,
+        base_url: impl Into<String>,
+        config: ProviderConfig,
+    ) -> Self {
+        let timeout = config
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to build HTTP client");
+
+        let model = model.into();
+        let thinking_budget = config
+            .thinking_budget
+            .filter(|_| Self::model_supports_thinking(&model));
+
+        Self {
+            client,
+            api_key: api_key.into(),
+            model,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            max_tokens: config.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            temperature: config.temperature,
+            thinking_budget,
+        }
+    }
+
+    /// Build the request body for `generateContent`.
+    fn build_request(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+    ) -> GeminiRequest {
+        let thinking_config = Self::model_supports_thinking(&self.model).then_some(ThinkingConfig {
+            include_thoughts: true,
+            thinking_budget: self.thinking_budget,
+        });
+
+        GeminiRequest {
+            contents: convert_messages(messages),
+            system_instruction: system_prompt
+                .filter(|s| !s.is_empty())
+                .map(|s| ApiContent {
+                    role: None,
+                    parts: vec![ApiPart::text(s)],
+                }),
+            tools: tools.filter(|t| !t.is_empty()).map(|t| {
+                vec![ApiTool {
+                    function_declarations: t.iter().map(|t| t.into()).collect(),
+                }]
+            }),
+            tool_config: options.tool_choice.as_ref().map(ToolConfig::from),
+            generation_config: GenerationConfig {
+                max_output_tokens: options.max_tokens.unwrap_or(self.max_tokens),
+                temperature: options.temperature.or(self.temperature),
+                stop_sequences: (!options.stop_sequences.is_empty()).then(|| options.stop_sequences.clone()),
+                thinking_config,
+                response_mime_type: None,
+                response_json_schema: None,
+            },
+        }
+    }
+
+    /// Build a request whose response must match a JSON schema.
+    fn build_structured_request(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        schema: &serde_json::Value,
+    ) -> GeminiRequest {
+        let mut request = self.build_request(messages, None, system_prompt, &ChatOptions::default());
+        request.generation_config.response_mime_type = Some("application/json".to_string());
+        request.generation_config.response_json_schema = Some(schema.clone());
+        request
+    }
+
+    /// Reject request options the Gemini API can't honor.
+    fn check_options(options: &ChatOptions, tools: Option<&[ToolDefinition]>) -> Result<(), ProviderError> {
+        options.check_tools(tools)?;
+        if options.parallel_tool_calls == Some(false) {
+            return Err(unsupported_option("Gemini", "disabling parallel tool calls"));
+        }
+        if !options.metadata.is_empty() {
+            return Err(unsupported_option("Gemini", "request metadata"));
+        }
+        Ok(())
+    }
+
+    /// Send a request to a model method and return the successful response.
+    async fn post(
+        &self,
+        method: &str,
+        request: &GeminiRequest,
+    ) -> Result<reqwest::Response, ProviderError> {
+        let response = self
+            .client
+            .post(format!("{}/models/{}:{}", self.base_url, self.model, method))
+            .header("x-goog-api-key", &self.api_key)
+            .header("content-type", "application/json")
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(with_retry_after(
+                self.handle_error_response(status.as_u16(), &error_text),
+                &headers,
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Send a non-streaming request and convert the response.
+    async fn send(&self, request: &GeminiRequest) -> Result<ProviderResponse, ProviderError> {
+        let start = Instant::now();
+
+        #[cfg(feature = "telemetry")]
+        debug!(model = %self.model, messages = request.contents.len(), "Sending chat request");
+
+        let result = self.post("generateContent", request).await;
+
+        #[cfg(feature = "telemetry")]
+        GLOBAL_METRICS.record_operation("gemini.chat", start.elapsed());
+        let _ = start;
+
+        let api_response: ApiResponse = result?
+            .json()
+            .await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+
+        let mut state = StreamState::default();
+        state.process(api_response, &|_| {})?;
+        let provider_response = state.into_response();
+
+        #[cfg(feature = "telemetry")]
+        if let Some(ref usage) = provider_response.usage {
+            GLOBAL_METRICS.record_tokens(usage.input_tokens as u64, usage.output_tokens as u64);
+        }
+
+        Ok(provider_response)
+    }
+
+    /// Get context window size for a model.
+    fn get_context_window(model: &str) -> u32 {
+        if model.contains("gemini-1.5-pro") {
+            2_097_152
+        } else if model.contains("gemini-1.5")
+            || model.contains("gemini-2")
+            || model.contains("gemini-3")
+        {
+            1_048_576
+        } else {
+            32_768
+        }
+    }
+
+    /// Get per-million-token pricing (USD) for a model, for prompts up to 200k tokens.
+    fn get_pricing(model: &str) -> Option<ModelPricing> {
+        let (input, output) = if model.contains("gemini-2.5-pro") {
+            (1.25, 10.0)
+        } else if model.contains("gemini-2.5-flash-lite") {
+            (0.10, 0.40)
+        } else if model.contains("gemini-2.5-flash") {
+            (0.30, 2.50)
+        } else if model.contains("gemini-2.0-flash-lite") {
+            (0.075, 0.30)
+        } else if model.contains("gemini-2.0-flash") {
+            (0.10, 0.40)
+        } else {
+            return None;
+        };
+
+        // Cached tokens cost 10% of input; implicit caching has no write cost
+        Some(ModelPricing::new(input, output).with_cache(input * 0.1, input))
+    }
+
+    /// Check if a model can think before answering.
+    fn model_supports_thinking(model: &str) -> bool {
+        model.contains("gemini-2.5") || model.contains("gemini-3")
+    }
+
+    /// Handle an error response from the API.
+    fn handle_error_response(&self, status_code: u16, body: &str) -> ProviderError {
+        let Ok(error) = serde_json::from_str::<ApiError>(body) else {
+            return ProviderError::api(body.to_string(), status_code);
+        };
+        let message = error.error.message;
+        match error.error.status.as_deref() {
+            Some("UNAUTHENTICATED") | Some("PERMISSION_DENIED") => ProviderError::AuthError(message),
+            Some("INVALID_ARGUMENT") if message.contains("API key") => ProviderError::AuthError(message),
+            Some("RESOURCE_EXHAUSTED") => ProviderError::RateLimited(message),
+            Some("UNAVAILABLE") => ProviderError::RateLimited("API overloaded".to_string()),
+            Some("NOT_FOUND") if message.contains("models/") => ProviderError::ModelNotFound(message),
+            _ => ProviderError::api(message, status_code),
+        }
+    }
+
+    /// Models known to work with the provider, used when listing fails.
+    fn known_models() -> Vec<ModelInfo> {
+        [
+            ("gemini-2.5-pro", "Gemini 2.5 Pro"),
+            ("gemini-2.5-flash", "Gemini 2.5 Flash"),
+            ("gemini-2.5-flash-lite", "Gemini 2.5 Flash-Lite"),
+            ("gemini-2.0-flash", "Gemini 2.0 Flash"),
+        ]
+        .into_iter()
+        .map(|(id, name)| ModelInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            provider: "Gemini".to_string(),
+            capabilities: ModelCapabilities {
+                vision: true,
+                tool_use: true,
+            },
+            context_window: Some(Self::get_context_window(id)),
+            pricing: Self::get_pricing(id),
+            deprecated: None,
+        })
+        .collect()
+    }
+}
+
+#[async_trait]
+impl Provider for GeminiProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+    ) -> Result<ProviderResponse, ProviderError> {
+        Self::check_options(options, tools)?;
+        let request = self.build_request(messages, tools, system_prompt, options);
+        self.send(&request).await
+    }
+
+    async fn stream_chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+        on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+    ) -> Result<ProviderResponse, ProviderError> {
+        Self::check_options(options, tools)?;
+        let request = self.build_request(messages, tools, system_prompt, options);
+        let start = Instant::now();
+
+        #[cfg(feature = "telemetry")]
+        debug!(model = %self.model, messages = messages.len(), "Sending streaming chat request");
+
+        let result = self.post("streamGenerateContent?alt=sse", &request).await;
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                #[cfg(feature = "telemetry")]
+                GLOBAL_METRICS.record_operation("gemini.stream_chat", start.elapsed());
+                return Err(e);
+            }
+        };
+
+        // Process SSE stream; every event is a partial GenerateContentResponse
+        let mut stream_state = StreamState::default();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| ProviderError::StreamError(e.to_string()))?;
+
+        for line in text.lines() {
+            let Some(data) = line.strip_prefix("data:") else {
+                continue;
+            };
+            match serde_json::from_str::<ApiResponse>(data.trim()) {
+                Ok(chunk) => stream_state.process(chunk, &on_event)?,
+                Err(e) => on_event(StreamEvent::Error(e.to_string())),
+            }
+        }
+
+        on_event(StreamEvent::Usage(stream_state.usage.clone()));
+        on_event(StreamEvent::Done(stream_state.stop_reason()));
+
+        let provider_response = stream_state.into_response();
+
+        // Record metrics
+        #[cfg(feature = "telemetry")]
+        {
+            GLOBAL_METRICS.record_operation("gemini.stream_chat", start.elapsed());
+            if let Some(ref usage) = provider_response.usage {
+                GLOBAL_METRICS.record_tokens(usage.input_tokens as u64, usage.output_tokens as u64);
+            }
+        }
+        let _ = start;
+
+        Ok(provider_response)
+    }
+
+    fn supports_tool_use(&self) -> bool {
+        true
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        schema: &serde_json::Value,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let request = self.build_structured_request(messages, system_prompt, schema);
+        self.send(&request).await
+    }
+
+    fn supports_vision(&self) -> bool {
+        // All Gemini models accept images
+        true
+    }
+
+    fn name(&self) -> &str {
+        "Gemini"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn context_window(&self) -> u32 {
+        Self::get_context_window(&self.model)
+    }
+
+    fn pricing(&self) -> Option<ModelPricing> {
+        Self::get_pricing(&self.model)
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        let response = self
+            .client
+            .get(format!("{}/models?pageSize=1000", self.base_url))
+            .header("x-goog-api-key", &self.api_key)
+            .send()
+            .await;
+
+        if let Ok(resp) = response {
+            if resp.status().is_success() {
+                if let Ok(models) = resp.json::<ModelsResponse>().await {
+                    return Ok(models
+                        .models
+                        .into_iter()
+                        .filter(|m| m.supported_generation_methods.iter().any(|g| g == "generateContent"))
+                        .map(|m| {
+                            let id = m.name.trim_start_matches("models/").to_string();
+                            ModelInfo {
+                                name: m.display_name.unwrap_or_else(|| id.clone()),
+                                provider: "Gemini".to_string(),
+                                capabilities: ModelCapabilities {
+                                    vision: true,
+                                    tool_use: true,
+                                },
+                                context_window: Some(
+                                    m.input_token_limit.unwrap_or_else(|| Self::get_context_window(&id)),
+                                ),
+                                pricing: Self::get_pricing(&id),
+                                deprecated: None,
+                                id,
+                            }
+                        })
+                        .collect());
+                }
+            }
+        }
+
+        Ok(Self::known_models())
+    }
+}
+
+// ============================================================================
+// Stream State
+// ============================================================================
+
+/// State accumulated from response chunks. A non-streaming response is a
+/// single chunk.
+#[derive(Default)]
+struct StreamState {
+    text_content: String,
+    reasoning_content: String,
+    /// Thought signature of the first function call, sent back with it.
+    reasoning_signature: Option<String>,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<String>,
+    usage: TokenUsage,
+}
+
+impl StreamState {
+    /// Apply a response chunk, emitting events for its parts.
+    fn process(
+        &mut self,
+        chunk: ApiResponse,
+        on_event: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<(), ProviderError> {
+        if let Some(usage) = chunk.usage_metadata {
+            self.usage = usage.into();
+        }
+
+        let Some(candidate) = chunk.candidates.into_iter().next() else {
+            if let Some(reason) = chunk.prompt_feedback.and_then(|f| f.block_reason) {
+                return Err(ProviderError::api_message(format!("Prompt blocked by Gemini: {}", reason)));
+            }
+            return Ok(());
+        };
+
+        if let Some(reason) = candidate.finish_reason {
+            self.finish_reason = Some(reason);
+        }
+
+        for part in candidate.content.map(|c| c.parts).unwrap_or_default() {
+            if let Some(call) = part.function_call {
+                if self.reasoning_signature.is_none() {
+                    self.reasoning_signature = part.thought_signature;
+                }
+                let id = call
+                    .id
+                    .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+                let input = call.args.unwrap_or_else(|| serde_json::json!({}));
+
+                on_event(StreamEvent::ToolUseStart {
+                    id: id.clone(),
+                    name: call.name.clone(),
+                });
+                on_event(StreamEvent::ToolInputDelta(input.to_string()));
+                on_event(StreamEvent::ToolUseEnd);
+                self.tool_calls.push(ToolCall {
+                    id,
+                    name: call.name,
+                    input,
+                });
+            } else if let Some(text) = part.text.filter(|t| !t.is_empty()) {
+                if part.thought == Some(true) {
+                    self.reasoning_content.push_str(&text);
+                    on_event(StreamEvent::ReasoningDelta(text));
+                } else {
+                    self.text_content.push_str(&text);
+                    on_event(StreamEvent::TextDelta(text));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gemini finishes with `STOP` whether or not it called functions.
+    fn stop_reason(&self) -> StopReason {
+        if !self.tool_calls.is_empty() {
+            StopReason::ToolUse
+        } else if self.finish_reason.as_deref() == Some("MAX_TOKENS") {
+            StopReason::MaxTokens
+        } else {
+            StopReason::EndTurn
+        }
+    }
+
+    fn into_response(self) -> ProviderResponse {
+        let stop_reason = self.stop_reason();
+        // A signature is kept with the (possibly empty) thought summary so the
+        // agent stores it with the tool calls
+        let reasoning_content = if self.reasoning_content.is_empty() && self.reasoning_signature.is_none() {
+            None
+        } else {
+            Some(self.reasoning_content)
+        };
+        ProviderResponse {
+            content: self.text_content,
+            tool_calls: self.tool_calls,
+            stop_reason,
+            reasoning_content,
+            reasoning_signature: self.reasoning_signature,
+            usage: Some(self.usage),
+        }
+    }
+}
+
+// ============================================================================
+// Message Conversion
+// ============================================================================
+
+/// Convert messages to Gemini contents.
+///
+/// Function responses are matched to calls by name, so tool results look up
+/// the name of the call they answer. Signed thinking goes back as the thought
+/// signature of the first function call in the same message.
+fn convert_messages(messages: &[Message]) -> Vec<ApiContent> {
+    let tool_names: HashMap<&str, &str> = messages
+        .iter()
+        .filter_map(|m| m.as_blocks())
+        .flatten()
+        .filter(|b| b.block_type == ContentBlockType::ToolUse)
+        .filter_map(|b| Some((b.id.as_deref()?, b.name.as_deref()?)))
+        .collect();
+
+    messages
+        .iter()
+        .filter_map(|msg| {
+            let role = match msg.role {
+                Role::Assistant => "model",
+                // System messages become user messages in the API
+                Role::User | Role::System => "user",
+            };
+
+            let parts = match &msg.content {
+                MessageContent::Text(text) if text.is_empty() => Vec::new(),
+                MessageContent::Text(text) => vec![ApiPart::text(text)],
+                MessageContent::Blocks(blocks) => convert_blocks(blocks, &tool_names),
+            };
+
+            (!parts.is_empty()).then(|| ApiContent {
+                role: Some(role.to_string()),
+                parts,
+            })
+        })
+        .collect()
+}
+
+fn convert_blocks(blocks: &[ContentBlock], tool_names: &HashMap<&str, &str>) -> Vec<ApiPart> {
+    let mut signature = blocks
+        .iter()
+        .find(|b| b.block_type == ContentBlockType::Thinking)
+        .and_then(|b| b.signature.clone());
+
+    let mut parts = Vec::new();
+    for block in blocks {
+        match block.block_type {
+            ContentBlockType::Text => {
+                if let Some(text) = block.text.as_deref().filter(|t| !t.is_empty()) {
+                    parts.push(ApiPart::text(text));
+                }
+            }
+            ContentBlockType::ToolUse => parts.push(ApiPart {
+                function_call: Some(ApiFunctionCall {
+                    id: None,
+                    name: block.name.clone().unwrap_or_default(),
+                    args: Some(block.input.clone().unwrap_or_else(|| serde_json::json!({}))),
+                }),
+                thought_signature: signature.take(),
+                ..Default::default()
+            }),
+            ContentBlockType::ToolResult => {
+                let name = block
+                    .tool_use_id
+                    .as_deref()
+                    .and_then(|id| tool_names.get(id))
+                    .copied()
+                    .unwrap_or_default();
+                let content = block.content.clone().unwrap_or_default();
+                let response = if block.is_error == Some(true) {
+                    serde_json::json!({ "error": content })
+                } else {
+                    serde_json::json!({ "content": content })
+                };
+                parts.push(ApiPart {
+                    function_response: Some(ApiFunctionResponse {
+                        name: name.to_string(),
+                        response,
+                    }),
+                    ..Default::default()
+                });
+                if let Some(ref image) = block.image {
+                    parts.push(ApiPart::image(image));
+                }
+            }
+            ContentBlockType::Image => {
+                if let Some(ref image) = block.image {
+                    parts.push(ApiPart::image(image));
+                }
+            }
+            // Thought summaries aren't sent back; only their signature is
+            ContentBlockType::Thinking => {}
+        }
+    }
+    parts
+}
+
+// ============================================================================
+// API Types
+// ============================================================================
+
+/// Request body for `generateContent` and `streamGenerateContent`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    contents: Vec<ApiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<ApiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<ApiTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_config: Option<ToolConfig>,
+    generation_config: GenerationConfig,
+}
+
+/// How the model may call functions.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolConfig {
+    function_calling_config: FunctionCallingConfig,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunctionCallingConfig {
+    /// "AUTO", "NONE" or "ANY".
+    mode: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_function_names: Option<Vec<String>>,
+}
+
+impl From<&ToolChoice> for ToolConfig {
+    fn from(choice: &ToolChoice) -> Self {
+        let (mode, allowed_function_names) = match choice {
+            ToolChoice::Auto => ("AUTO", None),
+            ToolChoice::None => ("NONE", None),
+            ToolChoice::Required => ("ANY", None),
+            ToolChoice::Tool(name) => ("ANY", Some(vec![name.clone()])),
+        };
+        Self {
+            function_calling_config: FunctionCallingConfig {
+                mode,
+                allowed_function_names,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    max_output_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking_config: Option<ThinkingConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_json_schema: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ThinkingConfig {
+    include_thoughts: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking_budget: Option<u32>,
+}
+
+/// A message: a role and its parts.
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiContent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(default)]
+    parts: Vec<ApiPart>,
+}
+
+/// One part of a message; exactly one of the data fields is set.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiPart {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    /// Whether `text` is a thought summary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thought: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thought_signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inline_data: Option<ApiBlob>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_call: Option<ApiFunctionCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_response: Option<ApiFunctionResponse>,
+}
+
+impl ApiPart {
+    fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Default::default()
+        }
+    }
+
+    fn image(image: &ImageSource) -> Self {
+        let mime_type = match image.media_type {
+            ImageMediaType::Jpeg => "image/jpeg",
+            ImageMediaType::Png => "image/png",
+            ImageMediaType::Gif => "image/gif",
+            ImageMediaType::Webp => "image/webp",
+        };
+        Self {
+            inline_data: Some(ApiBlob {
+                mime_type: mime_type.to_string(),
+                data: image.data.clone(),
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+/// Inline base64 data.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiBlob {
+    mime_type: String,
+    data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiFunctionCall {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    args: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiFunctionResponse {
+    name: String,
+    response: serde_json::Value,
+}
+
+/// Tool definitions in API format.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiTool {
+    function_declarations: Vec<ApiFunctionDeclaration>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiFunctionDeclaration {
+    name: String,
+    description: String,
+    /// Full JSON Schema, unlike `parameters` which takes an OpenAPI subset.
+    parameters_json_schema: serde_json::Value,
+}
+
+/// API response format, also used for each streamed chunk.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiResponse {
+    #[serde(default)]
+    candidates: Vec<ApiCandidate>,
+    #[serde(default)]
+    usage_metadata: Option<ApiUsage>,
+    #[serde(default)]
+    prompt_feedback: Option<ApiPromptFeedback>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiCandidate {
+    #[serde(default)]
+    content: Option<ApiContent>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiPromptFeedback {
+    #[serde(default)]
+    block_reason: Option<String>,
+}
+
+/// Token usage in API format.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiUsage {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+    #[serde(default)]
+    thoughts_token_count: u32,
+    #[serde(default)]
+    cached_content_token_count: Option<u32>,
+}
+
+/// API error response.
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    error: ApiErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorDetail {
+    message: String,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+/// Response of the models list endpoint.
+#[derive(Debug, Deserialize)]
+struct ModelsResponse {
+    #[serde(default)]
+    models: Vec<ApiModel>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiModel {
+    name: String,
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    input_token_limit: Option<u32>,
+    #[serde(default)]
+    supported_generation_methods: Vec<String>,
+}
+
+// ============================================================================
+// Type Conversions
+// ============================================================================
+
+impl From<&ToolDefinition> for ApiFunctionDeclaration {
+    fn from(tool: &ToolDefinition) -> Self {
+        Self {
+            name: tool.name.clone(),
+            description: tool.description.clone(),
+            parameters_json_schema: serde_json::to_value(&tool.input_schema).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ApiUsage> for TokenUsage {
+    fn from(usage: ApiUsage) -> Self {
+        // Thinking is billed as output; cached tokens are part of the prompt count
+        Self {
+            input_tokens: usage.prompt_token_count,
+            output_tokens: usage.candidates_token_count + usage.thoughts_token_count,
+            cache_creation_input_tokens: None,
+            cache_read_input_tokens: None,
+            cached_input_tokens: usage.cached_content_token_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn provider(base_url: &str, model: &str, config: ProviderConfig) -> GeminiProvider {
+        GeminiProvider::new("test-key", model, base_url, config)
+    }
+
+    #[test]
+    fn test_provider_creation() {
+        let provider = provider(
+            "https://generativelanguage.googleapis.com/v1beta/",
+            "gemini-2.5-flash",
+            ProviderConfig::default(),
+        );
+
+        assert_eq!(provider.name(), "Gemini");
+        assert_eq!(provider.model(), "gemini-2.5-flash");
+        assert_eq!(provider.base_url, "https://generativelanguage.googleapis.com/v1beta");
+        assert_eq!(provider.context_window(), 1_048_576);
+        assert!(provider.supports_tool_use());
+        assert!(provider.supports_vision());
+
+        let pricing = provider.pricing().unwrap();
+        assert_eq!((pricing.input, pricing.output), (0.30, 2.50));
+        assert!(GeminiProvider::get_pricing("gemma-3").is_none());
+    }
+
+    #[test]
+    fn test_request_conversion() {
+        let config = ProviderConfig {
+            thinking_budget: Some(2048),
+            ..Default::default()
+        };
+        let provider = provider("http://localhost", "gemini-2.5-pro", config);
+        let image = ImageSource::new_base64(ImageMediaType::Png, "iVBOR".to_string());
+        let messages = vec![
+            Message::with_blocks(
+                Role::User,
+                vec![ContentBlock::text("What is this?"), ContentBlock::image(image.clone())],
+            ),
+            Message::with_blocks(
+                Role::Assistant,
+                vec![
+                    ContentBlock::thinking_with_signature("", "sig-1"),
+                    ContentBlock::tool_use("call_a", "read_file", serde_json::json!({ "file_path": "a.rs" })),
+                    ContentBlock::tool_use("call_b", "glob", serde_json::json!({ "pattern": "*.rs" })),
+                ],
+            ),
+            Message::with_blocks(
+                Role::User,
+                vec![
+                    ContentBlock::tool_result_with_image("call_a", "Image: a.png", image),
+                    ContentBlock::tool_result("call_b", "no matches", true),
+                ],
+            ),
+        ];
+        let tools = vec![ToolDefinition::new("read_file", "Read a file")];
+
+        let options = ChatOptions::default();
+
+        let request = provider.build_request(&messages, Some(&tools), Some("Be brief"), &options);
+        let body = serde_json::to_value(request).unwrap();
+
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "Be brief");
+        assert_eq!(body["tools"][0]["functionDeclarations"][0]["name"], "read_file");
+        assert_eq!(body["tools"][0]["functionDeclarations"][0]["parametersJsonSchema"]["type"], "object");
+        assert_eq!(
+            body["generationConfig"]["thinkingConfig"],
+            serde_json::json!({ "includeThoughts": true, "thinkingBudget": 2048 })
+        );
+
+        let contents = body["contents"].as_array().unwrap();
+        assert_eq!(contents[0]["role"], "user");
+        assert_eq!(contents[0]["parts"][1]["inlineData"]["mimeType"], "image/png");
+
+        assert_eq!(contents[1]["role"], "model");
+        let calls = contents[1]["parts"].as_array().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0]["functionCall"]["name"], "read_file");
+        assert_eq!(calls[0]["thoughtSignature"], "sig-1");
+        assert!(calls[1].get("thoughtSignature").is_none());
+
+        let results = contents[2]["parts"].as_array().unwrap();
+        assert_eq!(results[0]["functionResponse"]["name"], "read_file");
+        assert_eq!(results[0]["functionResponse"]["response"]["content"], "Image: a.png");
+        assert_eq!(results[1]["inlineData"]["data"], "iVBOR");
+        assert_eq!(results[2]["functionResponse"]["name"], "glob");
+        assert_eq!(results[2]["functionResponse"]["response"]["error"], "no matches");
+    }
+
+    #[test]
+    fn test_thinking_only_for_thinking_models() {
+        let config = ProviderConfig {
+            thinking_budget: Some(2048),
+            ..Default::default()
+        };
+        let provider = provider("http://localhost", "gemini-2.0-flash", config);
+        let request = provider.build_request(&[Message::user("Hi")], None, None, &ChatOptions::default());
+        let body = serde_json::to_value(request).unwrap();
+
+        assert!(body["generationConfig"].get("thinkingConfig").is_none());
+        assert!(body.get("tools").is_none());
+        assert!(body.get("systemInstruction").is_none());
+    }
+
+    #[test]
+    fn test_request_options() {
+        let provider = provider("http://localhost", "gemini-2.5-flash", ProviderConfig::default());
+        let tools = vec![ToolDefinition::new("read_file", "Read a file")];
+        let options = ChatOptions::new()
+            .with_tool_choice(ToolChoice::Tool("read_file".to_string()))
+            .with_stop_sequences(["END"])
+            .with_max_tokens(512)
+            .with_temperature(0.1);
+
+        let request = provider.build_request(&[Message::user("Hi")], Some(&tools), None, &options);
+        let body = serde_json::to_value(request).unwrap();
+        assert_eq!(
+            body["toolConfig"],
+            serde_json::json!({ "functionCallingConfig": { "mode": "ANY", "allowedFunctionNames": ["read_file"] } })
+        );
+        assert_eq!(body["generationConfig"]["stopSequences"], serde_json::json!(["END"]));
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 512);
+
+        assert!(GeminiProvider::check_options(&options, Some(&tools)).is_ok());
+        let serial = ChatOptions::new().with_parallel_tool_calls(false);
+        assert!(GeminiProvider::check_options(&serial, Some(&tools)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chat_against_mock_server() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/models/gemini-2.5-flash:generateContent")
+            .match_header("x-goog-api-key", "test-key")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "contents": [{ "role": "user", "parts": [{ "text": "List the files" }] }]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "candidates": [{
+                        "content": { "role": "model", "parts": [
+                            { "text": "Checking.", "thought": true },
+                            { "text": "Let me look." },
+                            { "functionCall": { "name": "list_dir", "args": { "path": "." } }, "thoughtSignature": "sig" }
+                        ]},
+                        "finishReason": "STOP"
+                    }],
+                    "usageMetadata": {
+                        "promptTokenCount": 120,
+                        "candidatesTokenCount": 20,
+                        "thoughtsTokenCount": 30,
+                        "cachedContentTokenCount": 100
+                    }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let provider = provider(&server.url(), "gemini-2.5-flash", ProviderConfig::default());
+        let response = provider
+            .chat(&[Message::user("List the files")], None, None, &ChatOptions::default())
+            .await
+            .unwrap();
+        mock.assert_async().await;
+
+        assert_eq!(response.content, "Let me look.");
+        assert_eq!(response.reasoning_content.as_deref(), Some("Checking."));
+        assert_eq!(response.reasoning_signature.as_deref(), Some("sig"));
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.tool_calls[0].name, "list_dir");
+        assert_eq!(response.tool_calls[0].input, serde_json::json!({ "path": "." }));
+
+        let usage = response.usage.unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (120, 50));
+        assert_eq!(usage.cached_input_tokens, Some(100));
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_against_mock_server() {
+        let chunks = [
+            serde_json::json!({ "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Hello" }] } }] }),
+            serde_json::json!({
+                "candidates": [{ "content": { "role": "model", "parts": [{ "text": ", world" }] }, "finishReason": "MAX_TOKENS" }],
+                "usageMetadata": { "promptTokenCount": 5, "candidatesTokenCount": 3 }
+            }),
+        ];
+        let body: String = chunks.iter().map(|c| format!("data: {}\r\n\r\n", c)).collect();
+
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/models/gemini-2.5-flash:streamGenerateContent")
+            .match_query(mockito::Matcher::UrlEncoded("alt".into(), "sse".into()))
+            .with_header("content-type", "text/event-stream")
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let provider = provider(&server.url(), "gemini-2.5-flash", ProviderConfig::default());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let response = provider
+            .stream_chat(
+                &[Message::user("Hi")],
+                None,
+                None,
+                &ChatOptions::default(),
+                Box::new(move |event| sink.lock().unwrap().push(event)))
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "Hello, world");
+        assert_eq!(response.stop_reason, StopReason::MaxTokens);
+        assert_eq!(response.usage.unwrap().total(), 8);
+
+        let events = events.lock().unwrap();
+        let deltas: Vec<&str> = events
+            .iter()
+            .filter_map(|e| match e {
+                StreamEvent::TextDelta(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deltas, vec!["Hello", ", world"]);
+        assert!(matches!(events.last(), Some(StreamEvent::Done(StopReason::MaxTokens))));
+    }
+
+    #[tokio::test]
+    async fn test_error_mapping() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/models/gemini-2.5-flash:generateContent")
+            .with_status(429)
+            .with_body(r#"{"error":{"code":429,"message":"Quota exceeded","status":"RESOURCE_EXHAUSTED"}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/models/gemini-nope:generateContent")
+            .with_status(404)
+            .with_body(r#"{"error":{"code":404,"message":"models/gemini-nope is not found","status":"NOT_FOUND"}}"#)
+            .create_async()
+            .await;
+
+        let provider_a = provider(&server.url(), "gemini-2.5-flash", ProviderConfig::default());
+        let err = provider_a
+            .chat(&[Message::user("Hi")], None, None, &ChatOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::RateLimited(_)));
+
+        let provider_b = provider(&server.url(), "gemini-nope", ProviderConfig::default());
+        let err = provider_b
+            .chat(&[Message::user("Hi")], None, None, &ChatOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::ModelNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_list_models() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/models")
+            .match_query(mockito::Matcher::Any)
+            .with_body(
+                serde_json::json!({ "models": [
+                    {
+                        "name": "models/gemini-2.5-pro",
+                        "displayName": "Gemini 2.5 Pro",
+                        "inputTokenLimit": 1048576,
+                        "supportedGenerationMethods": ["generateContent", "countTokens"]
+                    },
+                    { "name": "models/text-embedding-004", "supportedGenerationMethods": ["embedContent"] }
+                ]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let provider = provider(&server.url(), "gemini-2.5-flash", ProviderConfig::default());
+        let models = provider.list_models().await.unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].id, "gemini-2.5-pro");
+        assert_eq!(models[0].name, "Gemini 2.5 Pro");
+        assert_eq!(models[0].pricing.as_ref().unwrap().input, 1.25);
+    }
+}
@@ -8,6 +8,22 @@
 //!
 //! - [`anthropic::AnthropicProvider`] - Claude models via Anthropic API
 //! - [`openai::OpenAIProvider`] - OpenAI, Ollama, and OpenAI-compatible APIs
+//! - [`gemini::GeminiProvider`] - Google Gemini models via the native Gemini API
+//! - [`runpod::RunPodProvider`] - RunPod serverless endpoints via the job queue
+//!   (endpoints with an OpenAI-compatible route use [`openai::OpenAIProvider`])
+//!
+//! [`capabilities`] asks Ollama and OpenAI-compatible servers for the context
+//! window, tool support and vision support of the model they serve.
+//!
+//! [`retry::RetryingProvider`] wraps any provider with automatic retries for
+//! rate limits, overloads and network failures.
+//!
+//! [`fallback::FallbackProvider`] tries an ordered chain of providers, moving
+//! on when a model fails after its retries (configured with `fallbackModels`).
+//!
+//! [`cassette::RecordingProvider`] records a provider's exchanges to a
+//! cassette file and [`cassette::ReplayProvider`] serves them back, for
+//! deterministic tests without network access.
 //!
 //! # Quick Start
 //!
@@ -20,6 +36,9 @@
 //! # For OpenAI
 //! export OPENAI_API_KEY=your-key
 //!
+//! # For Google Gemini
+//! export GEMINI_API_KEY=your-key
+//!
 //! # For Ollama (no key needed, just have it running)
 //! # Ollama is auto-detected at localhost:11434
 //! ```
@@ -31,7 +50,7 @@
 //!
 //! // Auto-detects provider from environment
 //! let provider = create_provider_from_env()?;
-//! let response = provider.chat(&messages, Some(&tools), None).await?;
+//! let response = provider.chat(&messages, Some(&tools), None, &ChatOptions::default()).await?;
 //! ```
 //!
 //! # Manual Configuration
@@ -46,14 +65,28 @@
 //! ```
 
 pub mod anthropic;
+pub mod capabilities;
+pub mod cassette;
+pub mod fallback;
+pub mod gemini;
 pub mod openai;
+mod responses;
+pub mod retry;
+pub mod runpod;
 
 pub use anthropic::AnthropicProvider;
+pub use cassette::{Cassette, RecordingProvider, ReplayProvider};
+pub use fallback::FallbackProvider;
+pub use gemini::GeminiProvider;
 pub use openai::OpenAIProvider;
+pub use retry::{RetryConfig, RetryingProvider};
+pub use runpod::RunPodProvider;
+
+use std::path::PathBuf;
 
 use crate::config::ResolvedConfig;
 use crate::error::ProviderError;
-use crate::types::{BoxedProvider, ProviderConfig};
+use crate::types::{BoxedProvider, ProviderConfig, RunPodApi};
 
 /// Supported provider types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -66,6 +99,12 @@ pub enum ProviderType {
     Ollama,
     /// Any OpenAI-compatible API
     OpenAICompatible,
+    /// Google Gemini models
+    Gemini,
+    /// RunPod serverless endpoints
+    RunPod,
+    /// Recorded responses from a cassette file
+    Replay,
 }
 
 impl ProviderType {
@@ -76,6 +115,10 @@ impl ProviderType {
             Self::OpenAI => "gpt-4o",
             Self::Ollama => "llama3.2",
             Self::OpenAICompatible => "gpt-4o",
+            Self::Gemini => "gemini-2.5-flash",
+            // Workers serve one model; the OpenAI route needs its name
+            Self::RunPod => "",
+            Self::Replay => "replay",
         }
     }
 
@@ -86,14 +129,18 @@ impl ProviderType {
             Self::OpenAI => "https://api.openai.com/v1",
             Self::Ollama => "http://localhost:11434/v1",
             Self::OpenAICompatible => "https://api.openai.com/v1",
+            Self::Gemini => "https://generativelanguage.googleapis.com/v1beta",
+            // Endpoints live under this root at /<endpoint-id>
+            Self::RunPod => "https://api.runpod.ai/v2",
+            Self::Replay => "",
         }
     }
 
     /// Check if this provider requires an API key.
     pub fn requires_api_key(&self) -> bool {
         match self {
-            Self::Anthropic | Self::OpenAI => true,
-            Self::Ollama | Self::OpenAICompatible => false,
+            Self::Anthropic | Self::OpenAI | Self::Gemini | Self::RunPod => true,
+            Self::Ollama | Self::OpenAICompatible | Self::Replay => false,
         }
     }
 }
@@ -119,6 +166,9 @@ impl std::str::FromStr for ProviderType {
             "openai" | "gpt" => Ok(Self::OpenAI),
             "ollama" => Ok(Self::Ollama),
             "openai-compatible" | "openai_compatible" => Ok(Self::OpenAICompatible),
+            "gemini" | "google" => Ok(Self::Gemini),
+            "runpod" => Ok(Self::RunPod),
+            "replay" => Ok(Self::Replay),
             _ => Err(ParseProviderTypeError),
         }
     }
@@ -131,6 +181,9 @@ impl std::fmt::Display for ProviderType {
             Self::OpenAI => write!(f, "OpenAI"),
             Self::Ollama => write!(f, "Ollama"),
             Self::OpenAICompatible => write!(f, "OpenAI-Compatible"),
+            Self::Gemini => write!(f, "Gemini"),
+            Self::RunPod => write!(f, "RunPod"),
+            Self::Replay => write!(f, "Replay"),
         }
     }
 }
@@ -148,7 +201,13 @@ impl std::fmt::Display for ProviderType {
 ///
 /// # Errors
 ///
-/// Returns an error if required configuration is missing (e.g., API key for Anthropic/OpenAI).
+/// Returns an error if required configuration is missing (e.g., API key for Anthropic/OpenAI/Gemini).
+///
+/// # Recording
+///
+/// When `config.record_to` or the `CODI_RECORD` environment variable names a
+/// cassette file, the provider's exchanges are recorded to it with
+/// [`RecordingProvider`]. Replay them with [`ProviderType::Replay`].
 ///
 /// # Example
 ///
@@ -164,6 +223,32 @@ impl std::fmt::Display for ProviderType {
 pub fn create_provider(
     provider_type: ProviderType,
     config: ProviderConfig,
+) -> Result<BoxedProvider, ProviderError> {
+    if provider_type == ProviderType::Replay {
+        let path = config
+            .cassette
+            .clone()
+            .or_else(|| std::env::var_os(cassette::CASSETTE_ENV).map(PathBuf::from))
+            .ok_or_else(|| ProviderError::NotConfigured(
+                "Cassette required for Replay. Set CODI_CASSETTE to a recorded cassette file.".to_string()
+            ))?;
+        return Ok(Box::new(ReplayProvider::load(&path)?));
+    }
+
+    let record_to = config
+        .record_to
+        .clone()
+        .or_else(|| std::env::var_os(cassette::RECORD_ENV).map(PathBuf::from));
+    let provider = create_api_provider(provider_type, config)?;
+    Ok(match record_to {
+        Some(path) => Box::new(RecordingProvider::new(provider, path)),
+        None => provider,
+    })
+}
+
+fn create_api_provider(
+    provider_type: ProviderType,
+    config: ProviderConfig,
 ) -> Result<BoxedProvider, ProviderError> {
     match provider_type {
         ProviderType::Anthropic => {
@@ -213,8 +298,11 @@ pub fn create_provider(
                 .clone()
                 .unwrap_or_else(|| provider_type.default_base_url().to_string());
 
-            // Ollama doesn't need an API key
-            Ok(Box::new(OpenAIProvider::new(None, model, base_url, config)))
+            // Ollama doesn't need an API key, and serves /api/show whatever its address
+            Ok(Box::new(
+                OpenAIProvider::new(None, model, base_url, config)
+                    .with_discovery_source(Some(capabilities::DiscoverySource::Ollama)),
+            ))
         }
         ProviderType::OpenAICompatible => {
             let model = config
@@ -229,6 +317,57 @@ pub fn create_provider(
 
             Ok(Box::new(OpenAIProvider::new(config.api_key.clone(), model, base_url, config)))
         }
+        ProviderType::Gemini => {
+            let api_key = config
+                .api_key
+                .clone()
+                .ok_or_else(|| ProviderError::NotConfigured("API key required for Gemini".to_string()))?;
+
+            let model = config
+                .model
+                .clone()
+                .unwrap_or_else(|| provider_type.default_model().to_string());
+
+            let base_url = config
+                .base_url
+                .clone()
+                .unwrap_or_else(|| provider_type.default_base_url().to_string());
+
+            Ok(Box::new(GeminiProvider::new(api_key, model, base_url, config)))
+        }
+        ProviderType::RunPod => {
+            let api_key = config
+                .api_key
+                .clone()
+                .ok_or_else(|| ProviderError::NotConfigured("API key required for RunPod. Set RUNPOD_API_KEY.".to_string()))?;
+
+            let endpoint_id = config
+                .endpoint_id
+                .clone()
+                .ok_or_else(|| ProviderError::NotConfigured(
+                    "Endpoint ID required for RunPod. Pass --endpoint-id or set RUNPOD_ENDPOINT_ID.".to_string()
+                ))?;
+
+            let api_root = config
+                .base_url
+                .clone()
+                .unwrap_or_else(|| provider_type.default_base_url().to_string());
+
+            match config.runpod_api.unwrap_or_default() {
+                RunPodApi::OpenAI => {
+                    let model = config
+                        .model
+                        .clone()
+                        .filter(|m| !m.is_empty())
+                        .ok_or_else(|| ProviderError::NotConfigured(
+                            "Model required for RunPod: pass the model the endpoint serves with --model".to_string()
+                        ))?;
+                    Ok(Box::new(runpod::openai_provider(api_key, model, &api_root, &endpoint_id, config)))
+                }
+                RunPodApi::Queue => Ok(Box::new(RunPodProvider::new(api_key, &endpoint_id, &api_root, config))),
+            }
+        }
+        ProviderType::Replay => unreachable!("replay providers are created by create_provider"),
     }
 }
 
@@ -239,19 +378,25 @@ pub fn create_provider(
 /// 1. Check `CODI_PROVIDER` env var for explicit provider selection
 /// 2. Check `ANTHROPIC_API_KEY` → use Anthropic
 /// 3. Check `OPENAI_API_KEY` → use OpenAI
-/// 4. Check if Ollama is running at localhost:11434 → use Ollama
-/// 5. Default to Anthropic (will fail if no API key)
+/// 4. Check `GEMINI_API_KEY` → use Gemini
+/// 5. Default to Ollama (works if it is running at localhost:11434)
 ///
 /// # Environment Variables
 ///
 /// | Variable | Description |
 /// |----------|-------------|
-/// | `CODI_PROVIDER` | Override provider: `anthropic`, `openai`, `ollama` |
+/// | `CODI_PROVIDER` | Override provider: `anthropic`, `openai`, `gemini`, `ollama`, `runpod`, `replay` |
 /// | `CODI_MODEL` | Override default model |
+/// | `CODI_CASSETTE` | Cassette file served by the `replay` provider |
+/// | `CODI_RECORD` | Record exchanges to this cassette file |
 /// | `ANTHROPIC_API_KEY` | Anthropic API key |
 /// | `ANTHROPIC_BASE_URL` | Custom Anthropic base URL |
 /// | `OPENAI_API_KEY` | OpenAI API key |
 /// | `OPENAI_BASE_URL` | Custom OpenAI base URL |
+/// | `GEMINI_API_KEY` | Gemini API key (`GOOGLE_API_KEY` also works) |
+/// | `GEMINI_BASE_URL` | Custom Gemini base URL |
+/// | `RUNPOD_API_KEY` | RunPod API key |
+/// | `RUNPOD_ENDPOINT_ID` | RunPod serverless endpoint ID |
 /// | `OLLAMA_BASE_URL` | Custom Ollama URL (default: localhost:11434) |
 ///
 /// # Example
@@ -278,6 +423,8 @@ pub fn create_provider_from_env() -> Result<BoxedProvider, ProviderError> {
             ProviderType::Anthropic
         } else if std::env::var("OPENAI_API_KEY").is_ok() {
             ProviderType::OpenAI
+        } else if gemini_api_key().is_some() {
+            ProviderType::Gemini
         } else {
             // Default to Ollama for local-first experience
             // (will work if Ollama is running, fail gracefully if not)
@@ -354,6 +501,39 @@ pub fn create_provider_from_env() -> Result<BoxedProvider, ProviderError> {
                 ..Default::default()
             }
         }
+        ProviderType::Gemini => {
+            let api_key = gemini_api_key()
+                .ok_or_else(|| ProviderError::NotConfigured(
+                    "GEMINI_API_KEY not set. Set it or use CODI_PROVIDER=ollama for local models.".to_string()
+                ))?;
+
+            let model = std::env::var("CODI_MODEL")
+                .unwrap_or_else(|_| provider_type.default_model().to_string());
+
+            let base_url = std::env::var("GEMINI_BASE_URL")
+                .unwrap_or_else(|_| provider_type.default_base_url().to_string());
+
+            ProviderConfig {
+                api_key: Some(api_key),
+                model: Some(model),
+                base_url: Some(base_url),
+                ..Default::default()
+            }
+        }
+        ProviderType::RunPod => {
+            let api_key = std::env::var("RUNPOD_API_KEY")
+                .map_err(|_| ProviderError::NotConfigured(
+                    "RUNPOD_API_KEY not set. Set it or use CODI_PROVIDER=ollama for local models.".to_string()
+                ))?;
+
+            ProviderConfig {
+                api_key: Some(api_key),
+                model: std::env::var("CODI_MODEL").ok(),
+                endpoint_id: std::env::var("RUNPOD_ENDPOINT_ID").ok(),
+                ..Default::default()
+            }
+        }
+        ProviderType::Replay => ProviderConfig::default(),
     };
 
     create_provider(provider_type, config)
@@ -383,6 +563,25 @@ pub fn openai(api_key: impl Into<String>, model: impl Into<String>) -> Result<Bo
     create_provider(ProviderType::OpenAI, config)
 }
 
+/// Convenience function to create a Gemini provider.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let provider = gemini("your-key", "gemini-2.5-flash")?;
+/// ```
+pub fn gemini(api_key: impl Into<String>, model: impl Into<String>) -> Result<BoxedProvider, ProviderError> {
+    let config = ProviderConfig::new(api_key, model);
+    create_provider(ProviderType::Gemini, config)
+}
+
+/// Gemini API key from `GEMINI_API_KEY`, falling back to `GOOGLE_API_KEY`.
+pub(crate) fn gemini_api_key() -> Option<String> {
+    std::env::var("GEMINI_API_KEY")
+        .or_else(|_| std::env::var("GOOGLE_API_KEY"))
+        .ok()
+}
+
 /// Convenience function to create an Ollama provider.
 ///
 /// No API key needed - just have Ollama running locally.
@@ -419,6 +618,8 @@ pub fn ollama_at(base_url: impl Into<String>, model: impl Into<String>) -> Resul
 /// Create a provider from a resolved configuration.
 ///
 /// This is the main entry point for creating providers from CLI/config file settings.
+/// When `fallbackModels` is configured, the provider is a [`FallbackProvider`]
+/// chain that starts with the configured provider and model.
 ///
 /// # Example
 ///
@@ -434,21 +635,74 @@ pub fn create_provider_from_config(config: &ResolvedConfig) -> Result<BoxedProvi
     let mut provider_config = ProviderConfig::default();
     provider_config.model = config.model.clone();
     provider_config.base_url = config.base_url.clone();
+    provider_config.prompt_caching = Some(config.prompt_caching);
+    provider_config.thinking_budget = config.thinking_budget;
+    provider_config.reasoning_effort = config.reasoning_effort;
+    provider_config.endpoint_id = config.endpoint_id.clone();
+    provider_config.runpod_api = config.runpod_api;
+    provider_config.openai_api = config.openai_api;
 
     // Get API key from environment based on provider type
+    provider_config.api_key = api_key_from_env(provider_type);
+
+    let primary = create_provider(provider_type, provider_config)?;
+    if config.fallback_models.is_empty() {
+        return Ok(primary);
+    }
+    Ok(Box::new(fallback::create_fallback_chain(primary, config)))
+}
+
+/// API key for a provider type from its environment variable.
+///
+/// Providers that don't need a key (Ollama, OpenAI-compatible, replay) get `None`.
+pub fn api_key_from_env(provider_type: ProviderType) -> Option<String> {
     match provider_type {
-        ProviderType::Anthropic => {
-            provider_config.api_key = std::env::var("ANTHROPIC_API_KEY").ok();
-        }
-        ProviderType::OpenAI => {
-            provider_config.api_key = std::env::var("OPENAI_API_KEY").ok();
-        }
-        ProviderType::Ollama | ProviderType::OpenAICompatible => {
-            // These don't require API keys (or use env vars differently)
-        }
+        ProviderType::Anthropic => std::env::var("ANTHROPIC_API_KEY").ok(),
+        ProviderType::OpenAI => std::env::var("OPENAI_API_KEY").ok(),
+        ProviderType::Gemini => gemini_api_key(),
+        ProviderType::RunPod => std::env::var("RUNPOD_API_KEY").ok(),
+        ProviderType::Ollama | ProviderType::OpenAICompatible | ProviderType::Replay => None,
     }
+}
+
+/// Error for a [`ChatOptions`](crate::types::ChatOptions) setting a provider can't honor.
+pub(crate) fn unsupported_option(provider: &str, option: &str) -> ProviderError {
+    ProviderError::UnsupportedOperation(format!("{} does not support {}", provider, option))
+}
+
+/// Create the summarization provider from a resolved configuration.
+///
+/// Uses `summarize_provider` / `summarize_model` (from `models.summarize` in the
+/// workspace config or `--summarize-provider` / `--summarize-model`). Returns
+/// `Ok(None)` when neither is set. A missing provider falls back to the primary
+/// provider, and the primary base URL is only reused for that same provider.
+pub fn create_summarize_provider_from_config(
+    config: &ResolvedConfig,
+) -> Result<Option<BoxedProvider>, ProviderError> {
+    if config.summarize_provider.is_none() && config.summarize_model.is_none() {
+        return Ok(None);
+    }
+
+    let provider_name = config
+        .summarize_provider
+        .as_deref()
+        .unwrap_or(&config.provider);
+    let provider_type: ProviderType = provider_name.parse().map_err(|_| {
+        ProviderError::NotConfigured(format!("Unknown summarize provider: {}", provider_name))
+    })?;
+    let same_provider = provider_name.eq_ignore_ascii_case(&config.provider);
+
+    let provider_config = ProviderConfig {
+        api_key: api_key_from_env(provider_type),
+        model: config.summarize_model.clone(),
+        base_url: if same_provider { config.base_url.clone() } else { None },
+        endpoint_id: if same_provider { config.endpoint_id.clone() } else { None },
+        runpod_api: if same_provider { config.runpod_api } else { None },
+        prompt_caching: Some(config.prompt_caching),
+        ..Default::default()
+    };
 
-    create_provider(provider_type, provider_config)
+    create_provider(provider_type, provider_config).map(Some)
 }
 
 /// Model information for listing available models.
@@ -538,6 +792,34 @@ pub fn get_available_models() -> Vec<AvailableModel> {
             supports_vision: false,
             context_window: 200_000,
         },
+        // Gemini models
+        AvailableModel {
+            provider: "gemini",
+            model_id: "gemini-2.5-pro",
+            name: "Gemini 2.5 Pro",
+            description: "Most capable Gemini model, with thinking",
+            supports_tools: true,
+            supports_vision: true,
+            context_window: 1_048_576,
+        },
+        AvailableModel {
+            provider: "gemini",
+            model_id: "gemini-2.5-flash",
+            name: "Gemini 2.5 Flash",
+            description: "Fast Gemini model with thinking",
+            supports_tools: true,
+            supports_vision: true,
+            context_window: 1_048_576,
+        },
+        AvailableModel {
+            provider: "gemini",
+            model_id: "gemini-2.5-flash-lite",
+            name: "Gemini 2.5 Flash-Lite",
+            description: "Lowest-cost Gemini model",
+            supports_tools: true,
+            supports_vision: true,
+            context_window: 1_048_576,
+        },
         // Ollama models (popular choices)
         AvailableModel {
             provider: "ollama",
@@ -591,6 +873,8 @@ pub fn is_provider_available(provider: &str) -> bool {
     match provider.to_lowercase().as_str() {
         "anthropic" | "claude" => std::env::var("ANTHROPIC_API_KEY").is_ok(),
         "openai" | "gpt" => std::env::var("OPENAI_API_KEY").is_ok(),
+        "gemini" | "google" => gemini_api_key().is_some(),
+        "runpod" => std::env::var("RUNPOD_API_KEY").is_ok(),
         "ollama" => true, // Ollama doesn't require an API key
         _ => false,
     }
@@ -608,6 +892,10 @@ mod tests {
         assert_eq!("openai".parse::<ProviderType>(), Ok(ProviderType::OpenAI));
         assert_eq!("gpt".parse::<ProviderType>(), Ok(ProviderType::OpenAI));
         assert_eq!("ollama".parse::<ProviderType>(), Ok(ProviderType::Ollama));
+        assert_eq!("gemini".parse::<ProviderType>(), Ok(ProviderType::Gemini));
+        assert_eq!("google".parse::<ProviderType>(), Ok(ProviderType::Gemini));
+        assert_eq!("runpod".parse::<ProviderType>(), Ok(ProviderType::RunPod));
+        assert_eq!("replay".parse::<ProviderType>(), Ok(ProviderType::Replay));
         assert!("invalid".parse::<ProviderType>().is_err());
     }
 
@@ -658,6 +946,54 @@ mod tests {
         assert_eq!(provider.model(), "gpt-4o");
     }
 
+    #[test]
+    fn test_create_provider_gemini() {
+        let config = ProviderConfig::new("test-key", "gemini-2.5-pro");
+        let provider = create_provider(ProviderType::Gemini, config).unwrap();
+        assert_eq!(provider.name(), "Gemini");
+        assert_eq!(provider.model(), "gemini-2.5-pro");
+
+        let result = create_provider(ProviderType::Gemini, ProviderConfig::default());
+        assert!(matches!(result, Err(ProviderError::NotConfigured(_))));
+    }
+
+    #[test]
+    fn test_create_provider_runpod() {
+        let config = ProviderConfig {
+            api_key: Some("test-key".to_string()),
+            model: Some("meta-llama/Llama-3.1-8B-Instruct".to_string()),
+            endpoint_id: Some("ep-123".to_string()),
+            ..Default::default()
+        };
+        let provider = create_provider(ProviderType::RunPod, config.clone()).unwrap();
+        assert_eq!(provider.name(), "RunPod");
+        assert_eq!(provider.model(), "meta-llama/Llama-3.1-8B-Instruct");
+        assert!(provider.supports_tool_use());
+
+        let queue = ProviderConfig {
+            runpod_api: Some(RunPodApi::Queue),
+            ..config.clone()
+        };
+        let provider = create_provider(ProviderType::RunPod, queue).unwrap();
+        assert_eq!(provider.name(), "RunPod");
+        assert!(!provider.supports_tool_use());
+
+        let no_endpoint = ProviderConfig {
+            endpoint_id: None,
+            ..config.clone()
+        };
+        assert!(matches!(
+            create_provider(ProviderType::RunPod, no_endpoint),
+            Err(ProviderError::NotConfigured(_))
+        ));
+
+        let no_model = ProviderConfig { model: None, ..config };
+        assert!(matches!(
+            create_provider(ProviderType::RunPod, no_model),
+            Err(ProviderError::NotConfigured(_))
+        ));
+    }
+
     #[test]
     fn test_create_provider_ollama() {
         let config = ProviderConfig {
@@ -672,12 +1008,77 @@ mod tests {
         assert_eq!(provider.model(), "llama3.2");
     }
 
+    #[test]
+    fn test_create_provider_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        let recorded = create_provider(
+            ProviderType::Ollama,
+            ProviderConfig {
+                model: Some("llama3.2".to_string()),
+                record_to: Some(path.clone()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        Cassette::for_provider(recorded.as_ref()).save(&path).unwrap();
+
+        let config = ProviderConfig {
+            cassette: Some(path),
+            ..Default::default()
+        };
+        let provider = create_provider(ProviderType::Replay, config).unwrap();
+        assert_eq!(provider.name(), "Ollama");
+        assert_eq!(provider.model(), "llama3.2");
+
+        let config = ProviderConfig {
+            cassette: Some(dir.path().join("missing.json")),
+            ..Default::default()
+        };
+        assert!(matches!(
+            create_provider(ProviderType::Replay, config),
+            Err(ProviderError::NotConfigured(_))
+        ));
+    }
+
     #[test]
     fn test_convenience_functions() {
         // These should all succeed
         assert!(anthropic("key", "claude-sonnet-4-20250514").is_ok());
         assert!(openai("key", "gpt-4o").is_ok());
+        assert!(gemini("key", "gemini-2.5-flash").is_ok());
         assert!(ollama("llama3.2").is_ok());
         assert!(ollama_at("http://localhost:11434/v1", "llama3.2").is_ok());
     }
+
+    #[test]
+    fn test_create_summarize_provider_unset() {
+        let config = ResolvedConfig::default();
+        assert!(create_summarize_provider_from_config(&config).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_create_summarize_provider_from_config() {
+        let config = ResolvedConfig {
+            provider: "anthropic".to_string(),
+            base_url: Some("https://proxy.example.com".to_string()),
+            summarize_provider: Some("ollama".to_string()),
+            summarize_model: Some("llama3.2".to_string()),
+            ..Default::default()
+        };
+        let provider = create_summarize_provider_from_config(&config)
+            .unwrap()
+            .expect("summarize provider");
+        assert_eq!(provider.name(), "Ollama");
+        assert_eq!(provider.model(), "llama3.2");
+    }
+
+    #[test]
+    fn test_create_summarize_provider_unknown() {
+        let config = ResolvedConfig {
+            summarize_provider: Some("nope".to_string()),
+            ..Default::default()
+        };
+        assert!(create_summarize_provider_from_config(&config).is_err());
+    }
 }
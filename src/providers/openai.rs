@@ -13,13 +13,19 @@
 //! - **Azure OpenAI** - Custom base URL with api-version query param
 //! - **Any OpenAI-compatible** - Just set base_url
 //!
+//! Requests go to Chat Completions unless the provider is configured with
+//! [`OpenAIApi::Responses`], which uses the Responses API for reasoning
+//! summaries and encrypted reasoning carried between turns.
+//!
 //! # API Reference
 //!
 //! See [OpenAI Chat Completions API](https://platform.openai.com/docs/api-reference/chat)
+//! and [OpenAI Responses API](https://platform.openai.com/docs/api-reference/responses)
 
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
 #[cfg(feature = "telemetry")]
@@ -30,11 +36,16 @@ use crate::telemetry::metrics::GLOBAL_METRICS;
 
 use crate::error::ProviderError;
 use crate::types::{
-    ContentBlockType, ImageMediaType, Message, MessageContent,
-    ModelCapabilities, ModelInfo, ModelPricing, Provider, ProviderConfig, ProviderResponse,
-    Role, StopReason, StreamEvent, TokenUsage, ToolCall, ToolDefinition,
+    ChatOptions, ContentBlock, ContentBlockType, ImageMediaType, ImageSource, Message, MessageContent,
+    ModelCapabilities, ModelInfo, ModelPricing, OpenAIApi, Provider, ProviderConfig, ProviderResponse,
+    ReasoningEffort, Role, StopReason, StreamEvent, TokenUsage, ToolCall, ToolChoice, ToolDefinition,
 };
 
+use super::capabilities::{self, DiscoveredCapabilities, DiscoverySource, ModelsResponse};
+use super::responses::{self, FunctionTool, ReasoningConfig, ResponsesRequest, ResponsesResponse};
+use super::retry::with_retry_after;
+use super::unsupported_option;
+
 /// Default OpenAI API base URL.
 pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
 
@@ -57,7 +68,10 @@ pub struct OpenAIProvider {
     base_url: String,
     max_tokens: u32,
     temperature: Option<f32>,
+    reasoning_effort: Option<ReasoningEffort>,
+    api: OpenAIApi,
     provider_name: String,
+    discovery_source: Option<DiscoverySource>,
 }
 
 impl OpenAIProvider {
@@ -80,6 +94,7 @@ impl OpenAIProvider {
 
         let base_url = base_url.into();
         let provider_name = Self::detect_provider_name(&base_url);
+        let discovery_source = Self::detect_discovery_source(&provider_name);
 
         Self {
             client,
@@ -88,10 +103,19 @@ impl OpenAIProvider {
             base_url,
             max_tokens: config.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
             temperature: config.temperature,
+            reasoning_effort: config.reasoning_effort,
+            api: config.openai_api.unwrap_or_default(),
             provider_name,
+            discovery_source,
         }
     }
 
+    /// Set where to discover model capabilities, overriding the guess from the base URL.
+    pub fn with_discovery_source(mut self, source: Option<DiscoverySource>) -> Self {
+        self.discovery_source = source;
+        self
+    }
+
     /// Create a provider for OpenAI.
     pub fn openai(api_key: impl Into<String>, model: impl Into<String>) -> Self {
         Self::new(
@@ -104,12 +128,7 @@ impl OpenAIProvider {
 
     /// Create a provider for Ollama (no API key needed).
     pub fn ollama(model: impl Into<String>) -> Self {
-        Self::new(
-            None,
-            model,
-            OLLAMA_BASE_URL,
-            ProviderConfig::default(),
-        )
+        Self::ollama_with_url(model, OLLAMA_BASE_URL)
     }
 
     /// Create a provider for Ollama with custom base URL.
@@ -120,6 +139,7 @@ impl OpenAIProvider {
             base_url,
             ProviderConfig::default(),
         )
+        .with_discovery_source(Some(DiscoverySource::Ollama))
     }
 
     /// Detect provider name from base URL.
@@ -136,6 +156,8 @@ impl OpenAIProvider {
             "Groq".to_string()
         } else if base_url.contains("deepseek") {
             "DeepSeek".to_string()
+        } else if base_url.contains("runpod") {
+            "RunPod".to_string()
         } else {
             "OpenAI-Compatible".to_string()
         }
@@ -147,6 +169,7 @@ impl OpenAIProvider {
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
     ) -> ChatRequest {
         let mut api_messages: Vec<ChatMessage> = Vec::new();
 
@@ -162,34 +185,355 @@ impl OpenAIProvider {
 
         // Convert messages
         for msg in messages {
-            api_messages.push(msg.into());
+            api_messages.extend(chat_messages(msg));
         }
 
         let tools_json: Option<Vec<ChatTool>> = tools.map(|t| t.iter().map(|t| t.into()).collect());
 
+        // OpenAI reasoning models take max_completion_tokens and reject temperature
+        let reasoning_model = Self::is_reasoning_model(&self.model);
+        let max_tokens = options.max_tokens.unwrap_or(self.max_tokens);
+
+        // Tool choice and parallel calls are only accepted alongside tools
+        let has_tools = tools_json.is_some();
+        let tool_choice = options.tool_choice.as_ref().filter(|_| has_tools).map(|choice| match choice {
+            ToolChoice::Auto => serde_json::json!("auto"),
+            ToolChoice::None => serde_json::json!("none"),
+            ToolChoice::Required => serde_json::json!("required"),
+            ToolChoice::Tool(name) => serde_json::json!({ "type": "function", "function": { "name": name } }),
+        });
+
         ChatRequest {
             model: self.model.clone(),
             messages: api_messages,
             tools: tools_json,
-            max_tokens: Some(self.max_tokens),
-            temperature: self.temperature,
+            tool_choice,
+            parallel_tool_calls: options.parallel_tool_calls.filter(|_| has_tools),
+            max_tokens: (!reasoning_model).then_some(max_tokens),
+            max_completion_tokens: reasoning_model.then_some(max_tokens),
+            temperature: if reasoning_model { None } else { options.temperature.or(self.temperature) },
+            stop: (!options.stop_sequences.is_empty()).then(|| options.stop_sequences.clone()),
+            metadata: (!options.metadata.is_empty()).then(|| options.metadata.clone()),
+            reasoning_effort: self.reasoning_effort.filter(|_| self.sends_reasoning_effort()),
             stream: Some(false),
+            response_format: None,
+        }
+    }
+
+    /// Reject request options this endpoint and model can't honor.
+    fn check_options(
+        &self,
+        options: &ChatOptions,
+        tools: Option<&[ToolDefinition]>,
+    ) -> Result<(), ProviderError> {
+        options.check_tools(tools)?;
+        if self.api == OpenAIApi::Responses && !options.stop_sequences.is_empty() {
+            return Err(unsupported_option("the Responses API", "stop sequences"));
+        }
+        let name = self.provider_name.as_str();
+        if options.forces_tool_use() && (!self.supports_tool_use() || name == "Ollama") {
+            return Err(unsupported_option(name, "forced tool use"));
+        }
+        if !options.metadata.is_empty() && !matches!(name, "OpenAI" | "Azure OpenAI") {
+            return Err(unsupported_option(name, "request metadata"));
+        }
+        if matches!(name, "OpenAI" | "Azure OpenAI") && Self::is_reasoning_model(&self.model) {
+            if options.temperature.is_some() {
+                return Err(unsupported_option(&self.model, "temperature"));
+            }
+            if !options.stop_sequences.is_empty() {
+                return Err(unsupported_option(&self.model, "stop sequences"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `reasoning_effort` can be sent for this model.
+    ///
+    /// OpenAI rejects it for models that don't reason; compatible servers
+    /// (Ollama, vLLM, OpenRouter) decide per model, so it is passed through.
+    fn sends_reasoning_effort(&self) -> bool {
+        match self.provider_name.as_str() {
+            "OpenAI" | "Azure OpenAI" => Self::is_reasoning_model(&self.model),
+            _ => true,
         }
     }
 
+    /// Send a non-streaming request and convert the response.
+    async fn send(&self, request: &ChatRequest) -> Result<ProviderResponse, ProviderError> {
+        let start = Instant::now();
+        let operation_name = format!("{}.chat", self.provider_name.to_lowercase().replace(' ', "_"));
+
+        #[cfg(feature = "telemetry")]
+        debug!(model = %self.model, messages = request.messages.len(), "Sending chat request");
+
+        let response = match self.post("chat/completions", request).await {
+            Ok(response) => response,
+            Err(e) => {
+                #[cfg(feature = "telemetry")]
+                GLOBAL_METRICS.record_operation(&operation_name, start.elapsed());
+                return Err(e);
+            }
+        };
+
+        let api_response: ChatResponse = response
+            .json()
+            .await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+
+        let provider_response: ProviderResponse = api_response.into();
+
+        // Record metrics
+        #[cfg(feature = "telemetry")]
+        {
+            GLOBAL_METRICS.record_operation(&operation_name, start.elapsed());
+            if let Some(ref usage) = provider_response.usage {
+                GLOBAL_METRICS.record_tokens(usage.input_tokens as u64, usage.output_tokens as u64);
+            }
+        }
+
+        Ok(provider_response)
+    }
+
+    /// POST a JSON body to an endpoint path and return the successful response.
+    async fn post(&self, path: &str, body: &impl Serialize) -> Result<reqwest::Response, ProviderError> {
+        let mut req = self
+            .client
+            .post(format!("{}/{}", self.base_url, path))
+            .header("content-type", "application/json");
+
+        // Add auth header if API key is set
+        if let Some(ref api_key) = self.api_key {
+            req = req.header("authorization", format!("Bearer {}", api_key));
+        }
+
+        let response = req
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(with_retry_after(
+                self.handle_error_response(status.as_u16(), &error_text),
+                &headers,
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Build the request body for the Responses API.
+    fn build_responses_request(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+    ) -> ResponsesRequest {
+        let reasoning_model = Self::is_reasoning_model(&self.model);
+        // OpenAI reasoning models always reason; compatible servers only when asked to
+        let reasoning = match self.provider_name.as_str() {
+            "OpenAI" | "Azure OpenAI" => reasoning_model,
+            _ => self.reasoning_effort.is_some(),
+        };
+        let has_tools = tools.is_some();
+
+        ResponsesRequest {
+            model: self.model.clone(),
+            instructions: system_prompt.map(str::to_string),
+            input: responses::input_items(messages),
+            tools: tools.map(|t| t.iter().map(FunctionTool::from).collect()),
+            tool_choice: options.tool_choice.as_ref().filter(|_| has_tools).map(responses::tool_choice),
+            parallel_tool_calls: options.parallel_tool_calls.filter(|_| has_tools),
+            max_output_tokens: options.max_tokens.unwrap_or(self.max_tokens),
+            temperature: if reasoning_model { None } else { options.temperature.or(self.temperature) },
+            reasoning: reasoning.then_some(ReasoningConfig {
+                effort: self.reasoning_effort,
+                summary: "auto",
+            }),
+            include: if reasoning {
+                vec![responses::ENCRYPTED_REASONING.to_string()]
+            } else {
+                Vec::new()
+            },
+            metadata: (!options.metadata.is_empty()).then(|| options.metadata.clone()),
+            text: None,
+            store: false,
+            stream: false,
+        }
+    }
+
+    /// Send a non-streaming Responses API request and convert the response.
+    async fn send_responses(&self, request: &ResponsesRequest) -> Result<ProviderResponse, ProviderError> {
+        let start = Instant::now();
+        let operation_name = format!("{}.chat", self.provider_name.to_lowercase().replace(' ', "_"));
+
+        #[cfg(feature = "telemetry")]
+        debug!(model = %self.model, items = request.input.len(), "Sending responses request");
+
+        let result = self.post("responses", request).await;
+
+        #[cfg(feature = "telemetry")]
+        GLOBAL_METRICS.record_operation(&operation_name, start.elapsed());
+        let _ = (start, operation_name);
+
+        let api_response: ResponsesResponse = result?
+            .json()
+            .await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+
+        let provider_response: ProviderResponse = api_response.into();
+
+        #[cfg(feature = "telemetry")]
+        if let Some(ref usage) = provider_response.usage {
+            GLOBAL_METRICS.record_tokens(usage.input_tokens as u64, usage.output_tokens as u64);
+        }
+
+        Ok(provider_response)
+    }
+
+    /// Send a streaming Responses API request, forwarding its events.
+    async fn stream_responses(
+        &self,
+        request: &ResponsesRequest,
+        on_event: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<ProviderResponse, ProviderError> {
+        let start = Instant::now();
+        let operation_name = format!("{}.stream_chat", self.provider_name.to_lowercase().replace(' ', "_"));
+
+        #[cfg(feature = "telemetry")]
+        debug!(model = %self.model, items = request.input.len(), "Sending streaming responses request");
+
+        let response = match self.post("responses", request).await {
+            Ok(response) => response,
+            Err(e) => {
+                #[cfg(feature = "telemetry")]
+                GLOBAL_METRICS.record_operation(&operation_name, start.elapsed());
+                return Err(e);
+            }
+        };
+
+        // Process SSE stream; the event type is repeated in each data payload
+        let mut stream_state = responses::StreamState::default();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| ProviderError::StreamError(e.to_string()))?;
+
+        for line in text.lines() {
+            if let Some(data) = line.strip_prefix("data:") {
+                stream_state.process(data.trim(), on_event)?;
+            }
+        }
+
+        let provider_response = stream_state.into_response()?;
+
+        // Record metrics
+        #[cfg(feature = "telemetry")]
+        {
+            GLOBAL_METRICS.record_operation(&operation_name, start.elapsed());
+            if let Some(ref usage) = provider_response.usage {
+                GLOBAL_METRICS.record_tokens(usage.input_tokens as u64, usage.output_tokens as u64);
+            }
+        }
+        let _ = (start, operation_name);
+
+        Ok(provider_response)
+    }
+
+    /// Where to discover model capabilities, or `None` when the built-in tables are authoritative.
+    fn discovery_source(&self) -> Option<DiscoverySource> {
+        self.discovery_source
+    }
+
+    /// Default discovery source for a provider detected from its base URL.
+    fn detect_discovery_source(provider_name: &str) -> Option<DiscoverySource> {
+        match provider_name {
+            "OpenAI" | "Azure OpenAI" => None,
+            "Ollama" => Some(DiscoverySource::Ollama),
+            _ => Some(DiscoverySource::ModelList),
+        }
+    }
+
+    /// Capabilities the server reported for the current model, if discovered.
+    fn discovered(&self) -> DiscoveredCapabilities {
+        capabilities::cached(&self.base_url, &self.model).unwrap_or_default()
+    }
+
+    /// Whether the endpoint accepts `response_format` with a JSON Schema.
+    fn endpoint_supports_structured_output(&self) -> bool {
+        matches!(self.provider_name.as_str(), "OpenAI" | "Azure OpenAI")
+    }
+
+    /// Build a request constrained to `schema` via `response_format`.
+    fn build_structured_request(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        schema: &serde_json::Value,
+    ) -> ChatRequest {
+        let mut request = self.build_request(messages, None, system_prompt, &ChatOptions::default());
+        request.response_format = Some(serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "final_answer",
+                "schema": schema,
+            },
+        }));
+        request
+    }
+
     /// Build a streaming request body.
     fn build_streaming_request(
         &self,
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
     ) -> ChatRequest {
-        let mut request = self.build_request(messages, tools, system_prompt);
+        let mut request = self.build_request(messages, tools, system_prompt, options);
         request.stream = Some(true);
         request
     }
 
-    /// Get context window size for a model.
+    /// Get per-million-token pricing (USD) for an OpenAI model.
+    ///
+    /// Cached prompt tokens are billed at a discount and there is no cache
+    /// write surcharge.
+    fn get_pricing(model: &str) -> Option<ModelPricing> {
+        let (input, output, cached) = if model.contains("gpt-4o-mini") {
+            (0.15, 0.60, 0.075)
+        } else if model.contains("gpt-4o") {
+            (2.50, 10.0, 1.25)
+        } else if model.contains("gpt-4.1-nano") {
+            (0.10, 0.40, 0.025)
+        } else if model.contains("gpt-4.1-mini") {
+            (0.40, 1.60, 0.10)
+        } else if model.contains("gpt-4.1") {
+            (2.0, 8.0, 0.50)
+        } else if model.contains("gpt-4-turbo") {
+            (10.0, 30.0, 10.0)
+        } else if model.contains("gpt-4") {
+            (30.0, 60.0, 30.0)
+        } else if model.contains("gpt-3.5-turbo") {
+            (0.50, 1.50, 0.50)
+        } else if model.contains("o1-mini") || model.contains("o3-mini") || model.contains("o4-mini") {
+            (1.10, 4.40, 0.55)
+        } else if model.contains("o1") {
+            (15.0, 60.0, 7.50)
+        } else if model.contains("o3") {
+            (2.0, 8.0, 0.50)
+        } else {
+            return None;
+        };
+
+        Some(ModelPricing::new(input, output).with_cache(cached, input))
+    }
+
+    /// Get context window size for a model from its name.
     fn get_context_window(model: &str) -> u32 {
         // GPT-4 variants
         if model.contains("gpt-4o") || model.contains("gpt-4-turbo") {
@@ -215,7 +559,7 @@ impl OpenAIProvider {
         }
     }
 
-    /// Check if a model supports vision.
+    /// Check if a model supports vision from its name.
     fn model_supports_vision(model: &str) -> bool {
         model.contains("gpt-4o")
             || model.contains("gpt-4-turbo")
@@ -223,7 +567,14 @@ impl OpenAIProvider {
             || model.contains("vision")
     }
 
-    /// Check if a model supports tool use.
+    /// Check if a model is an OpenAI reasoning model (o-series or gpt-5).
+    fn is_reasoning_model(model: &str) -> bool {
+        ["o1", "o3", "o4", "gpt-5"]
+            .iter()
+            .any(|prefix| model.starts_with(prefix))
+    }
+
+    /// Check if a model supports tool use from its name.
     fn model_supports_tools(model: &str) -> bool {
         // Most modern models support tools
         !model.contains("instruct") && !model.contains("davinci") && !model.contains("babbage")
@@ -237,55 +588,15 @@ impl Provider for OpenAIProvider {
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
     ) -> Result<ProviderResponse, ProviderError> {
-        let request = self.build_request(messages, tools, system_prompt);
-        let start = Instant::now();
-        let operation_name = format!("{}.chat", self.provider_name.to_lowercase().replace(' ', "_"));
-
-        #[cfg(feature = "telemetry")]
-        debug!(model = %self.model, messages = messages.len(), "Sending chat request");
-
-        let mut req = self
-            .client
-            .post(format!("{}/chat/completions", self.base_url))
-            .header("content-type", "application/json");
-
-        // Add auth header if API key is set
-        if let Some(ref api_key) = self.api_key {
-            req = req.header("authorization", format!("Bearer {}", api_key));
-        }
-
-        let response = req
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            #[cfg(feature = "telemetry")]
-            GLOBAL_METRICS.record_operation(&operation_name, start.elapsed());
-            return Err(self.handle_error_response(status.as_u16(), &error_text));
-        }
-
-        let api_response: ChatResponse = response
-            .json()
-            .await
-            .map_err(|e| ProviderError::ParseError(e.to_string()))?;
-
-        let provider_response: ProviderResponse = api_response.into();
-
-        // Record metrics
-        #[cfg(feature = "telemetry")]
-        {
-            GLOBAL_METRICS.record_operation(&operation_name, start.elapsed());
-            if let Some(ref usage) = provider_response.usage {
-                GLOBAL_METRICS.record_tokens(usage.input_tokens as u64, usage.output_tokens as u64);
-            }
+        self.check_options(options, tools)?;
+        if self.api == OpenAIApi::Responses {
+            let request = self.build_responses_request(messages, tools, system_prompt, options);
+            return self.send_responses(&request).await;
         }
-
-        Ok(provider_response)
+        let request = self.build_request(messages, tools, system_prompt, options);
+        self.send(&request).await
     }
 
     async fn stream_chat(
@@ -293,37 +604,30 @@ impl Provider for OpenAIProvider {
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
         on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
     ) -> Result<ProviderResponse, ProviderError> {
-        let request = self.build_streaming_request(messages, tools, system_prompt);
+        self.check_options(options, tools)?;
+        if self.api == OpenAIApi::Responses {
+            let mut request = self.build_responses_request(messages, tools, system_prompt, options);
+            request.stream = true;
+            return self.stream_responses(&request, on_event.as_ref()).await;
+        }
+        let request = self.build_streaming_request(messages, tools, system_prompt, options);
         let start = Instant::now();
         let operation_name = format!("{}.stream_chat", self.provider_name.to_lowercase().replace(' ', "_"));
 
         #[cfg(feature = "telemetry")]
         debug!(model = %self.model, messages = messages.len(), "Sending streaming chat request");
 
-        let mut req = self
-            .client
-            .post(format!("{}/chat/completions", self.base_url))
-            .header("content-type", "application/json");
-
-        if let Some(ref api_key) = self.api_key {
-            req = req.header("authorization", format!("Bearer {}", api_key));
-        }
-
-        let response = req
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            #[cfg(feature = "telemetry")]
-            GLOBAL_METRICS.record_operation(&operation_name, start.elapsed());
-            return Err(self.handle_error_response(status.as_u16(), &error_text));
-        }
+        let response = match self.post("chat/completions", &request).await {
+            Ok(response) => response,
+            Err(e) => {
+                #[cfg(feature = "telemetry")]
+                GLOBAL_METRICS.record_operation(&operation_name, start.elapsed());
+                return Err(e);
+            }
+        };
 
         // Process SSE stream
         let mut stream_state = StreamState::new();
@@ -375,11 +679,42 @@ impl Provider for OpenAIProvider {
     }
 
     fn supports_tool_use(&self) -> bool {
-        Self::model_supports_tools(&self.model)
+        self.discovered()
+            .tool_use
+            .unwrap_or_else(|| Self::model_supports_tools(&self.model))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.endpoint_supports_structured_output()
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        schema: &serde_json::Value,
+    ) -> Result<ProviderResponse, ProviderError> {
+        if !self.endpoint_supports_structured_output() {
+            return Err(ProviderError::UnsupportedOperation(format!(
+                "{} does not support structured output",
+                self.provider_name
+            )));
+        }
+        if self.api == OpenAIApi::Responses {
+            let mut request = self.build_responses_request(messages, None, system_prompt, &ChatOptions::default());
+            request.text = Some(serde_json::json!({
+                "format": { "type": "json_schema", "name": "final_answer", "schema": schema },
+            }));
+            return self.send_responses(&request).await;
+        }
+        let request = self.build_structured_request(messages, system_prompt, schema);
+        self.send(&request).await
     }
 
     fn supports_vision(&self) -> bool {
-        Self::model_supports_vision(&self.model)
+        self.discovered()
+            .vision
+            .unwrap_or_else(|| Self::model_supports_vision(&self.model))
     }
 
     fn name(&self) -> &str {
@@ -391,7 +726,25 @@ impl Provider for OpenAIProvider {
     }
 
     fn context_window(&self) -> u32 {
-        Self::get_context_window(&self.model)
+        self.discovered()
+            .context_window
+            .unwrap_or_else(|| Self::get_context_window(&self.model))
+    }
+
+    async fn discover_capabilities(&self) {
+        if let Some(source) = self.discovery_source() {
+            capabilities::discover(&self.client, &self.base_url, self.api_key.as_deref(), &self.model, source)
+                .await;
+        }
+    }
+
+    fn pricing(&self) -> Option<ModelPricing> {
+        // Only the OpenAI API has known prices; local and compatible servers are free or unknown
+        if self.base_url.contains("openai.com") {
+            Self::get_pricing(&self.model)
+        } else {
+            None
+        }
     }
 
     async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
@@ -410,15 +763,18 @@ impl Provider for OpenAIProvider {
             Ok(resp) if resp.status().is_success() => {
                 if let Ok(models_response) = resp.json::<ModelsResponse>().await {
                     return Ok(models_response.data.into_iter().map(|m| {
+                        let reported = m.capabilities();
                         ModelInfo {
                             id: m.id.clone(),
                             name: m.id.clone(),
                             provider: self.provider_name.clone(),
                             capabilities: ModelCapabilities {
-                                vision: Self::model_supports_vision(&m.id),
-                                tool_use: Self::model_supports_tools(&m.id),
+                                vision: reported.vision.unwrap_or_else(|| Self::model_supports_vision(&m.id)),
+                                tool_use: reported.tool_use.unwrap_or_else(|| Self::model_supports_tools(&m.id)),
                             },
-                            context_window: Some(Self::get_context_window(&m.id)),
+                            context_window: Some(
+                                reported.context_window.unwrap_or_else(|| Self::get_context_window(&m.id)),
+                            ),
                             pricing: None,
                             deprecated: None,
                         }
@@ -488,6 +844,14 @@ impl OpenAIProvider {
 
             let delta = &choice.delta;
 
+            // Reasoning from DeepSeek and vLLM (reasoning_content) or Ollama (reasoning)
+            if let Some(reasoning) = delta.reasoning_content.as_ref().or(delta.reasoning.as_ref()) {
+                if !reasoning.is_empty() {
+                    state.reasoning_content.push_str(reasoning);
+                    on_event(StreamEvent::ReasoningDelta(reasoning.clone()));
+                }
+            }
+
             // Handle content delta
             if let Some(ref content) = delta.content {
                 state.text_content.push_str(content);
@@ -539,10 +903,7 @@ impl OpenAIProvider {
                     tool_use: true,
                 },
                 context_window: Some(128_000),
-                pricing: Some(ModelPricing {
-                    input: 2.50,
-                    output: 10.0,
-                }),
+                pricing: Self::get_pricing("gpt-4o"),
                 deprecated: None,
             },
             ModelInfo {
@@ -554,10 +915,7 @@ impl OpenAIProvider {
                     tool_use: true,
                 },
                 context_window: Some(128_000),
-                pricing: Some(ModelPricing {
-                    input: 0.15,
-                    output: 0.60,
-                }),
+                pricing: Self::get_pricing("gpt-4o-mini"),
                 deprecated: None,
             },
             ModelInfo {
@@ -569,10 +927,7 @@ impl OpenAIProvider {
                     tool_use: true,
                 },
                 context_window: Some(128_000),
-                pricing: Some(ModelPricing {
-                    input: 10.0,
-                    output: 30.0,
-                }),
+                pricing: Self::get_pricing("gpt-4-turbo"),
                 deprecated: None,
             },
             ModelInfo {
@@ -584,10 +939,7 @@ impl OpenAIProvider {
                     tool_use: true,
                 },
                 context_window: Some(16_384),
-                pricing: Some(ModelPricing {
-                    input: 0.50,
-                    output: 1.50,
-                }),
+                pricing: Self::get_pricing("gpt-3.5-turbo"),
                 deprecated: None,
             },
         ]
@@ -601,6 +953,7 @@ impl OpenAIProvider {
 /// State accumulated during streaming.
 struct StreamState {
     text_content: String,
+    reasoning_content: String,
     tool_calls: Vec<ToolCall>,
     stop_reason: Option<StopReason>,
     input_tokens: u32,
@@ -614,6 +967,7 @@ impl StreamState {
     fn new() -> Self {
         Self {
             text_content: String::new(),
+            reasoning_content: String::new(),
             tool_calls: Vec::new(),
             stop_reason: None,
             input_tokens: 0,
@@ -640,7 +994,12 @@ impl StreamState {
             content: self.text_content,
             tool_calls: self.tool_calls,
             stop_reason: self.stop_reason.unwrap_or(StopReason::EndTurn),
-            reasoning_content: None,
+            reasoning_content: if self.reasoning_content.is_empty() {
+                None
+            } else {
+                Some(self.reasoning_content)
+            },
+            reasoning_signature: None,
             usage: Some(TokenUsage {
                 input_tokens: self.input_tokens,
                 output_tokens: self.output_tokens,
@@ -662,11 +1021,25 @@ struct ChatRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<ChatTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<ReasoningEffort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
 }
 
 /// Chat message format.
@@ -752,10 +1125,25 @@ struct ChatResponse {
 /// A choice in the response.
 #[derive(Debug, Deserialize)]
 struct ChatChoice {
-    message: ChatMessage,
+    message: ChatResponseMessage,
     finish_reason: Option<String>,
 }
 
+/// Assistant message in a response.
+#[derive(Debug, Deserialize)]
+struct ChatResponseMessage {
+    #[serde(default)]
+    content: Option<ChatContent>,
+    #[serde(default)]
+    tool_calls: Option<Vec<ChatToolCall>>,
+    /// Reasoning from DeepSeek and vLLM
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    /// Reasoning from Ollama and OpenRouter
+    #[serde(default)]
+    reasoning: Option<String>,
+}
+
 /// Token usage.
 #[derive(Debug, Deserialize)]
 struct ChatUsage {
@@ -785,18 +1173,10 @@ struct ChatStreamDelta {
     content: Option<String>,
     #[serde(default)]
     tool_calls: Option<Vec<ChatToolCall>>,
-}
-
-/// Models list response.
-#[derive(Debug, Deserialize)]
-struct ModelsResponse {
-    data: Vec<ModelData>,
-}
-
-/// Model data from list.
-#[derive(Debug, Deserialize)]
-struct ModelData {
-    id: String,
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    reasoning: Option<String>,
 }
 
 /// API error response.
@@ -860,17 +1240,7 @@ impl From<&Message> for ChatMessage {
                         }
                         ContentBlockType::Image => {
                             if let Some(ref img) = block.image {
-                                let media_type = match img.media_type {
-                                    ImageMediaType::Jpeg => "image/jpeg",
-                                    ImageMediaType::Png => "image/png",
-                                    ImageMediaType::Gif => "image/gif",
-                                    ImageMediaType::Webp => "image/webp",
-                                };
-                                content_parts.push(ChatContentPart::ImageUrl {
-                                    image_url: ImageUrl {
-                                        url: format!("data:{};base64,{}", media_type, img.data),
-                                    },
-                                });
+                                content_parts.push(image_part(img));
                             }
                         }
                         ContentBlockType::Thinking => {
@@ -931,6 +1301,66 @@ impl From<&Message> for ChatMessage {
     }
 }
 
+/// Convert a message, giving each tool result its own `tool` message.
+///
+/// Tool messages can only hold text, so images returned by tools follow in
+/// a user message.
+fn chat_messages(msg: &Message) -> Vec<ChatMessage> {
+    let results: Vec<&ContentBlock> = match &msg.content {
+        MessageContent::Blocks(blocks) => blocks
+            .iter()
+            .filter(|b| b.block_type == ContentBlockType::ToolResult)
+            .collect(),
+        MessageContent::Text(_) => Vec::new(),
+    };
+    if results.is_empty() {
+        return vec![msg.into()];
+    }
+
+    let mut messages: Vec<ChatMessage> = results
+        .iter()
+        .map(|block| ChatMessage {
+            role: "tool".to_string(),
+            content: Some(ChatContent::Text(block.content.clone().unwrap_or_default())),
+            tool_calls: None,
+            tool_call_id: block.tool_use_id.clone(),
+        })
+        .collect();
+
+    let images: Vec<ChatContentPart> = results
+        .iter()
+        .filter_map(|block| block.image.as_ref().map(image_part))
+        .collect();
+    if !images.is_empty() {
+        let mut parts = vec![ChatContentPart::Text {
+            text: "Images returned by the tool calls above:".to_string(),
+        }];
+        parts.extend(images);
+        messages.push(ChatMessage {
+            role: "user".to_string(),
+            content: Some(ChatContent::Parts(parts)),
+            tool_calls: None,
+            tool_call_id: None,
+        });
+    }
+    messages
+}
+
+/// Image content part with a base64 data URL.
+fn image_part(img: &ImageSource) -> ChatContentPart {
+    let media_type = match img.media_type {
+        ImageMediaType::Jpeg => "image/jpeg",
+        ImageMediaType::Png => "image/png",
+        ImageMediaType::Gif => "image/gif",
+        ImageMediaType::Webp => "image/webp",
+    };
+    ChatContentPart::ImageUrl {
+        image_url: ImageUrl {
+            url: format!("data:{};base64,{}", media_type, img.data),
+        },
+    }
+}
+
 impl From<&ToolDefinition> for ChatTool {
     fn from(tool: &ToolDefinition) -> Self {
         Self {
@@ -948,7 +1378,7 @@ impl From<ChatResponse> for ProviderResponse {
     fn from(response: ChatResponse) -> Self {
         let choice = response.choices.into_iter().next();
 
-        let (content, tool_calls, stop_reason) = if let Some(choice) = choice {
+        let (content, tool_calls, stop_reason, reasoning_content) = if let Some(choice) = choice {
             let content = match choice.message.content {
                 Some(ChatContent::Text(s)) => s,
                 Some(ChatContent::Parts(parts)) => {
@@ -988,16 +1418,23 @@ impl From<ChatResponse> for ProviderResponse {
                 _ => StopReason::EndTurn,
             };
 
-            (content, tool_calls, stop_reason)
+            let reasoning_content = choice
+                .message
+                .reasoning_content
+                .or(choice.message.reasoning)
+                .filter(|r| !r.is_empty());
+
+            (content, tool_calls, stop_reason, reasoning_content)
         } else {
-            (String::new(), Vec::new(), StopReason::EndTurn)
+            (String::new(), Vec::new(), StopReason::EndTurn, None)
         };
 
         Self {
             content,
             tool_calls,
             stop_reason,
-            reasoning_content: None,
+            reasoning_content,
+            reasoning_signature: None,
             usage: response.usage.map(|u| TokenUsage {
                 input_tokens: u.prompt_tokens,
                 output_tokens: u.completion_tokens,
@@ -1035,6 +1472,17 @@ mod tests {
         assert_eq!(OpenAIProvider::get_context_window("gpt-3.5-turbo"), 4_096);
     }
 
+    #[test]
+    fn test_pricing() {
+        let mini = OpenAIProvider::get_pricing("gpt-4o-mini").unwrap();
+        assert_eq!((mini.input, mini.output, mini.cache_read), (0.15, 0.60, Some(0.075)));
+        assert_eq!(OpenAIProvider::get_pricing("gpt-4o").unwrap().input, 2.50);
+        assert!(OpenAIProvider::get_pricing("llama3.2").is_none());
+
+        assert!(OpenAIProvider::openai("test-key", "gpt-4o").pricing().is_some());
+        assert!(OpenAIProvider::ollama("gpt-4o").pricing().is_none());
+    }
+
     #[test]
     fn test_vision_support() {
         assert!(OpenAIProvider::model_supports_vision("gpt-4o"));
@@ -1042,6 +1490,47 @@ mod tests {
         assert!(!OpenAIProvider::model_supports_vision("gpt-3.5-turbo"));
     }
 
+    #[tokio::test]
+    async fn test_discovered_capabilities_override_tables() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/api/show")
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "capabilities": ["completion", "vision"],
+                    "parameters": "num_ctx 32768",
+                    "model_info": { "general.architecture": "mistral3", "mistral3.context_length": 131072 }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let mut provider = OpenAIProvider::ollama_with_url("mistral-small3.1", format!("{}/v1", server.url()));
+        assert_eq!(provider.context_window(), 8_192);
+        assert!(provider.supports_tool_use());
+
+        provider.discover_capabilities().await;
+        assert_eq!(provider.context_window(), 32_768);
+        assert!(!provider.supports_tool_use());
+        assert!(provider.supports_vision());
+    }
+
+    #[test]
+    fn test_openai_skips_discovery() {
+        assert_eq!(OpenAIProvider::openai("test-key", "gpt-4o").discovery_source(), None);
+        assert_eq!(
+            OpenAIProvider::ollama("llama3.2").discovery_source(),
+            Some(DiscoverySource::Ollama)
+        );
+        let vllm = OpenAIProvider::new(None, "qwen", "http://gpu-box:8000/v1", ProviderConfig::default());
+        assert_eq!(vllm.discovery_source(), Some(DiscoverySource::ModelList));
+        // An explicit Ollama provider uses /api/show wherever it runs
+        let remote = OpenAIProvider::ollama_with_url("llama3.2", "http://gpu-box:11434/v1");
+        assert_eq!(remote.discovery_source(), Some(DiscoverySource::Ollama));
+    }
+
     #[test]
     fn test_message_conversion() {
         let msg = Message::user("Hello!");
@@ -1054,6 +1543,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tool_results_split_with_images_after() {
+        let image = ImageSource::new_base64(ImageMediaType::Png, "iVBOR".to_string());
+        let msg = Message::with_blocks(
+            Role::User,
+            vec![
+                ContentBlock::tool_result("t1", "plain", false),
+                ContentBlock::tool_result_with_image("t2", "Image: a.png (8x4)", image),
+            ],
+        );
+        let body = serde_json::to_value(chat_messages(&msg)).unwrap();
+
+        assert_eq!(body[0], serde_json::json!({ "role": "tool", "content": "plain", "tool_call_id": "t1" }));
+        assert_eq!(body[1]["tool_call_id"], "t2");
+        assert_eq!(body[1]["content"], "Image: a.png (8x4)");
+        assert_eq!(body[2]["role"], "user");
+        assert_eq!(body[2]["content"][1]["image_url"]["url"], "data:image/png;base64,iVBOR");
+    }
+
     #[test]
     fn test_tool_conversion() {
         let tool = ToolDefinition::new("test_tool", "A test tool");
@@ -1063,6 +1571,261 @@ mod tests {
         assert_eq!(chat_tool.function.name, "test_tool");
     }
 
+    #[test]
+    fn test_structured_request_uses_response_format() {
+        let provider = OpenAIProvider::openai("test-key", "gpt-4o");
+        let schema = serde_json::json!({ "type": "object", "required": ["count"] });
+        let request = provider.build_structured_request(&[Message::user("count")], Some("sys"), &schema);
+        let body = serde_json::to_value(&request).unwrap();
+
+        assert!(provider.supports_structured_output());
+        assert_eq!(body["response_format"]["type"], "json_schema");
+        assert_eq!(body["response_format"]["json_schema"]["schema"], schema);
+        assert!(body.get("tools").is_none());
+
+        // Other compatible endpoints fall back to prompt-based validation
+        assert!(!OpenAIProvider::ollama("llama3.2").supports_structured_output());
+    }
+
+    #[test]
+    fn test_reasoning_model_request() {
+        let config = ProviderConfig {
+            temperature: Some(0.2),
+            reasoning_effort: Some(ReasoningEffort::High),
+            ..Default::default()
+        };
+        let messages = [Message::user("hi")];
+
+        let provider = OpenAIProvider::new(Some("k".into()), "o3-mini", OPENAI_BASE_URL, config.clone());
+        let request = provider.build_request(&messages, None, None, &ChatOptions::default());
+        let body = serde_json::to_value(request).unwrap();
+        assert_eq!(body["reasoning_effort"], "high");
+        assert_eq!(body["max_completion_tokens"], DEFAULT_MAX_TOKENS);
+        assert!(body.get("max_tokens").is_none());
+        assert!(body.get("temperature").is_none());
+
+        let provider = OpenAIProvider::new(Some("k".into()), "gpt-4o", OPENAI_BASE_URL, config.clone());
+        let request = provider.build_request(&messages, None, None, &ChatOptions::default());
+        let body = serde_json::to_value(request).unwrap();
+        assert!(body.get("reasoning_effort").is_none());
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+
+        // Compatible servers get the setting for any model
+        let provider = OpenAIProvider::new(None, "qwen3", OLLAMA_BASE_URL, config);
+        let request = provider.build_request(&messages, None, None, &ChatOptions::default());
+        let body = serde_json::to_value(request).unwrap();
+        assert_eq!(body["reasoning_effort"], "high");
+    }
+
+    #[test]
+    fn test_request_options() {
+        let provider = OpenAIProvider::openai("k", "gpt-4o");
+        let tools = vec![ToolDefinition::new("read_file", "Read a file")];
+        let options = ChatOptions::new()
+            .with_tool_choice(ToolChoice::Tool("read_file".to_string()))
+            .with_parallel_tool_calls(false)
+            .with_stop_sequences(["END"])
+            .with_max_tokens(100)
+            .with_temperature(0.5)
+            .with_metadata("session", "s-1");
+
+        let request = provider.build_request(&[Message::user("hi")], Some(&tools), None, &options);
+        let body = serde_json::to_value(request).unwrap();
+        assert_eq!(
+            body["tool_choice"],
+            serde_json::json!({ "type": "function", "function": { "name": "read_file" } })
+        );
+        assert_eq!(body["parallel_tool_calls"], false);
+        assert_eq!(body["stop"], serde_json::json!(["END"]));
+        assert_eq!(body["max_tokens"], 100);
+        assert_eq!(body["temperature"], 0.5);
+        assert_eq!(body["metadata"], serde_json::json!({ "session": "s-1" }));
+        assert!(provider.check_options(&options, Some(&tools)).is_ok());
+
+        // Tool settings are dropped when no tools are offered
+        let options = ChatOptions::new().with_tool_choice(ToolChoice::None).with_parallel_tool_calls(true);
+        let request = provider.build_request(&[Message::user("hi")], None, None, &options);
+        let body = serde_json::to_value(request).unwrap();
+        assert!(body.get("tool_choice").is_none());
+        assert!(body.get("parallel_tool_calls").is_none());
+    }
+
+    #[test]
+    fn test_unsupported_options() {
+        let tools = vec![ToolDefinition::new("read_file", "Read a file")];
+
+        let reasoning = OpenAIProvider::openai("k", "o3-mini");
+        assert!(reasoning.check_options(&ChatOptions::new().with_temperature(0.2), None).is_err());
+        assert!(reasoning.check_options(&ChatOptions::new().with_stop_sequences(["END"]), None).is_err());
+        assert!(reasoning.check_options(&ChatOptions::new().with_max_tokens(500), None).is_ok());
+
+        let ollama = OpenAIProvider::ollama("llama3.2");
+        let forced = ChatOptions::new().with_tool_choice(ToolChoice::Required);
+        let err = ollama.check_options(&forced, Some(&tools)).unwrap_err();
+        assert_eq!(err.to_string(), "Unsupported operation: Ollama does not support forced tool use");
+        assert!(ollama.check_options(&ChatOptions::new().with_metadata("k", "v"), None).is_err());
+    }
+
+    fn responses_provider(model: &str, base_url: &str) -> OpenAIProvider {
+        let config = ProviderConfig {
+            openai_api: Some(OpenAIApi::Responses),
+            ..Default::default()
+        };
+        OpenAIProvider::new(Some("k".into()), model, base_url, config)
+    }
+
+    #[test]
+    fn test_responses_request() {
+        let tools = vec![ToolDefinition::new("read_file", "Read a file")];
+        let options = ChatOptions::new()
+            .with_tool_choice(ToolChoice::Required)
+            .with_max_tokens(500)
+            .with_metadata("session", "s-1");
+
+        let provider = responses_provider("o4-mini", OPENAI_BASE_URL);
+        let request = provider.build_responses_request(&[Message::user("hi")], Some(&tools), Some("sys"), &options);
+        let body = serde_json::to_value(request).unwrap();
+        assert_eq!(body["instructions"], "sys");
+        assert_eq!(body["input"][0]["role"], "user");
+        assert_eq!(body["tools"][0]["name"], "read_file");
+        assert_eq!(body["tool_choice"], "required");
+        assert_eq!(body["max_output_tokens"], 500);
+        assert_eq!(body["reasoning"], serde_json::json!({ "summary": "auto" }));
+        assert_eq!(body["include"], serde_json::json!(["reasoning.encrypted_content"]));
+        assert_eq!(body["metadata"], serde_json::json!({ "session": "s-1" }));
+        assert_eq!(body["store"], false);
+
+        // Models that don't reason get no reasoning settings
+        let provider = responses_provider("gpt-4o", OPENAI_BASE_URL);
+        let request = provider.build_responses_request(&[Message::user("hi")], None, None, &ChatOptions::default());
+        let body = serde_json::to_value(request).unwrap();
+        assert!(body.get("reasoning").is_none());
+        assert!(body.get("include").is_none());
+        assert!(body.get("tool_choice").is_none());
+
+        let err = provider
+            .check_options(&ChatOptions::new().with_stop_sequences(["END"]), None)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unsupported operation: the Responses API does not support stop sequences"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_responses_api_chat() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/responses")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "model": "o4-mini",
+                "store": false,
+                "stream": false,
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "status": "completed",
+                    "output": [
+                        { "type": "reasoning", "id": "rs_1", "summary": [], "encrypted_content": "gAAAA" },
+                        { "type": "function_call", "call_id": "call_1", "name": "read_file", "arguments": "{}" }
+                    ],
+                    "usage": { "input_tokens": 10, "output_tokens": 5 }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let provider = responses_provider("o4-mini", &format!("{}/v1", server.url()));
+        let response = provider
+            .chat(&[Message::user("hi")], None, None, &ChatOptions::default())
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.tool_calls[0].id, "call_1");
+        assert_eq!(response.reasoning_content.as_deref(), Some(""));
+        assert!(response.reasoning_signature.unwrap().contains("gAAAA"));
+    }
+
+    #[tokio::test]
+    async fn test_responses_api_stream() {
+        let mut server = mockito::Server::new_async().await;
+        let body = [
+            r#"{"type":"response.output_text.delta","delta":"Hel"}"#,
+            r#"{"type":"response.output_text.delta","delta":"lo"}"#,
+            r#"{"type":"response.completed","response":{"status":"completed","output":[{"type":"message","role":"assistant","content":[{"type":"output_text","text":"Hello"}]}],"usage":{"input_tokens":3,"output_tokens":2}}}"#,
+        ]
+        .iter()
+        .map(|data| format!("event: message\ndata: {}\n\n", data))
+        .collect::<String>();
+        server
+            .mock("POST", "/v1/responses")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "stream": true })))
+            .with_header("content-type", "text/event-stream")
+            .with_body(body)
+            .create_async()
+            .await;
+
+        let deltas = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+        let sink = deltas.clone();
+        let provider = responses_provider("gpt-4o", &format!("{}/v1", server.url()));
+        let response = provider
+            .stream_chat(
+                &[Message::user("hi")],
+                None,
+                None,
+                &ChatOptions::default(),
+                Box::new(move |event| {
+                    if let StreamEvent::TextDelta(text) = event {
+                        sink.lock().unwrap().push_str(&text);
+                    }
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(*deltas.lock().unwrap(), "Hello");
+        assert_eq!(response.content, "Hello");
+        assert_eq!(response.stop_reason, StopReason::EndTurn);
+        assert_eq!(response.usage.unwrap().output_tokens, 2);
+    }
+
+    #[test]
+    fn test_reasoning_parsing() {
+        let response: ChatResponse = serde_json::from_str(
+            r#"{"choices": [{"message": {"role": "assistant", "content": "4",
+                "reasoning_content": "2 + 2 = 4"}, "finish_reason": "stop"}]}"#,
+        )
+        .unwrap();
+        let response = ProviderResponse::from(response);
+        assert_eq!(response.content, "4");
+        assert_eq!(response.reasoning_content.as_deref(), Some("2 + 2 = 4"));
+
+        let provider = OpenAIProvider::ollama("qwen3");
+        let mut state = StreamState::new();
+        let reasoning = std::sync::Mutex::new(String::new());
+        let on_event = |event: StreamEvent| {
+            if let StreamEvent::ReasoningDelta(text) = event {
+                reasoning.lock().unwrap().push_str(&text);
+            }
+        };
+        for data in [
+            r#"{"choices": [{"delta": {"reasoning": "Think"}, "finish_reason": null}]}"#,
+            r#"{"choices": [{"delta": {"reasoning": "ing"}, "finish_reason": null}]}"#,
+            r#"{"choices": [{"delta": {"content": "Answer"}, "finish_reason": "stop"}]}"#,
+        ] {
+            let chunk: ChatStreamChunk = serde_json::from_str(data).unwrap();
+            provider.process_stream_chunk(&chunk, &mut state, &on_event);
+        }
+        assert_eq!(*reasoning.lock().unwrap(), "Thinking");
+        let response = state.into_response();
+        assert_eq!(response.content, "Answer");
+        assert_eq!(response.reasoning_content.as_deref(), Some("Thinking"));
+    }
+
     #[test]
     fn test_provider_name_detection() {
         assert_eq!(OpenAIProvider::detect_provider_name("https://api.openai.com/v1"), "OpenAI");
@@ -0,0 +1,735 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! OpenAI Responses API types and conversions.
+//!
+//! [`OpenAIProvider`](super::OpenAIProvider) calls `/responses` instead of
+//! `/chat/completions` when configured with `openaiApi: responses`. Requests
+//! are sent with `store: false`, so the full history goes out every turn and
+//! reasoning comes back as encrypted items. Those items are kept in the
+//! thinking block signature and sent back with the results of the tool calls
+//! they led to.
+//!
+//! # API Reference
+//!
+//! See [OpenAI Responses API](https://platform.openai.com/docs/api-reference/responses)
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ProviderError;
+use crate::types::{
+    ContentBlockType, ImageMediaType, ImageSource, Message, MessageContent, ProviderResponse,
+    ReasoningEffort, Role, StopReason, StreamEvent, TokenUsage, ToolCall, ToolChoice, ToolDefinition,
+};
+
+/// `include` value that returns reasoning as encrypted items.
+pub(crate) const ENCRYPTED_REASONING: &str = "reasoning.encrypted_content";
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+/// Request body for the Responses API.
+#[derive(Debug, Serialize)]
+pub(crate) struct ResponsesRequest {
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    pub input: Vec<InputItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<FunctionTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    pub max_output_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<ReasoningConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+    /// Output format, e.g. a JSON Schema for structured output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<serde_json::Value>,
+    pub store: bool,
+    pub stream: bool,
+}
+
+/// Reasoning settings for reasoning models.
+#[derive(Debug, Serialize)]
+pub(crate) struct ReasoningConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effort: Option<ReasoningEffort>,
+    /// Reasoning summary detail ("auto", "concise" or "detailed").
+    pub summary: &'static str,
+}
+
+/// Function tool definition.
+#[derive(Debug, Serialize)]
+pub(crate) struct FunctionTool {
+    #[serde(rename = "type")]
+    tool_type: &'static str,
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+impl From<&ToolDefinition> for FunctionTool {
+    fn from(tool: &ToolDefinition) -> Self {
+        Self {
+            tool_type: "function",
+            name: tool.name.clone(),
+            description: tool.description.clone(),
+            parameters: serde_json::to_value(&tool.input_schema).unwrap_or_default(),
+        }
+    }
+}
+
+/// Value for `tool_choice`.
+pub(crate) fn tool_choice(choice: &ToolChoice) -> serde_json::Value {
+    match choice {
+        ToolChoice::Auto => serde_json::json!("auto"),
+        ToolChoice::None => serde_json::json!("none"),
+        ToolChoice::Required => serde_json::json!("required"),
+        ToolChoice::Tool(name) => serde_json::json!({ "type": "function", "name": name }),
+    }
+}
+
+/// Item of the conversation sent as `input`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum InputItem {
+    Message {
+        role: &'static str,
+        content: Vec<InputContent>,
+    },
+    FunctionCall {
+        call_id: String,
+        name: String,
+        arguments: String,
+    },
+    FunctionCallOutput {
+        call_id: String,
+        output: String,
+    },
+    Reasoning(ReasoningItem),
+}
+
+/// Content part of an input message.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum InputContent {
+    InputText { text: String },
+    InputImage { image_url: String },
+    OutputText { text: String },
+}
+
+/// Reasoning output item, sent back verbatim on later turns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ReasoningItem {
+    id: String,
+    #[serde(default)]
+    summary: Vec<SummaryPart>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encrypted_content: Option<String>,
+}
+
+/// Part of a reasoning summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SummaryPart {
+    #[serde(rename = "type")]
+    part_type: String,
+    text: String,
+}
+
+// ============================================================================
+// Response Types
+// ============================================================================
+
+/// Response object, returned directly or in the final stream event.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ResponsesResponse {
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    incomplete_details: Option<IncompleteDetails>,
+    #[serde(default)]
+    output: Vec<OutputItem>,
+    #[serde(default)]
+    usage: Option<ResponsesUsage>,
+    #[serde(default)]
+    error: Option<ResponseError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IncompleteDetails {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseError {
+    message: String,
+}
+
+/// Output item; built-in tool calls and other item types are ignored.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OutputItem {
+    Message {
+        #[serde(default)]
+        content: Vec<OutputContent>,
+    },
+    FunctionCall {
+        call_id: String,
+        name: String,
+        #[serde(default)]
+        arguments: String,
+    },
+    Reasoning(ReasoningItem),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OutputContent {
+    OutputText { text: String },
+    Refusal { refusal: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponsesUsage {
+    input_tokens: u32,
+    output_tokens: u32,
+    #[serde(default)]
+    input_tokens_details: Option<InputTokensDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InputTokensDetails {
+    #[serde(default)]
+    cached_tokens: u32,
+}
+
+/// Server-sent event of a streaming response.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ResponsesStreamEvent {
+    #[serde(rename = "response.output_text.delta")]
+    OutputTextDelta { delta: String },
+    #[serde(rename = "response.refusal.delta")]
+    RefusalDelta { delta: String },
+    #[serde(rename = "response.reasoning_summary_part.added")]
+    ReasoningSummaryPartAdded {},
+    #[serde(rename = "response.reasoning_summary_text.delta")]
+    ReasoningSummaryTextDelta { delta: String },
+    #[serde(rename = "response.output_item.added")]
+    OutputItemAdded { item: OutputItem },
+    #[serde(rename = "response.function_call_arguments.delta")]
+    FunctionCallArgumentsDelta { delta: String },
+    #[serde(rename = "response.output_item.done")]
+    OutputItemDone { item: OutputItem },
+    #[serde(rename = "response.completed")]
+    Completed { response: ResponsesResponse },
+    #[serde(rename = "response.incomplete")]
+    Incomplete { response: ResponsesResponse },
+    #[serde(rename = "response.failed")]
+    Failed { response: ResponsesResponse },
+    #[serde(rename = "error")]
+    Error { message: String },
+    #[serde(other)]
+    Other,
+}
+
+// ============================================================================
+// Stream State
+// ============================================================================
+
+/// State accumulated during streaming.
+///
+/// Deltas are forwarded as they arrive; the response itself is taken from
+/// the final `response.completed` event, which repeats the full output.
+#[derive(Default)]
+pub(crate) struct StreamState {
+    reasoning_started: bool,
+    response: Option<ProviderResponse>,
+}
+
+impl StreamState {
+    /// Process the data of one server-sent event.
+    pub(crate) fn process(
+        &mut self,
+        data: &str,
+        on_event: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<(), ProviderError> {
+        let event = match serde_json::from_str::<ResponsesStreamEvent>(data) {
+            Ok(event) => event,
+            Err(e) => {
+                on_event(StreamEvent::Error(e.to_string()));
+                return Ok(());
+            }
+        };
+
+        match event {
+            ResponsesStreamEvent::OutputTextDelta { delta } | ResponsesStreamEvent::RefusalDelta { delta } => {
+                on_event(StreamEvent::TextDelta(delta));
+            }
+            // Separate summary parts the way the final response joins them
+            ResponsesStreamEvent::ReasoningSummaryPartAdded {} if self.reasoning_started => {
+                on_event(StreamEvent::ReasoningDelta("\n\n".to_string()));
+            }
+            ResponsesStreamEvent::ReasoningSummaryTextDelta { delta } => {
+                self.reasoning_started = true;
+                on_event(StreamEvent::ReasoningDelta(delta));
+            }
+            ResponsesStreamEvent::OutputItemAdded {
+                item: OutputItem::FunctionCall { call_id, name, .. },
+            } => {
+                on_event(StreamEvent::ToolUseStart { id: call_id, name });
+            }
+            ResponsesStreamEvent::FunctionCallArgumentsDelta { delta } => {
+                on_event(StreamEvent::ToolInputDelta(delta));
+            }
+            ResponsesStreamEvent::OutputItemDone {
+                item: OutputItem::FunctionCall { .. },
+            } => {
+                on_event(StreamEvent::ToolUseEnd);
+            }
+            ResponsesStreamEvent::Completed { response } | ResponsesStreamEvent::Incomplete { response } => {
+                let response = ProviderResponse::from(response);
+                on_event(StreamEvent::Usage(response.usage.clone().unwrap_or_default()));
+                on_event(StreamEvent::Done(response.stop_reason));
+                self.response = Some(response);
+            }
+            ResponsesStreamEvent::Failed { response } => {
+                let message = response
+                    .error
+                    .map(|e| e.message)
+                    .unwrap_or_else(|| "response failed".to_string());
+                return Err(ProviderError::StreamError(message));
+            }
+            ResponsesStreamEvent::Error { message } => {
+                return Err(ProviderError::StreamError(message));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The completed response, or an error if the stream ended early.
+    pub(crate) fn into_response(self) -> Result<ProviderResponse, ProviderError> {
+        self.response
+            .ok_or_else(|| ProviderError::StreamError("stream ended before the response completed".to_string()))
+    }
+}
+
+// ============================================================================
+// Type Conversions
+// ============================================================================
+
+/// Convert the conversation history to input items.
+pub(crate) fn input_items(messages: &[Message]) -> Vec<InputItem> {
+    let mut items = Vec::new();
+    for msg in messages {
+        push_message_items(&mut items, msg);
+    }
+    items
+}
+
+/// Append the items for one message, keeping its blocks in order.
+///
+/// Tool outputs can only hold text, so images returned by tools follow in a
+/// user message.
+fn push_message_items(items: &mut Vec<InputItem>, msg: &Message) {
+    let role = match msg.role {
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::System => "system",
+    };
+    let text_part = |text: &str| match msg.role {
+        Role::Assistant => InputContent::OutputText { text: text.to_string() },
+        _ => InputContent::InputText { text: text.to_string() },
+    };
+
+    let blocks = match &msg.content {
+        MessageContent::Text(s) => {
+            items.push(InputItem::Message { role, content: vec![text_part(s)] });
+            return;
+        }
+        MessageContent::Blocks(blocks) => blocks,
+    };
+
+    let mut content = Vec::new();
+    let mut tool_images = Vec::new();
+    let flush = |items: &mut Vec<InputItem>, content: &mut Vec<InputContent>| {
+        if !content.is_empty() {
+            items.push(InputItem::Message { role, content: std::mem::take(content) });
+        }
+    };
+
+    for block in blocks {
+        match block.block_type {
+            ContentBlockType::Text => {
+                if let Some(ref text) = block.text {
+                    content.push(text_part(text));
+                }
+            }
+            ContentBlockType::Image => {
+                if let Some(ref img) = block.image {
+                    content.push(InputContent::InputImage { image_url: data_url(img) });
+                }
+            }
+            ContentBlockType::ToolUse => {
+                flush(items, &mut content);
+                items.push(InputItem::FunctionCall {
+                    call_id: block.id.clone().unwrap_or_default(),
+                    name: block.name.clone().unwrap_or_default(),
+                    arguments: block
+                        .input
+                        .as_ref()
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| "{}".to_string()),
+                });
+            }
+            ContentBlockType::ToolResult => {
+                flush(items, &mut content);
+                items.push(InputItem::FunctionCallOutput {
+                    call_id: block.tool_use_id.clone().unwrap_or_default(),
+                    output: block.content.clone().unwrap_or_default(),
+                });
+                if let Some(ref img) = block.image {
+                    tool_images.push(InputContent::InputImage { image_url: data_url(img) });
+                }
+            }
+            ContentBlockType::Thinking => {
+                // Only reasoning returned by this API can be sent back
+                flush(items, &mut content);
+                if let Some(ref signature) = block.signature {
+                    items.extend(reasoning_items(signature).into_iter().map(InputItem::Reasoning));
+                }
+            }
+        }
+    }
+    flush(items, &mut content);
+
+    if !tool_images.is_empty() {
+        let mut content = vec![InputContent::InputText {
+            text: "Images returned by the tool calls above:".to_string(),
+        }];
+        content.extend(tool_images);
+        items.push(InputItem::Message { role: "user", content });
+    }
+}
+
+/// Base64 data URL for an image.
+fn data_url(img: &ImageSource) -> String {
+    let media_type = match img.media_type {
+        ImageMediaType::Jpeg => "image/jpeg",
+        ImageMediaType::Png => "image/png",
+        ImageMediaType::Gif => "image/gif",
+        ImageMediaType::Webp => "image/webp",
+    };
+    format!("data:{};base64,{}", media_type, img.data)
+}
+
+/// Reasoning items carried in a thinking block signature.
+///
+/// Signatures from other providers don't parse and yield no items.
+fn reasoning_items(signature: &str) -> Vec<ReasoningItem> {
+    serde_json::from_str(signature).unwrap_or_default()
+}
+
+/// Signature carrying the encrypted reasoning items of a response, if any.
+fn reasoning_signature(items: &[ReasoningItem]) -> Option<String> {
+    let encrypted: Vec<&ReasoningItem> = items.iter().filter(|i| i.encrypted_content.is_some()).collect();
+    if encrypted.is_empty() {
+        None
+    } else {
+        serde_json::to_string(&encrypted).ok()
+    }
+}
+
+impl From<ResponsesResponse> for ProviderResponse {
+    fn from(response: ResponsesResponse) -> Self {
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        let mut reasoning = Vec::new();
+
+        for item in response.output {
+            match item {
+                OutputItem::Message { content: parts } => {
+                    for part in parts {
+                        match part {
+                            OutputContent::OutputText { text } => content.push_str(&text),
+                            OutputContent::Refusal { refusal } => content.push_str(&refusal),
+                            OutputContent::Other => {}
+                        }
+                    }
+                }
+                OutputItem::FunctionCall { call_id, name, arguments } => {
+                    let input = serde_json::from_str(&arguments)
+                        .unwrap_or(serde_json::Value::Object(Default::default()));
+                    tool_calls.push(ToolCall { id: call_id, name, input });
+                }
+                OutputItem::Reasoning(item) => reasoning.push(item),
+                OutputItem::Other => {}
+            }
+        }
+
+        let max_tokens = response.status.as_deref() == Some("incomplete")
+            && response
+                .incomplete_details
+                .and_then(|d| d.reason)
+                .as_deref()
+                == Some("max_output_tokens");
+        let stop_reason = if max_tokens {
+            StopReason::MaxTokens
+        } else if !tool_calls.is_empty() {
+            StopReason::ToolUse
+        } else {
+            StopReason::EndTurn
+        };
+
+        let summary = reasoning
+            .iter()
+            .flat_map(|item| item.summary.iter().map(|part| part.text.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let reasoning_signature = reasoning_signature(&reasoning);
+        // A signature is kept with the (possibly empty) summary so the agent
+        // stores it with the tool calls
+        let reasoning_content = if summary.is_empty() && reasoning_signature.is_none() {
+            None
+        } else {
+            Some(summary)
+        };
+
+        Self {
+            content,
+            tool_calls,
+            stop_reason,
+            reasoning_content,
+            reasoning_signature,
+            usage: response.usage.map(|u| TokenUsage {
+                input_tokens: u.input_tokens,
+                output_tokens: u.output_tokens,
+                cached_input_tokens: u.input_tokens_details.map(|d| d.cached_tokens),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ContentBlock;
+    use std::sync::{Arc, Mutex};
+
+    fn completed_response() -> serde_json::Value {
+        serde_json::json!({
+            "status": "completed",
+            "output": [
+                {
+                    "type": "reasoning",
+                    "id": "rs_1",
+                    "summary": [{ "type": "summary_text", "text": "Need the file." }],
+                    "encrypted_content": "gAAAA"
+                },
+                { "type": "web_search_call", "id": "ws_1", "status": "completed" },
+                {
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{ "type": "output_text", "text": "Reading it.", "annotations": [] }]
+                },
+                {
+                    "type": "function_call",
+                    "id": "fc_1",
+                    "call_id": "call_1",
+                    "name": "read_file",
+                    "arguments": "{\"path\":\"a.rs\"}"
+                }
+            ],
+            "usage": {
+                "input_tokens": 120,
+                "input_tokens_details": { "cached_tokens": 100 },
+                "output_tokens": 30,
+                "output_tokens_details": { "reasoning_tokens": 20 }
+            }
+        })
+    }
+
+    #[test]
+    fn test_response_conversion() {
+        let response: ResponsesResponse = serde_json::from_value(completed_response()).unwrap();
+        let response = ProviderResponse::from(response);
+
+        assert_eq!(response.content, "Reading it.");
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.tool_calls[0].id, "call_1");
+        assert_eq!(response.tool_calls[0].input, serde_json::json!({ "path": "a.rs" }));
+        assert_eq!(response.reasoning_content.as_deref(), Some("Need the file."));
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.input_tokens, 120);
+        assert_eq!(usage.cached_input_tokens, Some(100));
+
+        let items = reasoning_items(response.reasoning_signature.as_deref().unwrap());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].encrypted_content.as_deref(), Some("gAAAA"));
+    }
+
+    #[test]
+    fn test_incomplete_response_stops_at_max_tokens() {
+        let response: ResponsesResponse = serde_json::from_value(serde_json::json!({
+            "status": "incomplete",
+            "incomplete_details": { "reason": "max_output_tokens" },
+            "output": [{ "type": "reasoning", "id": "rs_1", "summary": [] }]
+        }))
+        .unwrap();
+        let response = ProviderResponse::from(response);
+
+        assert_eq!(response.stop_reason, StopReason::MaxTokens);
+        // Reasoning without encrypted content can't be carried over
+        assert!(response.reasoning_signature.is_none());
+        assert!(response.reasoning_content.is_none());
+    }
+
+    #[test]
+    fn test_input_items_keep_block_order() {
+        let response: ResponsesResponse = serde_json::from_value(completed_response()).unwrap();
+        let signature = ProviderResponse::from(response).reasoning_signature.unwrap();
+        let image = ImageSource::new_base64(ImageMediaType::Png, "iVBOR".to_string());
+        let messages = [
+            Message::user("Read a.rs"),
+            Message::with_blocks(
+                Role::Assistant,
+                vec![
+                    ContentBlock::thinking_with_signature("Need the file.", signature),
+                    ContentBlock::text("Reading it."),
+                    ContentBlock::tool_use("call_1", "read_file", serde_json::json!({ "path": "a.rs" })),
+                ],
+            ),
+            Message::with_blocks(
+                Role::User,
+                vec![ContentBlock::tool_result_with_image("call_1", "fn main() {}", image)],
+            ),
+        ];
+        let body = serde_json::to_value(input_items(&messages)).unwrap();
+
+        assert_eq!(
+            body[0],
+            serde_json::json!({ "type": "message", "role": "user", "content": [{ "type": "input_text", "text": "Read a.rs" }] })
+        );
+        assert_eq!(body[1]["type"], "reasoning");
+        assert_eq!(body[1]["id"], "rs_1");
+        assert_eq!(body[1]["encrypted_content"], "gAAAA");
+        assert_eq!(body[2]["content"][0], serde_json::json!({ "type": "output_text", "text": "Reading it." }));
+        assert_eq!(
+            body[3],
+            serde_json::json!({ "type": "function_call", "call_id": "call_1", "name": "read_file", "arguments": "{\"path\":\"a.rs\"}" })
+        );
+        assert_eq!(
+            body[4],
+            serde_json::json!({ "type": "function_call_output", "call_id": "call_1", "output": "fn main() {}" })
+        );
+        assert_eq!(body[5]["role"], "user");
+        assert_eq!(body[5]["content"][1]["image_url"], "data:image/png;base64,iVBOR");
+    }
+
+    #[test]
+    fn test_foreign_thinking_signatures_are_dropped() {
+        let messages = [Message::with_blocks(
+            Role::Assistant,
+            vec![
+                ContentBlock::thinking_with_signature("Claude thinking", "EqQBCkYIBRgCKkA"),
+                ContentBlock::thinking("Unsigned"),
+                ContentBlock::text("Done."),
+            ],
+        )];
+        let body = serde_json::to_value(input_items(&messages)).unwrap();
+
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["type"], "message");
+    }
+
+    #[test]
+    fn test_stream_events() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let on_event = move |event: StreamEvent| sink.lock().unwrap().push(event);
+
+        let completed = serde_json::json!({ "type": "response.completed", "response": completed_response() });
+        let data = [
+            r#"{"type":"response.created","response":{"status":"in_progress","output":[]}}"#.to_string(),
+            r#"{"type":"response.output_item.added","item":{"type":"reasoning","id":"rs_1","summary":[]}}"#.to_string(),
+            r#"{"type":"response.reasoning_summary_part.added","summary_index":0}"#.to_string(),
+            r#"{"type":"response.reasoning_summary_text.delta","delta":"Need "}"#.to_string(),
+            r#"{"type":"response.reasoning_summary_text.delta","delta":"the file."}"#.to_string(),
+            r#"{"type":"response.reasoning_summary_part.added","summary_index":1}"#.to_string(),
+            r#"{"type":"response.output_text.delta","delta":"Reading it."}"#.to_string(),
+            r#"{"type":"response.output_item.added","item":{"type":"function_call","call_id":"call_1","name":"read_file","arguments":""}}"#.to_string(),
+            r#"{"type":"response.function_call_arguments.delta","delta":"{\"path\":\"a.rs\"}"}"#.to_string(),
+            r#"{"type":"response.output_item.done","item":{"type":"function_call","call_id":"call_1","name":"read_file","arguments":"{\"path\":\"a.rs\"}"}}"#.to_string(),
+            completed.to_string(),
+        ];
+
+        let mut state = StreamState::default();
+        for event in &data {
+            state.process(event, &on_event).unwrap();
+        }
+        let response = state.into_response().unwrap();
+
+        let events = events.lock().unwrap();
+        assert!(matches!(&events[0], StreamEvent::ReasoningDelta(d) if d == "Need "));
+        assert!(matches!(&events[2], StreamEvent::ReasoningDelta(d) if d == "\n\n"));
+        assert!(matches!(&events[3], StreamEvent::TextDelta(d) if d == "Reading it."));
+        assert!(matches!(&events[4], StreamEvent::ToolUseStart { id, name } if id == "call_1" && name == "read_file"));
+        assert!(matches!(&events[5], StreamEvent::ToolInputDelta(d) if d == "{\"path\":\"a.rs\"}"));
+        assert!(matches!(events[6], StreamEvent::ToolUseEnd));
+        assert!(matches!(&events[7], StreamEvent::Usage(u) if u.output_tokens == 30));
+        assert!(matches!(events[8], StreamEvent::Done(StopReason::ToolUse)));
+        assert_eq!(events.len(), 9);
+
+        assert_eq!(response.tool_calls.len(), 1);
+        assert!(response.reasoning_signature.is_some());
+    }
+
+    #[test]
+    fn test_stream_failures() {
+        let on_event = |_: StreamEvent| {};
+
+        let mut state = StreamState::default();
+        let failed = r#"{"type":"response.failed","response":{"status":"failed","output":[],"error":{"code":"server_error","message":"boom"}}}"#;
+        let err = state.process(failed, &on_event).unwrap_err();
+        assert!(matches!(err, ProviderError::StreamError(ref m) if m == "boom"));
+
+        let mut state = StreamState::default();
+        state
+            .process(r#"{"type":"response.output_text.delta","delta":"partial"}"#, &on_event)
+            .unwrap();
+        assert!(matches!(state.into_response(), Err(ProviderError::StreamError(_))));
+    }
+
+    #[test]
+    fn test_tool_choice_and_tools() {
+        assert_eq!(tool_choice(&ToolChoice::Required), serde_json::json!("required"));
+        assert_eq!(
+            tool_choice(&ToolChoice::Tool("read_file".to_string())),
+            serde_json::json!({ "type": "function", "name": "read_file" })
+        );
+
+        let tool = FunctionTool::from(&ToolDefinition::new("read_file", "Read a file"));
+        let body = serde_json::to_value(tool).unwrap();
+        assert_eq!(body["type"], "function");
+        assert_eq!(body["name"], "read_file");
+        assert!(body.get("function").is_none());
+    }
+}
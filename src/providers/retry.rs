@@ -0,0 +1,568 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Automatic retries for transient provider failures.
+//!
+//! [`RetryingProvider`] wraps any [`Provider`] and retries requests that fail
+//! with a retryable [`ProviderError`] (rate limits, overloads, network errors
+//! and timeouts). Delays grow exponentially with jitter, and a server-provided
+//! `Retry-After` delay takes precedence over the computed backoff.
+//!
+//! Streaming requests are only retried while nothing has been streamed yet.
+//! Once text or tool input has reached the caller, a retry would duplicate
+//! output, so the error is returned instead.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+
+use crate::config::ResolvedConfig;
+use crate::error::ProviderError;
+use crate::types::{
+    BoxedProvider, ChatOptions, Message, ModelInfo, ModelPricing, Provider, ProviderResponse, StreamEvent,
+    ToolDefinition,
+};
+
+/// Retry policy for provider requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// Maximum number of attempts, including the first (1 disables retries).
+    pub max_attempts: u32,
+    /// Delay before the first retry in milliseconds.
+    pub initial_delay_ms: u64,
+    /// Upper bound for a single computed backoff delay in milliseconds.
+    pub max_delay_ms: u64,
+    /// Give up once this much time has passed since the first attempt.
+    pub max_elapsed_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay_ms: 1_000,
+            max_delay_ms: 30_000,
+            max_elapsed_ms: 120_000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// A policy that never retries.
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Build a policy from `maxRetryAttempts` / `maxRetryElapsedMs` in the resolved config.
+    pub fn from_config(config: &ResolvedConfig) -> Self {
+        Self {
+            max_attempts: config.max_retry_attempts.max(1),
+            max_elapsed_ms: config.max_retry_elapsed_ms,
+            ..Default::default()
+        }
+    }
+
+    /// Check if this policy allows any retries.
+    pub fn is_enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    /// Exponential backoff with jitter for the given failed attempt (1-based).
+    ///
+    /// The delay doubles with every attempt up to `max_delay_ms`, and the
+    /// result is randomized between half and the full value so that
+    /// concurrent clients do not retry in lockstep.
+    pub fn backoff_delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(20);
+        let base = self
+            .initial_delay_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_delay_ms);
+        let half = base / 2;
+        let jitter = if half > 0 { random_u64() % (half + 1) } else { 0 };
+        Duration::from_millis(half + jitter)
+    }
+
+    /// Delay before retrying after `error` on the given failed attempt (1-based).
+    ///
+    /// Returns `None` when the error is not retryable, attempts are exhausted,
+    /// or waiting would exceed the elapsed-time budget.
+    pub fn next_delay(
+        &self,
+        error: &ProviderError,
+        attempt: u32,
+        elapsed: Duration,
+    ) -> Option<Duration> {
+        if !error.is_retryable() || attempt >= self.max_attempts {
+            return None;
+        }
+
+        let delay = error
+            .retry_after()
+            .unwrap_or_else(|| self.backoff_delay(attempt));
+
+        if elapsed + delay > Duration::from_millis(self.max_elapsed_ms) {
+            return None;
+        }
+        Some(delay)
+    }
+}
+
+/// Parse a retry delay from response headers.
+///
+/// Supports `retry-after-ms` (milliseconds, sent by OpenAI) and `retry-after`
+/// as either a number of seconds or an HTTP date.
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    if let Some(ms) = headers
+        .get("retry-after-ms")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+    {
+        if ms.is_finite() && ms >= 0.0 {
+            return Some(Duration::from_millis(ms as u64));
+        }
+    }
+
+    let value = headers.get("retry-after")?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.signed_duration_since(chrono::Utc::now());
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Attach the `Retry-After` delay from `headers` to `error`, if present.
+pub fn with_retry_after(error: ProviderError, headers: &HeaderMap) -> ProviderError {
+    match parse_retry_after(headers) {
+        Some(delay) => error.with_retry_after(delay),
+        None => error,
+    }
+}
+
+/// Short status line for a pending retry, e.g. "Retrying in 8s… (attempt 2/5)".
+pub fn retry_status(attempt: u32, max_attempts: u32, delay_ms: u64) -> String {
+    format!(
+        "Retrying in {}s… (attempt {}/{})",
+        delay_ms.div_ceil(1000),
+        attempt,
+        max_attempts
+    )
+}
+
+/// Pseudo-random value for jitter; uses the per-process random hasher keys.
+fn random_u64() -> u64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
+
+/// Provider wrapper that retries transient failures.
+pub struct RetryingProvider {
+    inner: BoxedProvider,
+    config: RetryConfig,
+}
+
+impl RetryingProvider {
+    /// Wrap a provider with the given retry policy.
+    pub fn new(inner: BoxedProvider, config: RetryConfig) -> Self {
+        Self { inner, config }
+    }
+
+    /// Get the retry policy.
+    pub fn config(&self) -> &RetryConfig {
+        &self.config
+    }
+
+    fn log_retry(&self, error: &ProviderError, attempt: u32, delay: Duration) {
+        tracing::warn!(
+            "{} request failed (attempt {}/{}): {}; retrying in {:.1}s",
+            self.inner.name(),
+            attempt,
+            self.config.max_attempts,
+            error,
+            delay.as_secs_f64()
+        );
+    }
+}
+
+#[async_trait]
+impl Provider for RetryingProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let start = Instant::now();
+        let mut attempt = 1;
+
+        loop {
+            let error = match self.inner.chat(messages, tools, system_prompt, options).await {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+
+            let Some(delay) = self.config.next_delay(&error, attempt, start.elapsed()) else {
+                return Err(error);
+            };
+            self.log_retry(&error, attempt, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn stream_chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+        on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let on_event: Arc<dyn Fn(StreamEvent) + Send + Sync> = Arc::from(on_event);
+        let start = Instant::now();
+        let mut attempt = 1;
+
+        loop {
+            let streamed = Arc::new(AtomicBool::new(false));
+            let forward = {
+                let on_event = on_event.clone();
+                let streamed = streamed.clone();
+                Box::new(move |event: StreamEvent| {
+                    if matches!(
+                        event,
+                        StreamEvent::TextDelta(_)
+                            | StreamEvent::ReasoningDelta(_)
+                            | StreamEvent::ToolUseStart { .. }
+                            | StreamEvent::ToolInputDelta(_)
+                    ) {
+                        streamed.store(true, Ordering::SeqCst);
+                    }
+                    on_event(event);
+                })
+            };
+
+            let error = match self
+                .inner
+                .stream_chat(messages, tools, system_prompt, options, forward)
+                .await
+            {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+
+            // Partial output has already been shown; retrying would repeat it.
+            if streamed.load(Ordering::SeqCst) {
+                return Err(error);
+            }
+
+            let Some(delay) = self.config.next_delay(&error, attempt, start.elapsed()) else {
+                return Err(error);
+            };
+            self.log_retry(&error, attempt, delay);
+            on_event(StreamEvent::Retrying {
+                attempt: attempt + 1,
+                max_attempts: self.config.max_attempts,
+                delay_ms: delay.as_millis() as u64,
+                reason: error.to_string(),
+            });
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn supports_tool_use(&self) -> bool {
+        self.inner.supports_tool_use()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.inner.supports_structured_output()
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        schema: &serde_json::Value,
+    ) -> Result<ProviderResponse, ProviderError> {
+        let start = Instant::now();
+        let mut attempt = 1;
+
+        loop {
+            let error = match self.inner.chat_structured(messages, system_prompt, schema).await {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+
+            let Some(delay) = self.config.next_delay(&error, attempt, start.elapsed()) else {
+                return Err(error);
+            };
+            self.log_retry(&error, attempt, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn context_window(&self) -> u32 {
+        self.inner.context_window()
+    }
+
+    fn pricing(&self) -> Option<ModelPricing> {
+        self.inner.pricing()
+    }
+
+    async fn discover_capabilities(&self) {
+        self.inner.discover_capabilities().await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, ProviderError> {
+        self.inner.list_models().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::Mutex;
+
+    /// Provider that fails a fixed number of times before succeeding.
+    struct FlakyProvider {
+        failures: u32,
+        calls: Arc<AtomicU32>,
+        error: fn() -> ProviderError,
+        partial_output: bool,
+    }
+
+    impl FlakyProvider {
+        fn new(failures: u32, error: fn() -> ProviderError) -> (Self, Arc<AtomicU32>) {
+            let calls = Arc::new(AtomicU32::new(0));
+            let provider = Self {
+                failures,
+                calls: calls.clone(),
+                error,
+                partial_output: false,
+            };
+            (provider, calls)
+        }
+    }
+
+    #[async_trait]
+    impl Provider for FlakyProvider {
+        async fn chat(
+            &self,
+            messages: &[Message],
+            tools: Option<&[ToolDefinition]>,
+            system_prompt: Option<&str>,
+            options: &ChatOptions,
+        ) -> Result<ProviderResponse, ProviderError> {
+            self.stream_chat(messages, tools, system_prompt, options, Box::new(|_| {}))
+                .await
+        }
+
+        async fn stream_chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolDefinition]>,
+            _system_prompt: Option<&str>,
+            _options: &ChatOptions,
+            on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+        ) -> Result<ProviderResponse, ProviderError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if self.partial_output {
+                on_event(StreamEvent::TextDelta("partial".to_string()));
+            }
+            if call <= self.failures {
+                return Err((self.error)());
+            }
+            Ok(ProviderResponse::text("ok"))
+        }
+
+        fn supports_tool_use(&self) -> bool {
+            false
+        }
+
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn model(&self) -> &str {
+            "flaky-model"
+        }
+    }
+
+    fn fast_config(max_attempts: u32) -> RetryConfig {
+        RetryConfig {
+            max_attempts,
+            initial_delay_ms: 1,
+            max_delay_ms: 2,
+            max_elapsed_ms: 10_000,
+        }
+    }
+
+    fn overloaded() -> ProviderError {
+        ProviderError::RateLimited("API overloaded".to_string())
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let config = RetryConfig {
+            max_attempts: 10,
+            initial_delay_ms: 1_000,
+            max_delay_ms: 8_000,
+            max_elapsed_ms: 60_000,
+        };
+        for _ in 0..20 {
+            let first = config.backoff_delay(1).as_millis();
+            assert!((500..=1_000).contains(&first));
+            let third = config.backoff_delay(3).as_millis();
+            assert!((2_000..=4_000).contains(&third));
+            let capped = config.backoff_delay(9).as_millis();
+            assert!((4_000..=8_000).contains(&capped));
+        }
+    }
+
+    #[test]
+    fn test_next_delay_limits() {
+        let config = RetryConfig {
+            max_attempts: 3,
+            max_elapsed_ms: 10_000,
+            ..Default::default()
+        };
+
+        let auth = ProviderError::AuthError("bad key".to_string());
+        assert_eq!(config.next_delay(&auth, 1, Duration::ZERO), None);
+        assert!(config.next_delay(&overloaded(), 2, Duration::ZERO).is_some());
+        assert_eq!(config.next_delay(&overloaded(), 3, Duration::ZERO), None);
+
+        // Retry-After wins over the computed backoff, within the elapsed budget
+        let err = overloaded().with_retry_after(Duration::from_secs(8));
+        assert_eq!(
+            config.next_delay(&err, 1, Duration::ZERO),
+            Some(Duration::from_secs(8))
+        );
+        assert_eq!(config.next_delay(&err, 1, Duration::from_secs(3)), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert("retry-after", "8".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(8)));
+
+        headers.insert("retry-after-ms", "1500".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_millis(1500)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert("retry-after", "soon".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_retry_status() {
+        assert_eq!(retry_status(2, 5, 7_200), "Retrying in 8s… (attempt 2/5)");
+        assert_eq!(retry_status(3, 3, 0), "Retrying in 0s… (attempt 3/3)");
+    }
+
+    #[tokio::test]
+    async fn test_chat_retries_until_success() {
+        let (inner, calls) = FlakyProvider::new(2, overloaded);
+        let provider = RetryingProvider::new(Box::new(inner), fast_config(5));
+
+        let response = provider.chat(&[], None, None, &ChatOptions::default()).await.unwrap();
+        assert_eq!(response.content, "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_chat_gives_up_after_max_attempts() {
+        let (inner, calls) = FlakyProvider::new(10, overloaded);
+        let provider = RetryingProvider::new(Box::new(inner), fast_config(3));
+
+        let err = provider.chat(&[], None, None, &ChatOptions::default()).await.unwrap_err();
+        assert!(err.is_rate_limited());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_fails_immediately() {
+        let (inner, calls) =
+            FlakyProvider::new(1, || ProviderError::AuthError("bad key".to_string()));
+        let provider = RetryingProvider::new(Box::new(inner), fast_config(5));
+
+        assert!(provider.chat(&[], None, None, &ChatOptions::default()).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_emits_retrying_event() {
+        let (inner, calls) = FlakyProvider::new(1, || ProviderError::api("overloaded", 529));
+        let provider = RetryingProvider::new(Box::new(inner), fast_config(4));
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let response = provider
+            .stream_chat(
+                &[],
+                None,
+                None,
+                &ChatOptions::default(),
+                Box::new(move |e| sink.lock().unwrap().push(e)),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let events = events.lock().unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [StreamEvent::Retrying { attempt: 2, max_attempts: 4, reason, .. }] if reason.contains("overloaded")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stream_not_retried_after_partial_output() {
+        let (mut inner, calls) = FlakyProvider::new(1, overloaded);
+        inner.partial_output = true;
+        let provider = RetryingProvider::new(Box::new(inner), fast_config(5));
+
+        let result = provider.stream_chat(&[], None, None, &ChatOptions::default(), Box::new(|_| {})).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
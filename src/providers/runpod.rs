@@ -0,0 +1,719 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! RunPod serverless provider implementation.
+//!
+//! RunPod endpoints are addressed by endpoint ID under
+//! `https://api.runpod.ai/v2/<endpoint>`. Two APIs are supported:
+//!
+//! - [`RunPodApi::OpenAI`] (default) - the worker's OpenAI-compatible route
+//!   at `/openai/v1`, served by worker-vllm and similar workers. Requests go
+//!   through [`OpenAIProvider`] with streaming and tool use.
+//! - [`RunPodApi::Queue`] - the async job API: a job is submitted to `/run`
+//!   and `/status/<job>` is polled until it finishes. This works with any
+//!   worker; input follows the worker-vllm `messages` format and common
+//!   output shapes are understood.
+//!
+//! [`RunPodApi::OpenAI`]: crate::types::RunPodApi::OpenAI
+//! [`RunPodApi::Queue`]: crate::types::RunPodApi::Queue
+//!
+//! Serverless workers scale to zero, so the first request after a while
+//! waits for a cold start. Timeouts allow for it: a queued job may wait
+//! [`DEFAULT_COLD_START_TIMEOUT_SECS`] for a worker before the request
+//! timeout starts counting.
+//!
+//! # Configuration
+//!
+//! ```bash
+//! export RUNPOD_API_KEY=your-key
+//! codi --provider runpod --endpoint-id abc123 --model meta-llama/Llama-3.1-8B-Instruct
+//! ```
+//!
+//! Set `runpodApi: queue` in `.codi.json` for workers without the OpenAI route.
+//!
+//! # API Reference
+//!
+//! See [RunPod Serverless](https://docs.runpod.io/serverless/endpoints/send-requests) for details.
+
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "telemetry")]
+use tracing::debug;
+
+#[cfg(feature = "telemetry")]
+use crate::telemetry::metrics::GLOBAL_METRICS;
+
+use crate::error::ProviderError;
+use crate::types::{
+    ChatOptions, ContentBlock, ContentBlockType, Message, MessageContent, Provider, ProviderConfig,
+    ProviderResponse, Role, StopReason, StreamEvent, TokenUsage, ToolDefinition,
+};
+
+use super::openai::OpenAIProvider;
+use super::retry::with_retry_after;
+use super::unsupported_option;
+
+/// Default max output tokens if not specified.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Default time a request may run once a worker picks it up, in seconds.
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Default time to wait for a worker to start, in seconds.
+pub const DEFAULT_COLD_START_TIMEOUT_SECS: u64 = 600;
+
+/// Default interval between job status polls.
+const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+
+/// Timeout for a single `/run` or `/status` request.
+const QUEUE_REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// URL of an endpoint under the RunPod API root (e.g. `https://api.runpod.ai/v2`).
+pub fn endpoint_url(api_root: &str, endpoint_id: &str) -> String {
+    format!("{}/{}", api_root.trim_end_matches('/'), endpoint_id)
+}
+
+/// Create a provider for an endpoint's OpenAI-compatible route.
+///
+/// Without an explicit `timeout_ms`, requests may take a cold start plus the
+/// default request timeout, since RunPod holds them while a worker starts.
+pub fn openai_provider(
+    api_key: impl Into<String>,
+    model: impl Into<String>,
+    api_root: &str,
+    endpoint_id: &str,
+    mut config: ProviderConfig,
+) -> OpenAIProvider {
+    config.timeout_ms.get_or_insert((DEFAULT_COLD_START_TIMEOUT_SECS + DEFAULT_TIMEOUT_SECS) * 1000);
+    let base_url = format!("{}/openai/v1", endpoint_url(api_root, endpoint_id));
+    OpenAIProvider::new(Some(api_key.into()), model, base_url, config)
+}
+
+/// RunPod provider for the async job queue API.
+///
+/// Implements the [`Provider`] trait by submitting jobs to `/run` and polling
+/// `/status` until they complete.
+pub struct RunPodProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    endpoint_url: String,
+    max_tokens: u32,
+    temperature: Option<f32>,
+    timeout: Duration,
+    cold_start_timeout: Duration,
+    poll_interval: Duration,
+}
+
+impl RunPodProvider {
+    /// Create a new RunPod queue provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - RunPod API key
+    /// * `endpoint_id` - Serverless endpoint ID
+    /// * `api_root` - API root without the endpoint (e.g., "https://api.runpod.ai/v2")
+    /// * `config` - Additional configuration options; the model is only informational
+    pub fn new(
+        api_key: impl Into<String>,
+        endpoint_id: &str,
+        api_root: &str,
+        config: ProviderConfig,
+    ) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(QUEUE_REQUEST_TIMEOUT_SECS))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            client,
+            api_key: api_key.into(),
+            model: config.model.clone().unwrap_or_else(|| endpoint_id.to_string()),
+            endpoint_url: endpoint_url(api_root, endpoint_id),
+            max_tokens: config.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            temperature: config.temperature,
+            timeout: config
+                .timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(Duration::from_secs(DEFAULT_TIMEOUT_SECS)),
+            cold_start_timeout: Duration::from_secs(DEFAULT_COLD_START_TIMEOUT_SECS),
+            poll_interval: Duration::from_millis(DEFAULT_POLL_INTERVAL_MS),
+        }
+    }
+
+    /// Set how long a job may wait in the queue for a worker.
+    pub fn with_cold_start_timeout(mut self, timeout: Duration) -> Self {
+        self.cold_start_timeout = timeout;
+        self
+    }
+
+    /// Set the interval between status polls.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Build the job input in the worker-vllm format.
+    fn build_request(
+        &self,
+        messages: &[Message],
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+    ) -> JobRequest {
+        let mut api_messages = Vec::new();
+        if let Some(system) = system_prompt.filter(|s| !s.is_empty()) {
+            api_messages.push(JobMessage {
+                role: "system".to_string(),
+                content: system.to_string(),
+            });
+        }
+        api_messages.extend(messages.iter().filter_map(convert_message));
+
+        JobRequest {
+            input: JobInput {
+                messages: api_messages,
+                sampling_params: SamplingParams {
+                    max_tokens: options.max_tokens.unwrap_or(self.max_tokens),
+                    temperature: options.temperature.or(self.temperature),
+                    stop: (!options.stop_sequences.is_empty()).then(|| options.stop_sequences.clone()),
+                },
+            },
+        }
+    }
+
+    /// Send a request to the endpoint and parse the job it returns.
+    async fn send_job_request(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<JobStatus, ProviderError> {
+        let response = request
+            .bearer_auth(&self.api_key)
+            .send()
+            .await
+            .map_err(|e| ProviderError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(with_retry_after(
+                handle_error_response(status.as_u16(), &error_text),
+                &headers,
+            ));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| ProviderError::ParseError(e.to_string()))
+    }
+
+    /// Submit a job and poll it until it finishes.
+    async fn run_job(&self, request: &JobRequest) -> Result<JobStatus, ProviderError> {
+        let mut job = self
+            .send_job_request(self.client.post(format!("{}/run", self.endpoint_url)).json(request))
+            .await?;
+
+        let submitted = Instant::now();
+        // Set once a worker picks the job up
+        let mut started: Option<Instant> = None;
+
+        loop {
+            match job.status.as_str() {
+                "COMPLETED" => return Ok(job),
+                "FAILED" => {
+                    let error = job.error.map(|e| error_text(&e)).unwrap_or_else(|| "job failed".to_string());
+                    return Err(ProviderError::api_message(format!("RunPod job {} failed: {}", job.id, error)));
+                }
+                "CANCELLED" => {
+                    return Err(ProviderError::api_message(format!("RunPod job {} was cancelled", job.id)));
+                }
+                "TIMED_OUT" => {
+                    return Err(ProviderError::Timeout(submitted.elapsed().as_millis() as u64));
+                }
+                "IN_PROGRESS" => {
+                    let started = *started.get_or_insert_with(Instant::now);
+                    if started.elapsed() > self.timeout {
+                        self.cancel(&job.id).await;
+                        return Err(ProviderError::Timeout(self.timeout.as_millis() as u64));
+                    }
+                }
+                // IN_QUEUE while a worker starts
+                _ => {
+                    if started.is_none() && submitted.elapsed() > self.cold_start_timeout {
+                        self.cancel(&job.id).await;
+                        return Err(ProviderError::Timeout(self.cold_start_timeout.as_millis() as u64));
+                    }
+                }
+            }
+
+            tokio::time::sleep(self.poll_interval).await;
+            job = self
+                .send_job_request(self.client.get(format!("{}/status/{}", self.endpoint_url, job.id)))
+                .await?;
+        }
+    }
+
+    /// Cancel a job that ran out of time, ignoring failures.
+    async fn cancel(&self, job_id: &str) {
+        let _ = self
+            .client
+            .post(format!("{}/cancel/{}", self.endpoint_url, job_id))
+            .bearer_auth(&self.api_key)
+            .send()
+            .await;
+    }
+}
+
+#[async_trait]
+impl Provider for RunPodProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        _tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+    ) -> Result<ProviderResponse, ProviderError> {
+        // Jobs take plain messages, so tool options have nothing to act on
+        if options.forces_tool_use() {
+            return Err(unsupported_option("RunPod job queue", "forced tool use"));
+        }
+        if !options.metadata.is_empty() {
+            return Err(unsupported_option("RunPod job queue", "request metadata"));
+        }
+        let request = self.build_request(messages, system_prompt, options);
+        let start = Instant::now();
+
+        #[cfg(feature = "telemetry")]
+        debug!(model = %self.model, messages = messages.len(), "Submitting RunPod job");
+
+        let result = self.run_job(&request).await;
+
+        #[cfg(feature = "telemetry")]
+        GLOBAL_METRICS.record_operation("runpod.chat", start.elapsed());
+        let _ = start;
+
+        let job = result?;
+        let mut content = String::new();
+        let mut usage = TokenUsage::default();
+        collect_output(&job.output.unwrap_or_default(), &mut content, &mut usage);
+
+        #[cfg(feature = "telemetry")]
+        GLOBAL_METRICS.record_tokens(usage.input_tokens as u64, usage.output_tokens as u64);
+
+        Ok(ProviderResponse {
+            content,
+            tool_calls: Vec::new(),
+            stop_reason: StopReason::EndTurn,
+            reasoning_content: None,
+            reasoning_signature: None,
+            usage: Some(usage),
+        })
+    }
+
+    async fn stream_chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolDefinition]>,
+        system_prompt: Option<&str>,
+        options: &ChatOptions,
+        on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
+    ) -> Result<ProviderResponse, ProviderError> {
+        // Jobs only report their output once complete
+        let response = self.chat(messages, tools, system_prompt, options).await?;
+        if !response.content.is_empty() {
+            on_event(StreamEvent::TextDelta(response.content.clone()));
+        }
+        if let Some(ref usage) = response.usage {
+            on_event(StreamEvent::Usage(usage.clone()));
+        }
+        on_event(StreamEvent::Done(response.stop_reason));
+        Ok(response)
+    }
+
+    fn supports_tool_use(&self) -> bool {
+        // Workers take plain messages; enable extract_tools_from_text for tools
+        false
+    }
+
+    fn name(&self) -> &str {
+        "RunPod"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+// ============================================================================
+// Message Conversion
+// ============================================================================
+
+/// Flatten a message to role and text; tool exchanges become text.
+fn convert_message(msg: &Message) -> Option<JobMessage> {
+    let role = match msg.role {
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::System => "system",
+    };
+
+    let content = match &msg.content {
+        MessageContent::Text(text) => text.clone(),
+        MessageContent::Blocks(blocks) => blocks
+            .iter()
+            .filter_map(block_text)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+    };
+
+    (!content.is_empty()).then(|| JobMessage {
+        role: role.to_string(),
+        content,
+    })
+}
+
+fn block_text(block: &ContentBlock) -> Option<String> {
+    match block.block_type {
+        ContentBlockType::Text => block.text.clone().filter(|t| !t.is_empty()),
+        ContentBlockType::ToolUse => Some(format!(
+            "[Tool call: {} {}]",
+            block.name.as_deref().unwrap_or_default(),
+            block.input.as_ref().map(|i| i.to_string()).unwrap_or_default()
+        )),
+        ContentBlockType::ToolResult => Some(format!(
+            "[Tool result{}]\n{}",
+            if block.is_error == Some(true) { " (error)" } else { "" },
+            block.content.as_deref().unwrap_or_default()
+        )),
+        ContentBlockType::Image => Some("[image omitted]".to_string()),
+        ContentBlockType::Thinking => None,
+    }
+}
+
+/// Collect text and usage from a job's output.
+///
+/// Understands plain strings, `{"text"}`/`{"output"}` objects, OpenAI-style
+/// `choices` (with `message.content`, `text` or worker-vllm `tokens`), and
+/// lists of any of these, such as worker-vllm's batched output.
+fn collect_output(output: &serde_json::Value, content: &mut String, usage: &mut TokenUsage) {
+    use serde_json::Value;
+
+    match output {
+        Value::String(text) => content.push_str(text),
+        Value::Array(items) => {
+            for item in items {
+                collect_output(item, content, usage);
+            }
+        }
+        Value::Object(map) => {
+            if let Some(choices) = map.get("choices").and_then(Value::as_array) {
+                for choice in choices {
+                    if let Some(text) = choice.pointer("/message/content").and_then(Value::as_str) {
+                        content.push_str(text);
+                    } else if let Some(text) = choice.get("text").and_then(Value::as_str) {
+                        content.push_str(text);
+                    } else if let Some(tokens) = choice.get("tokens").and_then(Value::as_array) {
+                        tokens.iter().filter_map(Value::as_str).for_each(|t| content.push_str(t));
+                    }
+                }
+            } else if let Some(inner) = map.get("text").or_else(|| map.get("output")) {
+                collect_output(inner, content, usage);
+            }
+
+            if let Some(counts) = map.get("usage") {
+                let count = |keys: &[&str]| {
+                    keys.iter()
+                        .find_map(|k| counts.get(*k).and_then(Value::as_u64))
+                        .unwrap_or(0) as u32
+                };
+                usage.input_tokens += count(&["input", "prompt_tokens", "input_tokens"]);
+                usage.output_tokens += count(&["output", "completion_tokens", "output_tokens"]);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Handle an error response from the API.
+fn handle_error_response(status_code: u16, body: &str) -> ProviderError {
+    let message = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("error").map(error_text))
+        .unwrap_or_else(|| body.to_string());
+    match status_code {
+        401 | 403 => ProviderError::AuthError(message),
+        404 => ProviderError::NotConfigured(format!("RunPod endpoint not found: {}", message)),
+        429 => ProviderError::RateLimited(message),
+        _ => ProviderError::api(message, status_code),
+    }
+}
+
+/// Error messages are strings or objects depending on the worker.
+fn error_text(error: &serde_json::Value) -> String {
+    match error {
+        serde_json::Value::String(text) => text.clone(),
+        other => other
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| other.to_string()),
+    }
+}
+
+// ============================================================================
+// API Types
+// ============================================================================
+
+/// Request body for `/run`.
+#[derive(Debug, Serialize)]
+struct JobRequest {
+    input: JobInput,
+}
+
+#[derive(Debug, Serialize)]
+struct JobInput {
+    messages: Vec<JobMessage>,
+    sampling_params: SamplingParams,
+}
+
+#[derive(Debug, Serialize)]
+struct JobMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SamplingParams {
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<String>>,
+}
+
+/// Job state returned by `/run` and `/status`.
+#[derive(Debug, Deserialize)]
+struct JobStatus {
+    id: String,
+    status: String,
+    #[serde(default)]
+    output: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(api_root: &str) -> RunPodProvider {
+        RunPodProvider::new("test-key", "ep-123", api_root, ProviderConfig::default())
+            .with_poll_interval(Duration::from_millis(10))
+    }
+
+    #[test]
+    fn test_endpoint_urls() {
+        assert_eq!(endpoint_url("https://api.runpod.ai/v2/", "ep-123"), "https://api.runpod.ai/v2/ep-123");
+
+        let provider = provider("https://api.runpod.ai/v2");
+        assert_eq!(provider.endpoint_url, "https://api.runpod.ai/v2/ep-123");
+        assert_eq!(provider.name(), "RunPod");
+        assert_eq!(provider.model(), "ep-123");
+        assert!(!provider.supports_tool_use());
+    }
+
+    #[test]
+    fn test_request_conversion() {
+        let provider = provider("http://localhost");
+        let messages = vec![
+            Message::user("Read a.rs"),
+            Message::with_blocks(
+                Role::Assistant,
+                vec![ContentBlock::tool_use("call_1", "read_file", serde_json::json!({ "path": "a.rs" }))],
+            ),
+            Message::with_blocks(Role::User, vec![ContentBlock::tool_result("call_1", "fn main() {}", false)]),
+        ];
+
+        let options = ChatOptions::new().with_stop_sequences(["</s>"]);
+        let request = provider.build_request(&messages, Some("Be brief"), &options);
+        let body = serde_json::to_value(request).unwrap();
+        let sent = body["input"]["messages"].as_array().unwrap();
+        assert_eq!(sent[0], serde_json::json!({ "role": "system", "content": "Be brief" }));
+        assert_eq!(sent[2]["content"], r#"[Tool call: read_file {"path":"a.rs"}]"#);
+        assert_eq!(sent[3]["content"], "[Tool result]\nfn main() {}");
+        assert_eq!(body["input"]["sampling_params"]["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(body["input"]["sampling_params"]["stop"], serde_json::json!(["</s>"]));
+    }
+
+    #[test]
+    fn test_collect_output_shapes() {
+        let cases = [
+            (serde_json::json!("plain"), "plain", 0),
+            (serde_json::json!({ "text": "wrapped" }), "wrapped", 0),
+            (
+                serde_json::json!([{ "choices": [{ "tokens": ["Hel", "lo"] }], "usage": { "input": 7, "output": 2 } }]),
+                "Hello",
+                9,
+            ),
+            (
+                serde_json::json!({
+                    "choices": [{ "message": { "role": "assistant", "content": "chat" } }],
+                    "usage": { "prompt_tokens": 3, "completion_tokens": 1 }
+                }),
+                "chat",
+                4,
+            ),
+        ];
+
+        for (output, text, tokens) in cases {
+            let mut content = String::new();
+            let mut usage = TokenUsage::default();
+            collect_output(&output, &mut content, &mut usage);
+            assert_eq!(content, text);
+            assert_eq!(usage.total(), tokens);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_job_against_mock_server() {
+        let mut server = mockito::Server::new_async().await;
+        let run = server
+            .mock("POST", "/ep-123/run")
+            .match_header("authorization", "Bearer test-key")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "input": { "messages": [{ "role": "user", "content": "Hi" }] }
+            })))
+            .with_body(r#"{"id":"job-1","status":"IN_QUEUE"}"#)
+            .create_async()
+            .await;
+        let in_progress = server
+            .mock("GET", "/ep-123/status/job-1")
+            .with_body(r#"{"id":"job-1","status":"IN_PROGRESS"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let completed = server
+            .mock("GET", "/ep-123/status/job-1")
+            .with_body(
+                serde_json::json!({
+                    "id": "job-1",
+                    "status": "COMPLETED",
+                    "output": [{ "choices": [{ "tokens": ["Hello!"] }], "usage": { "input": 4, "output": 2 } }]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let response = provider(&server.url())
+            .chat(&[Message::user("Hi")], None, None, &ChatOptions::default())
+            .await
+            .unwrap();
+
+        run.assert_async().await;
+        in_progress.assert_async().await;
+        completed.assert_async().await;
+        assert_eq!(response.content, "Hello!");
+        assert_eq!(response.stop_reason, StopReason::EndTurn);
+        let usage = response.usage.unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (4, 2));
+    }
+
+    #[tokio::test]
+    async fn test_failed_job() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/ep-123/run")
+            .with_body(r#"{"id":"job-2","status":"FAILED","error":"CUDA out of memory"}"#)
+            .create_async()
+            .await;
+
+        let err = provider(&server.url())
+            .chat(&[Message::user("Hi")], None, None, &ChatOptions::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("CUDA out of memory"));
+    }
+
+    #[tokio::test]
+    async fn test_cold_start_timeout_cancels_job() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/ep-123/run")
+            .with_body(r#"{"id":"job-3","status":"IN_QUEUE"}"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/ep-123/status/job-3")
+            .with_body(r#"{"id":"job-3","status":"IN_QUEUE"}"#)
+            .create_async()
+            .await;
+        let cancel = server
+            .mock("POST", "/ep-123/cancel/job-3")
+            .with_body(r#"{"id":"job-3","status":"CANCELLED"}"#)
+            .create_async()
+            .await;
+
+        let err = provider(&server.url())
+            .with_cold_start_timeout(Duration::from_millis(50))
+            .chat(&[Message::user("Hi")], None, None, &ChatOptions::default())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, ProviderError::Timeout(50)));
+        cancel.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_error_mapping() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/ep-123/run")
+            .with_status(401)
+            .with_body(r#"{"error":"Unauthorized"}"#)
+            .create_async()
+            .await;
+
+        let err = provider(&server.url())
+            .chat(&[Message::user("Hi")], None, None, &ChatOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::AuthError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_openai_route_against_mock_server() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/ep-123/openai/v1/chat/completions")
+            .match_header("authorization", "Bearer test-key")
+            .with_body(
+                serde_json::json!({
+                    "id": "chatcmpl-1",
+                    "choices": [{
+                        "index": 0,
+                        "message": { "role": "assistant", "content": "From vLLM" },
+                        "finish_reason": "stop"
+                    }],
+                    "usage": { "prompt_tokens": 5, "completion_tokens": 3, "total_tokens": 8 }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let provider = openai_provider("test-key", "llama", &server.url(), "ep-123", ProviderConfig::default());
+        let response = provider
+            .chat(&[Message::user("Hi")], None, None, &ChatOptions::default())
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(response.content, "From vLLM");
+        assert_eq!(response.usage.unwrap().total(), 8);
+    }
+}
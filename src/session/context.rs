@@ -203,9 +203,14 @@ pub fn estimate_messages_tokens(messages: &[Message]) -> u64 {
 
 /// Estimate tokens for text content.
 pub fn estimate_text_tokens(text: &str) -> u64 {
+    estimate_tokens_for_chars(text.len())
+}
+
+/// Estimate tokens for a number of characters (bytes) of text.
+pub fn estimate_tokens_for_chars(chars: usize) -> u64 {
     // Simple estimation: ~4 chars per token on average
     // This is a rough estimate; for accurate counting, use tiktoken
-    (text.len() as f64 * TOKENS_PER_CHAR) as u64
+    (chars as f64 * TOKENS_PER_CHAR) as u64
 }
 
 /// Extract text content from a message.
@@ -68,7 +68,7 @@ pub use context::{
 pub use service::SessionService;
 pub use storage::{SessionStorage, SCHEMA_VERSION};
 pub use types::{
-    Session, SessionConfig, SessionId, SessionInfo, SessionMessage, Todo, TodoStatus,
+    format_session_tree, Session, SessionConfig, SessionId, SessionInfo, SessionMessage, Todo, TodoStatus,
 };
 
 #[cfg(test)]
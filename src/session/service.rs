@@ -102,6 +102,28 @@ impl SessionService {
         Ok(session)
     }
 
+    /// Fork a session: create a child of `parent` holding `messages`.
+    ///
+    /// `messages` is usually the parent's conversation up to the fork point.
+    pub async fn fork(&self, parent: &Session, messages: &[Message]) -> Result<Session, ToolError> {
+        let title = format!("{} (fork)", parent.title);
+        let mut session = self
+            .create_child(&parent.id, title, parent.project_path.clone())
+            .await?;
+        session.label = parent.label.clone();
+        session.provider = parent.provider.clone();
+        session.model = parent.model.clone();
+        session.conversation_summary = parent.conversation_summary.clone();
+
+        let storage = self.storage.lock().await;
+        storage.update_session(&session)?;
+        for message in messages {
+            storage.add_message(&SessionMessage::from_message(session.id.clone(), message))?;
+        }
+
+        Ok(session)
+    }
+
     /// Get a session by ID.
     pub async fn get(&self, id: &str) -> Result<Option<Session>, ToolError> {
         #[cfg(feature = "telemetry")]
@@ -201,6 +223,30 @@ impl SessionService {
         Ok(messages)
     }
 
+    /// Truncate a session to its first `keep` messages.
+    ///
+    /// Message timestamps have one-second resolution, so kept messages that
+    /// share the cutoff second are deleted along with the rest and re-added.
+    /// Returns the number of messages removed.
+    pub async fn truncate_messages(&self, session_id: &str, keep: usize) -> Result<u32, ToolError> {
+        let storage = self.storage.lock().await;
+        let messages = storage.get_messages(session_id)?;
+        let Some(cutoff) = messages.get(keep).map(|m| m.created_at) else {
+            return Ok(0);
+        };
+
+        let deleted = storage.delete_messages_after(session_id, cutoff - 1)?;
+        let same_second: Vec<&SessionMessage> = messages[..keep]
+            .iter()
+            .filter(|m| m.created_at >= cutoff)
+            .collect();
+        for message in &same_second {
+            storage.add_message(message)?;
+        }
+
+        Ok(deleted - same_second.len() as u32)
+    }
+
     /// Get message count for a session.
     pub async fn get_message_count(&self, session_id: &str) -> Result<u32, ToolError> {
         let storage = self.storage.lock().await;
@@ -316,6 +362,7 @@ impl SessionService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::session::context::get_message_text;
     use tempfile::TempDir;
 
     async fn create_test_service() -> (SessionService, TempDir) {
@@ -409,6 +456,54 @@ mod tests {
         assert_eq!(messages.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_fork_session() {
+        let (service, _temp) = create_test_service().await;
+
+        let parent = service
+            .create("Approach".to_string(), "/path".to_string())
+            .await
+            .unwrap();
+        let history = [Message::user("Plan it"), Message::assistant("Two options")];
+        for message in &history {
+            service.add_message(&parent.id, message).await.unwrap();
+        }
+
+        let fork = service.fork(&parent, &history[..1]).await.unwrap();
+        assert_eq!(fork.parent_id.as_deref(), Some(parent.id.as_str()));
+        assert_eq!(fork.title, "Approach (fork)");
+        assert_eq!(service.get_messages(&fork.id).await.unwrap().len(), 1);
+        assert_eq!(service.get_messages(&parent.id).await.unwrap().len(), 2);
+
+        let sessions = service.list().await.unwrap();
+        let info = sessions.iter().find(|s| s.id == fork.id).unwrap();
+        assert_eq!(info.parent_id.as_deref(), Some(parent.id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_truncate_messages() {
+        let (service, _temp) = create_test_service().await;
+
+        let session = service
+            .create("Truncate Test".to_string(), "/path".to_string())
+            .await
+            .unwrap();
+
+        for text in ["one", "two", "three", "four"] {
+            service.add_message(&session.id, &Message::user(text)).await.unwrap();
+        }
+
+        let removed = service.truncate_messages(&session.id, 2).await.unwrap();
+        assert_eq!(removed, 2);
+
+        let messages = service.get_messages(&session.id).await.unwrap();
+        let texts: Vec<String> = messages.iter().map(get_message_text).collect();
+        assert_eq!(texts.iter().map(|t| t.trim()).collect::<Vec<_>>(), vec!["one", "two"]);
+
+        // Keeping more than exists is a no-op
+        assert_eq!(service.truncate_messages(&session.id, 5).await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_search_sessions() {
         let (service, _temp) = create_test_service().await;
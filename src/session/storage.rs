@@ -317,7 +317,8 @@ impl SessionStorage {
             SELECT s.id, s.title, s.label, s.project_path, s.project_name,
                    s.provider, s.model, s.prompt_tokens, s.completion_tokens, s.cost,
                    s.summary_message_id, s.created_at, s.updated_at,
-                   (SELECT COUNT(*) FROM session_messages WHERE session_id = s.id) as message_count
+                   (SELECT COUNT(*) FROM session_messages WHERE session_id = s.id) as message_count,
+                   s.parent_id
             FROM sessions s
             ORDER BY s.updated_at DESC
             "#,
@@ -332,6 +333,7 @@ impl SessionStorage {
 
                 Ok(SessionInfo {
                     id: row.get(0)?,
+                    parent_id: row.get(14)?,
                     title: row.get(1)?,
                     label: row.get(2)?,
                     project_path: row.get(3)?,
@@ -367,7 +369,8 @@ impl SessionStorage {
             SELECT s.id, s.title, s.label, s.project_path, s.project_name,
                    s.provider, s.model, s.prompt_tokens, s.completion_tokens, s.cost,
                    s.summary_message_id, s.created_at, s.updated_at,
-                   (SELECT COUNT(*) FROM session_messages WHERE session_id = s.id) as message_count
+                   (SELECT COUNT(*) FROM session_messages WHERE session_id = s.id) as message_count,
+                   s.parent_id
             FROM sessions s
             WHERE LOWER(s.title) LIKE ?
                OR LOWER(s.label) LIKE ?
@@ -386,6 +389,7 @@ impl SessionStorage {
 
                 Ok(SessionInfo {
                     id: row.get(0)?,
+                    parent_id: row.get(14)?,
                     title: row.get(1)?,
                     label: row.get(2)?,
                     project_path: row.get(3)?,
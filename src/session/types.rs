@@ -3,6 +3,8 @@
 
 //! Session types for conversation persistence and management.
 
+use std::collections::{HashMap, HashSet};
+
 use serde::{Deserialize, Serialize};
 
 use crate::types::{ContentBlock, Message, Role};
@@ -102,6 +104,8 @@ impl Session {
 pub struct SessionInfo {
     /// Session ID.
     pub id: SessionId,
+    /// Session this one was forked from.
+    pub parent_id: Option<SessionId>,
     /// Session title.
     pub title: String,
     /// Optional label.
@@ -154,6 +158,55 @@ impl SessionInfo {
     }
 }
 
+/// Render sessions as a fork hierarchy, one line per session.
+///
+/// Sessions whose parent isn't in `sessions` are roots and keep their order;
+/// forks are listed under their parent, oldest first. The `current` session
+/// is marked with `*`.
+pub fn format_session_tree(sessions: &[SessionInfo], current: Option<&str>) -> String {
+    let ids: HashSet<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+    let mut roots = Vec::new();
+    let mut children: HashMap<&str, Vec<&SessionInfo>> = HashMap::new();
+    for session in sessions {
+        match session.parent_id.as_deref() {
+            Some(parent) if ids.contains(parent) => children.entry(parent).or_default().push(session),
+            _ => roots.push(session),
+        }
+    }
+    for forks in children.values_mut() {
+        forks.sort_by_key(|s| s.created_at);
+    }
+
+    fn push_lines(
+        session: &SessionInfo,
+        children: &HashMap<&str, Vec<&SessionInfo>>,
+        current: Option<&str>,
+        prefix: &str,
+        connector: &str,
+        lines: &mut Vec<String>,
+    ) {
+        let marker = if current == Some(session.id.as_str()) { "* " } else { "  " };
+        lines.push(format!("{}{}{}{}  {}", marker, prefix, connector, session.format(), session.id));
+
+        let child_prefix = match connector {
+            "├─ " => format!("{}│  ", prefix),
+            "└─ " => format!("{}   ", prefix),
+            _ => prefix.to_string(),
+        };
+        let forks = children.get(session.id.as_str()).map(Vec::as_slice).unwrap_or_default();
+        for (i, fork) in forks.iter().enumerate() {
+            let connector = if i + 1 == forks.len() { "└─ " } else { "├─ " };
+            push_lines(fork, children, current, &child_prefix, connector, lines);
+        }
+    }
+
+    let mut lines = Vec::new();
+    for root in roots {
+        push_lines(root, &children, current, "", "", &mut lines);
+    }
+    lines.join("\n")
+}
+
 /// A message stored in a session.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionMessage {
@@ -330,6 +383,7 @@ mod tests {
     fn test_session_info_format() {
         let info = SessionInfo {
             id: "test".to_string(),
+            parent_id: None,
             title: "Test Session".to_string(),
             label: Some("My Label".to_string()),
             project_path: "/path".to_string(),
@@ -352,6 +406,43 @@ mod tests {
         assert!(formatted.contains("[myproject]"));
     }
 
+    #[test]
+    fn test_format_session_tree() {
+        let info = |id: &str, parent: Option<&str>, created_at: i64| SessionInfo {
+            id: id.to_string(),
+            parent_id: parent.map(str::to_string),
+            title: id.to_uppercase(),
+            label: None,
+            project_path: "/path".to_string(),
+            project_name: None,
+            provider: None,
+            model: None,
+            message_count: 2,
+            has_summary: false,
+            total_tokens: 0,
+            cost: 0.0,
+            created_at,
+            updated_at: created_at,
+        };
+        let sessions = vec![
+            info("b", Some("a"), 3),
+            info("a", None, 1),
+            info("c", Some("a"), 2),
+            info("d", Some("c"), 4),
+            info("orphan", Some("pruned"), 5),
+        ];
+
+        let tree = format_session_tree(&sessions, Some("d"));
+        let lines: Vec<&str> = tree.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("  A (2 msgs)"));
+        assert!(lines[1].starts_with("  ├─ C"));
+        assert!(lines[2].starts_with("* │  └─ D"));
+        assert!(lines[3].starts_with("  └─ B"));
+        assert!(lines[4].starts_with("  ORPHAN"));
+        assert!(lines[4].ends_with("  orphan"));
+    }
+
     #[test]
     fn test_todo_status() {
         let todo = Todo::new("Write tests".to_string());
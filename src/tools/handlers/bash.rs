@@ -79,6 +79,10 @@ impl ToolHandler for BashHandler {
         true // Shell commands can modify the system
     }
 
+    fn max_output_lines(&self) -> Option<usize> {
+        Some(MAX_OUTPUT_LINES)
+    }
+
     #[cfg_attr(feature = "telemetry", instrument(skip(self, input), fields(command, cwd, timeout_ms, exit_code)))]
     async fn execute(&self, input: serde_json::Value) -> Result<ToolOutput, ToolError> {
         let args: BashArgs = parse_arguments(&input)?;
@@ -247,13 +251,12 @@ fn format_bash_output(result: &BashResult) -> String {
         ));
     }
 
-    // Add stdout
+    // Add stdout (the registry spills anything past MAX_OUTPUT_LINES)
     if !result.stdout.is_empty() {
-        let truncated = truncate_output(&result.stdout, MAX_OUTPUT_LINES);
-        parts.push(truncated);
+        parts.push(result.stdout.clone());
     }
 
-    // Add stderr if present
+    // Add stderr if present, kept short so it can't crowd out stdout
     if !result.stderr.is_empty() {
         let truncated = truncate_output(&result.stderr, MAX_OUTPUT_LINES / 4);
         parts.push(format!("\n[stderr]\n{truncated}"));
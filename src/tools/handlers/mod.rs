@@ -11,6 +11,7 @@ mod glob;
 mod grep;
 mod list_dir;
 mod read_file;
+mod read_output;
 mod write_file;
 mod manage_rag;
 mod manage_symbols;
@@ -26,5 +27,6 @@ pub use manage_rag::ManageRAGHandler;
 pub use manage_symbols::ManageSymbolsHandler;
 pub use rag_search::RAGSearchHandler;
 pub use read_file::ReadFileHandler;
+pub use read_output::ReadOutputHandler;
 pub use symbol_index::FindSymbolHandler;
 pub use write_file::WriteFileHandler;
@@ -7,6 +7,7 @@
 //! - Offset and limit for reading portions of large files
 //! - Line numbers in output
 //! - UTF-8 handling with lossy conversion
+//! - Images (png, jpg, gif, webp) returned as image content
 
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -18,6 +19,7 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use tracing::{debug, instrument};
 
 use crate::error::ToolError;
+use crate::tools::image::{is_image_path, load_image};
 use crate::tools::registry::{ToolHandler, ToolOutput};
 use crate::tools::{parse_arguments, DEFAULT_READ_LIMIT, MAX_LINE_LENGTH};
 use crate::types::{InputSchema, ToolDefinition};
@@ -51,7 +53,10 @@ fn default_limit() -> usize {
 #[async_trait]
 impl ToolHandler for ReadFileHandler {
     fn definition(&self) -> ToolDefinition {
-        ToolDefinition::new("read_file", "Read the contents of a file")
+        ToolDefinition::new(
+            "read_file",
+            "Read the contents of a file. Images (png, jpg, gif, webp) are returned as images",
+        )
             .with_schema(
                 InputSchema::new()
                     .with_property("file_path", serde_json::json!({
@@ -107,6 +112,11 @@ impl ToolHandler for ReadFileHandler {
             ));
         }
 
+        if is_image_path(&path) {
+            let image = load_image(&path).await?;
+            return Ok(ToolOutput::image(image.describe(&path), image.source));
+        }
+
         // Read the file
         let lines = read_file_lines(&path, args.offset, args.limit).await?;
 
@@ -414,6 +424,26 @@ mod tests {
         assert!(content.contains("L2: line2"));
     }
 
+    #[tokio::test]
+    async fn test_read_file_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.png");
+        image::RgbImage::new(8, 4).save(&path).unwrap();
+
+        let handler = ReadFileHandler;
+        let result = handler
+            .execute(serde_json::json!({
+                "file_path": path.to_str().unwrap()
+            }))
+            .await
+            .unwrap();
+
+        assert!(result.content().ends_with("shot.png (8x4)"));
+        let image = result.attached_image().unwrap();
+        assert_eq!(image.media_type, crate::types::ImageMediaType::Png);
+        assert!(!image.data.is_empty());
+    }
+
     #[test]
     fn test_format_line_basic() {
         let bytes = b"hello world";
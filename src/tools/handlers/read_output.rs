@@ -0,0 +1,212 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Read output tool handler.
+//!
+//! Pages through or searches tool output that was too long to return in full
+//! and was saved to the [`OutputStore`] under a handle.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::ToolError;
+use crate::tools::output_store::OutputStore;
+use crate::tools::registry::{ToolHandler, ToolOutput};
+use crate::tools::{parse_arguments, truncate_text, DEFAULT_READ_LIMIT, MAX_LINE_LENGTH};
+use crate::types::{InputSchema, ToolDefinition};
+
+/// Default number of lines returned per page.
+const DEFAULT_PAGE_LINES: usize = 200;
+
+/// Handler for the `read_output` tool.
+pub struct ReadOutputHandler {
+    store: Arc<OutputStore>,
+}
+
+impl ReadOutputHandler {
+    /// Create a handler reading from `store`.
+    pub fn new(store: Arc<OutputStore>) -> Self {
+        Self { store }
+    }
+}
+
+/// Arguments for the read_output tool.
+#[derive(Debug, Deserialize)]
+struct ReadOutputArgs {
+    /// Handle from the truncated tool output.
+    handle: String,
+
+    /// 1-indexed line number to start from (default: 1).
+    #[serde(default = "default_offset")]
+    offset: usize,
+
+    /// Maximum number of lines to return (default: 200).
+    #[serde(default = "default_limit")]
+    limit: usize,
+
+    /// Only return lines matching this regex.
+    #[serde(default)]
+    pattern: Option<String>,
+}
+
+fn default_offset() -> usize {
+    1
+}
+
+fn default_limit() -> usize {
+    DEFAULT_PAGE_LINES
+}
+
+#[async_trait]
+impl ToolHandler for ReadOutputHandler {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "read_output",
+            "Page through or search the full output of an earlier tool call that was too long to show",
+        )
+        .with_schema(
+            InputSchema::new()
+                .with_property("handle", serde_json::json!({
+                    "type": "string",
+                    "description": "Handle given in the truncated output (e.g. 'bash-3')"
+                }))
+                .with_property("offset", serde_json::json!({
+                    "type": "integer",
+                    "description": "1-indexed line number to start from (default: 1)"
+                }))
+                .with_property("limit", serde_json::json!({
+                    "type": "integer",
+                    "description": "Maximum number of lines to return (default: 200)"
+                }))
+                .with_property("pattern", serde_json::json!({
+                    "type": "string",
+                    "description": "Regex; only matching lines at or after offset are returned"
+                }))
+                .with_required(vec!["handle".to_string()]),
+        )
+    }
+
+    fn is_mutating(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> Result<ToolOutput, ToolError> {
+        let args: ReadOutputArgs = parse_arguments(&input)?;
+
+        if args.offset == 0 {
+            return Err(ToolError::InvalidInput(
+                "offset must be a 1-indexed line number".to_string(),
+            ));
+        }
+        if args.limit == 0 {
+            return Err(ToolError::InvalidInput(
+                "limit must be greater than zero".to_string(),
+            ));
+        }
+        let limit = args.limit.min(DEFAULT_READ_LIMIT);
+
+        let pattern = args
+            .pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid pattern: {e}")))?;
+
+        let content = self.store.load(&args.handle).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                ToolError::InvalidInput(format!("No saved output with handle {}", args.handle))
+            }
+            _ => ToolError::IoError(format!("Failed to read saved output: {e}")),
+        })?;
+
+        let total = content.lines().count();
+        if args.offset > total.max(1) {
+            return Err(ToolError::InvalidInput(format!(
+                "offset exceeds output length ({total} lines)"
+            )));
+        }
+
+        let mut matching = content
+            .lines()
+            .enumerate()
+            .skip(args.offset - 1)
+            .filter(|(_, line)| pattern.as_ref().is_none_or(|re| re.is_match(line)));
+        let page: Vec<String> = matching
+            .by_ref()
+            .take(limit)
+            .map(|(i, line)| format!("L{}: {}", i + 1, truncate_text(line, MAX_LINE_LENGTH)))
+            .collect();
+        let next = matching.next().map(|(i, _)| i + 1);
+
+        let mut output = if page.is_empty() {
+            "No matching lines.".to_string()
+        } else {
+            page.join("\n")
+        };
+        match next {
+            Some(line) => output.push_str(&format!(
+                "\n\n[{total} lines total; more from line {line}, use offset={line} to continue]"
+            )),
+            None => output.push_str(&format!("\n\n[end of output, {total} lines total]")),
+        }
+
+        Ok(ToolOutput::success(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handler_with_output(lines: usize) -> (ReadOutputHandler, String) {
+        let store = Arc::new(OutputStore::temporary());
+        let content: Vec<String> = (1..=lines).map(|i| format!("line {i}")).collect();
+        let handle = store.save("bash", &content.join("\n")).unwrap();
+        (ReadOutputHandler::new(store), handle)
+    }
+
+    #[tokio::test]
+    async fn test_read_output_pages() {
+        let (handler, handle) = handler_with_output(10);
+
+        let output = handler
+            .execute(serde_json::json!({ "handle": handle, "offset": 3, "limit": 2 }))
+            .await
+            .unwrap();
+        assert_eq!(
+            output.content(),
+            "L3: line 3\nL4: line 4\n\n[10 lines total; more from line 5, use offset=5 to continue]"
+        );
+
+        let output = handler
+            .execute(serde_json::json!({ "handle": handle, "offset": 9 }))
+            .await
+            .unwrap();
+        assert!(output.content().ends_with("L10: line 10\n\n[end of output, 10 lines total]"));
+    }
+
+    #[tokio::test]
+    async fn test_read_output_searches() {
+        let (handler, handle) = handler_with_output(30);
+
+        let output = handler
+            .execute(serde_json::json!({ "handle": handle, "pattern": "^line 2\\d$", "limit": 3 }))
+            .await
+            .unwrap();
+        assert!(output.content().starts_with("L20: line 20\nL21: line 21\nL22: line 22\n"));
+        assert!(output.content().contains("more from line 23"));
+    }
+
+    #[tokio::test]
+    async fn test_read_output_unknown_handle() {
+        let (handler, _) = handler_with_output(1);
+        let err = handler
+            .execute(serde_json::json!({ "handle": "bash-42" }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No saved output with handle bash-42"));
+    }
+}
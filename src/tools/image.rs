@@ -0,0 +1,233 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Image loading for vision-capable models.
+//!
+//! Images are sent to providers as base64 [`ImageSource`]s. Provider caps
+//! differ, so [`load_image`] keeps every image within the strictest common
+//! limits: the long edge is scaled down to [`MAX_IMAGE_DIMENSION`] pixels and
+//! the base64 data must fit in [`MAX_IMAGE_BYTES`]. Images already within the
+//! limits are sent unchanged.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader};
+
+use crate::error::ToolError;
+use crate::types::{ImageMediaType, ImageSource};
+
+/// Maximum length of an image's long edge in pixels.
+pub const MAX_IMAGE_DIMENSION: u32 = 1568;
+
+/// Maximum size of an image's base64 data in bytes.
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024; // 5 MiB
+
+/// JPEG quality used when an image has to be re-encoded to fit.
+const JPEG_QUALITY: u8 = 85;
+
+/// An image ready to send to a provider.
+#[derive(Debug, Clone)]
+pub struct LoadedImage {
+    /// Base64 image data.
+    pub source: ImageSource,
+    /// Width after any downscaling.
+    pub width: u32,
+    /// Height after any downscaling.
+    pub height: u32,
+    /// Original dimensions, if the image was downscaled or re-encoded.
+    pub original: Option<(u32, u32)>,
+}
+
+impl LoadedImage {
+    /// One-line description for the model, e.g. `Image: a.png (800x600)`.
+    pub fn describe(&self, path: &Path) -> String {
+        match self.original {
+            Some((w, h)) => format!(
+                "Image: {} ({}x{}, resized from {}x{})",
+                path.display(),
+                self.width,
+                self.height,
+                w,
+                h
+            ),
+            None => format!("Image: {} ({}x{})", path.display(), self.width, self.height),
+        }
+    }
+}
+
+/// Media type for a path with an image extension (png, jpg/jpeg, gif, webp).
+pub fn media_type_for_path(path: &Path) -> Option<ImageMediaType> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some(ImageMediaType::Png),
+        "jpg" | "jpeg" => Some(ImageMediaType::Jpeg),
+        "gif" => Some(ImageMediaType::Gif),
+        "webp" => Some(ImageMediaType::Webp),
+        _ => None,
+    }
+}
+
+/// Whether a path has an image extension supported by providers.
+pub fn is_image_path(path: &Path) -> bool {
+    media_type_for_path(path).is_some()
+}
+
+/// Read an image file and prepare it for a provider.
+pub async fn load_image(path: &Path) -> Result<LoadedImage, ToolError> {
+    let bytes = tokio::fs::read(path).await.map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => ToolError::FileNotFound(path.display().to_string()),
+        std::io::ErrorKind::PermissionDenied => {
+            ToolError::PermissionDenied(path.display().to_string())
+        }
+        _ => ToolError::IoError(format!("Failed to read image: {e}")),
+    })?;
+
+    // Decoding and resizing large images is CPU-bound
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || prepare_image(bytes))
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(format!("Image task failed: {e}")))?
+        .map_err(|e| ToolError::InvalidInput(format!("{}: {}", path.display(), e)))
+}
+
+/// Fit encoded image bytes within the provider limits.
+pub fn prepare_image(bytes: Vec<u8>) -> Result<LoadedImage, String> {
+    let format = image::guess_format(&bytes).map_err(|_| "not a recognized image".to_string())?;
+    let media_type = match format {
+        ImageFormat::Png => ImageMediaType::Png,
+        ImageFormat::Jpeg => ImageMediaType::Jpeg,
+        ImageFormat::Gif => ImageMediaType::Gif,
+        ImageFormat::WebP => ImageMediaType::Webp,
+        other => return Err(format!("unsupported image format {:?}", other)),
+    };
+
+    let (width, height) = ImageReader::with_format(Cursor::new(&bytes), format)
+        .into_dimensions()
+        .map_err(|e| format!("invalid image: {e}"))?;
+
+    if width.max(height) <= MAX_IMAGE_DIMENSION && fits(&bytes) {
+        return Ok(LoadedImage {
+            source: ImageSource::new_base64(media_type, encode_base64(&bytes)),
+            width,
+            height,
+            original: None,
+        });
+    }
+
+    let decoded = image::load_from_memory_with_format(&bytes, format)
+        .map_err(|e| format!("invalid image: {e}"))?;
+    let mut max_edge = width.max(height).min(MAX_IMAGE_DIMENSION);
+
+    // Lossless PNG first to keep screenshots crisp, then shrinking JPEGs
+    loop {
+        let resized = decoded.resize(max_edge, max_edge, FilterType::Triangle);
+        let (data, media_type) = if format == ImageFormat::Png {
+            match encode_png(&resized) {
+                Some(data) if fits(&data) => (data, ImageMediaType::Png),
+                _ => (encode_jpeg(&resized)?, ImageMediaType::Jpeg),
+            }
+        } else {
+            (encode_jpeg(&resized)?, ImageMediaType::Jpeg)
+        };
+
+        if fits(&data) {
+            return Ok(LoadedImage {
+                source: ImageSource::new_base64(media_type, encode_base64(&data)),
+                width: resized.width(),
+                height: resized.height(),
+                original: Some((width, height)),
+            });
+        }
+        if max_edge <= 64 {
+            return Err("image is too large to send".to_string());
+        }
+        max_edge = max_edge * 3 / 4;
+    }
+}
+
+/// Whether image bytes stay within [`MAX_IMAGE_BYTES`] once base64-encoded.
+fn fits(bytes: &[u8]) -> bool {
+    bytes.len().div_ceil(3) * 4 <= MAX_IMAGE_BYTES
+}
+
+fn encode_png(image: &DynamicImage) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+        .ok()?;
+    Some(data)
+}
+
+fn encode_jpeg(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(|e| format!("failed to encode image: {e}"))?;
+    Ok(data)
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, Rgb([200, 30, 30])));
+        encode_png(&image).unwrap()
+    }
+
+    #[test]
+    fn test_media_type_for_path() {
+        assert_eq!(media_type_for_path(Path::new("a/shot.PNG")), Some(ImageMediaType::Png));
+        assert_eq!(media_type_for_path(Path::new("b.jpeg")), Some(ImageMediaType::Jpeg));
+        assert_eq!(media_type_for_path(Path::new("c.webp")), Some(ImageMediaType::Webp));
+        assert_eq!(media_type_for_path(Path::new("main.rs")), None);
+        assert_eq!(media_type_for_path(Path::new("png")), None);
+    }
+
+    #[test]
+    fn test_small_image_is_sent_unchanged() {
+        let bytes = png(40, 20);
+        let loaded = prepare_image(bytes.clone()).unwrap();
+
+        assert_eq!((loaded.width, loaded.height), (40, 20));
+        assert_eq!(loaded.original, None);
+        assert_eq!(loaded.source.media_type, ImageMediaType::Png);
+        assert_eq!(loaded.source.data, encode_base64(&bytes));
+        assert_eq!(loaded.describe(Path::new("a.png")), "Image: a.png (40x20)");
+    }
+
+    #[test]
+    fn test_large_image_is_downscaled() {
+        let loaded = prepare_image(png(3136, 1000)).unwrap();
+
+        assert_eq!(loaded.width, MAX_IMAGE_DIMENSION);
+        assert_eq!(loaded.height, 500);
+        assert_eq!(loaded.original, Some((3136, 1000)));
+        assert_eq!(
+            loaded.describe(Path::new("a.png")),
+            "Image: a.png (1568x500, resized from 3136x1000)"
+        );
+    }
+
+    #[test]
+    fn test_limit_applies_to_base64_size() {
+        let largest = MAX_IMAGE_BYTES / 4 * 3;
+        assert!(fits(&vec![0; largest]));
+        assert!(!fits(&vec![0; largest + 1]));
+        assert!(!fits(&vec![0; MAX_IMAGE_BYTES]));
+    }
+
+    #[test]
+    fn test_rejects_non_images() {
+        assert!(prepare_image(b"fn main() {}".to_vec()).is_err());
+    }
+}
@@ -28,9 +28,12 @@
 //! ```
 
 pub mod handlers;
+pub mod image;
+pub mod output_store;
 pub mod registry;
 
 pub use handlers::*;
+pub use output_store::OutputStore;
 pub use registry::{DispatchResult, ToolHandler, ToolOutput, ToolRegistry, ToolRegistryBuilder};
 
 use serde::Deserialize;
@@ -51,6 +54,10 @@ where
 pub const TELEMETRY_PREVIEW_MAX_BYTES: usize = 2 * 1024; // 2 KiB
 pub const TELEMETRY_PREVIEW_MAX_LINES: usize = 64;
 
+/// Maximum bytes of tool output returned to the model; longer output is saved
+/// to the registry's [`OutputStore`] and previewed.
+pub const MAX_OUTPUT_BYTES: usize = 100 * 1024; // 100 KiB
+
 /// Default limit for file reading operations.
 pub const DEFAULT_READ_LIMIT: usize = 2000;
 
@@ -63,6 +70,9 @@ pub const DEFAULT_TIMEOUT_MS: u64 = 120_000; // 2 minutes
 /// Maximum timeout for command execution in milliseconds.
 pub const MAX_TIMEOUT_MS: u64 = 600_000; // 10 minutes
 
+/// Built-in tools that write the single file named by their `file_path` input.
+pub const FILE_EDIT_TOOLS: &[&str] = &["write_file", "edit_file"];
+
 /// Truncate text to a maximum byte length, respecting UTF-8 boundaries.
 pub fn truncate_text(text: &str, max_bytes: usize) -> String {
     if text.len() <= max_bytes {
@@ -146,6 +156,14 @@ mod tests {
         assert!(truncated.contains("omitted"));
     }
 
+    #[test]
+    fn test_file_edit_tools_are_registered() {
+        let registry = ToolRegistry::with_defaults();
+        for name in FILE_EDIT_TOOLS {
+            assert!(registry.contains(name), "{} is not registered", name);
+        }
+    }
+
     #[test]
     fn test_parse_arguments() {
         #[derive(Deserialize)]
@@ -0,0 +1,285 @@
+// Copyright 2026 Layne Penney
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Scratch storage for oversized tool output.
+//!
+//! Output longer than [`MAX_OUTPUT_BYTES`](super::MAX_OUTPUT_BYTES), or than
+//! a tool's [`max_output_lines`](super::ToolHandler::max_output_lines), isn't
+//! lost: the registry saves the full output here and gives the model a
+//! head/tail preview with a handle. The `read_output` tool pages or searches
+//! the saved output by handle.
+//!
+//! Outputs of a saved session are kept in `~/.codi/outputs/<session-id>/`, so
+//! handles in a reloaded conversation still resolve. Until a session is
+//! opened, a store made with [`OutputStore::temporary`] uses its own
+//! directory under the system temp dir and removes it when dropped.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::config::get_global_config_dir;
+
+/// Directory inside the global config directory holding session outputs.
+pub const OUTPUTS_DIR: &str = "outputs";
+
+/// Saved tool outputs, addressed by handle.
+#[derive(Debug)]
+pub struct OutputStore {
+    location: Mutex<Location>,
+    next_id: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Location {
+    dir: PathBuf,
+    /// Remove `dir` when the store moves away from it or is dropped.
+    temporary: bool,
+}
+
+impl Location {
+    fn release(&self) {
+        if self.temporary {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+impl OutputStore {
+    /// Store outputs in `dir`, which is created on first use and kept.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self::at(dir.into(), false)
+    }
+
+    /// Store outputs in a fresh temp directory removed when the store is dropped.
+    pub fn temporary() -> Self {
+        Self::at(
+            std::env::temp_dir().join(format!("codi-output-{}", uuid::Uuid::new_v4())),
+            true,
+        )
+    }
+
+    /// Store the outputs of a saved session, falling back to a temp directory.
+    pub fn for_session(session_id: &str) -> Self {
+        match session_outputs_dir(session_id) {
+            Some(dir) => Self::new(dir),
+            None => Self::temporary(),
+        }
+    }
+
+    fn at(dir: PathBuf, temporary: bool) -> Self {
+        Self {
+            location: Mutex::new(Location { dir, temporary }),
+            next_id: AtomicUsize::new(1),
+        }
+    }
+
+    /// Directory the outputs are written to.
+    pub fn dir(&self) -> PathBuf {
+        self.location.lock().unwrap().dir.clone()
+    }
+
+    /// Switch to the outputs of a saved session, e.g. after loading it.
+    pub fn open_session(&self, session_id: &str) {
+        let Some(dir) = session_outputs_dir(session_id) else {
+            return;
+        };
+        let mut location = self.location.lock().unwrap();
+        location.release();
+        *location = Location { dir, temporary: false };
+    }
+
+    /// Copy the current outputs to a forked session and switch to it.
+    pub fn fork_session(&self, session_id: &str) -> io::Result<()> {
+        let Some(dir) = session_outputs_dir(session_id) else {
+            return Ok(());
+        };
+        let mut location = self.location.lock().unwrap();
+        if location.dir.exists() {
+            std::fs::create_dir_all(&dir)?;
+            for entry in std::fs::read_dir(&location.dir)? {
+                let entry = entry?;
+                std::fs::copy(entry.path(), dir.join(entry.file_name()))?;
+            }
+        }
+        location.release();
+        *location = Location { dir, temporary: false };
+        Ok(())
+    }
+
+    /// Save the output of a tool call and return its handle.
+    pub fn save(&self, tool_name: &str, content: &str) -> io::Result<String> {
+        let prefix: String = tool_name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        let dir = self.dir();
+        std::fs::create_dir_all(&dir)?;
+
+        // A reopened session already holds earlier handles
+        loop {
+            let handle = format!("{}-{}", prefix, self.next_id.fetch_add(1, Ordering::SeqCst));
+            match OpenOptions::new().write(true).create_new(true).open(handle_path(&dir, &handle)?) {
+                Ok(mut file) => {
+                    file.write_all(content.as_bytes())?;
+                    return Ok(handle);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Load a saved output by handle.
+    pub fn load(&self, handle: &str) -> io::Result<String> {
+        std::fs::read_to_string(handle_path(&self.dir(), handle)?)
+    }
+}
+
+impl Drop for OutputStore {
+    fn drop(&mut self) {
+        self.location.lock().unwrap().release();
+    }
+}
+
+/// File of a handle; rejects anything that could escape the store.
+fn handle_path(dir: &Path, handle: &str) -> io::Result<PathBuf> {
+    let valid = !handle.is_empty()
+        && handle
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid output handle: {}", handle),
+        ));
+    }
+    Ok(dir.join(format!("{}.txt", handle)))
+}
+
+/// Directory holding the saved outputs of a session.
+pub fn session_outputs_dir(session_id: &str) -> Option<PathBuf> {
+    get_global_config_dir().map(|dir| dir.join(OUTPUTS_DIR).join(session_id))
+}
+
+/// Delete the saved outputs of a session.
+pub fn remove_session_outputs(session_id: &str) -> io::Result<()> {
+    match session_outputs_dir(session_id) {
+        Some(dir) if dir.exists() => std::fs::remove_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+/// Head and tail of `output`, within `max_lines` lines and `max_bytes` bytes.
+fn head_and_tail(output: &str, max_lines: usize, max_bytes: usize) -> (&str, &str) {
+    let output = output.strip_suffix('\n').unwrap_or(output);
+    let keep = max_lines / 2;
+    let half = max_bytes / 2;
+
+    let mut head_end = match keep {
+        0 => 0,
+        _ => output.match_indices('\n').nth(keep - 1).map_or(output.len(), |(i, _)| i),
+    }
+    .min(half);
+    while !output.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+
+    let mut tail_start = match keep {
+        0 => output.len(),
+        _ => output.rmatch_indices('\n').nth(keep - 1).map_or(0, |(i, _)| i + 1),
+    }
+    .max(output.len().saturating_sub(half))
+    .max(head_end);
+    while !output.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+
+    (&output[..head_end], &output[tail_start..])
+}
+
+/// Head/tail preview of `output`, with `note` describing what was left out.
+pub fn preview(output: &str, max_lines: usize, max_bytes: usize, note: &str) -> String {
+    let (head, tail) = head_and_tail(output, max_lines, max_bytes);
+    format!(
+        "{}\n\n... [output truncated: {} lines, {} bytes in total{}] ...\n\n{}",
+        head,
+        output.lines().count(),
+        output.len(),
+        note,
+        tail
+    )
+}
+
+/// Head/tail preview of output saved as `handle`.
+pub fn spill_preview(output: &str, max_lines: usize, max_bytes: usize, handle: &str) -> String {
+    preview(
+        output,
+        max_lines,
+        max_bytes,
+        &format!(
+            ". The full output is saved as `{}`; \
+             use read_output with this handle to page through or search it",
+            handle
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let store = OutputStore::temporary();
+        let dir = store.dir();
+
+        let first = store.save("bash", "one\ntwo").unwrap();
+        let second = store.save("bash", "three").unwrap();
+        assert_eq!(first, "bash-1");
+        assert_eq!(second, "bash-2");
+        assert_eq!(store.load(&first).unwrap(), "one\ntwo");
+
+        assert!(store.load("../../etc/passwd").is_err());
+        assert!(store.load("bash-99").is_err());
+
+        drop(store);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_reopened_store_keeps_earlier_outputs() {
+        let temp = tempfile::tempdir().unwrap();
+        let first = OutputStore::new(temp.path()).save("bash", "before").unwrap();
+
+        let store = OutputStore::new(temp.path());
+        let second = store.save("bash", "after").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(store.load(&first).unwrap(), "before");
+        assert_eq!(store.load(&second).unwrap(), "after");
+    }
+
+    #[test]
+    fn test_spill_preview() {
+        let output: Vec<String> = (1..=20).map(|i| format!("line{i}")).collect();
+        let preview = spill_preview(&output.join("\n"), 6, 1024, "bash-3");
+
+        assert!(preview.starts_with("line1\nline2\nline3\n"));
+        assert!(preview.ends_with("line18\nline19\nline20"));
+        assert!(preview.contains("[output truncated: 20 lines, 130 bytes in total. The full output is saved as `bash-3`"));
+        assert!(!preview.contains("line4\n"));
+    }
+
+    #[test]
+    fn test_preview_caps_long_lines() {
+        let output = format!("{}\n{}", "a".repeat(5_000), "é".repeat(5_000));
+        let preview = preview(&output, usize::MAX, 1_000, "");
+
+        let (head, tail) = preview.split_once("\n\n... [").unwrap();
+        assert_eq!(head, "a".repeat(500));
+        assert!(tail.ends_with(&"é".repeat(250)));
+        assert!(preview.len() < 1_200);
+    }
+}
@@ -19,7 +19,10 @@ use tracing::{debug, info_span, Instrument};
 use crate::error::ToolError;
 #[cfg(feature = "telemetry")]
 use crate::telemetry::metrics::GLOBAL_METRICS;
-use crate::types::ToolDefinition;
+use crate::types::{ImageSource, ToolDefinition};
+
+use super::output_store::{preview, spill_preview, OutputStore};
+use super::MAX_OUTPUT_BYTES;
 
 /// Output from executing a tool.
 #[derive(Debug, Clone)]
@@ -35,6 +38,11 @@ pub enum ToolOutput {
         success: bool,
         metadata: Option<serde_json::Value>,
     },
+    /// Image for vision-capable models, with a text description
+    Image {
+        content: String,
+        image: ImageSource,
+    },
 }
 
 impl ToolOutput {
@@ -63,11 +71,28 @@ impl ToolOutput {
         }
     }
 
+    /// Create a successful image output.
+    pub fn image(content: impl Into<String>, image: ImageSource) -> Self {
+        Self::Image {
+            content: content.into(),
+            image,
+        }
+    }
+
     /// Get the content string.
     pub fn content(&self) -> &str {
         match self {
             Self::Text { content, .. } => content,
             Self::Structured { content, .. } => content,
+            Self::Image { content, .. } => content,
+        }
+    }
+
+    /// Get the attached image, if any.
+    pub fn attached_image(&self) -> Option<&ImageSource> {
+        match self {
+            Self::Image { image, .. } => Some(image),
+            _ => None,
         }
     }
 
@@ -76,6 +101,26 @@ impl ToolOutput {
         match self {
             Self::Text { success, .. } => *success,
             Self::Structured { success, .. } => *success,
+            Self::Image { .. } => true,
+        }
+    }
+
+    /// Replace the content, keeping success, metadata and any image.
+    pub fn with_content(self, content: impl Into<String>) -> Self {
+        match self {
+            Self::Text { success, .. } => Self::Text {
+                content: content.into(),
+                success,
+            },
+            Self::Structured { success, metadata, .. } => Self::Structured {
+                content: content.into(),
+                success,
+                metadata,
+            },
+            Self::Image { image, .. } => Self::Image {
+                content: content.into(),
+                image,
+            },
         }
     }
 
@@ -132,13 +177,24 @@ pub trait ToolHandler: Send + Sync {
         false
     }
 
+    /// Maximum lines of output to return to the model, if limited.
+    ///
+    /// Output over this or [`MAX_OUTPUT_BYTES`] is saved to the registry's
+    /// [`OutputStore`] and replaced with a head/tail preview and a handle for
+    /// `read_output`. Without a store, the middle is dropped.
+    fn max_output_lines(&self) -> Option<usize> {
+        None
+    }
+
     /// Execute the tool with the given input parameters.
     async fn execute(&self, input: serde_json::Value) -> Result<ToolOutput, ToolError>;
 }
 
 /// Registry of available tools, maps names to handlers.
+#[derive(Clone)]
 pub struct ToolRegistry {
     handlers: HashMap<String, Arc<dyn ToolHandler>>,
+    output_store: Option<Arc<OutputStore>>,
 }
 
 impl ToolRegistry {
@@ -146,12 +202,23 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             handlers: HashMap::new(),
+            output_store: None,
         }
     }
 
     /// Create a registry with default tools.
+    ///
+    /// Oversized output is saved to a temporary [`OutputStore`] that lives
+    /// as long as the registry.
     pub fn with_defaults() -> Self {
+        Self::with_output_store(Arc::new(OutputStore::temporary()))
+    }
+
+    /// Create a registry with default tools, saving oversized output to `store`.
+    pub fn with_output_store(store: Arc<OutputStore>) -> Self {
         let mut builder = ToolRegistryBuilder::new();
+        builder.output_store(store.clone());
+        builder.register(super::handlers::ReadOutputHandler::new(store));
 
         // Register all default handlers
         builder.register(super::handlers::ReadFileHandler);
@@ -191,6 +258,56 @@ impl ToolRegistry {
         self.handlers.keys().map(String::as_str).collect()
     }
 
+    /// Create a registry with only the tools whose names pass `keep`.
+    pub fn filtered(&self, keep: impl Fn(&str) -> bool) -> ToolRegistry {
+        ToolRegistry {
+            handlers: self
+                .handlers
+                .iter()
+                .filter(|(name, _)| keep(name))
+                .map(|(name, handler)| (name.clone(), handler.clone()))
+                .collect(),
+            output_store: self.output_store.clone(),
+        }
+    }
+
+    /// Turn the registry back into a builder to register more tools.
+    pub fn into_builder(self) -> ToolRegistryBuilder {
+        ToolRegistryBuilder {
+            handlers: self.handlers,
+            output_store: self.output_store,
+        }
+    }
+
+    /// Store used for oversized output, if any.
+    pub fn output_store(&self) -> Option<&Arc<OutputStore>> {
+        self.output_store.as_ref()
+    }
+
+    /// Apply the output byte limit and a handler's line limit, saving the full
+    /// output if possible.
+    fn limit_output(&self, handler: &dyn ToolHandler, tool_name: &str, output: ToolOutput) -> ToolOutput {
+        let max_lines = handler.max_output_lines().unwrap_or(usize::MAX);
+        let content = output.content();
+        if content.len() <= MAX_OUTPUT_BYTES && content.lines().count() <= max_lines {
+            return output;
+        }
+
+        let saved = self
+            .output_store
+            .as_ref()
+            .map(|store| store.save(tool_name, content));
+        let content = match saved {
+            Some(Ok(handle)) => spill_preview(content, max_lines, MAX_OUTPUT_BYTES, &handle),
+            Some(Err(e)) => {
+                tracing::warn!("Failed to save {} output: {}", tool_name, e);
+                preview(content, max_lines, MAX_OUTPUT_BYTES, "")
+            }
+            None => preview(content, max_lines, MAX_OUTPUT_BYTES, ""),
+        };
+        output.with_content(content)
+    }
+
     /// Dispatch a tool call and return the result.
     ///
     /// When the `telemetry` feature is enabled, this method is instrumented
@@ -230,6 +347,7 @@ impl ToolRegistry {
 
         match result {
             Ok(output) => {
+                let output = self.limit_output(handler.as_ref(), tool_name, output);
                 #[cfg(feature = "telemetry")]
                 debug!(
                     tool = %tool_name,
@@ -284,6 +402,7 @@ pub struct DispatchResult {
 /// Builder for constructing a ToolRegistry.
 pub struct ToolRegistryBuilder {
     handlers: HashMap<String, Arc<dyn ToolHandler>>,
+    output_store: Option<Arc<OutputStore>>,
 }
 
 impl ToolRegistryBuilder {
@@ -291,9 +410,16 @@ impl ToolRegistryBuilder {
     pub fn new() -> Self {
         Self {
             handlers: HashMap::new(),
+            output_store: None,
         }
     }
 
+    /// Save oversized tool output to `store`.
+    pub fn output_store(&mut self, store: Arc<OutputStore>) -> &mut Self {
+        self.output_store = Some(store);
+        self
+    }
+
     /// Register a tool handler.
     pub fn register<T: ToolHandler + 'static>(&mut self, handler: T) -> &mut Self {
         let def = handler.definition();
@@ -312,6 +438,7 @@ impl ToolRegistryBuilder {
     pub fn build(self) -> ToolRegistry {
         ToolRegistry {
             handlers: self.handlers,
+            output_store: self.output_store,
         }
     }
 }
@@ -386,6 +513,30 @@ mod tests {
         assert!(!registry.contains("mock3"));
     }
 
+    #[test]
+    fn test_registry_filtered_and_extended() {
+        let mut builder = ToolRegistryBuilder::new();
+        for name in ["mock1", "mock2"] {
+            builder.register(MockTool {
+                name: name.to_string(),
+                mutating: false,
+            });
+        }
+        let registry = builder.build();
+
+        let subset = registry.filtered(|name| name == "mock1");
+        assert!(subset.contains("mock1"));
+        assert!(!subset.contains("mock2"));
+
+        let mut builder = registry.into_builder();
+        builder.register(MockTool {
+            name: "mock3".to_string(),
+            mutating: true,
+        });
+        let extended = builder.build();
+        assert_eq!(extended.tool_names().len(), 3);
+    }
+
     #[tokio::test]
     async fn test_registry_dispatch() {
         let mut builder = ToolRegistryBuilder::new();
@@ -405,6 +556,87 @@ mod tests {
         assert!(!result.is_error);
     }
 
+    struct LongOutputTool;
+
+    #[async_trait]
+    impl ToolHandler for LongOutputTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition::new("long", "Prints many lines")
+        }
+
+        fn max_output_lines(&self) -> Option<usize> {
+            Some(10)
+        }
+
+        async fn execute(&self, _input: serde_json::Value) -> Result<ToolOutput, ToolError> {
+            let lines: Vec<String> = (1..=100).map(|i| format!("line {i}")).collect();
+            Ok(ToolOutput::structured(lines.join("\n"), true, serde_json::json!({ "exit_code": 0 })))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_spills_long_output() {
+        let store = Arc::new(OutputStore::temporary());
+        let mut builder = ToolRegistryBuilder::new();
+        builder.output_store(store.clone());
+        builder.register(LongOutputTool);
+        let registry = builder.build();
+
+        let result = registry.dispatch("long", serde_json::json!({})).await.unwrap();
+        let content = result.output.content();
+        assert!(content.starts_with("line 1\n"));
+        assert!(content.ends_with("line 100"));
+        assert!(content.contains("[output truncated: 100 lines, 791 bytes in total. The full output is saved as `long-1`"));
+        assert!(matches!(result.output, ToolOutput::Structured { .. }));
+
+        let saved = store.load("long-1").unwrap();
+        assert_eq!(saved.lines().count(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_registry_truncates_without_store() {
+        let mut builder = ToolRegistryBuilder::new();
+        builder.register(LongOutputTool);
+        let registry = builder.build();
+
+        let result = registry.dispatch("long", serde_json::json!({})).await.unwrap();
+        assert!(result.output.content().contains("... [output truncated: 100 lines, 791 bytes in total] ..."));
+    }
+
+    struct WideOutputTool;
+
+    #[async_trait]
+    impl ToolHandler for WideOutputTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition::new("wide", "Prints one huge line")
+        }
+
+        async fn execute(&self, _input: serde_json::Value) -> Result<ToolOutput, ToolError> {
+            Ok(ToolOutput::success("x".repeat(MAX_OUTPUT_BYTES * 2)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_spills_oversized_output_of_any_tool() {
+        let store = Arc::new(OutputStore::temporary());
+        let mut builder = ToolRegistryBuilder::new();
+        builder.output_store(store.clone());
+        builder.register(WideOutputTool);
+        let registry = builder.build();
+
+        let result = registry.dispatch("wide", serde_json::json!({})).await.unwrap();
+        assert!(result.output.content().len() <= MAX_OUTPUT_BYTES + 300);
+        assert!(result.output.content().contains("saved as `wide-1`"));
+        assert_eq!(store.load("wide-1").unwrap().len(), MAX_OUTPUT_BYTES * 2);
+    }
+
+    #[test]
+    fn test_default_registry_has_read_output() {
+        let registry = ToolRegistry::with_defaults();
+        assert!(registry.get("read_output").is_some());
+        assert!(registry.output_store().is_some());
+    }
+
     #[tokio::test]
     async fn test_registry_dispatch_not_found() {
         let registry = ToolRegistry::new();
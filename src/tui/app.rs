@@ -5,13 +5,19 @@
 
 use std::io;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use crossterm::event::{KeyCode, KeyModifiers};
 use ratatui::prelude::*;
 use ratatui::text::Line;
 use tokio::sync::{mpsc, watch};
 
+use crate::agent::budget::{format_cost, BudgetConfig};
+use crate::agent::checkpoint::{
+    remove_session_checkpoints, Checkpoint, CheckpointStore, SharedCheckpointStore,
+};
+use crate::agent::plan::Plan;
+use crate::agent::task::{provider_factory, with_task_tool, TaskConfig};
 use crate::agent::{
     Agent, AgentCallbacks, AgentConfig, AgentOptions,
     ConfirmationResult, ToolConfirmation, TurnStats,
@@ -19,10 +25,15 @@ use crate::agent::{
 use crate::config::ResolvedConfig;
 use crate::error::{AgentError, Result as CodiResult, ToolError};
 use crate::completion::{complete_line, get_completion_matches};
+use crate::providers::fallback::fallback_status;
+use crate::providers::retry::{retry_status, RetryConfig};
 use crate::orchestrate::{Commander, CommanderConfig, WorkerConfig, WorkerStatus, WorkspaceInfo, PermissionResult};
 use crate::session::{Session, SessionInfo, SessionService};
-use crate::tools::ToolRegistry;
-use crate::types::{BoxedProvider, MessageContent, Role};
+use crate::tools::output_store::remove_session_outputs;
+use crate::tools::{OutputStore, ToolRegistry};
+use crate::types::{
+    BoxedProvider, ContentBlockType, MessageContent, Role, SharedProvider, StreamEvent,
+};
 
 use super::commands::{execute_async_command, handle_command, CommandResult};
 use super::events::{Event, EventHandler};
@@ -40,6 +51,8 @@ pub enum AppMode {
     Help,
     /// Showing tool confirmation dialog.
     ConfirmTool,
+    /// Showing a plan waiting for approval.
+    ConfirmPlan,
 }
 
 /// A message in the conversation.
@@ -49,10 +62,14 @@ pub struct Message {
     pub role: Role,
     /// Message content.
     pub content: String,
+    /// Reasoning shown before the content; display only, not sent back to the model.
+    pub reasoning: String,
     /// Whether this message is still being streamed.
     pub streaming: bool,
     /// Rendered lines (cached for display).
     pub rendered_lines: Vec<Line<'static>>,
+    /// Agent history position of a prompt sent to the agent, for `/rewind`.
+    pub agent_position: Option<usize>,
 }
 
 impl Message {
@@ -62,8 +79,10 @@ impl Message {
         Self {
             role: Role::User,
             content,
+            reasoning: String::new(),
             streaming: false,
             rendered_lines: Vec::new(),
+            agent_position: None,
         }
     }
 
@@ -73,8 +92,10 @@ impl Message {
         Self {
             role: Role::Assistant,
             content,
+            reasoning: String::new(),
             streaming: false,
             rendered_lines: Vec::new(),
+            agent_position: None,
         }
     }
 
@@ -83,8 +104,10 @@ impl Message {
         Self {
             role: Role::Assistant,
             content: String::new(),
+            reasoning: String::new(),
             streaming: true,
             rendered_lines: Vec::new(),
+            agent_position: None,
         }
     }
 
@@ -98,6 +121,11 @@ impl Message {
         self.content.push_str(text);
     }
 
+    /// Append reasoning to a streaming message.
+    pub fn append_reasoning(&mut self, text: &str) {
+        self.reasoning.push_str(text);
+    }
+
     /// Set rendered lines from streaming.
     pub fn set_rendered_lines(&mut self, lines: Vec<Line<'static>>) {
         self.rendered_lines = lines;
@@ -118,23 +146,28 @@ impl Message {
 
     /// Create from a session message.
     pub fn from_session_message(msg: &crate::types::Message) -> Self {
+        // Extract text from blocks, keeping thinking apart
+        let blocks_text = |thinking: bool| match &msg.content {
+            MessageContent::Text(_) => String::new(),
+            MessageContent::Blocks(blocks) => blocks
+                .iter()
+                .filter(|block| (block.block_type == ContentBlockType::Thinking) == thinking)
+                .filter_map(|block| block.text.as_ref())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
         let content = match &msg.content {
             MessageContent::Text(text) => text.clone(),
-            MessageContent::Blocks(blocks) => {
-                // Extract text from blocks
-                blocks
-                    .iter()
-                    .filter_map(|block| block.text.as_ref())
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            }
+            MessageContent::Blocks(_) => blocks_text(false),
         };
         Self {
             role: msg.role,
             content,
+            reasoning: blocks_text(true),
             streaming: false,
             rendered_lines: Vec::new(),
+            agent_position: None,
         }
     }
 }
@@ -144,6 +177,8 @@ impl Message {
 pub enum AppEvent {
     /// Text delta received from streaming.
     TextDelta(String),
+    /// Reasoning delta received from streaming.
+    ReasoningDelta(String),
     /// Tool call started (id, name, input).
     ToolStart(String, String, serde_json::Value),
     /// Tool output line received during execution.
@@ -156,6 +191,12 @@ pub enum AppEvent {
     ConfirmRequest(ToolConfirmation),
     /// Context compaction started (true) or finished (false).
     Compaction(bool),
+    /// A provider request failed and will be retried (attempt, max_attempts, delay_ms).
+    Retrying(u32, u32, u64),
+    /// A provider request failed and another model answers instead (from, to).
+    Fallback(String, String),
+    /// A hook blocked an action or reported feedback (event, message).
+    Hook(String, String),
 }
 
 /// Pending tool confirmation.
@@ -179,6 +220,8 @@ pub struct App {
     pub scroll_offset: u16,
     /// Whether the app should quit.
     pub should_quit: bool,
+    /// Whether reasoning blocks are expanded (toggled with Ctrl+T).
+    pub show_reasoning: bool,
     /// Status message to display.
     pub status: Option<String>,
     /// AI agent.
@@ -195,6 +238,8 @@ pub struct App {
     pending_confirmation: Option<PendingConfirmation>,
     /// Last turn stats.
     pub last_turn_stats: Option<TurnStats>,
+    /// Total cost in USD of the turns run in this app.
+    pub session_cost: f64,
     /// Turn start time for elapsed time display.
     pub turn_start_time: Option<std::time::Instant>,
     /// Input history.
@@ -217,6 +262,16 @@ pub struct App {
     config: Option<ResolvedConfig>,
     /// Auto-approve all tool operations (from --yes CLI flag).
     auto_approve_all: bool,
+    /// Plan mode: read-only tools, turns end with a plan to approve.
+    plan_mode: bool,
+    /// Plan from the last plan-mode turn, waiting for approval.
+    pending_plan: Option<Plan>,
+    /// Provider for model-generated summaries during context compaction.
+    summarize_provider: Option<SharedProvider>,
+    /// File checkpoints of the turns in this conversation.
+    checkpoints: SharedCheckpointStore,
+    /// Oversized tool output of this conversation.
+    outputs: Arc<OutputStore>,
     // Background agent task
     /// Receiver for agent returning from a background chat task.
     pending_agent: Option<tokio::sync::oneshot::Receiver<(Agent, CodiResult<String>)>>,
@@ -256,6 +311,7 @@ impl App {
             cursor_pos: 0,
             scroll_offset: 0,
             should_quit: false,
+            show_reasoning: false,
             status: None,
             agent: None,
             terminal_width: None,
@@ -264,6 +320,7 @@ impl App {
             event_tx: Some(tx),
             pending_confirmation: None,
             last_turn_stats: None,
+            session_cost: 0.0,
             turn_start_time: None,
             input_history: Vec::new(),
             history_index: None,
@@ -275,6 +332,11 @@ impl App {
             completion_hint: None,
             config: None,
             auto_approve_all: false,
+            plan_mode: false,
+            pending_plan: None,
+            summarize_provider: None,
+            checkpoints: Arc::new(Mutex::new(CheckpointStore::in_memory())),
+            outputs: Arc::new(OutputStore::temporary()),
             pending_agent: None,
             pending_agent_cancel: None,
             cancel_requested: false,
@@ -316,11 +378,80 @@ impl App {
         self.auto_approve_all = auto_approve;
     }
 
+    /// Set the provider used to summarize context during compaction.
+    /// Call before `set_provider`; also updates an existing agent.
+    pub fn set_summarize_provider(&mut self, provider: Option<SharedProvider>) {
+        if let Some(ref mut agent) = self.agent {
+            agent.set_summarize_provider(provider.clone());
+        }
+        self.summarize_provider = provider;
+    }
+
     /// Get the auto-approve-all flag value.
     pub fn auto_approve_all(&self) -> bool {
         self.auto_approve_all
     }
 
+    /// Whether plan mode is on.
+    pub fn plan_mode(&self) -> bool {
+        self.plan_mode
+    }
+
+    /// Turn plan mode on or off (from `/plan` or the --plan CLI flag).
+    pub fn set_plan_mode(&mut self, plan_mode: bool) {
+        self.plan_mode = plan_mode;
+        if let Some(ref mut agent) = self.agent {
+            agent.set_plan_mode(plan_mode);
+        }
+    }
+
+    /// Get the plan waiting for approval.
+    pub fn pending_plan(&self) -> Option<&Plan> {
+        self.pending_plan.as_ref()
+    }
+
+    /// In plan mode, keep the plan ending `response` for approval.
+    /// Returns whether a plan is waiting.
+    pub fn offer_plan(&mut self, response: &str) -> bool {
+        if self.plan_mode {
+            self.pending_plan = Plan::parse(response);
+        }
+        self.pending_plan.is_some()
+    }
+
+    /// Approve the pending plan: leave plan mode and return the prompt that
+    /// carries the plan out.
+    pub fn approve_plan(&mut self) -> Option<String> {
+        let plan = self.pending_plan.take()?;
+        self.set_plan_mode(false);
+        self.status = Some("Plan approved, executing".to_string());
+        Some(plan.execution_prompt())
+    }
+
+    /// Reject the pending plan and stay in plan mode to refine it.
+    pub fn reject_plan(&mut self) {
+        self.pending_plan = None;
+        self.status = Some("Plan rejected. Refine the request, or /plan off to leave plan mode".to_string());
+    }
+
+    /// Get the checkpoint store shared with agents created for this app.
+    pub fn checkpoints(&self) -> SharedCheckpointStore {
+        self.checkpoints.clone()
+    }
+
+    /// Get the store for oversized tool output of this conversation.
+    pub fn outputs(&self) -> Arc<OutputStore> {
+        self.outputs.clone()
+    }
+
+    /// Number the next agent turn after the user prompts in the conversation.
+    ///
+    /// Call before adding the prompt so checkpoints line up with `/rewind`.
+    pub fn begin_checkpoint_turn(&self) {
+        let turn = self.messages.iter().filter(|m| m.role == Role::User).count();
+        self.checkpoints.lock().unwrap().set_next_turn(turn);
+    }
+
     /// Build an `AgentConfig` from the stored `ResolvedConfig`, or use defaults.
     fn build_agent_config(&self) -> AgentConfig {
         if let Some(ref config) = self.config {
@@ -328,16 +459,23 @@ impl App {
                 max_iterations: 50,
                 max_consecutive_errors: 3,
                 max_turn_duration_ms: 120_000,
-                max_context_tokens: config.max_context_tokens as usize,
+                max_context_tokens: config.max_context_tokens.map(|t| t as usize),
                 use_tools: !config.no_tools,
                 extract_tools_from_text: config.extract_tools_from_text,
                 auto_approve_all: self.auto_approve_all,
                 auto_approve_tools: config.auto_approve.clone(),
                 dangerous_patterns: config.dangerous_patterns.clone(),
+                max_parallel_tools: config.max_parallel_tools as usize,
+                retry: RetryConfig::from_config(config),
+                budget: BudgetConfig::from_config(config),
+                hooks: config.hooks.clone(),
+                plan_mode: self.plan_mode,
+                ..AgentConfig::default()
             }
         } else {
             let mut default_config = AgentConfig::default();
             default_config.auto_approve_all = self.auto_approve_all;
+            default_config.plan_mode = self.plan_mode;
             default_config
         }
     }
@@ -349,7 +487,6 @@ impl App {
 
     /// Set the AI provider and create an agent.
     pub fn set_provider(&mut self, provider: BoxedProvider) {
-        let registry = Arc::new(ToolRegistry::with_defaults());
         let event_tx = self.event_tx.clone().unwrap();
 
         let callbacks = AgentCallbacks {
@@ -384,16 +521,52 @@ impl App {
                     let _ = tx.send(AppEvent::TurnComplete(stats.clone()));
                 }
             })),
-            on_stream_event: None,
+            on_stream_event: Some(Arc::new({
+                let tx = event_tx.clone();
+                move |event: &StreamEvent| match event {
+                    StreamEvent::Retrying { attempt, max_attempts, delay_ms, .. } => {
+                        let _ = tx.send(AppEvent::Retrying(*attempt, *max_attempts, *delay_ms));
+                    }
+                    StreamEvent::Fallback { from, to, .. } => {
+                        let _ = tx.send(AppEvent::Fallback(from.clone(), to.clone()));
+                    }
+                    StreamEvent::ReasoningDelta(text) => {
+                        let _ = tx.send(AppEvent::ReasoningDelta(text.clone()));
+                    }
+                    _ => {}
+                }
+            })),
+            on_hook: Some(Arc::new({
+                let tx = event_tx.clone();
+                move |event: &str, message: &str| {
+                    let _ = tx.send(AppEvent::Hook(event.to_string(), message.to_string()));
+                }
+            })),
         };
 
-        self.agent = Some(Agent::new(AgentOptions {
+        // Sub-agents need their own provider, so the task tool requires a config
+        let registry = match self.config {
+            Some(ref config) => with_task_tool(
+                ToolRegistry::with_output_store(self.outputs.clone()),
+                provider_factory(config),
+                &callbacks,
+                TaskConfig::from_config(config),
+            ),
+            None => ToolRegistry::with_output_store(self.outputs.clone()),
+        };
+        let registry = Arc::new(registry);
+
+        let mut agent = Agent::new(AgentOptions {
             provider,
             tool_registry: registry,
             system_prompt: Some(self.build_system_prompt()),
             config: self.build_agent_config(),
             callbacks,
-        }));
+            summarize_provider: self.summarize_provider.clone(),
+            output_schema: None,
+        });
+        agent.set_checkpoints(Some(self.checkpoints.clone()));
+        self.agent = Some(agent);
     }
 
     /// Run the main event loop.
@@ -441,9 +614,12 @@ impl App {
                     self.pending_agent = None;
                     self.pending_agent_cancel = None;
                     match result {
-                        Ok(_) => {
+                        Ok(response) => {
                             // Response was streamed via callbacks; TurnComplete will finalize
                             self.cancel_requested = false;
+                            if self.offer_plan(&response) && self.mode != AppMode::Waiting {
+                                self.mode = AppMode::ConfirmPlan;
+                            }
                         }
                         Err(e) => {
                             let cancelled = e
@@ -500,6 +676,9 @@ impl App {
                 AppEvent::TextDelta(text) => {
                     self.handle_text_delta(&text);
                 }
+                AppEvent::ReasoningDelta(text) => {
+                    self.handle_reasoning_delta(&text);
+                }
                 AppEvent::ToolStart(id, name, input) => {
                     // Create a new exec cell for this tool
                     let cell = crate::tui::components::ExecCell::new(
@@ -529,8 +708,13 @@ impl App {
                     }
                 }
                 AppEvent::TurnComplete(stats) => {
+                    self.session_cost += stats.cost;
                     self.last_turn_stats = Some(stats);
-                    self.mode = AppMode::Normal;
+                    self.mode = if self.pending_plan.is_some() {
+                        AppMode::ConfirmPlan
+                    } else {
+                        AppMode::Normal
+                    };
                     self.status = None;
                     self.turn_start_time = None; // Reset turn start time
 
@@ -547,6 +731,15 @@ impl App {
                         self.status = Some("Context compacted".to_string());
                     }
                 }
+                AppEvent::Retrying(attempt, max_attempts, delay_ms) => {
+                    self.status = Some(retry_status(attempt, max_attempts, delay_ms));
+                }
+                AppEvent::Fallback(from, to) => {
+                    self.status = Some(fallback_status(&from, &to));
+                }
+                AppEvent::Hook(event, message) => {
+                    self.status = Some(format!("[{}] {}", event, message));
+                }
             }
         }
     }
@@ -562,8 +755,10 @@ impl App {
             let width = self.terminal_width.map(|w| (w.saturating_sub(4)) as usize);
             self.stream_controller = Some(StreamController::new(width));
 
-            // Add streaming message
-            self.messages.push(Message::streaming());
+            // Add streaming message, unless reasoning already started one
+            if !self.messages.last().is_some_and(|msg| msg.streaming) {
+                self.messages.push(Message::streaming());
+            }
         }
 
         // Push delta to controller
@@ -579,6 +774,21 @@ impl App {
         }
     }
 
+    /// Handle reasoning delta from streaming.
+    fn handle_reasoning_delta(&mut self, text: &str) {
+        if self.cancel_requested {
+            return;
+        }
+
+        if !self.messages.last().is_some_and(|msg| msg.streaming) {
+            self.messages.push(Message::streaming());
+        }
+        if let Some(msg) = self.messages.last_mut() {
+            msg.append_reasoning(text);
+        }
+        self.scroll_to_bottom();
+    }
+
     /// Tick the streaming animation.
     fn tick_streaming(&mut self) {
         if let Some(ref mut controller) = self.stream_controller {
@@ -649,9 +859,10 @@ impl App {
     async fn handle_key(&mut self, key: KeyCode, modifiers: KeyModifiers) {
         match self.mode {
             AppMode::Normal => self.handle_normal_key(key, modifiers).await,
-            AppMode::Waiting => self.handle_waiting_key(key),
+            AppMode::Waiting => self.handle_waiting_key(key, modifiers),
             AppMode::Help => self.handle_help_key(key),
             AppMode::ConfirmTool => self.handle_confirm_key(key),
+            AppMode::ConfirmPlan => self.handle_plan_key(key),
         }
     }
 
@@ -680,6 +891,11 @@ impl App {
                     self.should_quit = true;
                     return;
                 }
+                // Handle Ctrl+T
+                if c == 't' && modifiers.contains(KeyModifiers::CONTROL) {
+                    self.toggle_reasoning();
+                    return;
+                }
 
                 self.input.insert(self.cursor_pos, c);
                 self.cursor_pos += 1;
@@ -745,9 +961,13 @@ impl App {
         "/versions" => Some("Show version information"),
         "/context" => Some("Show/compact conversation context"),
         "/compact" => Some("Compress context to save tokens"),
+        "/undo" => Some("Revert the last turn's file changes"),
+        "/rewind" => Some("Rewind conversation and files by n turns"),
         "/save" => Some("Save current session"),
         "/load" => Some("Load a session"),
         "/sessions" => Some("List all sessions"),
+        "/sessions tree" => Some("Show sessions and their forks"),
+        "/fork" => Some("Branch the conversation into a new session"),
         "/models" => Some("List available AI models"),
         "/models anthropic" => Some("Show Claude models"),
         "/models openai" => Some("Show GPT models"),
@@ -863,12 +1083,19 @@ impl App {
     }
 
     /// Handle key while waiting for response.
-    fn handle_waiting_key(&mut self, key: KeyCode) {
+    fn handle_waiting_key(&mut self, key: KeyCode, modifiers: KeyModifiers) {
         if key == KeyCode::Esc {
             self.request_cancel();
+        } else if key == KeyCode::Char('t') && modifiers.contains(KeyModifiers::CONTROL) {
+            self.toggle_reasoning();
         }
     }
 
+    /// Expand or collapse reasoning blocks.
+    fn toggle_reasoning(&mut self) {
+        self.show_reasoning = !self.show_reasoning;
+    }
+
     fn request_cancel(&mut self) {
         if self.cancel_requested {
             return;
@@ -905,6 +1132,23 @@ impl App {
         }
     }
 
+    /// Handle key while a plan waits for approval.
+    fn handle_plan_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                self.mode = AppMode::Normal;
+                if let Some(prompt) = self.approve_plan() {
+                    self.send_prompt(prompt);
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.mode = AppMode::Normal;
+                self.reject_plan();
+            }
+            _ => {}
+        }
+    }
+
     /// Respond to a pending confirmation.
     fn respond_to_confirmation(&mut self, result: ConfirmationResult) {
         if let Some(mut pending) = self.pending_confirmation.take() {
@@ -936,24 +1180,7 @@ impl App {
                 }
                 CommandResult::Prompt(prompt) => {
                     // Command generated a prompt to send to the AI
-                    self.messages.push(Message::user(&prompt));
-                    self.scroll_to_bottom();
-
-                    if let Some(mut agent) = self.agent.take() {
-                        self.mode = AppMode::Waiting;
-                        self.status = Some("Thinking...".to_string());
-
-                        let (tx, rx) = tokio::sync::oneshot::channel();
-                        self.pending_agent = Some(rx);
-                        let (cancel_tx, cancel_rx) = watch::channel(false);
-                        self.pending_agent_cancel = Some(cancel_tx);
-                        self.cancel_requested = false;
-
-                        tokio::spawn(async move {
-                            let result = agent.chat_with_cancel(&prompt, cancel_rx).await;
-                            let _ = tx.send((agent, result));
-                        });
-                    }
+                    self.send_prompt(prompt);
                 }
                 CommandResult::Ok | CommandResult::Error(_) => {
                     // Already handled synchronously
@@ -962,8 +1189,16 @@ impl App {
             return;
         }
 
-        // Add user message
-        self.messages.push(Message::user(&input));
+        self.send_prompt(input);
+    }
+
+    /// Add a user prompt to the conversation and run the agent on it.
+    fn send_prompt(&mut self, input: String) {
+        self.begin_checkpoint_turn();
+        self.messages.push(Message {
+            agent_position: self.agent.as_ref().map(|agent| agent.history_position()),
+            ..Message::user(&input)
+        });
         self.scroll_to_bottom();
 
         // Get AI response - spawn on background task so the event loop stays responsive
@@ -1071,9 +1306,9 @@ impl App {
 
     /// Compact conversation context by summarizing older messages.
     /// Returns the number of messages that were summarized.
-    pub fn compact_conversation(&mut self) -> usize {
+    pub async fn compact_conversation(&mut self) -> usize {
         if let Some(ref mut agent) = self.agent {
-            agent.compact_context()
+            agent.compact_context().await
         } else {
             0
         }
@@ -1135,6 +1370,8 @@ impl App {
         let title = title.unwrap_or_else(|| "New Session".to_string());
         let session = service.create(title, self.project_path.clone()).await?;
 
+        *self.checkpoints.lock().unwrap() = CheckpointStore::for_session(&session.id);
+        self.outputs.open_session(&session.id);
         self.current_session_id = Some(session.id.clone());
         self.current_session = Some(session);
         self.messages.clear();
@@ -1200,6 +1437,8 @@ impl App {
 
         // Convert session messages to TUI messages
         self.messages = session_messages.iter().map(Message::from_session_message).collect();
+        *self.checkpoints.lock().unwrap() = CheckpointStore::for_session(&session.id);
+        self.outputs.open_session(&session.id);
         self.current_session_id = Some(session.id.clone());
         self.current_session = Some(session);
         self.scroll_offset = 0;
@@ -1239,6 +1478,14 @@ impl App {
         })?;
 
         let deleted = service.delete(id).await?;
+        if deleted {
+            if let Err(e) = remove_session_checkpoints(id) {
+                tracing::warn!("Failed to remove checkpoints of session {}: {}", id, e);
+            }
+            if let Err(e) = remove_session_outputs(id) {
+                tracing::warn!("Failed to remove saved output of session {}: {}", id, e);
+            }
+        }
 
         // If we deleted the current session, clear it
         if deleted && self.current_session_id.as_deref() == Some(id) {
@@ -1249,6 +1496,133 @@ impl App {
         Ok(deleted)
     }
 
+    /// Revert the file changes of the most recent turn that changed files.
+    pub fn undo_last_turn(&mut self) -> Result<Option<Checkpoint>, ToolError> {
+        self.checkpoints.lock().unwrap().undo().map_err(|e| {
+            ToolError::ExecutionFailed(format!("Failed to restore checkpoint: {}", e))
+        })
+    }
+
+    /// Rewind the last `turns` user turns.
+    ///
+    /// Restores the files those turns changed and drops them from the
+    /// conversation, the agent history and the current session.
+    /// Returns the reverted checkpoints, newest first.
+    pub async fn rewind(&mut self, turns: usize) -> Result<Vec<Checkpoint>, ToolError> {
+        let prompts: Vec<usize> = self
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.role == Role::User)
+            .map(|(i, _)| i)
+            .collect();
+        if turns == 0 || turns > prompts.len() {
+            return Err(ToolError::InvalidInput(format!(
+                "Can rewind between 1 and {} turns",
+                prompts.len()
+            )));
+        }
+
+        let first_turn = prompts.len() - turns;
+        let cut = prompts[first_turn];
+        let reverted = self.checkpoints.lock().unwrap().rewind_to(first_turn).map_err(|e| {
+            ToolError::ExecutionFailed(format!("Failed to restore checkpoint: {}", e))
+        })?;
+
+        self.truncate_conversation(cut);
+
+        if let (Some(service), Some(session_id)) = (&self.session_service, &self.current_session_id) {
+            service.truncate_messages(session_id, cut).await?;
+        }
+
+        Ok(reverted)
+    }
+
+    /// Fork the conversation into a child of the current session and switch to it.
+    ///
+    /// The first `keep` messages (all if `None`) are copied into the fork. An
+    /// unsaved conversation is saved as the parent session first.
+    pub async fn fork_session(&mut self, keep: Option<usize>) -> Result<Session, ToolError> {
+        let keep = keep.unwrap_or(self.messages.len());
+        if keep == 0 || keep > self.messages.len() {
+            return Err(ToolError::InvalidInput(format!(
+                "Message index must be between 1 and {}",
+                self.messages.len()
+            )));
+        }
+
+        let service = self.session_service.as_ref().ok_or_else(|| {
+            ToolError::ExecutionFailed("Session service not available".to_string())
+        })?;
+
+        let history: Vec<crate::types::Message> =
+            self.messages.iter().map(Message::to_session_message).collect();
+        let parent = match self.current_session {
+            Some(ref session) => session.clone(),
+            None => {
+                let session = service
+                    .create("New Session".to_string(), self.project_path.clone())
+                    .await?;
+                for message in &history {
+                    service.add_message(&session.id, message).await?;
+                }
+                session
+            }
+        };
+        let fork = service.fork(&parent, &history[..keep]).await?;
+
+        self.truncate_conversation(keep);
+        *self.checkpoints.lock().unwrap() = CheckpointStore::for_session(&fork.id);
+        // The forked history still refers to saved output by handle
+        if let Err(e) = self.outputs.fork_session(&fork.id) {
+            tracing::warn!("Failed to copy saved output to session {}: {}", fork.id, e);
+        }
+        self.current_session_id = Some(fork.id.clone());
+        self.current_session = Some(fork.clone());
+
+        Ok(fork)
+    }
+
+    /// Drop messages from index `len` onward, keeping the agent history in step.
+    fn truncate_conversation(&mut self, len: usize) {
+        // Prompts loaded from a session were never sent to this agent and have no position
+        let position = self.messages[len..].iter().filter_map(|m| m.agent_position).min();
+        if let (Some(agent), Some(position)) = (self.agent.as_mut(), position) {
+            agent.truncate_to_position(position);
+        }
+        self.messages.truncate(len);
+        self.scroll_to_bottom();
+    }
+
+    /// Get turn and session cost for status bar display, once anything was spent.
+    pub fn cost_status(&self) -> Option<String> {
+        if self.session_cost <= 0.0 {
+            return None;
+        }
+        let turn_cost = self.last_turn_stats.as_ref().map(|s| s.cost).unwrap_or(0.0);
+        Some(format!(
+            "{} turn, {} session",
+            format_cost(turn_cost),
+            format_cost(self.session_cost)
+        ))
+    }
+
+    /// Get last turn's prompt cache usage for status bar display, if any.
+    pub fn cache_status(&self) -> Option<String> {
+        let stats = self.last_turn_stats.as_ref()?;
+        if stats.cache_read_tokens == 0 && stats.cache_write_tokens == 0 {
+            return None;
+        }
+        // Share of the prompt served from cache
+        let prompt = stats.input_tokens + stats.cache_read_tokens + stats.cache_write_tokens;
+        Some(format!(
+            "{} cached ({}% hit), {} cache write",
+            stats.cache_read_tokens,
+            stats.cache_read_tokens * 100 / prompt.max(1),
+            stats.cache_write_tokens
+        ))
+    }
+
     /// Get session info for status bar display.
     pub fn session_status(&self) -> Option<String> {
         self.current_session.as_ref().map(|session| {
@@ -1515,6 +1889,39 @@ mod tests {
         assert!(!msg.streaming);
     }
 
+    #[test]
+    fn test_reasoning_streams_into_response_message() {
+        let mut app = App::new();
+        app.handle_reasoning_delta("Look at ");
+        app.handle_reasoning_delta("the tests");
+        app.handle_text_delta("All green.");
+
+        assert_eq!(app.messages.len(), 1);
+        assert_eq!(app.messages[0].reasoning, "Look at the tests");
+        assert_eq!(app.messages[0].content, "All green.");
+        // Reasoning isn't part of the persisted message
+        let session_msg = app.messages[0].to_session_message();
+        assert_eq!(session_msg.as_text(), Some("All green."));
+
+        assert!(!app.show_reasoning);
+        app.handle_waiting_key(KeyCode::Char('t'), KeyModifiers::CONTROL);
+        assert!(app.show_reasoning);
+    }
+
+    #[test]
+    fn test_from_session_message_separates_thinking() {
+        let msg = crate::types::Message::with_blocks(
+            Role::Assistant,
+            vec![
+                crate::types::ContentBlock::thinking_with_signature("Plan", "sig"),
+                crate::types::ContentBlock::text("Answer"),
+            ],
+        );
+        let msg = Message::from_session_message(&msg);
+        assert_eq!(msg.content, "Answer");
+        assert_eq!(msg.reasoning, "Plan");
+    }
+
     #[test]
     fn test_clear_messages() {
         let mut app = App::new();
@@ -1687,4 +2094,185 @@ mod tests {
             _ => panic!("expected Compaction variant"),
         }
     }
+
+    #[test]
+    fn test_retrying_event_sets_status() {
+        let mut app = App::new();
+        app.event_tx.as_ref().unwrap().send(AppEvent::Retrying(2, 5, 8_000)).unwrap();
+        app.process_app_events();
+        assert_eq!(app.status.as_deref(), Some("Retrying in 8s… (attempt 2/5)"));
+    }
+
+    #[test]
+    fn test_fallback_event_sets_status() {
+        let mut app = App::new();
+        let event = AppEvent::Fallback("Anthropic/claude-sonnet-4".to_string(), "OpenAI/gpt-4o".to_string());
+        app.event_tx.as_ref().unwrap().send(event).unwrap();
+        app.process_app_events();
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Anthropic/claude-sonnet-4 failed; answering with OpenAI/gpt-4o")
+        );
+    }
+
+    #[test]
+    fn test_turn_complete_accumulates_cost() {
+        let mut app = App::new();
+        assert_eq!(app.cost_status(), None);
+
+        for cost in [0.25, 0.0125] {
+            let stats = TurnStats {
+                cost,
+                ..Default::default()
+            };
+            app.event_tx.as_ref().unwrap().send(AppEvent::TurnComplete(stats)).unwrap();
+            app.process_app_events();
+        }
+        assert!((app.session_cost - 0.2625).abs() < 1e-9);
+        assert_eq!(app.cost_status().as_deref(), Some("$0.0125 turn, $0.2625 session"));
+    }
+
+    #[test]
+    fn test_cache_status() {
+        let mut app = App::new();
+        app.last_turn_stats = Some(TurnStats {
+            input_tokens: 500,
+            ..Default::default()
+        });
+        assert_eq!(app.cache_status(), None);
+
+        app.last_turn_stats = Some(TurnStats {
+            input_tokens: 500,
+            cache_read_tokens: 9_000,
+            cache_write_tokens: 500,
+            ..Default::default()
+        });
+        assert_eq!(app.cache_status().as_deref(), Some("9000 cached (90% hit), 500 cache write"));
+    }
+
+    #[test]
+    fn test_plan_turn_waits_for_approval() {
+        let mut app = App::new();
+        let response = "```plan\n{\"summary\": \"Rename\", \"steps\": [{\"description\": \"Rename module\", \"files\": [\"src/a.rs\"]}]}\n```";
+
+        // Outside plan mode responses are never treated as plans
+        assert!(!app.offer_plan(response));
+
+        app.set_plan_mode(true);
+        assert!(app.offer_plan(response));
+        app.event_tx.as_ref().unwrap().send(AppEvent::TurnComplete(TurnStats::default())).unwrap();
+        app.process_app_events();
+        assert_eq!(app.mode, AppMode::ConfirmPlan);
+
+        let prompt = app.approve_plan().unwrap();
+        assert!(prompt.contains("1. Rename module"));
+        assert!(!app.plan_mode());
+        assert!(app.pending_plan().is_none());
+
+        app.set_plan_mode(true);
+        app.offer_plan(response);
+        app.reject_plan();
+        assert!(app.plan_mode());
+        assert!(app.pending_plan().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fork_session_copies_messages_into_child() {
+        let temp = tempfile::tempdir().unwrap();
+        let storage = crate::session::SessionStorage::open_at(&temp.path().join("sessions.db")).unwrap();
+        let mut app = App::new();
+        app.session_service = Some(SessionService::with_storage(storage));
+        for text in ["first", "answer", "second", "answer"] {
+            app.messages.push(Message::user(text));
+        }
+
+        assert!(app.fork_session(Some(5)).await.is_err());
+
+        let fork = app.fork_session(Some(2)).await.unwrap();
+        assert_eq!(app.current_session_id.as_deref(), Some(fork.id.as_str()));
+        assert_eq!(app.messages.len(), 2);
+
+        // The unsaved conversation was saved in full as the parent
+        let service = app.session_service.as_ref().unwrap();
+        let parent_id = fork.parent_id.clone().unwrap();
+        assert_eq!(service.get_messages(&parent_id).await.unwrap().len(), 4);
+        assert_eq!(service.get_messages(&fork.id).await.unwrap().len(), 2);
+
+        // Forking again branches from the fork
+        let nested = app.fork_session(None).await.unwrap();
+        assert_eq!(nested.parent_id.as_deref(), Some(fork.id.as_str()));
+    }
+
+    #[test]
+    fn test_truncate_conversation_uses_agent_positions() {
+        let cassette: crate::providers::Cassette =
+            serde_json::from_str(r#"{"provider": "replay", "model": "m", "contextWindow": 8000}"#).unwrap();
+        let mut agent = Agent::new(AgentOptions {
+            provider: Box::new(crate::providers::ReplayProvider::new(cassette)),
+            tool_registry: Arc::new(ToolRegistry::new()),
+            system_prompt: None,
+            config: AgentConfig::default(),
+            callbacks: AgentCallbacks::default(),
+            summarize_provider: None,
+            output_schema: None,
+        });
+
+        // A loaded prompt the agent never saw, then two prompts whose agent
+        // messages don't match their text (image blocks, attachment notes)
+        let mut app = App::new();
+        app.messages.push(Message::user("loaded"));
+        app.messages.push(Message::assistant("answer"));
+        for prompt in ["look at @a.png", "and @b.png"] {
+            app.messages.push(Message {
+                agent_position: Some(agent.history_position()),
+                ..Message::user(prompt)
+            });
+            agent.messages_mut().push(crate::types::Message::with_blocks(
+                Role::User,
+                vec![crate::types::ContentBlock::text(format!("{} [image]", prompt))],
+            ));
+            agent.messages_mut().push(crate::types::Message::assistant("seen"));
+            app.messages.push(Message::assistant("seen"));
+        }
+        app.agent = Some(agent);
+
+        app.truncate_conversation(4);
+        assert_eq!(app.agent.as_ref().unwrap().messages().len(), 2);
+        app.truncate_conversation(2);
+        assert!(app.agent.as_ref().unwrap().messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rewind_restores_files_and_truncates_conversation() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("main.rs");
+        std::fs::write(&file, "v0").unwrap();
+
+        let mut app = App::new();
+        for (turn, version) in ["v1", "v2", "v3"].into_iter().enumerate() {
+            app.begin_checkpoint_turn();
+            app.messages.push(Message::user(format!("prompt {}", turn)));
+            let mut store = app.checkpoints.lock().unwrap();
+            store.begin_turn();
+            store.capture(&file).unwrap();
+            std::fs::write(&file, version).unwrap();
+            store.finish_turn().unwrap();
+            drop(store);
+            app.messages.push(Message::assistant("done"));
+        }
+
+        assert!(app.rewind(4).await.is_err());
+
+        let reverted = app.rewind(2).await.unwrap();
+        assert_eq!(reverted.len(), 2);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+        assert_eq!(app.messages.len(), 2);
+        assert_eq!(app.messages[0].content, "prompt 0");
+
+        // Undo reverts the remaining turn's changes but keeps the conversation
+        assert_eq!(app.undo_last_turn().unwrap().unwrap().turn, 0);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v0");
+        assert_eq!(app.messages.len(), 2);
+        assert!(app.undo_last_turn().unwrap().is_none());
+    }
 }
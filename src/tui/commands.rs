@@ -8,6 +8,8 @@
 //! `CommandResult::Async` which signals that the command handler needs to
 //! be awaited in the main event loop.
 
+use crate::session::format_session_tree;
+
 use super::app::App;
 
 /// Command result that can include an optional prompt to send to the AI.
@@ -35,6 +37,10 @@ pub enum AsyncCommand {
     SessionList,
     /// Delete a session by ID.
     SessionDelete(String),
+    /// Fork the conversation, keeping the first n messages (all if `None`).
+    SessionFork(Option<usize>),
+    /// Show the session fork hierarchy.
+    SessionTree,
 
     // Orchestration commands
     /// Delegate a task to a worker (branch, task).
@@ -49,6 +55,14 @@ pub enum AsyncCommand {
     WorktreesCleanup,
     /// Respond to a permission request (worker_id, request_id, approved).
     PermissionRespond(String, String, bool),
+
+    // Context commands
+    /// Summarize older messages to compact the context.
+    CompactSummarize,
+
+    // Checkpoint commands
+    /// Rewind the conversation and files by a number of turns.
+    Rewind(usize),
 }
 
 /// Check if arguments contain a help flag (-h, --help, ?)
@@ -116,6 +130,19 @@ fn handle_command_inner(app: &mut App, input: &str, depth: usize) -> CommandResu
             handle_compact(app, args)
         }
 
+        // Checkpoint commands
+        "/undo" => {
+            handle_undo(app, args)
+        }
+        "/rewind" => {
+            handle_rewind(app, args)
+        }
+
+        // Plan mode
+        "/plan" => {
+            handle_plan(app, args)
+        }
+
         // Model commands
         "/model" | "/switch" => {
             handle_model(app, args)
@@ -128,6 +155,12 @@ fn handle_command_inner(app: &mut App, input: &str, depth: usize) -> CommandResu
         "/session" | "/s" => {
             handle_session(app, args)
         }
+        "/sessions" => {
+            handle_session(app, if args.is_empty() { "list" } else { args })
+        }
+        "/fork" => {
+            handle_fork(app, args)
+        }
 
         // Debug commands
         "/debug" => {
@@ -194,7 +227,9 @@ Show available commands and their usage. Type a command name for specific help.
 Categories:
   Info:        status, version
   Navigation:  compact, models, sessions  
-  Git:         branch, diff, pr, stash, undo
+  Checkpoints: undo, rewind
+  Planning:    plan
+  Git:         branch, diff, pr, stash
   Code:        refactor, fix, test, optimize
   Memory:      memory
 
@@ -215,6 +250,40 @@ Get detailed information about current session including:
 Example: /status
             "
         }
+        "/undo" => {
+            "
+Usage: /undo
+
+Revert the file changes made by the most recent turn that wrote files
+(write_file, edit_file, or bash inside a git repository).
+The conversation is left as is.
+
+Example: /undo
+            "
+        }
+        "/rewind" => {
+            "
+Usage: /rewind <n>
+
+Rewind the last n turns: restore the files they changed and remove them
+from the conversation and the saved session.
+
+Example: /rewind 2
+            "
+        }
+        "/plan" => {
+            "
+Usage: /plan [on|off|<task>]
+
+Toggle plan mode. In plan mode only read-only tools are available and the
+model finishes with a plan (steps and files to change). The plan is shown
+for approval; approving it leaves plan mode and executes the plan.
+
+With a task, turn plan mode on and start planning it.
+
+Example: /plan split the config loader into its own module
+            "
+        }
         "/models" => {
             "
 Usage: /models [provider] [--local]
@@ -271,10 +340,11 @@ Examples:
   /memory I'm learning Rust async/await patterns
             "
         }
-        "/sessions" | "/session" => {
+        "/sessions" | "/session" | "/fork" => {
             "
-Usage: /sessions [info|info <name>|clear]
+Usage: /sessions [info|info <name>|tree|clear]
 Usage: /session label [text]
+Usage: /fork [message-index]
 
 Manage conversation sessions with SQLite persistence.
 
@@ -284,6 +354,9 @@ Commands:
   label [text]           Set current session label/name
   sessions               List all sessions
   sessions info [name]   Show session details
+  sessions tree          Show sessions with their forks
+  fork [n]               Copy the first n messages (default: all)
+                         into a child session and switch to it
 
 Options:
   label <text>           Set session name/description
@@ -389,6 +462,41 @@ pub async fn execute_async_command(app: &mut App, cmd: AsyncCommand) -> CommandR
             }
         }
 
+        AsyncCommand::SessionFork(keep) => {
+            match app.fork_session(keep).await {
+                Ok(fork) => {
+                    app.status = Some(format!(
+                        "Forked into {} ({} messages)",
+                        fork.id,
+                        app.messages.len()
+                    ));
+                    CommandResult::Ok
+                }
+                Err(e) => {
+                    app.status = Some(format!("Failed to fork session: {}", e));
+                    CommandResult::Error(e.to_string())
+                }
+            }
+        }
+
+        AsyncCommand::SessionTree => {
+            match app.list_sessions().await {
+                Ok(sessions) if sessions.is_empty() => {
+                    app.status = Some("No sessions found".to_string());
+                    CommandResult::Ok
+                }
+                Ok(sessions) => {
+                    let tree = format_session_tree(&sessions, app.current_session_id.as_deref());
+                    app.status = Some(format!("Sessions:\n{}", tree));
+                    CommandResult::Ok
+                }
+                Err(e) => {
+                    app.status = Some(format!("Failed to list sessions: {}", e));
+                    CommandResult::Error(e.to_string())
+                }
+            }
+        }
+
         AsyncCommand::SessionList => {
             match app.list_sessions().await {
                 Ok(sessions) => {
@@ -534,6 +642,45 @@ pub async fn execute_async_command(app: &mut App, cmd: AsyncCommand) -> CommandR
                 }
             }
         }
+        AsyncCommand::Rewind(turns) => {
+            match app.rewind(turns).await {
+                Ok(reverted) => {
+                    let files: usize = reverted.iter().map(|c| c.files.len()).sum();
+                    app.status = Some(format!(
+                        "Rewound {} turn{}, restored {} file{}",
+                        turns,
+                        if turns == 1 { "" } else { "s" },
+                        files,
+                        if files == 1 { "" } else { "s" }
+                    ));
+                    CommandResult::Ok
+                }
+                Err(e) => {
+                    app.status = Some(format!("Failed to rewind: {}", e));
+                    CommandResult::Error(e.to_string())
+                }
+            }
+        }
+        AsyncCommand::CompactSummarize => {
+            let summarized = app.compact_conversation().await;
+            if summarized > 0 {
+                let remaining = app.messages.len();
+                app.status = Some(format!(
+                    "Context summarized: {} older messages condensed, {} messages retained",
+                    summarized, remaining
+                ));
+            } else if !app.has_agent() {
+                app.status = Some("No agent available to summarize context".to_string());
+            } else if app.messages.len() <= 10 {
+                app.status = Some(format!(
+                    "Not enough messages to summarize ({} messages, need > 10)",
+                    app.messages.len()
+                ));
+            } else {
+                app.status = Some("Context already summarized".to_string());
+            }
+            CommandResult::Ok
+        }
     }
 }
 
@@ -574,6 +721,9 @@ fn handle_status(app: &mut App) -> CommandResult {
             stats.tool_call_count
         ));
     }
+    if let Some(cache) = app.cache_status() {
+        status_lines.push(format!("Cache: {}", cache));
+    }
 
     app.status = Some(status_lines.join(" | "));
     CommandResult::Ok
@@ -598,25 +748,8 @@ fn handle_compact(app: &mut App, args: &str) -> CommandResult {
             CommandResult::Ok
         }
         "summarize" => {
-            // Trigger context summarization
-            let summarized = app.compact_conversation();
-            if summarized > 0 {
-                let remaining = app.messages.len();
-                app.status = Some(format!(
-                    "Context summarized: {} older messages condensed, {} messages retained",
-                    summarized, remaining
-                ));
-            } else if !app.has_agent() {
-                app.status = Some("No agent available to summarize context".to_string());
-            } else if app.messages.len() <= 10 {
-                app.status = Some(format!(
-                    "Not enough messages to summarize ({} messages, need > 10)",
-                    app.messages.len()
-                ));
-            } else {
-                app.status = Some("Context already summarized".to_string());
-            }
-            CommandResult::Ok
+            // Summarization may call a model (async)
+            CommandResult::Async(AsyncCommand::CompactSummarize)
         }
         _ => {
             app.status = Some("Usage: /compact [status|summarize]".to_string());
@@ -625,6 +758,69 @@ fn handle_compact(app: &mut App, args: &str) -> CommandResult {
     }
 }
 
+/// Handle /undo command - revert the last turn's file changes.
+fn handle_undo(app: &mut App, args: &str) -> CommandResult {
+    if !args.trim().is_empty() {
+        app.status = Some("Usage: /undo (reverts the last turn's file changes)".to_string());
+        return CommandResult::Error("/undo takes no arguments".to_string());
+    }
+    match app.undo_last_turn() {
+        Ok(Some(checkpoint)) => {
+            let files: Vec<String> = checkpoint
+                .files
+                .iter()
+                .map(|f| f.path.display().to_string())
+                .collect();
+            app.status = Some(format!(
+                "Reverted turn {}: {}",
+                checkpoint.turn + 1,
+                files.join(", ")
+            ));
+            CommandResult::Ok
+        }
+        Ok(None) => {
+            app.status = Some("No file changes to undo".to_string());
+            CommandResult::Ok
+        }
+        Err(e) => {
+            app.status = Some(format!("Failed to undo: {}", e));
+            CommandResult::Error(e.to_string())
+        }
+    }
+}
+
+/// Handle /rewind command - rewind conversation and files by n turns.
+fn handle_rewind(app: &mut App, args: &str) -> CommandResult {
+    match args.trim().parse::<usize>() {
+        Ok(turns) if turns > 0 => CommandResult::Async(AsyncCommand::Rewind(turns)),
+        _ => {
+            app.status = Some("Usage: /rewind <n>".to_string());
+            CommandResult::Error("Invalid rewind count".to_string())
+        }
+    }
+}
+
+/// Handle /plan command - toggle plan mode, or start planning a task.
+fn handle_plan(app: &mut App, args: &str) -> CommandResult {
+    let plan_mode = match args.trim() {
+        "" => !app.plan_mode(),
+        "on" => true,
+        "off" => false,
+        task => {
+            app.set_plan_mode(true);
+            return CommandResult::Prompt(task.to_string());
+        }
+    };
+
+    app.set_plan_mode(plan_mode);
+    app.status = Some(if plan_mode {
+        "Plan mode on: read-only tools, the turn ends with a plan to approve".to_string()
+    } else {
+        "Plan mode off".to_string()
+    });
+    CommandResult::Ok
+}
+
 /// Handle /model and /switch commands.
 fn handle_model(app: &mut App, args: &str) -> CommandResult {
     if args.is_empty() {
@@ -644,7 +840,7 @@ fn handle_model(app: &mut App, args: &str) -> CommandResult {
         
         // Validate provider
         match provider.as_str() {
-            "anthropic" | "claude" | "openai" | "gpt" | "ollama" => {
+            "anthropic" | "claude" | "openai" | "gpt" | "gemini" | "google" | "ollama" => {
                 // Update config
                 app.update_config(provider.clone(), model.clone());
                 
@@ -655,7 +851,7 @@ fn handle_model(app: &mut App, args: &str) -> CommandResult {
                 CommandResult::Ok
             }
             _ => {
-                app.status = Some(format!("Unknown provider: {}. Valid providers: anthropic, openai, ollama", provider));
+                app.status = Some(format!("Unknown provider: {}. Valid providers: anthropic, openai, gemini, ollama", provider));
                 CommandResult::Error(format!("Unknown provider: {}", provider))
             }
         }
@@ -780,6 +976,10 @@ fn handle_session(app: &mut App, args: &str) -> CommandResult {
             // List sessions (async)
             CommandResult::Async(AsyncCommand::SessionList)
         }
+        "tree" => {
+            // Show fork hierarchy (async)
+            CommandResult::Async(AsyncCommand::SessionTree)
+        }
         "delete" => {
             // Delete session (async)
             if subargs.is_empty() {
@@ -789,12 +989,27 @@ fn handle_session(app: &mut App, args: &str) -> CommandResult {
             CommandResult::Async(AsyncCommand::SessionDelete(subargs.to_string()))
         }
         _ => {
-            app.status = Some("Usage: /session [new|save|load|list|delete|status]".to_string());
+            app.status = Some("Usage: /session [new|save|load|list|tree|delete|status]".to_string());
             CommandResult::Error(format!("Unknown session subcommand: {}", subcommand))
         }
     }
 }
 
+/// Handle /fork command - branch the conversation into a child session.
+fn handle_fork(app: &mut App, args: &str) -> CommandResult {
+    let args = args.trim();
+    if args.is_empty() {
+        return CommandResult::Async(AsyncCommand::SessionFork(None));
+    }
+    match args.parse::<usize>() {
+        Ok(index) if index > 0 => CommandResult::Async(AsyncCommand::SessionFork(Some(index))),
+        _ => {
+            app.status = Some("Usage: /fork [message-index]".to_string());
+            CommandResult::Error("Invalid message index".to_string())
+        }
+    }
+}
+
 /// Handle /debug command - show internal state.
 fn handle_debug(app: &mut App) -> CommandResult {
     let mut info = Vec::new();
@@ -1166,6 +1381,70 @@ mod tests {
         assert!(app.messages.is_empty());
     }
 
+    #[test]
+    fn test_fork_and_tree_commands() {
+        let mut app = App::default();
+        assert!(matches!(
+            handle_command(&mut app, "/fork"),
+            CommandResult::Async(AsyncCommand::SessionFork(None))
+        ));
+        assert!(matches!(
+            handle_command(&mut app, "/fork 3"),
+            CommandResult::Async(AsyncCommand::SessionFork(Some(3)))
+        ));
+        assert!(matches!(handle_command(&mut app, "/fork x"), CommandResult::Error(_)));
+        assert!(matches!(
+            handle_command(&mut app, "/sessions tree"),
+            CommandResult::Async(AsyncCommand::SessionTree)
+        ));
+        assert!(matches!(
+            handle_command(&mut app, "/sessions"),
+            CommandResult::Async(AsyncCommand::SessionList)
+        ));
+    }
+
+    #[test]
+    fn test_rewind_command() {
+        let mut app = App::default();
+        assert!(matches!(
+            handle_command(&mut app, "/rewind 2"),
+            CommandResult::Async(AsyncCommand::Rewind(2))
+        ));
+        assert!(matches!(handle_command(&mut app, "/rewind"), CommandResult::Error(_)));
+        assert!(matches!(handle_command(&mut app, "/rewind 0"), CommandResult::Error(_)));
+    }
+
+    #[test]
+    fn test_plan_command() {
+        let mut app = App::new();
+        assert!(matches!(handle_command(&mut app, "/plan"), CommandResult::Ok));
+        assert!(app.plan_mode());
+        assert!(matches!(handle_command(&mut app, "/plan"), CommandResult::Ok));
+        assert!(!app.plan_mode());
+
+        match handle_command(&mut app, "/plan rename the session module") {
+            CommandResult::Prompt(prompt) => assert_eq!(prompt, "rename the session module"),
+            _ => panic!("expected a prompt"),
+        }
+        assert!(app.plan_mode());
+        assert!(matches!(handle_command(&mut app, "/plan off"), CommandResult::Ok));
+        assert!(!app.plan_mode());
+    }
+
+    #[test]
+    fn test_undo_command_without_checkpoints() {
+        let mut app = App::default();
+        assert!(matches!(handle_command(&mut app, "/undo"), CommandResult::Ok));
+        assert_eq!(app.status.as_deref(), Some("No file changes to undo"));
+    }
+
+    #[test]
+    fn test_undo_command_rejects_arguments() {
+        let mut app = App::default();
+        assert!(matches!(handle_command(&mut app, "/undo staged"), CommandResult::Error(_)));
+        assert!(app.status.unwrap().starts_with("Usage: /undo"));
+    }
+
     #[test]
     fn test_exit_command() {
         let mut app = App::default();
@@ -1213,6 +1492,21 @@ mod tests {
         assert!(app.status.is_some());
     }
 
+    #[test]
+    fn test_compact_summarize_returns_async() {
+        let mut app = App::default();
+        let result = handle_command(&mut app, "/compact summarize");
+        assert!(matches!(result, CommandResult::Async(AsyncCommand::CompactSummarize)));
+    }
+
+    #[tokio::test]
+    async fn test_compact_summarize_without_agent() {
+        let mut app = App::default();
+        let result = execute_async_command(&mut app, AsyncCommand::CompactSummarize).await;
+        assert!(matches!(result, CommandResult::Ok));
+        assert!(app.status.as_ref().unwrap().contains("No agent"));
+    }
+
     #[test]
     fn test_session_status_without_service() {
         let mut app = App::default();
@@ -19,18 +19,25 @@ use crossterm::{
     ExecutableCommand,
 };
 
+use crate::agent::budget::{format_cost, BudgetConfig};
+use crate::agent::summarize::resolve_summarize_provider;
+use crate::agent::task::{provider_factory, with_task_tool, TaskConfig};
 use crate::agent::{AgentCallbacks, AgentConfig, AgentOptions, TurnStats};
 use crate::config::ResolvedConfig;
 use crate::providers::create_provider_from_config;
+use crate::providers::fallback::fallback_status;
+use crate::providers::retry::{retry_status, RetryConfig};
 use crate::tools::ToolRegistry;
+use crate::types::SharedProvider;
 
-use super::app::App;
+use super::app::{App, Message};
 use super::commands::{execute_async_command, handle_command, CommandResult};
 
 /// Run the terminal-style REPL.
 pub async fn run_terminal_repl(
     config: &ResolvedConfig,
     auto_approve: bool,
+    plan_mode: bool,
     debug_mode: bool,
 ) -> anyhow::Result<()> {
     // Print welcome banner
@@ -40,9 +47,14 @@ pub async fn run_terminal_repl(
         println!("⚙  Debug mode enabled - tool calls will be shown");
         println!();
     }
+    if plan_mode {
+        println!("⚙  Plan mode enabled - read-only tools, plans need approval");
+        println!();
+    }
     
     // Create app state
     let mut app = TerminalApp::new(config.clone(), auto_approve, debug_mode).await?;
+    app.app.set_plan_mode(plan_mode);
     
     // Main loop
     loop {
@@ -61,6 +73,7 @@ pub async fn run_terminal_repl(
                 CommandResult::Ok => {
                     // Check for debug toggle command
                     if trimmed == "/debug" {
+                        app.app.status = None;
                         app.debug_mode = !app.debug_mode;
                         if app.debug_mode {
                             println!("⚙  Debug mode enabled - tool calls will be shown");
@@ -75,6 +88,7 @@ pub async fn run_terminal_repl(
                         println!("Goodbye!");
                         break;
                     }
+                    print_command_status(&mut app.app);
                 }
                 CommandResult::Async(cmd) => {
                     // Execute async command and handle result
@@ -84,6 +98,7 @@ pub async fn run_terminal_repl(
                                 println!("Goodbye!");
                                 break;
                             }
+                            print_command_status(&mut app.app);
                         }
                         CommandResult::Error(msg) => {
                             eprintln!("Error: {}", msg);
@@ -93,7 +108,7 @@ pub async fn run_terminal_repl(
                 }
                 CommandResult::Prompt(prompt) => {
                     // Send prompt to AI
-                    if let Err(e) = app.send_message(&prompt).await {
+                    if let Err(e) = app.send_and_review_plan(&prompt).await {
                         eprintln!("Error: {}", e);
                     }
                 }
@@ -103,7 +118,7 @@ pub async fn run_terminal_repl(
             }
         } else {
             // Regular chat message
-            if let Err(e) = app.send_message(trimmed).await {
+            if let Err(e) = app.send_and_review_plan(trimmed).await {
                 eprintln!("Error: {}", e);
             }
         }
@@ -135,26 +150,57 @@ pub struct TerminalApp {
     pub app: App,
     pub config: ResolvedConfig,
     pub tool_registry: Arc<ToolRegistry>,
+    pub summarize_provider: Option<SharedProvider>,
     pub debug_mode: bool,
+    /// Total cost in USD of all messages sent in this REPL session.
+    pub session_cost: f64,
 }
 
 impl TerminalApp {
     pub async fn new(config: ResolvedConfig, auto_approve: bool, debug_mode: bool) -> anyhow::Result<Self> {
-        let mut app = App::with_project_path(std::env::current_dir()?);
+        let project_path = std::env::current_dir()?;
+        let mut app = App::with_project_path(&project_path);
         
         app.set_config(config.clone());
         app.set_auto_approve(auto_approve);
         
-        let tool_registry = Arc::new(ToolRegistry::with_defaults());
+        let summarize_provider = resolve_summarize_provider(&config, &project_path).await;
+        app.set_summarize_provider(summarize_provider.clone());
+        
+        let tool_registry = Arc::new(ToolRegistry::with_output_store(app.outputs()));
         
         Ok(Self {
             app,
             config,
             tool_registry,
+            summarize_provider,
             debug_mode,
+            session_cost: 0.0,
         })
     }
     
+    /// Send a message and, if it produced a plan in plan mode, ask to approve
+    /// it and execute the approved plan.
+    pub async fn send_and_review_plan(&mut self, content: &str) -> anyhow::Result<()> {
+        self.send_message(content).await?;
+
+        let Some(plan) = self.app.pending_plan() else {
+            return Ok(());
+        };
+        print_plan(&plan.format());
+
+        if confirm_plan()? {
+            if let Some(prompt) = self.app.approve_plan() {
+                print_command_status(&mut self.app);
+                self.send_message(&prompt).await?;
+            }
+        } else {
+            self.app.reject_plan();
+            print_command_status(&mut self.app);
+        }
+        Ok(())
+    }
+
     pub async fn send_message(
         &mut self,
         content: &str,
@@ -197,6 +243,8 @@ impl TerminalApp {
                         let _ = tx.send(StreamEvent::TurnComplete(stats.clone()));
                     }
                 })),
+                on_stream_event: Some(retry_notifier(tx.clone())),
+                on_hook: Some(Arc::new(hook_notifier(tx.clone()))),
                 ..Default::default()
             }
         } else {
@@ -215,29 +263,55 @@ impl TerminalApp {
                         let _ = tx.send(StreamEvent::TurnComplete(stats.clone()));
                     }
                 })),
+                on_stream_event: Some(retry_notifier(tx.clone())),
+                on_hook: Some(Arc::new(hook_notifier(tx.clone()))),
                 ..Default::default()
             }
         };
         
+        // Only the agent's callbacks hold senders, so the output loop ends with the agent
+        drop(tx);
+        
         // Create agent config
         let agent_config = AgentConfig {
             use_tools: true,
             auto_approve_all: self.app.auto_approve_all(),
+            retry: RetryConfig::from_config(&self.config),
+            budget: BudgetConfig::from_config(&self.config),
+            hooks: self.config.hooks.clone(),
+            plan_mode: self.app.plan_mode(),
             ..Default::default()
         };
         
+        // Sub-agents report tool activity through the same callbacks
+        let tool_registry = Arc::new(with_task_tool(
+            (*self.tool_registry).clone(),
+            provider_factory(&self.config),
+            &callbacks,
+            TaskConfig::from_config(&self.config),
+        ));
+
         // Create agent
         let mut agent = crate::agent::Agent::new(AgentOptions {
             provider,
-            tool_registry: self.tool_registry.clone(),
+            tool_registry,
             system_prompt: None,
             config: agent_config,
             callbacks,
+            summarize_provider: self.summarize_provider.clone(),
+            output_schema: None,
         });
+        agent.set_session_cost(self.session_cost);
+        agent.set_checkpoints(Some(self.app.checkpoints()));
+        
+        // Track the conversation so /rewind and /session save see it
+        self.app.begin_checkpoint_turn();
+        self.app.messages.push(Message::user(content));
         
         // Run chat in background with owned content
         let chat_handle = tokio::spawn(async move {
-            agent.chat(&content_owned).await
+            let result = agent.chat(&content_owned).await;
+            (result, agent.session_cost())
         });
         
         // Print assistant prefix
@@ -247,6 +321,7 @@ impl TerminalApp {
         let mut stdout = io::stdout();
         let start_time = Instant::now();
         let mut in_tool_call = false;
+        let mut turn_cost = 0.0;
         
         while let Some(event) = rx.recv().await {
             match event {
@@ -268,20 +343,35 @@ impl TerminalApp {
                     // Show result in debug mode
                     print_tool_result(&result, is_error);
                 }
-                StreamEvent::TurnComplete(_stats) => {
+                StreamEvent::Retrying(status) | StreamEvent::Hook(status) => {
+                    print_notice(&status);
+                }
+                StreamEvent::TurnComplete(stats) => {
+                    turn_cost = stats.cost;
                     break;
                 }
             }
         }
         
         // Wait for chat to complete
-        let _ = chat_handle.await?;
+        let (result, session_cost) = chat_handle.await?;
+        self.session_cost = session_cost;
+        match result {
+            Ok(response) => {
+                self.app.offer_plan(&response);
+                self.app.messages.push(Message::assistant(&response));
+            }
+            Err(e) => print_agent_error(&e.to_string()),
+        }
         
-        // Print elapsed time
+        // Print elapsed time and cost
         let elapsed = start_time.elapsed();
         if elapsed.as_secs() > 0 {
             print_elapsed(elapsed.as_secs_f64());
         }
+        if turn_cost > 0.0 {
+            print_cost(turn_cost, self.session_cost);
+        }
         
         println!(); // Final newline
         
@@ -294,9 +384,43 @@ enum StreamEvent {
     Text(String),
     ToolStart(String, serde_json::Value),
     ToolResult(String, bool),
+    Retrying(String),
+    Hook(String),
     TurnComplete(TurnStats),
 }
 
+/// Forward provider retry and fallback notices to the output loop.
+fn retry_notifier(
+    tx: tokio::sync::mpsc::UnboundedSender<StreamEvent>,
+) -> Arc<dyn Fn(&crate::types::StreamEvent) + Send + Sync> {
+    Arc::new(move |event: &crate::types::StreamEvent| {
+        let status = match event {
+            crate::types::StreamEvent::Retrying { attempt, max_attempts, delay_ms, .. } => {
+                retry_status(*attempt, *max_attempts, *delay_ms)
+            }
+            crate::types::StreamEvent::Fallback { from, to, .. } => fallback_status(from, to),
+            _ => return,
+        };
+        let _ = tx.send(StreamEvent::Retrying(status));
+    })
+}
+
+/// Forward hook block/feedback notices to the output loop.
+fn hook_notifier(
+    tx: tokio::sync::mpsc::UnboundedSender<StreamEvent>,
+) -> impl Fn(&str, &str) + Send + Sync + 'static {
+    move |event: &str, message: &str| {
+        let _ = tx.send(StreamEvent::Hook(format!("[{}] {}", event, message)));
+    }
+}
+
+/// Print the status message a command left on the app, if any.
+fn print_command_status(app: &mut App) {
+    if let Some(status) = app.status.take() {
+        println!("{}", status.trim());
+    }
+}
+
 fn print_welcome(config: &ResolvedConfig) -> anyhow::Result<()> {
     use std::io::{self, Write};
     
@@ -396,6 +520,71 @@ fn print_tool_result(result: &str, is_error: bool) {
     let _ = stdout.flush();
 }
 
+fn print_notice(status: &str) {
+    use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+    use crossterm::ExecutableCommand;
+    use std::io::{self, Write};
+    
+    let mut stdout = io::stdout();
+    let _ = stdout.execute(SetForegroundColor(Color::Yellow));
+    let _ = stdout.execute(Print(format!("\n{}\n", status)));
+    let _ = stdout.execute(ResetColor);
+    let _ = stdout.flush();
+}
+
+fn print_plan(plan: &str) {
+    use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+    use crossterm::ExecutableCommand;
+    use std::io::{self, Write};
+    
+    let mut stdout = io::stdout();
+    let _ = stdout.execute(SetForegroundColor(Color::Magenta));
+    let _ = stdout.execute(Print("\n── Plan ──\n"));
+    let _ = stdout.execute(ResetColor);
+    for line in plan.lines() {
+        let _ = stdout.execute(Print(format!("  {}\n", line)));
+    }
+    let _ = stdout.flush();
+}
+
+/// Ask whether to approve the plan that was just printed.
+fn confirm_plan() -> anyhow::Result<bool> {
+    print!("Approve and execute this plan? [y/N] ");
+    io::stdout().flush()?;
+    
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn print_agent_error(message: &str) {
+    use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+    use crossterm::ExecutableCommand;
+    use std::io::{self, Write};
+    
+    let mut stdout = io::stdout();
+    let _ = stdout.execute(SetForegroundColor(Color::Red));
+    let _ = stdout.execute(Print(format!("\n✗ {}", message)));
+    let _ = stdout.execute(ResetColor);
+    let _ = stdout.flush();
+}
+
+fn print_cost(turn_cost: f64, session_cost: f64) {
+    use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+    use crossterm::ExecutableCommand;
+    use std::io::{self, Write};
+    
+    let mut stdout = io::stdout();
+    let _ = stdout.execute(SetForegroundColor(Color::DarkGrey));
+    let _ = stdout.execute(Print(format!(
+        " [{} turn, {} session]",
+        format_cost(turn_cost),
+        format_cost(session_cost)
+    )));
+    let _ = stdout.execute(ResetColor);
+    let _ = stdout.flush();
+}
+
 fn print_elapsed(seconds: f64) {
     use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
     use crossterm::ExecutableCommand;
@@ -58,6 +58,7 @@ pub fn draw(f: &mut Frame, app: &App) {
     match app.mode {
         AppMode::Help => draw_help(f),
         AppMode::ConfirmTool => draw_confirmation(f, app),
+        AppMode::ConfirmPlan => draw_plan_confirmation(f, app),
         _ => {}
     }
 }
@@ -98,6 +99,10 @@ fn draw_messages(f: &mut Frame, app: &App, area: Rect) {
             ),
         };
 
+        if !msg.reasoning.is_empty() {
+            lines.extend(reasoning_lines(&msg.reasoning, app.show_reasoning));
+        }
+
         // Use pre-rendered lines if available (from streaming)
         if !msg.rendered_lines.is_empty() {
             // First line gets the prefix
@@ -176,6 +181,33 @@ fn draw_messages(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(messages, area);
 }
 
+/// Reasoning block: a one-line summary when collapsed, the full text when expanded.
+fn reasoning_lines(reasoning: &str, expanded: bool) -> Vec<Line<'static>> {
+    let style = Style::default()
+        .fg(Color::DarkGray)
+        .add_modifier(Modifier::ITALIC);
+    let reasoning = reasoning.trim();
+
+    if !expanded {
+        return vec![Line::from(Span::styled(
+            format!(
+                "  ▸ Thinking ({} lines, Ctrl+T to expand)",
+                reasoning.lines().count()
+            ),
+            style,
+        ))];
+    }
+
+    let mut lines = vec![Line::from(Span::styled(
+        "  ▾ Thinking (Ctrl+T to collapse)",
+        style,
+    ))];
+    for line in reasoning.lines() {
+        lines.push(Line::from(Span::styled(format!("  │ {}", line), style)));
+    }
+    lines
+}
+
 /// Draw the input area with Codex-style minimal design.
 fn draw_input(f: &mut Frame, app: &App, area: Rect) {
     let (_title, border_style) = match app.mode {
@@ -183,6 +215,7 @@ fn draw_input(f: &mut Frame, app: &App, area: Rect) {
         AppMode::Waiting => ("", Style::default().fg(Color::Yellow)),
         AppMode::Help => ("", Style::default().fg(Color::Cyan)),
         AppMode::ConfirmTool => ("", Style::default().fg(Color::Red)),
+        AppMode::ConfirmPlan => ("", Style::default().fg(Color::Magenta)),
     };
 
     let block = Block::default()
@@ -279,6 +312,16 @@ fn draw_status(f: &mut Frame, app: &App, area: Rect) {
         spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
     }
 
+    if app.plan_mode() {
+        spans.push(Span::styled(
+            "PLAN",
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
+    }
+
     // Show session info if available
     if let Some(session_status) = app.session_status() {
         spans.push(Span::styled(
@@ -312,6 +355,18 @@ fn draw_status(f: &mut Frame, app: &App, area: Rect) {
                 Style::default().fg(Color::DarkGray),
             ));
         }
+        if let Some(cache) = app.cache_status() {
+            spans.push(Span::styled(
+                format!(" | {}", cache),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if let Some(cost) = app.cost_status() {
+            spans.push(Span::styled(
+                format!(" | {}", cost),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
     }
 
     let status = Paragraph::new(Line::from(spans)).style(Style::default().bg(Color::Black));
@@ -355,6 +410,18 @@ fn draw_help(f: &mut Frame) {
             Span::styled("/compact", Style::default().fg(Color::Yellow)),
             Span::raw("        - Context management"),
         ]),
+        Line::from(vec![
+            Span::styled("/undo", Style::default().fg(Color::Yellow)),
+            Span::raw("           - Revert last turn's file changes"),
+        ]),
+        Line::from(vec![
+            Span::styled("/rewind <n>", Style::default().fg(Color::Yellow)),
+            Span::raw("     - Rewind n turns"),
+        ]),
+        Line::from(vec![
+            Span::styled("/plan [task]", Style::default().fg(Color::Yellow)),
+            Span::raw("    - Toggle plan mode"),
+        ]),
         Line::from(vec![
             Span::styled("/model", Style::default().fg(Color::Yellow)),
             Span::raw("          - Show/switch model"),
@@ -391,6 +458,14 @@ fn draw_help(f: &mut Frame) {
             Span::styled("/session list", Style::default().fg(Color::Yellow)),
             Span::raw("    - List saved sessions"),
         ]),
+        Line::from(vec![
+            Span::styled("/session tree", Style::default().fg(Color::Yellow)),
+            Span::raw("    - Show session forks"),
+        ]),
+        Line::from(vec![
+            Span::styled("/fork [n]", Style::default().fg(Color::Yellow)),
+            Span::raw("        - Fork from message n"),
+        ]),
         Line::from(""),
         Line::from(Span::styled(
             " Orchestration (Multi-Agent) ",
@@ -435,6 +510,10 @@ fn draw_help(f: &mut Frame) {
             Span::styled("Ctrl+C/D", Style::default().fg(Color::Yellow)),
             Span::raw("        - Quit"),
         ]),
+        Line::from(vec![
+            Span::styled("Ctrl+T", Style::default().fg(Color::Yellow)),
+            Span::raw("          - Expand/collapse thinking"),
+        ]),
         Line::from(vec![
             Span::styled("Up/Down", Style::default().fg(Color::Yellow)),
             Span::raw("         - Navigate input history"),
@@ -544,6 +623,66 @@ fn draw_confirmation(f: &mut Frame, app: &App) {
     f.render_widget(confirmation_widget, area);
 }
 
+/// Draw the plan approval overlay.
+fn draw_plan_confirmation(f: &mut Frame, app: &App) {
+    let area = centered_rect(70, 60, f.area());
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            " Approve Plan? ",
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+
+    if let Some(plan) = app.pending_plan() {
+        for line in plan.format().lines() {
+            lines.push(Line::from(Span::raw(format!("  {}", line))));
+        }
+        let files = plan.files();
+        if !files.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!("  {} file(s) to change", files.len()),
+                Style::default().fg(Color::Cyan),
+            )));
+        }
+    }
+
+    lines.extend(vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(
+                "[Y]",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" Approve and execute  "),
+            Span::styled(
+                "[N]",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" Keep planning"),
+        ]),
+    ]);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Plan ")
+        .title_style(Style::default().fg(Color::Magenta))
+        .style(Style::default().bg(Color::Black));
+
+    let plan_widget = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, area);
+    f.render_widget(plan_widget, area);
+}
+
 /// Draw the exec cells area.
 fn draw_exec_cells(f: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
@@ -8,6 +8,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 // ============================================================================
 // Message Types
@@ -106,6 +107,10 @@ pub struct ContentBlock {
     /// Image data for image blocks
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image: Option<ImageSource>,
+
+    /// Provider signature for thinking blocks that must be sent back verbatim
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
 }
 
 impl ContentBlock {
@@ -121,6 +126,7 @@ impl ContentBlock {
             content: None,
             is_error: None,
             image: None,
+            signature: None,
         }
     }
 
@@ -136,6 +142,7 @@ impl ContentBlock {
             content: None,
             is_error: None,
             image: None,
+            signature: None,
         }
     }
 
@@ -151,6 +158,19 @@ impl ContentBlock {
             content: Some(content.into()),
             is_error: if is_error { Some(true) } else { None },
             image: None,
+            signature: None,
+        }
+    }
+
+    /// Create a tool_result content block carrying an image as well as text.
+    pub fn tool_result_with_image(
+        tool_use_id: impl Into<String>,
+        content: impl Into<String>,
+        image: ImageSource,
+    ) -> Self {
+        Self {
+            image: Some(image),
+            ..Self::tool_result(tool_use_id, content, false)
         }
     }
 
@@ -166,6 +186,7 @@ impl ContentBlock {
             content: None,
             is_error: None,
             image: Some(source),
+            signature: None,
         }
     }
 
@@ -181,6 +202,15 @@ impl ContentBlock {
             content: None,
             is_error: None,
             image: None,
+            signature: None,
+        }
+    }
+
+    /// Create a thinking block with the signature the provider needs to verify it.
+    pub fn thinking_with_signature(text: impl Into<String>, signature: impl Into<String>) -> Self {
+        Self {
+            signature: Some(signature.into()),
+            ..Self::thinking(text)
         }
     }
 }
@@ -351,6 +381,9 @@ pub struct ToolResult {
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
+    /// Image returned alongside the text, for vision-capable models
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageSource>,
 }
 
 impl ToolResult {
@@ -360,6 +393,7 @@ impl ToolResult {
             tool_use_id: tool_use_id.into(),
             content: content.into(),
             is_error: None,
+            image: None,
         }
     }
 
@@ -369,6 +403,7 @@ impl ToolResult {
             tool_use_id: tool_use_id.into(),
             content: error.into(),
             is_error: Some(true),
+            image: None,
         }
     }
 }
@@ -460,6 +495,16 @@ impl TokenUsage {
     pub fn total(&self) -> u32 {
         self.input_tokens + self.output_tokens
     }
+
+    /// Get the size of the prompt that was sent, including cached tokens.
+    ///
+    /// Anthropic reports cache reads and writes separately from `input_tokens`;
+    /// OpenAI's `cached_input_tokens` is already part of `input_tokens`.
+    pub fn context_tokens(&self) -> u32 {
+        self.input_tokens
+            + self.cache_creation_input_tokens.unwrap_or(0)
+            + self.cache_read_input_tokens.unwrap_or(0)
+    }
 }
 
 /// Reason why the model stopped generating.
@@ -483,6 +528,10 @@ pub struct ProviderResponse {
     /// Optional reasoning/thinking content from reasoning models
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_content: Option<String>,
+    /// Signature of the reasoning, when the provider requires it to be sent
+    /// back with tool results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_signature: Option<String>,
     /// Token usage information
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage: Option<TokenUsage>,
@@ -496,6 +545,7 @@ impl ProviderResponse {
             tool_calls: Vec::new(),
             stop_reason: StopReason::EndTurn,
             reasoning_content: None,
+            reasoning_signature: None,
             usage: None,
         }
     }
@@ -507,6 +557,7 @@ impl ProviderResponse {
             tool_calls: Vec::new(),
             stop_reason: StopReason::EndTurn,
             reasoning_content: None,
+            reasoning_signature: None,
             usage: None,
         }
     }
@@ -565,12 +616,56 @@ pub struct ModelCapabilities {
 }
 
 /// Pricing information per million tokens (USD).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ModelPricing {
     /// Input cost per million tokens
     pub input: f64,
     /// Output cost per million tokens
     pub output: f64,
+    /// Cost per million tokens read from the prompt cache (defaults to `input`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read: Option<f64>,
+    /// Cost per million tokens written to the prompt cache (defaults to `input`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write: Option<f64>,
+}
+
+impl ModelPricing {
+    /// Create pricing without cache rates.
+    pub fn new(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_read: None,
+            cache_write: None,
+        }
+    }
+
+    /// Set the cache read and write rates.
+    pub fn with_cache(mut self, read: f64, write: f64) -> Self {
+        self.cache_read = Some(read);
+        self.cache_write = Some(write);
+        self
+    }
+
+    /// Compute the cost in USD of a single response's token usage.
+    ///
+    /// Cached tokens reported by OpenAI are part of `input_tokens`, so they are
+    /// moved from the input rate to the cache read rate. Anthropic reports
+    /// cache reads and writes separately from `input_tokens`.
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let openai_cached = usage.cached_input_tokens.unwrap_or(0).min(usage.input_tokens);
+        let uncached_input = (usage.input_tokens - openai_cached) as f64;
+        let cache_read = (usage.cache_read_input_tokens.unwrap_or(0) + openai_cached) as f64;
+        let cache_write = usage.cache_creation_input_tokens.unwrap_or(0) as f64;
+
+        (uncached_input * self.input
+            + cache_read * self.cache_read.unwrap_or(self.input)
+            + cache_write * self.cache_write.unwrap_or(self.input)
+            + usage.output_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
 }
 
 /// Information about an available model.
@@ -629,6 +724,82 @@ pub struct ProviderConfig {
     /// Request timeout in milliseconds
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_ms: Option<u64>,
+
+    /// Mark prompt prefixes for caching where the provider supports it (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_caching: Option<bool>,
+
+    /// Token budget for extended thinking (Anthropic Claude 3.7+ and 4 models)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<u32>,
+
+    /// Reasoning effort for OpenAI o-series and gpt-5 models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+
+    /// RunPod serverless endpoint ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint_id: Option<String>,
+
+    /// Which RunPod endpoint API to call (default: OpenAI-compatible)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runpod_api: Option<RunPodApi>,
+
+    /// Which OpenAI API to call (default: Chat Completions)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openai_api: Option<OpenAIApi>,
+
+    /// Cassette file served by the replay provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cassette: Option<PathBuf>,
+
+    /// Cassette file to record exchanges to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record_to: Option<PathBuf>,
+}
+
+/// How much reasoning a model should do before answering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    /// Value sent in API requests.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// API used to talk to a RunPod serverless endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunPodApi {
+    /// The worker's OpenAI-compatible route (`/openai/v1`), e.g. worker-vllm.
+    #[default]
+    OpenAI,
+    /// The async job queue (`/run` and `/status`), for any worker.
+    Queue,
+}
+
+/// API used to talk to OpenAI and OpenAI-compatible servers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenAIApi {
+    /// Chat Completions (`/chat/completions`), spoken by every compatible server.
+    #[default]
+    Chat,
+    /// The Responses API (`/responses`), with reasoning summaries and encrypted reasoning carry-over.
+    Responses,
 }
 
 impl ProviderConfig {
@@ -668,6 +839,120 @@ impl ProviderConfig {
     }
 }
 
+// ============================================================================
+// Request Options
+// ============================================================================
+
+/// How the model may use the tools offered in a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// The model decides whether to call tools (the default).
+    Auto,
+    /// The model must not call tools.
+    None,
+    /// The model must call at least one tool.
+    Required,
+    /// The model must call the named tool.
+    Tool(String),
+}
+
+/// Per-request options for [`Provider::chat`] and [`Provider::stream_chat`].
+///
+/// Unset options fall back to the provider's [`ProviderConfig`]. Providers
+/// return [`ProviderError::UnsupportedOperation`] for options they can't honor.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatOptions {
+    /// How the model may use the offered tools
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+
+    /// Allow (or forbid) several tool calls in one response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+
+    /// Stop generating at any of these sequences
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+
+    /// Maximum tokens to generate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+
+    /// Sampling temperature (0.0 - 2.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    /// Request metadata passed to the API (e.g. `user_id`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+}
+
+impl ChatOptions {
+    /// Options that leave everything to the provider's configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the tool choice.
+    pub fn with_tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.tool_choice = Some(choice);
+        self
+    }
+
+    /// Allow or forbid parallel tool calls.
+    pub fn with_parallel_tool_calls(mut self, enabled: bool) -> Self {
+        self.parallel_tool_calls = Some(enabled);
+        self
+    }
+
+    /// Set the stop sequences.
+    pub fn with_stop_sequences(mut self, stops: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.stop_sequences = stops.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the max tokens.
+    pub fn with_max_tokens(mut self, tokens: u32) -> Self {
+        self.max_tokens = Some(tokens);
+        self
+    }
+
+    /// Set the temperature.
+    pub fn with_temperature(mut self, temp: f32) -> Self {
+        self.temperature = Some(temp);
+        self
+    }
+
+    /// Add a metadata entry.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Whether the model is required to call a tool.
+    pub fn forces_tool_use(&self) -> bool {
+        matches!(self.tool_choice, Some(ToolChoice::Required | ToolChoice::Tool(_)))
+    }
+
+    /// Check that the tool choice can be satisfied by the offered tools.
+    pub fn check_tools(&self, tools: Option<&[ToolDefinition]>) -> Result<(), ProviderError> {
+        let tools = tools.unwrap_or_default();
+        match self.tool_choice {
+            Some(ToolChoice::Required) if tools.is_empty() => Err(ProviderError::UnsupportedOperation(
+                "tool_choice \"required\" needs at least one tool".to_string(),
+            )),
+            Some(ToolChoice::Tool(ref name)) if !tools.iter().any(|t| &t.name == name) => {
+                Err(ProviderError::UnsupportedOperation(format!(
+                    "tool_choice names tool '{}', which is not offered",
+                    name
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 // ============================================================================
 // Streaming Types
 // ============================================================================
@@ -701,6 +986,28 @@ pub enum StreamEvent {
 
     /// An error occurred during streaming.
     Error(String),
+
+    /// A retryable request failed and will be retried after a delay.
+    Retrying {
+        /// The attempt that is about to start (2 for the first retry).
+        attempt: u32,
+        /// Maximum number of attempts allowed.
+        max_attempts: u32,
+        /// Delay before the next attempt, in milliseconds.
+        delay_ms: u64,
+        /// Description of the error that triggered the retry.
+        reason: String,
+    },
+
+    /// A model failed and the request moves to the next model in a fallback chain.
+    Fallback {
+        /// The model that failed, as "Provider/model".
+        from: String,
+        /// The model that will answer instead, as "Provider/model".
+        to: String,
+        /// Description of the error that triggered the fallback.
+        reason: String,
+    },
 }
 
 impl StreamEvent {
@@ -751,6 +1058,7 @@ use crate::error::ProviderError;
 ///         messages: &[Message],
 ///         tools: Option<&[ToolDefinition]>,
 ///         system_prompt: Option<&str>,
+///         options: &ChatOptions,
 ///     ) -> Result<ProviderResponse, ProviderError> {
 ///         // Implementation...
 ///     }
@@ -765,6 +1073,7 @@ pub trait Provider: Send + Sync {
     /// * `messages` - Conversation history
     /// * `tools` - Optional tool definitions for function calling
     /// * `system_prompt` - Optional system prompt
+    /// * `options` - Per-request options (tool choice, sampling, metadata)
     ///
     /// # Returns
     /// Provider response with content and any tool calls
@@ -773,6 +1082,7 @@ pub trait Provider: Send + Sync {
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
     ) -> Result<ProviderResponse, ProviderError>;
 
     /// Send a streaming chat completion request.
@@ -781,6 +1091,7 @@ pub trait Provider: Send + Sync {
     /// * `messages` - Conversation history
     /// * `tools` - Optional tool definitions
     /// * `system_prompt` - Optional system prompt
+    /// * `options` - Per-request options (tool choice, sampling, metadata)
     /// * `on_event` - Callback for each stream event
     ///
     /// # Returns
@@ -790,6 +1101,7 @@ pub trait Provider: Send + Sync {
         messages: &[Message],
         tools: Option<&[ToolDefinition]>,
         system_prompt: Option<&str>,
+        options: &ChatOptions,
         on_event: Box<dyn Fn(StreamEvent) + Send + Sync>,
     ) -> Result<ProviderResponse, ProviderError>;
 
@@ -801,6 +1113,27 @@ pub trait Provider: Send + Sync {
         false
     }
 
+    /// Check if this provider can constrain a response to a JSON Schema
+    /// with [`chat_structured`](Self::chat_structured).
+    fn supports_structured_output(&self) -> bool {
+        false
+    }
+
+    /// Request a final answer whose content is JSON matching `schema`.
+    ///
+    /// Providers that support it use their native mechanism (structured
+    /// outputs, forced tool calls) and return the JSON as the response content.
+    async fn chat_structured(
+        &self,
+        _messages: &[Message],
+        _system_prompt: Option<&str>,
+        _schema: &serde_json::Value,
+    ) -> Result<ProviderResponse, ProviderError> {
+        Err(ProviderError::UnsupportedOperation(
+            "Structured output not supported".to_string(),
+        ))
+    }
+
     /// Get the name of this provider for display purposes.
     fn name(&self) -> &str;
 
@@ -812,6 +1145,23 @@ pub trait Provider: Send + Sync {
         128_000 // Default 128k
     }
 
+    /// Ask the server about the current model's capabilities.
+    ///
+    /// The agent calls this at the start of each turn so that
+    /// [`context_window`](Self::context_window),
+    /// [`supports_tool_use`](Self::supports_tool_use) and
+    /// [`supports_vision`](Self::supports_vision) reflect the served model.
+    /// Providers cache what they discover, so repeated calls are cheap.
+    async fn discover_capabilities(&self) {}
+
+    /// Get the pricing for the current model, if known.
+    ///
+    /// Used to compute turn and session costs. Local and unknown models
+    /// return `None` and are treated as free.
+    fn pricing(&self) -> Option<ModelPricing> {
+        None
+    }
+
     /// List available models from this provider.
     ///
     /// Not all providers may support model listing.
@@ -865,6 +1215,22 @@ mod tests {
         assert!(tool.input_schema.properties.contains_key("path"));
     }
 
+    #[test]
+    fn test_chat_options_check_tools() {
+        let tools = [ToolDefinition::new("read_file", "Read a file")];
+
+        assert!(ChatOptions::new().check_tools(None).is_ok());
+        assert!(ChatOptions::new().with_tool_choice(ToolChoice::None).check_tools(None).is_ok());
+
+        let required = ChatOptions::new().with_tool_choice(ToolChoice::Required);
+        assert!(required.forces_tool_use());
+        assert!(required.check_tools(Some(&tools)).is_ok());
+        assert!(required.check_tools(None).is_err());
+
+        let named = ChatOptions::new().with_tool_choice(ToolChoice::Tool("write_file".to_string()));
+        assert!(named.check_tools(Some(&tools)).is_err());
+    }
+
     #[test]
     fn test_structured_result() {
         let success: StructuredResult<String> = StructuredResult::success("data".to_string());
@@ -884,6 +1250,49 @@ mod tests {
             ..Default::default()
         };
         assert_eq!(usage.total(), 150);
+        assert_eq!(usage.context_tokens(), 100);
+
+        let cached = TokenUsage {
+            input_tokens: 10,
+            cache_creation_input_tokens: Some(200),
+            cache_read_input_tokens: Some(3_000),
+            cached_input_tokens: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(cached.context_tokens(), 3_210);
+    }
+
+    #[test]
+    fn test_model_pricing_cost() {
+        // Anthropic-style usage: cache tokens are reported separately
+        let pricing = ModelPricing::new(3.0, 15.0).with_cache(0.30, 3.75);
+        let usage = TokenUsage {
+            input_tokens: 1_000,
+            output_tokens: 2_000,
+            cache_creation_input_tokens: Some(10_000),
+            cache_read_input_tokens: Some(100_000),
+            cached_input_tokens: None,
+        };
+        let expected = (1_000.0 * 3.0 + 2_000.0 * 15.0 + 10_000.0 * 3.75 + 100_000.0 * 0.30) / 1e6;
+        assert!((pricing.cost(&usage) - expected).abs() < 1e-9);
+
+        // OpenAI-style usage: cached tokens are a subset of input tokens
+        let pricing = ModelPricing::new(2.50, 10.0).with_cache(1.25, 2.50);
+        let usage = TokenUsage {
+            input_tokens: 10_000,
+            output_tokens: 500,
+            cached_input_tokens: Some(8_000),
+            ..Default::default()
+        };
+        let expected = (2_000.0 * 2.50 + 8_000.0 * 1.25 + 500.0 * 10.0) / 1e6;
+        assert!((pricing.cost(&usage) - expected).abs() < 1e-9);
+
+        // Missing cache rates fall back to the input rate
+        let usage = TokenUsage {
+            cache_read_input_tokens: Some(1_000_000),
+            ..Default::default()
+        };
+        assert!((ModelPricing::new(1.0, 5.0).cost(&usage) - 1.0).abs() < 1e-9);
     }
 
     #[test]